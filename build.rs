@@ -3,10 +3,13 @@
 use std::env;
 use std::path::Path;
 use std::process::Command;
-use built;
 use built::Options;
 
 fn main() {
+        // The database is selected by a feature, the code checks it with these cfgs
+        println!("cargo:rustc-check-cfg=cfg(sqlite)");
+        println!("cargo:rustc-check-cfg=cfg(mysql)");
+        println!("cargo:rustc-check-cfg=cfg(postgresql)");
         #[cfg(feature = "sqlite")]
         println!("cargo:rustc-cfg=sqlite");
         #[cfg(feature = "mysql")]
@@ -81,8 +84,8 @@ fn version_from_git_info() -> Result<String, std::io::Error> {
 fn run(args: &[&str]) -> Result<String, std::io::Error> {
         let out = Command::new(args[0]).args(&args[1..]).output()?;
         if !out.status.success() {
-                use std::io::{Error, ErrorKind};
-                return Err(Error::new(ErrorKind::Other, "Command not successful"));
+                use std::io::Error;
+                return Err(Error::other("Command not successful"));
         }
         Ok(String::from_utf8(out.stdout).unwrap().trim().to_string())
 }
\ No newline at end of file
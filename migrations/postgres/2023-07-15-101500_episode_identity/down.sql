@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX podcast_episodes_guid_index;
+ALTER TABLE podcast_episodes DROP COLUMN removed_upstream;
\ No newline at end of file
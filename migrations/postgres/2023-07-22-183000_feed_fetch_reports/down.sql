@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE feed_fetch_reports;
\ No newline at end of file
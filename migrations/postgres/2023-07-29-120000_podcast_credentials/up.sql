@@ -0,0 +1,6 @@
+-- Your SQL goes here
+CREATE TABLE podcast_credentials (
+    podcast_id INTEGER PRIMARY KEY NOT NULL,
+    credentials TEXT NOT NULL,
+    FOREIGN KEY (podcast_id) REFERENCES podcasts(id)
+);
\ No newline at end of file
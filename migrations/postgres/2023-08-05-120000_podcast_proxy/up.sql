@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE podcasts ADD COLUMN proxy_url TEXT;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX podcast_episodes_search_idx;
+DROP INDEX podcasts_search_idx;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- The expressions have to match the ones used in src/models/search.rs, otherwise the indexes aren't used
+CREATE INDEX podcasts_search_idx ON podcasts USING GIN ((
+    setweight(to_tsvector('simple', name), 'A') ||
+    setweight(to_tsvector('simple', coalesce(author, '')), 'B') ||
+    setweight(to_tsvector('simple', coalesce(keywords, '')), 'B') ||
+    setweight(to_tsvector('simple', coalesce(summary, '')), 'C')
+));
+
+CREATE INDEX podcast_episodes_search_idx ON podcast_episodes USING GIN ((
+    setweight(to_tsvector('simple', name), 'A') ||
+    setweight(to_tsvector('simple', description), 'C')
+));
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE filters DROP COLUMN tag_id;
+DROP TABLE tags_podcasts;
+DROP TABLE tags;
@@ -0,0 +1,22 @@
+-- Your SQL goes here
+CREATE TABLE queue_items (
+    id SERIAL PRIMARY KEY NOT NULL,
+    username TEXT NOT NULL,
+    podcast_episode_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (username, podcast_episode_id),
+    FOREIGN KEY (podcast_episode_id) REFERENCES podcast_episodes(id)
+);
+
+CREATE TABLE queue_auto_add_podcasts (
+    username TEXT NOT NULL,
+    podcast_id INTEGER NOT NULL,
+    PRIMARY KEY (username, podcast_id),
+    FOREIGN KEY (podcast_id) REFERENCES podcasts(id)
+);
+
+CREATE TABLE queue_feed_tokens (
+    username TEXT PRIMARY KEY NOT NULL,
+    token TEXT NOT NULL UNIQUE
+);
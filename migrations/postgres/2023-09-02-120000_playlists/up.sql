@@ -0,0 +1,19 @@
+-- Your SQL goes here
+CREATE TABLE playlists (
+    id SERIAL PRIMARY KEY NOT NULL,
+    username TEXT NOT NULL,
+    name TEXT NOT NULL,
+    rules TEXT,
+    feed_token TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (username, name)
+);
+
+CREATE TABLE playlist_items (
+    playlist_id INTEGER NOT NULL,
+    podcast_episode_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (playlist_id, podcast_episode_id),
+    FOREIGN KEY (playlist_id) REFERENCES playlists(id),
+    FOREIGN KEY (podcast_episode_id) REFERENCES podcast_episodes(id)
+);
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE episode_states (
+    username TEXT NOT NULL,
+    podcast_episode_id INTEGER NOT NULL,
+    state TEXT NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (username, podcast_episode_id),
+    FOREIGN KEY (podcast_episode_id) REFERENCES podcast_episodes(id)
+);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX listening_rollups_day;
+DROP TABLE listening_rollups;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE listening_rollups (
+    username TEXT NOT NULL,
+    day DATE NOT NULL,
+    hour INTEGER NOT NULL,
+    podcast_id INTEGER NOT NULL,
+    podcast_episode_id INTEGER NOT NULL,
+    listened_seconds INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (username, day, hour, podcast_episode_id),
+    FOREIGN KEY (podcast_id) REFERENCES podcasts(id),
+    FOREIGN KEY (podcast_episode_id) REFERENCES podcast_episodes(id)
+);
+
+CREATE INDEX listening_rollups_day ON listening_rollups (day);
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE bookmarks (
+    id SERIAL PRIMARY KEY NOT NULL,
+    username TEXT NOT NULL,
+    podcast_episode_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    end_position INTEGER,
+    note TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (podcast_episode_id) REFERENCES podcast_episodes(id)
+);
+
+CREATE INDEX bookmarks_username ON bookmarks (username);
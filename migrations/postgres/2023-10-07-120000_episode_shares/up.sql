@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE episode_shares (
+    id SERIAL PRIMARY KEY NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    username TEXT NOT NULL,
+    podcast_episode_id INTEGER NOT NULL,
+    start_position INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (podcast_episode_id) REFERENCES podcast_episodes(id)
+);
+
+CREATE INDEX episode_shares_username ON episode_shares (username);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE podcast_episodes DROP COLUMN season;
+ALTER TABLE podcast_episodes DROP COLUMN episode_number;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE podcast_episodes ADD COLUMN season INTEGER;
+ALTER TABLE podcast_episodes ADD COLUMN episode_number INTEGER;
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+ALTER TABLE podcast_episodes ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- The policy without a podcast applies to every podcast that doesn't override it
+CREATE TABLE cleanup_policies (
+    id SERIAL PRIMARY KEY NOT NULL,
+    podcast_id INTEGER UNIQUE,
+    keep_latest INTEGER,
+    delete_played_after_days INTEGER,
+    max_bytes BIGINT,
+    FOREIGN KEY (podcast_id) REFERENCES podcasts(id)
+);
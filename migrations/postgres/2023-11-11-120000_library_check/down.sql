@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE podcast_episodes DROP COLUMN file_size;
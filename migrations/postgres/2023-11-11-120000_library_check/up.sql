@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- Size of the stored file after the download, files that became smaller are truncated
+ALTER TABLE podcast_episodes ADD COLUMN file_size BIGINT;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE podcast_episodes ADD COLUMN removed_upstream BOOLEAN NOT NULL DEFAULT FALSE;
+CREATE INDEX podcast_episodes_guid_index ON podcast_episodes (podcast_id, guid);
\ No newline at end of file
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE feed_fetch_reports (
+    podcast_id INTEGER PRIMARY KEY NOT NULL,
+    fetched_at TIMESTAMP NOT NULL,
+    successful BOOLEAN NOT NULL,
+    error TEXT,
+    new_episodes INTEGER NOT NULL DEFAULT 0,
+    updated_episodes INTEGER NOT NULL DEFAULT 0,
+    skipped_items TEXT NOT NULL DEFAULT '[]',
+    FOREIGN KEY (podcast_id) REFERENCES podcasts(id)
+);
\ No newline at end of file
@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER podcast_episodes_fts_update;
+DROP TRIGGER podcast_episodes_fts_delete;
+DROP TRIGGER podcast_episodes_fts_insert;
+DROP TABLE podcast_episodes_fts;
+DROP TRIGGER podcasts_fts_update;
+DROP TRIGGER podcasts_fts_delete;
+DROP TRIGGER podcasts_fts_insert;
+DROP TABLE podcasts_fts;
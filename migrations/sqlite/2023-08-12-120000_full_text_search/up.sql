@@ -0,0 +1,55 @@
+-- Your SQL goes here
+CREATE VIRTUAL TABLE podcasts_fts USING fts5(
+    name,
+    author,
+    summary,
+    keywords,
+    content='podcasts',
+    content_rowid='id',
+    tokenize='unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER podcasts_fts_insert AFTER INSERT ON podcasts BEGIN
+    INSERT INTO podcasts_fts(rowid, name, author, summary, keywords)
+    VALUES (new.id, new.name, new.author, new.summary, new.keywords);
+END;
+
+CREATE TRIGGER podcasts_fts_delete AFTER DELETE ON podcasts BEGIN
+    INSERT INTO podcasts_fts(podcasts_fts, rowid, name, author, summary, keywords)
+    VALUES ('delete', old.id, old.name, old.author, old.summary, old.keywords);
+END;
+
+CREATE TRIGGER podcasts_fts_update AFTER UPDATE OF name, author, summary, keywords ON podcasts BEGIN
+    INSERT INTO podcasts_fts(podcasts_fts, rowid, name, author, summary, keywords)
+    VALUES ('delete', old.id, old.name, old.author, old.summary, old.keywords);
+    INSERT INTO podcasts_fts(rowid, name, author, summary, keywords)
+    VALUES (new.id, new.name, new.author, new.summary, new.keywords);
+END;
+
+CREATE VIRTUAL TABLE podcast_episodes_fts USING fts5(
+    name,
+    description,
+    content='podcast_episodes',
+    content_rowid='id',
+    tokenize='unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER podcast_episodes_fts_insert AFTER INSERT ON podcast_episodes BEGIN
+    INSERT INTO podcast_episodes_fts(rowid, name, description)
+    VALUES (new.id, new.name, new.description);
+END;
+
+CREATE TRIGGER podcast_episodes_fts_delete AFTER DELETE ON podcast_episodes BEGIN
+    INSERT INTO podcast_episodes_fts(podcast_episodes_fts, rowid, name, description)
+    VALUES ('delete', old.id, old.name, old.description);
+END;
+
+CREATE TRIGGER podcast_episodes_fts_update AFTER UPDATE OF name, description ON podcast_episodes BEGIN
+    INSERT INTO podcast_episodes_fts(podcast_episodes_fts, rowid, name, description)
+    VALUES ('delete', old.id, old.name, old.description);
+    INSERT INTO podcast_episodes_fts(rowid, name, description)
+    VALUES (new.id, new.name, new.description);
+END;
+
+INSERT INTO podcasts_fts(podcasts_fts) VALUES ('rebuild');
+INSERT INTO podcast_episodes_fts(podcast_episodes_fts) VALUES ('rebuild');
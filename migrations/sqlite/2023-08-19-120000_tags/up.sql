@@ -0,0 +1,22 @@
+-- Your SQL goes here
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    username TEXT NOT NULL,
+    name TEXT NOT NULL,
+    parent_id INTEGER,
+    color TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (parent_id) REFERENCES tags(id)
+);
+
+CREATE INDEX tags_username ON tags (username);
+
+CREATE TABLE tags_podcasts (
+    tag_id INTEGER NOT NULL,
+    podcast_id INTEGER NOT NULL,
+    PRIMARY KEY (tag_id, podcast_id),
+    FOREIGN KEY (tag_id) REFERENCES tags(id),
+    FOREIGN KEY (podcast_id) REFERENCES podcasts(id)
+);
+
+ALTER TABLE filters ADD COLUMN tag_id INTEGER;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE queue_feed_tokens;
+DROP TABLE queue_auto_add_podcasts;
+DROP TABLE queue_items;
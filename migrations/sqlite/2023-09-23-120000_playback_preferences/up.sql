@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE playback_preferences (
+    username TEXT NOT NULL,
+    podcast_id INTEGER NOT NULL,
+    playback_speed REAL NOT NULL DEFAULT 1.0,
+    volume INTEGER NOT NULL DEFAULT 100,
+    skip_intro_seconds INTEGER NOT NULL DEFAULT 0,
+    skip_outro_seconds INTEGER NOT NULL DEFAULT 0,
+    trim_silence BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (username, podcast_id),
+    FOREIGN KEY (podcast_id) REFERENCES podcasts(id)
+);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+UPDATE podcasts SET directory_name = 'podcasts/' || directory_name WHERE directory_name <> '';
+UPDATE podcasts SET image_url = 'podcasts/' || image_url
+WHERE image_url <> '' AND image_url NOT LIKE '%://%';
+UPDATE podcast_episodes SET local_url = 'podcasts/' || local_url WHERE local_url <> '';
+UPDATE podcast_episodes SET local_image_url = 'podcasts/' || local_image_url
+WHERE local_image_url <> '';
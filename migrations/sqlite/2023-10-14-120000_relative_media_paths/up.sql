@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- Paths of downloaded files are relative to the media root instead of the working directory
+UPDATE podcasts SET directory_name = substr(directory_name, 10) WHERE directory_name LIKE 'podcasts/%';
+UPDATE podcasts SET image_url = substr(image_url, 10) WHERE image_url LIKE 'podcasts/%';
+UPDATE podcast_episodes SET local_url = substr(local_url, 10) WHERE local_url LIKE 'podcasts/%';
+UPDATE podcast_episodes SET local_image_url = substr(local_image_url, 10)
+WHERE local_image_url LIKE 'podcasts/%';
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE cleanup_policies;
+ALTER TABLE podcast_episodes DROP COLUMN pinned;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE podcast_episodes DROP COLUMN chapters_url;
+ALTER TABLE podcasts DROP COLUMN write_metadata_tags;
+ALTER TABLE settings DROP COLUMN write_metadata_tags;
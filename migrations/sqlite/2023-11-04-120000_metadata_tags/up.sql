@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE settings ADD COLUMN write_metadata_tags BOOLEAN NOT NULL DEFAULT FALSE;
+-- Podcasts without a value use the setting
+ALTER TABLE podcasts ADD COLUMN write_metadata_tags BOOLEAN;
+ALTER TABLE podcast_episodes ADD COLUMN chapters_url TEXT;
@@ -22,7 +22,9 @@ use sha256::digest;
 use crate::models::oidc_model::{CustomJwk, CustomJwkSet};
 use crate::mutex::LockResultExt;
 use crate::service::jwkservice::JWKService;
+use crate::service::http_client::HttpClientFactory;
 
+#[derive(Default)]
 pub struct AuthFilter {
 }
 
@@ -37,12 +39,6 @@ pub struct AuthFilterMiddleware<S>{
     service: Rc<S>
 }
 
-impl Default for AuthFilter {
-    fn default() -> Self {
-        Self {
-        }
-    }
-}
 
 impl<S, B> Transform<S, ServiceRequest> for AuthFilter
     where
@@ -79,31 +75,33 @@ impl<S, B> Service<ServiceRequest> for AuthFilterMiddleware<S>
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         if var(BASIC_AUTH).is_ok() {
-            return self.handle_basic_auth(req);
+            self.handle_basic_auth(req)
         }
         else if var(OIDC_AUTH).is_ok(){
-            return self.handle_oidc_auth(req)
+            self.handle_oidc_auth(req)
         }
         else{
             // It can only be no auth
-            return self.handle_no_auth(req);
+            self.handle_no_auth(req)
         }
     }
 }
 
+type AuthFuture<B> = Pin<Box<dyn futures_util::Future<Output=Result<ServiceResponse<EitherBody<B>>, Error>>>>;
+
 impl<S, B> AuthFilterMiddleware<S> where B: 'static + MessageBody, S: 'static + Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error>, S::Future: 'static {
     fn handle_basic_auth(&self, req: ServiceRequest) ->
-    Pin<Box<dyn futures_util::Future<Output=Result<ServiceResponse<EitherBody<B>>, Error>>>> {
+    AuthFuture<B> {
         let opt_auth_header = req.headers().get("Authorization");
         if opt_auth_header.is_none() {
             return Box::pin(ok(req.error_response(ErrorUnauthorized("Unauthorized")).map_into_right_body()));
         }
         let authorization = opt_auth_header.unwrap().to_str();
-        return match authorization {
+        match authorization {
             Ok(auth) => {
                 let (username, password) = AuthFilter::extract_basic_auth(auth);
                 let res = req.app_data::<web::Data<DbPool>>().unwrap();
-                let found_user = User::find_by_username(username.as_str(), &mut *res.get().unwrap());
+                let found_user = User::find_by_username(username.as_str(), &mut res.get().unwrap());
                 if found_user.is_none() {
                     return Box::pin(ok(req.error_response(ErrorUnauthorized("Unauthorized"))
                         .map_into_right_body()))
@@ -116,7 +114,7 @@ impl<S, B> AuthFilterMiddleware<S> where B: 'static + MessageBody, S: 'static +
                             req.extensions_mut().insert(unwrapped_user);
                             let service = Rc::clone(&self.service);
                             async move {
-                                return service
+                                service
                                     .call(req)
                                     .await
                                     .map(|res| res.map_into_left_body())
@@ -134,7 +132,7 @@ impl<S, B> AuthFilterMiddleware<S> where B: 'static + MessageBody, S: 'static +
                     req.extensions_mut().insert(unwrapped_user);
                     let service = Rc::clone(&self.service);
                     async move {
-                        return service
+                        service
                             .call(req)
                             .await
                             .map(|res| res.map_into_left_body())
@@ -150,7 +148,7 @@ impl<S, B> AuthFilterMiddleware<S> where B: 'static + MessageBody, S: 'static +
         }
     }
 
-    fn handle_oidc_auth(&self, req: ServiceRequest) -> Pin<Box<dyn futures_util::Future<Output=Result<ServiceResponse<EitherBody<B>>, Error>>>> {
+    fn handle_oidc_auth(&self, req: ServiceRequest) -> AuthFuture<B> {
         let token_res = req.headers().get("Authorization").unwrap().to_str();
         if token_res.is_err() {
             return Box::pin(ok(req.error_response(ErrorUnauthorized("Unauthorized")).map_into_right_body()));
@@ -194,14 +192,14 @@ impl<S, B> AuthFilterMiddleware<S> where B: 'static + MessageBody, S: 'static +
         }).collect::<Vec<CustomJwk>>();
 
         let jwk = response.clone();
-        let custom_jwk = jwk.get(0).expect("Your jwk set needs to have RS256");
+        let custom_jwk = jwk.first().expect("Your jwk set needs to have RS256");
 
         let jwk_string = serde_json::to_string(&custom_jwk).unwrap();
 
-        let jwk = from_str::<Jwk>(&*jwk_string).unwrap();
+        let jwk = from_str::<Jwk>(&jwk_string).unwrap();
         let key = DecodingKey::from_jwk(&jwk).unwrap();
         let validation = Validation::new(Algorithm::RS256);
-        return match decode::<Value>(&token, &key, &validation) {
+        match decode::<Value>(&token, &key, &validation) {
             Ok(decoded) => {
                 let username = decoded.claims.get("preferred_username").unwrap().as_str().unwrap();
                 let pool = req.app_data::<web::Data<DbPool>>().cloned().unwrap();
@@ -247,13 +245,12 @@ impl<S, B> AuthFilterMiddleware<S> where B: 'static + MessageBody, S: 'static +
         }
     }
 
-    fn handle_no_auth(&self, req: ServiceRequest) -> Pin<Box<dyn
-    futures_util::Future<Output=Result<ServiceResponse<EitherBody<B>>, Error>>>>{
+    fn handle_no_auth(&self, req: ServiceRequest) -> AuthFuture<B>{
         let user = User::create_standard_admin_user();
         req.extensions_mut().insert(user);
         let service = Rc::clone(&self.service);
         async move {
-            return service
+            service
                 .call(req)
                 .await
                 .map(|res| res.map_into_left_body())
@@ -277,14 +274,17 @@ impl AuthFilter{
 
     pub fn get_jwk() -> CustomJwkSet {
         let jwk_uri = var("OIDC_JWKS").expect("OIDC_JWKS must be set");
-        let response = reqwest::blocking::get(jwk_uri).unwrap()
-            .json::<CustomJwkSet>().unwrap();
-        response
+        
+        HttpClientFactory::new().build_sync_client()
+            .get(jwk_uri)
+            .send()
+            .unwrap()
+            .json::<CustomJwkSet>().unwrap()
     }
 
     pub fn basic_auth_login(rq: String) -> (String, String) {
         let (u,p) = Self::extract_basic_auth(rq.as_str());
 
-        return (u.to_string(),p.to_string())
+        (u.to_string(),p.to_string())
     }
 }
\ No newline at end of file
@@ -15,6 +15,17 @@ use crate::models::episode::Episode;
 use crate::models::favorites::Favorite;
 use crate::models::session::Session;
 use crate::models::subscription::Subscription;
+use crate::models::tag::Tag;
+use crate::models::queue::{QueueAutoAddPodcast, QueueFeedToken, QueueItem};
+use crate::models::playlist::Playlist;
+use crate::models::episode_state::EpisodeState;
+use crate::models::listening_stats::ListeningRollup;
+use crate::models::playback_preference::PlaybackPreference;
+use crate::models::bookmark::Bookmark;
+use crate::models::library_check::{LibraryIssue, LibraryIssueKind, LibraryRepairAction};
+use crate::models::episode_share::EpisodeShare;
+use crate::service::credential_service::CredentialService;
+use crate::service::library_service::LibraryService;
 use crate::service::podcast_episode_service::PodcastEpisodeService;
 use crate::service::rust_service::PodcastService;
 use crate::models::podcast_history_item::PodcastHistoryItem;
@@ -28,13 +39,14 @@ pub fn start_command_line(mut args: Args){
             println!(r" The following commands are available:
             users => Handles user management
             podcasts => Handles podcast management
+            library => Handles the downloaded files
             ")
         }
         "podcasts"=>{
             println!("Podcast management");
-            match args.nth(0).unwrap().as_str() {
+            match args.next().unwrap().as_str() {
                 "refresh"=> {
-                    let podcast_rss_feed = args.nth(0);
+                    let podcast_rss_feed = args.next();
                     if podcast_rss_feed.is_none(){
                         println!("Please provide a podcast rss feed url");
                         exit(1);
@@ -44,7 +56,9 @@ pub fn start_command_line(mut args: Args){
                     let conn = &mut establish_connection();
 
 
-                    let replaced_feed = rss_feed.replace("'", "").replace(" ","");
+                    // Credentials aren't part of the stored feed url
+                    let replaced_feed = CredentialService::redact_url(&rss_feed.replace("'", "")
+                        .replace(" ",""));
                     println!("Refreshing podcast {}", replaced_feed);
 
                     let podcast = Podcast::get_podcast_by_rss_feed(replaced_feed, conn);
@@ -94,26 +108,17 @@ pub fn start_command_line(mut args: Args){
         },
         "users"=>{
             println!("User management");
-            match args.nth(0).unwrap().as_str() {
+            match args.next().unwrap().as_str() {
                 "add"=> {
                     let mut user = read_user_account();
 
 
                     println!("Should a user with the following settings be applied {:?}",user);
 
-                    match ask_for_confirmation(){
-                        Ok(..)=>{
-                            user.password = Some(digest(user.password.unwrap()));
-                            match User::insert_user(&mut user, &mut establish_connection()){
-                                Ok(..)=>{
-                                    println!("User succesfully created")
-                                },
-                                Err(..)=>{
-
-                                }
-                            }
-                        },
-                        Err(..)=> {
+                    if ask_for_confirmation().is_ok() {
+                        user.password = Some(digest(user.password.unwrap()));
+                        if User::insert_user(&mut user, &mut establish_connection()).is_ok() {
+                            println!("User succesfully created")
                         }
                     }
                 }
@@ -144,6 +149,36 @@ pub fn start_command_line(mut args: Args){
                                 .expect("Error deleting sessions");
                             Subscription::delete_by_username(&trim_string(username.clone()),
                                                              &mut establish_connection()).expect("TODO: panic message");
+                            Tag::delete_by_username(&trim_string(username.clone()),
+                                                    &mut establish_connection())
+                                .expect("Error deleting tags");
+                            QueueItem::delete_by_username(&trim_string(username.clone()),
+                                                          &mut establish_connection())
+                                .expect("Error deleting queue");
+                            QueueAutoAddPodcast::delete_by_username(&trim_string(username.clone()),
+                                                                    &mut establish_connection())
+                                .expect("Error deleting podcasts added to the queue");
+                            QueueFeedToken::delete_by_username(&trim_string(username.clone()),
+                                                               &mut establish_connection())
+                                .expect("Error deleting queue feed token");
+                            Playlist::delete_by_username(&trim_string(username.clone()),
+                                                         &mut establish_connection())
+                                .expect("Error deleting playlists");
+                            EpisodeState::delete_by_username(&trim_string(username.clone()),
+                                                             &mut establish_connection())
+                                .expect("Error deleting episode states");
+                            ListeningRollup::delete_by_username(&trim_string(username.clone()),
+                                                                &mut establish_connection())
+                                .expect("Error deleting listening statistics");
+                            PlaybackPreference::delete_by_username(&trim_string(username.clone()),
+                                                                   &mut establish_connection())
+                                .expect("Error deleting playback preferences");
+                            Bookmark::delete_by_username(&trim_string(username.clone()),
+                                                         &mut establish_connection())
+                                .expect("Error deleting bookmarks");
+                            EpisodeShare::delete_by_username(&trim_string(username.clone()),
+                                                             &mut establish_connection())
+                                .expect("Error deleting share links");
                             User::delete_by_username(trim_string(username.clone()),
                                                      &mut establish_connection())
                                 .expect("Error deleting user");
@@ -191,6 +226,44 @@ pub fn start_command_line(mut args: Args){
                 }
             }
         },
+        "library"=>{
+            println!("Library management");
+            match args.next().unwrap_or_default().as_str() {
+                "relocate"=>{
+                    let arguments = args.collect::<Vec<String>>();
+                    let dry_run = arguments.iter().any(|argument| argument == "--dry-run");
+                    match arguments.iter().find(|argument| !argument.starts_with("--")) {
+                        Some(new_root)=>relocate_library(new_root, dry_run),
+                        None=>{
+                            println!("Please provide the new media root");
+                            exit(1);
+                        }
+                    }
+                }
+                "reorganize"=>{
+                    let dry_run = args.any(|argument| argument == "--dry-run");
+                    reorganize_library(dry_run);
+                }
+                "check"=>{
+                    let arguments = args.collect::<Vec<String>>();
+                    let repair = arguments.iter().any(|argument| argument == "--repair");
+                    let delete_orphans = arguments.iter()
+                        .any(|argument| argument == "--delete-orphans");
+                    check_library(repair, delete_orphans);
+                }
+                _=>{
+                    println!(r" The following commands are available:
+                    relocate <new media root> [--dry-run] => Moves the downloaded files to a \
+                    new directory. Stop PodFetch before and set MEDIA_ROOT afterwards.
+                    reorganize [--dry-run] => Moves the downloaded files to the paths of the \
+                    current naming settings
+                    check [--repair] [--delete-orphans] => Finds missing, damaged and \
+                    orphaned files. --repair downloads episodes again and moves orphans to the \
+                    quarantine directory or deletes them.
+                    ")
+                }
+            }
+        }
         "debug"=>{
             create_debug_message();
         }
@@ -200,6 +273,166 @@ pub fn start_command_line(mut args: Args){
     }
 }
 
+fn relocate_library(new_root: &str, dry_run: bool) {
+    let conn = &mut establish_connection();
+    let plan = match LibraryService::plan_relocation(conn, new_root) {
+        Ok(plan)=>plan,
+        Err(e)=>{
+            println!("{}", e);
+            exit(1);
+        }
+    };
+    println!("Moving the library from {} to {}", plan.from.display(), plan.to.display());
+    for entry in &plan.entries {
+        println!("  {}", entry.to_string_lossy());
+    }
+    println!("{} stored paths are made relative to the media root", plan.path_updates.len());
+    if !plan.missing_files.is_empty() {
+        println!("{} stored files don't exist and stay missing:", plan.missing_files.len());
+        for missing_file in &plan.missing_files {
+            println!("  {}", missing_file);
+        }
+    }
+    if dry_run {
+        println!("Dry run, nothing was changed");
+        return;
+    }
+    println!("Should the library be moved?");
+    if ask_for_confirmation().is_err() {
+        return;
+    }
+    match LibraryService::relocate(conn, &plan) {
+        Ok(..)=>println!("Library moved. Set MEDIA_ROOT to {} before starting PodFetch again",
+                         plan.to.display()),
+        Err(e)=>{
+            println!("{}", e);
+            exit(1);
+        }
+    }
+}
+
+fn reorganize_library(dry_run: bool) {
+    let conn = &mut establish_connection();
+    let plan = match LibraryService::plan_reorganization(conn) {
+        Ok(plan)=>plan,
+        Err(e)=>{
+            println!("{}", e);
+            exit(1);
+        }
+    };
+    if plan.podcasts.is_empty() {
+        println!("All files already match the naming settings");
+        return;
+    }
+    for podcast in &plan.podcasts {
+        println!("{}", podcast.podcast_name);
+        let changes = podcast.directory.iter()
+            .chain(podcast.image.iter())
+            .chain(podcast.episodes.iter()
+                .flat_map(|episode| std::iter::once(&episode.file).chain(episode.image.iter())));
+        for change in changes {
+            println!("  - {}", change.from);
+            println!("  + {}", change.to);
+        }
+    }
+    let collisions = plan.podcasts.iter()
+        .filter(|podcast| podcast.collision)
+        .count() + plan.podcasts.iter()
+        .flat_map(|podcast| &podcast.episodes)
+        .filter(|episode| episode.collision)
+        .count();
+    if collisions > 0 {
+        println!("{} directories were already taken and got a numeric suffix", collisions);
+    }
+    if dry_run {
+        println!("Dry run, nothing was changed");
+        return;
+    }
+    println!("Should the files be moved?");
+    if ask_for_confirmation().is_err() {
+        return;
+    }
+    let result = LibraryService::reorganize(conn, &plan);
+    println!("Moved {} podcasts and {} episodes", result.moved_podcasts, result.moved_episodes);
+    if !result.failures.is_empty() {
+        println!("{} files couldn't be moved and kept their path:", result.failures.len());
+        for failure in &result.failures {
+            println!("  {} ({}): {}", failure.name, failure.path, failure.error);
+        }
+        exit(1);
+    }
+}
+
+fn check_library(repair: bool, delete_orphans: bool) {
+    let conn = &mut establish_connection();
+    let report = match LibraryService::check_library(conn) {
+        Ok(report)=>report,
+        Err(e)=>{
+            println!("{}", e);
+            exit(1);
+        }
+    };
+    println!("Checked {} downloaded episodes and {} files", report.checked_episodes,
+             report.checked_files);
+    if report.issues.is_empty() {
+        println!("No issues found");
+        return;
+    }
+    for issue in &report.issues {
+        println!("  {}", describe_library_issue(issue));
+    }
+    if !repair {
+        println!("{} issues found, run the check with --repair to fix them", report.issues.len());
+        exit(1);
+    }
+    match delete_orphans {
+        true=>println!("Should the issues be repaired? Orphans are deleted."),
+        false=>println!("Should the issues be repaired? Orphans are moved to the quarantine \
+        directory.")
+    }
+    if ask_for_confirmation().is_err() {
+        return;
+    }
+    let result = LibraryService::repair_library(conn, &report, delete_orphans);
+    for library_repair in &result.repairs {
+        let action = match library_repair.action {
+            LibraryRepairAction::Redownloaded=>"downloaded again",
+            LibraryRepairAction::StatusReset=>"couldn't be downloaded, marked as not downloaded",
+            LibraryRepairAction::ArtworkDownloaded=>"artwork downloaded again",
+            LibraryRepairAction::Quarantined=>"moved to the quarantine directory",
+            LibraryRepairAction::Deleted=>"deleted"
+        };
+        println!("  {}: {}", library_repair.issue.path, action);
+    }
+    println!("Repaired {} issues", result.repairs.len());
+    if !result.failures.is_empty() {
+        println!("{} issues couldn't be repaired:", result.failures.len());
+        for failure in &result.failures {
+            println!("  {}: {}", failure.issue.path, failure.error);
+        }
+        exit(1);
+    }
+}
+
+fn describe_library_issue(issue: &LibraryIssue) -> String {
+    let kind = match issue.kind {
+        LibraryIssueKind::MissingFile=>"Missing file",
+        LibraryIssueKind::EmptyFile=>"Empty file",
+        LibraryIssueKind::TruncatedFile=>"Truncated file",
+        LibraryIssueKind::MissingArtwork=>"Missing artwork",
+        LibraryIssueKind::OrphanedFile=>"Orphaned file",
+        LibraryIssueKind::OrphanedDirectory=>"Orphaned directory"
+    };
+    let mut description = format!("{}: {}", kind, issue.path);
+    if let Some(name) = &issue.name {
+        description.push_str(&format!(" ({})", name));
+    }
+    if let Some(details) = &issue.details {
+        description.push_str(&format!(", {}", details));
+    }
+    description
+}
+
 fn list_users() -> Vec<UserWithoutPassword> {
     let users = User::find_all_users(&mut establish_connection());
 
@@ -213,10 +446,10 @@ fn list_users() -> Vec<UserWithoutPassword> {
 
 pub fn read_user_account()->User{
     let mut username = String::new();
-    let password;
+    
 
     let role = Role::VALUES.map(|v|{
-        return v.to_string()
+        v.to_string()
     }).join(", ");
     retry_read("Enter your username: ", &mut username);
 
@@ -225,27 +458,27 @@ pub fn read_user_account()->User{
         println!("User already exists");
         exit(1);
     }
-    password = retry_read_secret("Enter your password: ");
+    let password = retry_read_secret("Enter your password: ");
     let assigned_role = retry_read_role(&format!("Select your role {}",&role));
 
-    let user = User{
+    
+
+    User{
         id: 0,
         username: trim_string(username.clone()),
         role: assigned_role.to_string(),
         password: Some(trim_string(password)),
         explicit_consent: false,
         created_at: get_current_timestamp_str(),
-    };
-
-    user
+    }
 }
 
 pub fn retry_read(prompt: &str, input: &mut String){
     println!("{}",prompt);
     stdin().read_line(input).unwrap();
-    match  input.len()>0{
+    match  !input.is_empty(){
         true => {
-            if input.trim().len()==0{
+            if input.trim().is_empty(){
                 retry_read(prompt, input);
             }
         }
@@ -260,9 +493,9 @@ pub fn retry_read_secret(prompt: &str)->String{
     println!("{}",prompt);
     stdout().flush().unwrap();
     let input = read_password().unwrap();
-    match  input.len()>0{
+    match  !input.is_empty(){
         true => {
-            if input.trim().len()==0{
+            if input.trim().is_empty(){
                 retry_read_secret(prompt);
             }
         }
@@ -281,7 +514,7 @@ pub fn retry_read_role(prompt: &str)->Role{
     match res{
         Err(..)=> {
             println!("Error setting role. Please choose one of the possible roles.");
-            return retry_read_role(prompt);
+            retry_read_role(prompt)
         }
         Ok(..)=>{
             res.unwrap()
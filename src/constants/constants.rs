@@ -2,7 +2,6 @@ use std::fmt;
 use std::fmt::Formatter;
 use std::str::FromStr;
 
-pub static ITUNES_URL: &str = "https://itunes.apple.com/search?term=";
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum PodcastType {
@@ -88,15 +87,91 @@ pub const BASIC_AUTH:&str = "BASIC_AUTH";
 
 pub const USERNAME:&str = "USERNAME";
 pub const PASSWORD:&str = "PASSWORD";
+pub const DATABASE_URL:&str = "DATABASE_URL";
 
 
 pub const STANDARD_USER: &str = "user123";
 
 
-pub const ERR_SETTINGS_FORMAT: &str = "A podcast/episode format needs to contain an opening and \
-closing bracket ({}).";
+// Filesystems allow 255 bytes per name, the rest is left for the suffix of duplicate names
+pub const MAX_DIRECTORY_NAME_LENGTH: usize = 200;
+pub const NAMING_PREVIEW_LIMIT: i64 = 10;
 
 pub const PODCAST_FILENAME: &str = "podcast";
 pub const PODCAST_IMAGENAME:&str = "image";
-
-pub const POLLING_INTERVAL: u32 = 300;
\ No newline at end of file
+pub const DEFAULT_PODCAST_SUFFIX: &str = "mp3";
+pub const DEFAULT_IMAGE_SUFFIX: &str = "jpg";
+
+pub const FEED_ACCEPT_HEADER: &str = "application/rss+xml,application/atom+xml,application/feed+json,\
+application/xml,application/json";
+
+pub const POLLING_INTERVAL: u32 = 300;
+
+pub const MAX_FEED_PAGES: u32 = 50;
+
+
+pub const CREDENTIAL_ENCRYPTION_KEY: &str = "CREDENTIAL_ENCRYPTION_KEY";
+pub const CREDENTIAL_KEY_FILE: &str = "./db/credentials.key";
+
+pub const DEFAULT_USER_AGENT: &str = concat!("PodFetch/", env!("CARGO_PKG_VERSION"),
+" (+https://github.com/SamTV12345/PodFetch)");
+pub const CONNECT_TIMEOUT: u64 = 10;
+pub const READ_TIMEOUT: u64 = 60;
+pub const MAX_REDIRECTS: usize = 10;
+pub const MAX_RESPONSE_SIZE: u64 = 50 * 1024 * 1024;
+pub const MAX_DOWNLOAD_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+pub const DEFAULT_SEARCH_PAGE_SIZE: i64 = 20;
+pub const MAX_SEARCH_PAGE_SIZE: i64 = 100;
+
+pub const DEFAULT_ITUNES_API_URL: &str = "https://itunes.apple.com";
+pub const DEFAULT_PODINDEX_API_URL: &str = "https://api.podcastindex.org/api/1.0";
+pub const DEFAULT_FYYD_API_URL: &str = "https://api.fyyd.de/0.2";
+pub const DEFAULT_GPODDER_DIRECTORY_URL: &str = "https://gpodder.net";
+pub const DEFAULT_TRENDING_LIMIT: u32 = 20;
+pub const MAX_TRENDING_LIMIT: u32 = 100;
+
+pub const DEFAULT_RECOMMENDATION_LIMIT: usize = 20;
+pub const MAX_RECOMMENDATION_LIMIT: usize = 100;
+
+// Smart playlists without a limit stop at this many episodes
+pub const MAX_SMART_PLAYLIST_SIZE: i32 = 500;
+
+// An episode counts as played once this share of it was listened to
+pub const PLAYED_THRESHOLD_PERCENT: i32 = 95;
+
+pub const STATS_TOP_LIMIT: usize = 10;
+pub const WRAPPED_TOP_LIMIT: usize = 5;
+// Listening time between two watch logs can't exceed the elapsed time at this playback rate,
+// anything beyond that was skipped
+pub const MAX_PLAYBACK_RATE: i64 = 3;
+// The first watch log of an episode has no earlier log to compare with
+pub const MAX_UNTRACKED_LISTENING_SECONDS: i64 = 60;
+
+pub const DEFAULT_PLAYBACK_SPEED: f32 = 1.0;
+pub const MIN_PLAYBACK_SPEED: f32 = 0.25;
+pub const MAX_PLAYBACK_SPEED: f32 = 4.0;
+// The volume in percent, the UI boosts quiet shows up to 300 percent
+pub const DEFAULT_VOLUME: i32 = 100;
+pub const MAX_VOLUME: i32 = 300;
+// Skipping more than this many seconds at the start or end is most likely a mistake
+pub const MAX_SKIP_SECONDS: i32 = 3600;
+
+pub const MAX_BOOKMARK_NOTE_LENGTH: usize = 10000;
+
+pub const DEFAULT_SHARE_VALIDITY_DAYS: i64 = 7;
+pub const MAX_SHARE_VALIDITY_DAYS: i64 = 365;
+// The media url on a share page is signed and only valid for this long, reloading the page
+// creates a new one
+pub const SHARE_MEDIA_URL_VALIDITY_SECONDS: i64 = 6 * 60 * 60;
+
+pub const DEFAULT_MEDIA_ROOT: &str = "podcasts";
+// Downloaded files are served below this path of the server url, wherever the media root is
+pub const MEDIA_URL_PATH: &str = "podcasts";
+pub const S3_SECRET_ACCESS_KEY: &str = "S3_SECRET_ACCESS_KEY";
+pub const DEFAULT_S3_REGION: &str = "us-east-1";
+// Every part of a multipart upload except the last one has to be at least 5 MiB
+pub const S3_UPLOAD_PART_SIZE: usize = 8 * 1024 * 1024;
+pub const DEFAULT_PRESIGNED_URL_EXPIRY: u64 = 60 * 60;
+// Presigned urls of S3 can't be valid for longer than a week
+pub const MAX_PRESIGNED_URL_EXPIRY: u64 = 7 * 24 * 60 * 60;
@@ -1,12 +1,6 @@
 use crate::models;
-use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
-use actix_web::HttpResponse;
-use futures::future::LocalBoxFuture;
-use models::itunes_models::{ItunesModel};
 use crate::models::podcast_episode::PodcastEpisode;
 use models::podcasts::Podcast;
-use std::future;
-use std::future::Ready;
 use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
 use utoipa::{Modify, OpenApi};
 use crate::models::dto_models::PodcastFavorUpdateModel;
@@ -23,7 +17,9 @@ use crate::models::models::PodcastAddModel;
 use crate::models::notification::Notification;
 use crate::models::invite::Invite;
 use crate::models::filter::Filter;
+use crate::models::feed_fetch_report::{FeedFetchReportDto, SkippedFeedItem};
 use crate::models::opml_model::OpmlModel;
+use crate::models::podcast_credential::{PodcastCredentials, PodcastCredentialsInfo};
 use crate::controllers::sys_info_controller::SysExtraInfo;
 use crate::controllers::user_controller::UserOnboardingModel;
 use crate::models::user::User;
@@ -32,6 +28,48 @@ use crate::controllers::websocket_controller::*;
 use crate::controllers::user_controller::*;
 use crate::controllers::sys_info_controller::*;
 use crate::controllers::podcast_episode_controller::*;
+use crate::controllers::search_controller::*;
+use crate::controllers::discovery_controller::*;
+use crate::controllers::recommendation_controller::*;
+use crate::controllers::tag_controller::*;
+use crate::models::tag::{Tag, TagCreateModel, TagDto};
+use crate::controllers::queue_controller::*;
+use crate::models::queue::{QueueAddModel, QueueFeedUrls, QueueItemDto, QueueOrderModel};
+use crate::controllers::playlist_controller::*;
+use crate::models::playlist::{PlaylistCreateModel, PlaylistDto, PlaylistItemDto, PlaylistOrder,
+                              SmartPlaylistRules};
+use crate::models::episode_state::{EpisodeStateBulkModel, EpisodeStateDto, EpisodeStateType,
+                                   EpisodeStateUpdateModel};
+use crate::controllers::statistics_controller::*;
+use crate::models::listening_stats::{CompletionStats, ListeningPeriod, ListeningRecord,
+                                     ListeningStats, ListeningStreaks, StatsExportFormat,
+                                     TopEpisode, TopPodcast, WrappedReport};
+use crate::controllers::playback_preference_controller::*;
+use crate::models::playback_preference::{PlaybackPreferencesDto, PlaybackPreferencesUpdateModel};
+use crate::models::podcast_dto::PodcastWithPreferences;
+use crate::controllers::bookmark_controller::*;
+use crate::models::bookmark::{BookmarkCreateModel, BookmarkDto, BookmarkExportFormat};
+use crate::controllers::personal_data_controller::*;
+use crate::models::personal_data::PersonalDataExport;
+use crate::controllers::share_controller::*;
+use crate::controllers::media_controller::*;
+use crate::controllers::library_controller::*;
+use crate::controllers::cleanup_controller::*;
+use crate::models::cleanup::{CleanupEpisode, CleanupPolicyDto, CleanupReason, CleanupReport,
+                             PinnedUpdateModel};
+use crate::models::episode_share::{EpisodeShareCreateModel, EpisodeShareDto};
+use crate::models::library_reorganization::{EpisodeReorganization, PathChange,
+                                            PodcastReorganization, ReorganizationFailure,
+                                            ReorganizationPlan, ReorganizationResult};
+use crate::models::library_check::{LibraryCheckReport, LibraryIssue, LibraryIssueKind,
+                                   LibraryRepair, LibraryRepairAction, LibraryRepairFailure,
+                                   LibraryRepairModel, LibraryRepairResult};
+use crate::models::recommendation::{EpisodeRecommendation, PodcastRecommendation,
+                                    RecommendationReason, RecommendationReasonType,
+                                    Recommendations};
+use crate::models::discovered_podcast::{DiscoveredPodcast, DiscoveryProviderInfo};
+use crate::models::search::{EpisodeSearchResult, PodcastSearchResult};
+use crate::service::search_service::{EpisodeSearchPage, PodcastSearchPage};
 
 #[derive(OpenApi)]
 #[openapi(
@@ -54,15 +92,50 @@ paths(
     get_last_watched,update_role,create_invite,get_invites,get_invite,
     get_unread_notifications,login,get_info,get_users,get_user,
     dismiss_notifications,get_public_config,onboard_user,
-    get_watchtime,get_timeline,download_podcast_episodes_of_podcast,update_name,get_sys_info,
+    get_watchtime,get_timeline,download_podcast_episodes_of_podcast,update_name,preview_name,get_sys_info,
     get_filter,search_podcasts,add_podcast_by_feed,refresh_all_podcasts,update_active_podcast,
-delete_podcast,proxy_podcast
+delete_podcast,proxy_podcast,get_fetch_report,fetch_podcast_history,get_podcast_credentials,
+update_podcast_credentials,delete_podcast_credentials,update_podcast_proxy,update_podcast_metadata_tags,search_episodes,
+search_podcasts_full_text,get_discovery_providers,search_discovery_provider,get_trending_podcasts,
+lookup_discovered_podcast,subscribe_discovered_podcast,get_recommendations,
+get_tags,create_tag,update_tag,delete_tag,add_podcast_to_tag,remove_podcast_from_tag,
+get_queue,add_to_queue,clear_queue,reorder_queue,remove_from_queue,move_to_top_of_queue,
+get_queue_auto_add_podcasts,add_queue_auto_add_podcast,remove_queue_auto_add_podcast,
+get_queue_feed_urls,reset_queue_feed_urls,get_rss_feed_for_queue,get_m3u_playlist_for_queue,
+get_playlists,create_playlist,get_playlist,update_playlist,delete_playlist,get_playlist_episodes,
+add_episode_to_playlist,remove_episode_from_playlist,reset_playlist_feed_urls,
+get_rss_feed_for_playlist,get_m3u_playlist_for_playlist,get_pls_playlist_for_playlist,
+get_episode_states_of_podcast,get_episode_state,update_episode_state,update_episode_states,
+get_listening_stats,get_instance_listening_stats,get_wrapped,export_listening_stats,
+get_all_playback_preferences,get_playback_preferences,update_playback_preferences,
+reset_playback_preferences,get_bookmarks,export_bookmarks,update_bookmark,delete_bookmark,
+get_episode_bookmarks,create_bookmark,export_personal_data,get_shares,create_share,revoke_share,
+get_share_page,get_share_media,get_media,get_reorganization_plan,reorganize_library,check_library,
+repair_library,
+get_cleanup_policy,update_cleanup_policy,get_podcast_cleanup_policy,update_podcast_cleanup_policy,
+delete_podcast_cleanup_policy,preview_cleanup,pin_episode
 ),
 components(
-schemas(Podcast, PodcastEpisode, ItunesModel, PodcastHistoryItem,PodcastFavorUpdateModel,
+schemas(Podcast, PodcastEpisode, PodcastHistoryItem,PodcastFavorUpdateModel,
 PodcastWatchedEpisodeModel, PodcastWatchedPostModel, PodcastAddModel,Notification, Setting,
 Invite,
-Filter,OpmlModel,DeletePodcast, UpdateNameSettings,SysExtraInfo,UserOnboardingModel,User,InvitePostModel)
+Filter,OpmlModel,DeletePodcast, UpdateNameSettings,NamingPreview,SysExtraInfo,UserOnboardingModel,User,InvitePostModel,
+FeedFetchReportDto,SkippedFeedItem,PodcastCredentials,PodcastCredentialsInfo,
+PodcastProxyModel,PodcastMetadataTagsModel,EpisodeSearchPage,EpisodeSearchResult,PodcastSearchPage,PodcastSearchResult,DiscoveredPodcast,DiscoveryProviderInfo,DiscoverySubscribeModel,
+Recommendations,EpisodeRecommendation,PodcastRecommendation,RecommendationReason,RecommendationReasonType,
+Tag,TagDto,TagCreateModel,
+QueueItemDto,QueueAddModel,QueueOrderModel,QueueFeedUrls,
+PlaylistDto,PlaylistItemDto,PlaylistCreateModel,SmartPlaylistRules,PlaylistOrder,
+EpisodeStateDto,EpisodeStateUpdateModel,EpisodeStateBulkModel,EpisodeStateType,
+ListeningStats,ListeningPeriod,TopPodcast,TopEpisode,CompletionStats,ListeningStreaks,WrappedReport,
+ListeningRecord,StatsExportFormat,
+PlaybackPreferencesDto,PlaybackPreferencesUpdateModel,PodcastWithPreferences,
+BookmarkDto,BookmarkCreateModel,BookmarkExportFormat,PersonalDataExport,
+EpisodeShareDto,EpisodeShareCreateModel,
+ReorganizationPlan,PodcastReorganization,EpisodeReorganization,PathChange,ReorganizationResult,
+ReorganizationFailure,LibraryCheckReport,LibraryIssue,LibraryIssueKind,LibraryRepairModel,
+LibraryRepairResult,LibraryRepair,LibraryRepairAction,LibraryRepairFailure,
+CleanupPolicyDto,CleanupReport,CleanupEpisode,CleanupReason,PinnedUpdateModel)
 ),
 tags(
 (name = "podcasts", description = "Podcast management endpoints."),
@@ -71,6 +144,18 @@ tags(
 (name = "notifications", description = "Notification management endpoints."),
 (name = "settings", description = "Settings management endpoints. Settings are globally scoped."),
 (name = "info", description = "Gets multiple  information about your installation."),
+(name = "search", description = "Full text search over podcasts and episodes."),
+(name = "discovery", description = "Searches podcast directories like iTunes or Podcast Index."),
+(name = "recommendations", description = "Recommendations based on the listening history."),
+(name = "tags", description = "User defined tags and folders for podcasts."),
+(name = "queue", description = "The \"Up Next\" queue of a user and its private feeds."),
+(name = "playlists", description = "Manual and smart playlists of a user and their private feeds."),
+(name = "statistics", description = "Listening statistics and the yearly review."),
+(name = "playback", description = "Playback preferences of a user per podcast."),
+(name = "bookmarks", description = "Timestamped bookmarks and clips of a user."),
+(name = "shares", description = "Public links to single episodes for people without an account."),
+(name = "library", description = "Maintenance of the downloaded files."),
+(name = "cleanup", description = "Policies for deleting downloaded episodes."),
 
 ),
 modifiers(&SecurityAddon)
@@ -88,124 +173,3 @@ impl Modify for SecurityAddon {
         )
     }
 }
-
-pub struct ApiKeyMiddleware<S> {
-    service: S,
-    log_only: bool,
-}
-
-const API_KEY_NAME: &str = "todo_apikey";
-const API_KEY: &str = "utoipa-rocks";
-
-struct RequireApiKey;
-
-impl<S> Transform<S, ServiceRequest> for RequireApiKey
-where
-    S: Service<
-        ServiceRequest,
-        Response = ServiceResponse<actix_web::body::BoxBody>,
-        Error = actix_web::Error,
-    >,
-    S::Future: 'static,
-{
-    type Response = ServiceResponse<actix_web::body::BoxBody>;
-    type Error = actix_web::Error;
-    type Transform = ApiKeyMiddleware<S>;
-    type InitError = ();
-    type Future = Ready<Result<Self::Transform, Self::InitError>>;
-
-    fn new_transform(&self, service: S) -> Self::Future {
-        future::ready(Ok(ApiKeyMiddleware {
-            service,
-            log_only: false,
-        }))
-    }
-}
-
-/// Log api key middleware only logs about missing or invalid api keys
-struct LogApiKey;
-
-impl<S> Transform<S, ServiceRequest> for LogApiKey
-where
-    S: Service<
-        ServiceRequest,
-        Response = ServiceResponse<actix_web::body::BoxBody>,
-        Error = actix_web::Error,
-    >,
-    S::Future: 'static,
-{
-    type Response = ServiceResponse<actix_web::body::BoxBody>;
-    type Error = actix_web::Error;
-    type Transform = ApiKeyMiddleware<S>;
-    type InitError = ();
-    type Future = Ready<Result<Self::Transform, Self::InitError>>;
-
-    fn new_transform(&self, service: S) -> Self::Future {
-        future::ready(Ok(ApiKeyMiddleware {
-            service,
-            log_only: true,
-        }))
-    }
-}
-impl<S> Service<ServiceRequest> for ApiKeyMiddleware<S>
-where
-    S: Service<
-        ServiceRequest,
-        Response = ServiceResponse<actix_web::body::BoxBody>,
-        Error = actix_web::Error,
-    >,
-    S::Future: 'static,
-{
-    type Response = ServiceResponse<actix_web::body::BoxBody>;
-    type Error = actix_web::Error;
-    type Future = LocalBoxFuture<'static, Result<Self::Response, actix_web::Error>>;
-
-    fn poll_ready(
-        &self,
-        ctx: &mut core::task::Context<'_>,
-    ) -> std::task::Poll<Result<(), Self::Error>> {
-        self.service.poll_ready(ctx)
-    }
-
-    fn call(&self, req: ServiceRequest) -> Self::Future {
-        let response = |req: ServiceRequest, response: HttpResponse| -> Self::Future {
-            Box::pin(async { Ok(req.into_response(response)) })
-        };
-
-        match req.headers().get(API_KEY_NAME) {
-            Some(key) if key != API_KEY => {
-                if self.log_only {
-                    log::debug!("Incorrect api api provided!!!")
-                } else {
-                    return response(
-                        req,
-                        HttpResponse::Unauthorized().json(String::from("incorrect api key")),
-                    );
-                }
-            }
-            None => {
-                if self.log_only {
-                    log::debug!("Missing api key!!!")
-                } else {
-                    return response(
-                        req,
-                        HttpResponse::Unauthorized().json(String::from("missing api key")),
-                    );
-                }
-            }
-            _ => (), // just passthrough
-        }
-
-        if self.log_only {
-            log::debug!("Performing operation")
-        }
-
-        let future = self.service.call(req);
-
-        Box::pin(async move {
-            let response = future.await?;
-
-            Ok(response)
-        })
-    }
-}
@@ -0,0 +1,142 @@
+use actix_web::web::{Data, Path, Query};
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder, ResponseError};
+use std::sync::Mutex;
+use crate::DbPool;
+use crate::models::bookmark::{BookmarkCreateModel, BookmarkExportFormat};
+use crate::models::user::User;
+use crate::mutex::LockResultExt;
+use crate::service::bookmark_service::BookmarkService;
+use crate::service::mapping_service::MappingService;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkQuery {
+    podcast_id: Option<i32>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BookmarkExportQuery {
+    format: Option<BookmarkExportFormat>
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("podcastId", description="Only the bookmarks of this podcast")),
+responses(
+(status = 200, description = "Gets the bookmarks of the user across all podcasts, newest \
+first.", body = [BookmarkDto])),
+tag="bookmarks"
+)]
+#[get("/bookmarks")]
+pub async fn get_bookmarks(query: Query<BookmarkQuery>, conn: Data<DbPool>,
+                           mapping_service: Data<Mutex<MappingService>>,
+                           requester: Option<web::ReqData<User>>) -> impl Responder {
+    let bookmarks = BookmarkService::get_bookmarks(&mut conn.get().unwrap(),
+                                                   &requester.unwrap().username,
+                                                   query.podcast_id,
+                                                   &mapping_service.lock().ignore_poison());
+    HttpResponse::Ok().json(bookmarks)
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("format", description="markdown or json, defaults to markdown")),
+responses(
+(status = 200, description = "Exports the bookmarks of the user grouped by podcast and \
+episode.")),
+tag="bookmarks"
+)]
+#[get("/bookmarks/export")]
+pub async fn export_bookmarks(query: Query<BookmarkExportQuery>, conn: Data<DbPool>,
+                              mapping_service: Data<Mutex<MappingService>>,
+                              requester: Option<web::ReqData<User>>) -> impl Responder {
+    let format = query.format.unwrap_or(BookmarkExportFormat::Markdown);
+    let (content_type, extension) = match format {
+        BookmarkExportFormat::Markdown => ("text/markdown; charset=utf-8", "md"),
+        BookmarkExportFormat::Json => ("application/json", "json")
+    };
+    let export = BookmarkService::export(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                         format, &mapping_service.lock().ignore_poison());
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(("Content-Disposition",
+                        format!("attachment; filename=\"bookmarks.{}\"", extension)))
+        .body(export)
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=BookmarkCreateModel,
+responses(
+(status = 200, description = "Updates the position, end position and note of a bookmark.", body =
+BookmarkDto)),
+tag="bookmarks"
+)]
+#[put("/bookmarks/{id}")]
+pub async fn update_bookmark(id: Path<i32>, bookmark: web::Json<BookmarkCreateModel>,
+                             conn: Data<DbPool>, mapping_service: Data<Mutex<MappingService>>,
+                             requester: Option<web::ReqData<User>>) -> impl Responder {
+    match BookmarkService::update_bookmark(&mut conn.get().unwrap(),
+                                           &requester.unwrap().username, id.into_inner(),
+                                           &bookmark, &mapping_service.lock().ignore_poison()) {
+        Ok(bookmark) => HttpResponse::Ok().json(bookmark),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Deletes a bookmark.")),
+tag="bookmarks"
+)]
+#[delete("/bookmarks/{id}")]
+pub async fn delete_bookmark(id: Path<i32>, conn: Data<DbPool>,
+                             requester: Option<web::ReqData<User>>) -> impl Responder {
+    match BookmarkService::delete_bookmark(&mut conn.get().unwrap(),
+                                           &requester.unwrap().username, id.into_inner()) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("id", description="The episode id (uuid) of the podcast episode")),
+responses(
+(status = 200, description = "Gets the bookmarks of the user in an episode ordered by \
+position.", body = [BookmarkDto])),
+tag="bookmarks"
+)]
+#[get("/podcast/episode/{id}/bookmarks")]
+pub async fn get_episode_bookmarks(id: Path<String>, conn: Data<DbPool>,
+                                   mapping_service: Data<Mutex<MappingService>>,
+                                   requester: Option<web::ReqData<User>>) -> impl Responder {
+    match BookmarkService::get_bookmarks_of_episode(&mut conn.get().unwrap(),
+                                                    &requester.unwrap().username, &id,
+                                                    &mapping_service.lock().ignore_poison()) {
+        Ok(bookmarks) => HttpResponse::Ok().json(bookmarks),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("id", description="The episode id (uuid) of the podcast episode")),
+request_body=BookmarkCreateModel,
+responses(
+(status = 200, description = "Bookmarks a position in an episode. Bookmarks with an end \
+position are clips.", body = BookmarkDto)),
+tag="bookmarks"
+)]
+#[post("/podcast/episode/{id}/bookmarks")]
+pub async fn create_bookmark(id: Path<String>, bookmark: web::Json<BookmarkCreateModel>,
+                             conn: Data<DbPool>, mapping_service: Data<Mutex<MappingService>>,
+                             requester: Option<web::ReqData<User>>) -> impl Responder {
+    match BookmarkService::create_bookmark(&mut conn.get().unwrap(),
+                                           &requester.unwrap().username, &id, &bookmark,
+                                           &mapping_service.lock().ignore_poison()) {
+        Ok(bookmark) => HttpResponse::Ok().json(bookmark),
+        Err(e) => e.error_response()
+    }
+}
@@ -0,0 +1,149 @@
+use actix_web::web::{Data, Path};
+use actix_web::{delete, get, put, web, HttpResponse, Responder, ResponseError};
+use crate::DbPool;
+use crate::models::cleanup::{CleanupPolicyDto, PinnedUpdateModel};
+use crate::models::user::User;
+use crate::service::cleanup_service::CleanupService;
+use crate::service::mapping_service::MappingService;
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the cleanup policy that applies to every podcast without its \
+own policy.", body = CleanupPolicyDto)),
+tag="cleanup"
+)]
+#[get("/cleanup/policy")]
+pub async fn get_cleanup_policy(conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    if !requester.unwrap().is_admin() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match CleanupService::get_policy(&mut conn.get().unwrap(), None) {
+        Ok(policy) => HttpResponse::Ok().json(policy),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=CleanupPolicyDto,
+responses(
+(status = 200, description = "Saves the cleanup policy that applies to every podcast. The \
+maximum size limits the space of all downloads.", body = CleanupPolicyDto)),
+tag="cleanup"
+)]
+#[put("/cleanup/policy")]
+pub async fn update_cleanup_policy(policy: web::Json<CleanupPolicyDto>, conn: Data<DbPool>,
+                                   requester: Option<web::ReqData<User>>) -> impl Responder {
+    if !requester.unwrap().is_admin() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match CleanupService::save_policy(&mut conn.get().unwrap(), None, &policy) {
+        Ok(policy) => HttpResponse::Ok().json(policy),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the cleanup policy of a podcast. Empty rules are taken from \
+the global policy.", body = CleanupPolicyDto)),
+tag="cleanup"
+)]
+#[get("/podcast/{id}/cleanup/policy")]
+pub async fn get_podcast_cleanup_policy(id: Path<i32>, conn: Data<DbPool>,
+                                        requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    if !requester.unwrap().is_admin() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match CleanupService::get_policy(&mut conn.get().unwrap(), Some(id.into_inner())) {
+        Ok(policy) => HttpResponse::Ok().json(policy),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=CleanupPolicyDto,
+responses(
+(status = 200, description = "Saves the cleanup policy of a podcast. The maximum size limits the \
+space of the downloads of the podcast.", body = CleanupPolicyDto)),
+tag="cleanup"
+)]
+#[put("/podcast/{id}/cleanup/policy")]
+pub async fn update_podcast_cleanup_policy(id: Path<i32>, policy: web::Json<CleanupPolicyDto>,
+                                           conn: Data<DbPool>,
+                                           requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    if !requester.unwrap().is_admin() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match CleanupService::save_policy(&mut conn.get().unwrap(), Some(id.into_inner()), &policy) {
+        Ok(policy) => HttpResponse::Ok().json(policy),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Deletes the cleanup policy of a podcast, the global policy applies \
+again.", body = CleanupPolicyDto)),
+tag="cleanup"
+)]
+#[delete("/podcast/{id}/cleanup/policy")]
+pub async fn delete_podcast_cleanup_policy(id: Path<i32>, conn: Data<DbPool>,
+                                           requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    if !requester.unwrap().is_admin() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match CleanupService::delete_policy(&mut conn.get().unwrap(), id.into_inner()) {
+        Ok(policy) => HttpResponse::Ok().json(policy),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the episodes a cleanup would delete, why and how much space \
+it would free. Nothing is deleted.", body = CleanupReport)),
+tag="cleanup"
+)]
+#[get("/cleanup/preview")]
+pub async fn preview_cleanup(conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    if !requester.unwrap().is_admin() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match web::block(move || CleanupService::plan_cleanup(&mut conn.get().unwrap(), true)).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string())
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=PinnedUpdateModel,
+responses(
+(status = 200, description = "Pins or unpins an episode. Pinned episodes are never deleted by \
+the cleanup.", body = PodcastEpisode)),
+tag="cleanup"
+)]
+#[put("/podcast/episode/{id}/pin")]
+pub async fn pin_episode(id: Path<String>, pinned: web::Json<PinnedUpdateModel>,
+                         conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    if !requester.unwrap().is_privileged_user() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match CleanupService::set_pinned(&mut conn.get().unwrap(), &id, pinned.pinned) {
+        Ok(podcast_episode) => HttpResponse::Ok().json(MappingService::new()
+            .map_podcastepisode_to_dto(&podcast_episode)),
+        Err(e) => e.error_response()
+    }
+}
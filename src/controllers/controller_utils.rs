@@ -2,15 +2,15 @@ use serde_json::Value;
 use crate::service::environment_service::EnvironmentService;
 
 pub fn unwrap_string(value: &Value) -> String {
-    return value.to_string().replace("\"", "");
+    value.to_string().replace("\"", "")
 }
 
 pub fn unwrap_string_audio(value: &Value) -> String {
-    return match value.to_string().is_empty() {
+    match value.to_string().is_empty() {
         true => {
             let env = EnvironmentService::new();
-            let url = env.server_url.clone().to_owned() + &"ui/default.jpg".to_string();
-            url
+            
+            env.server_url.clone().to_owned() + "ui/default.jpg"
         },
         false => {
             value.to_string().replace("\"", "")
@@ -20,6 +20,6 @@ pub fn unwrap_string_audio(value: &Value) -> String {
 
 pub fn get_default_image() -> String {
     let env = EnvironmentService::new();
-    let url = env.server_url.clone().to_owned() + &"ui/default.jpg".to_string();
-    url
+    
+    env.server_url.clone().to_owned() + "ui/default.jpg"
 }
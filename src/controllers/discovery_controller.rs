@@ -0,0 +1,150 @@
+use actix::Addr;
+use utoipa::ToSchema;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{get, post, web, HttpResponse, Responder, ResponseError};
+use crate::constants::constants::{DEFAULT_TRENDING_LIMIT, MAX_TRENDING_LIMIT};
+use crate::DbPool;
+use crate::models::user::User;
+use crate::models::web_socket_message::Lobby;
+use crate::service::discovery::DiscoveryService;
+use crate::service::rust_service::PodcastService;
+
+#[derive(Debug, Deserialize)]
+pub struct DiscoverySearchModel {
+    q: String
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiscoveryTrendingModel {
+    limit: Option<u32>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiscoveryLookupModel {
+    id: String
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DiscoverySubscribeModel {
+    id: String
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets all podcast directories and whether they are configured.",
+body = [DiscoveryProviderInfo])),
+tag="discovery"
+)]
+#[get("/discovery/providers")]
+pub async fn get_discovery_providers() -> impl Responder {
+    HttpResponse::Ok().json(DiscoveryService::new().get_providers())
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("provider", description="The id of the podcast directory, e.g. itunes"),
+("q", description="The search term")),
+responses(
+(status = 200, description = "Searches a podcast directory.", body = [DiscoveredPodcast])),
+tag="discovery"
+)]
+#[get("/discovery/{provider}/search")]
+pub async fn search_discovery_provider(provider: Path<String>, query: Query<DiscoverySearchModel>,
+                                       requester: Option<web::ReqData<User>>) -> impl Responder {
+    if !requester.unwrap().is_privileged_user(){
+        return HttpResponse::Unauthorized().json("Unauthorized");
+    }
+    let discovery_service = DiscoveryService::new();
+    let provider = match discovery_service.get_provider(&provider) {
+        Ok(provider) => provider,
+        Err(e) => return HttpResponse::BadRequest().json(e)
+    };
+    match provider.search(&query.q).await {
+        Ok(podcasts) => HttpResponse::Ok().json(podcasts),
+        Err(e) => HttpResponse::BadGateway().json(e)
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("provider", description="The id of the podcast directory, e.g. podindex"),
+("limit", description="Maximum number of podcasts. At most 100.")),
+responses(
+(status = 200, description = "Gets the trending podcasts of a podcast directory.", body =
+[DiscoveredPodcast])),
+tag="discovery"
+)]
+#[get("/discovery/{provider}/trending")]
+pub async fn get_trending_podcasts(provider: Path<String>, query: Query<DiscoveryTrendingModel>,
+                                   requester: Option<web::ReqData<User>>) -> impl Responder {
+    if !requester.unwrap().is_privileged_user(){
+        return HttpResponse::Unauthorized().json("Unauthorized");
+    }
+    let discovery_service = DiscoveryService::new();
+    let provider = match discovery_service.get_provider(&provider) {
+        Ok(provider) => provider,
+        Err(e) => return HttpResponse::BadRequest().json(e)
+    };
+    if !provider.supports_trending() {
+        return HttpResponse::BadRequest().json(format!("{} has no trending podcasts",
+                                                       provider.name()));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_TRENDING_LIMIT).clamp(1, MAX_TRENDING_LIMIT);
+    match provider.trending(limit).await {
+        Ok(podcasts) => HttpResponse::Ok().json(podcasts),
+        Err(e) => HttpResponse::BadGateway().json(e)
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("provider", description="The id of the podcast directory, e.g. fyyd"),
+("id", description="The id of the podcast in the directory")),
+responses(
+(status = 200, description = "Looks up a podcast in a podcast directory.", body =
+DiscoveredPodcast)),
+tag="discovery"
+)]
+#[get("/discovery/{provider}/podcasts")]
+pub async fn lookup_discovered_podcast(provider: Path<String>, query: Query<DiscoveryLookupModel>,
+                                       requester: Option<web::ReqData<User>>) -> impl Responder {
+    if !requester.unwrap().is_privileged_user(){
+        return HttpResponse::Unauthorized().json("Unauthorized");
+    }
+    let discovery_service = DiscoveryService::new();
+    let provider = match discovery_service.get_provider(&provider) {
+        Ok(provider) => provider,
+        Err(e) => return HttpResponse::BadRequest().json(e)
+    };
+    match provider.lookup(&query.id).await {
+        Ok(Some(podcast)) => HttpResponse::Ok().json(podcast),
+        Ok(None) => HttpResponse::NotFound().json("Podcast not found"),
+        Err(e) => HttpResponse::BadGateway().json(e)
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("provider", description="The id of the podcast directory, e.g. gpodder")),
+request_body=DiscoverySubscribeModel,
+responses(
+(status = 200, description = "Subscribes to a podcast of a podcast directory.", body = Podcast)),
+tag="discovery"
+)]
+#[post("/discovery/{provider}/podcasts")]
+pub async fn subscribe_discovered_podcast(provider: Path<String>,
+                                          podcast: web::Json<DiscoverySubscribeModel>,
+                                          lobby: Data<Addr<Lobby>>, conn: Data<DbPool>,
+                                          requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    if !requester.unwrap().is_privileged_user(){
+        return HttpResponse::Unauthorized().json("Unauthorized");
+    }
+    let mut podcast_service = PodcastService::new();
+    match podcast_service.insert_discovered_podcast(&mut conn.get().unwrap(), &provider,
+                                                    &podcast.id, lobby).await {
+        Ok(podcast) => HttpResponse::Ok().json(podcast),
+        Err(e) => e.error_response()
+    }
+}
@@ -0,0 +1,98 @@
+use actix_web::web::Data;
+use actix_web::{get, post, web, HttpResponse, Responder};
+use crate::DbPool;
+use crate::models::library_check::LibraryRepairModel;
+use crate::models::user::User;
+use crate::service::library_service::LibraryService;
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the files that would be moved to match the current naming \
+settings.", body = ReorganizationPlan)),
+tag="library"
+)]
+#[get("/library/reorganize")]
+pub async fn get_reorganization_plan(conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    if !requester.unwrap().is_admin() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match web::block(move || LibraryService::plan_reorganization(&mut conn.get().unwrap())).await
+    {
+        Ok(Ok(plan)) => HttpResponse::Ok().json(plan),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(e),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string())
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Moves the downloaded files to the paths of the current naming \
+settings. Files that can't be moved keep their path and are listed as failures.",
+body = ReorganizationResult)),
+tag="library"
+)]
+#[post("/library/reorganize")]
+pub async fn reorganize_library(conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    if !requester.unwrap().is_admin() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match web::block(move || {
+        let conn = &mut conn.get().unwrap();
+        LibraryService::plan_reorganization(conn)
+            .map(|plan| LibraryService::reorganize(conn, &plan))
+    }).await {
+        Ok(Ok(result)) => HttpResponse::Ok().json(result),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(e),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string())
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Compares the downloaded episodes and images with the files in the \
+media root. Nothing is changed.", body = LibraryCheckReport)),
+tag="library"
+)]
+#[get("/library/check")]
+pub async fn check_library(conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    if !requester.unwrap().is_admin() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match web::block(move || LibraryService::check_library(&mut conn.get().unwrap())).await {
+        Ok(Ok(report)) => HttpResponse::Ok().json(report),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(e),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string())
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=LibraryRepairModel,
+responses(
+(status = 200, description = "Checks the library and repairs what it can. Episodes with missing \
+or damaged files are downloaded again, orphans are moved to the quarantine directory or \
+deleted.", body = LibraryRepairResult)),
+tag="library"
+)]
+#[post("/library/check")]
+pub async fn repair_library(options: web::Json<LibraryRepairModel>, conn: Data<DbPool>,
+                            requester: Option<web::ReqData<User>>) -> impl Responder {
+    if !requester.unwrap().is_admin() {
+        return HttpResponse::Unauthorized().finish();
+    }
+    match web::block(move || {
+        let conn = &mut conn.get().unwrap();
+        LibraryService::check_library(conn)
+            .map(|report| LibraryService::repair_library(conn, &report, options.delete_orphans))
+    }).await {
+        Ok(Ok(result)) => HttpResponse::Ok().json(result),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(e),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string())
+    }
+}
@@ -0,0 +1,17 @@
+use actix_web::web::Path;
+use actix_web::{route, HttpRequest, HttpResponse};
+use crate::service::storage::StorageService;
+
+#[utoipa::path(
+get,
+path="/podcasts/{path}",
+params(("path", description="Path of the file relative to the media root")),
+responses(
+(status = 200, description = "Serves a downloaded episode or image. Files of an object store are \
+streamed or redirected to, depending on the media delivery.")),
+tag="podcasts"
+)]
+#[route("/podcasts/{path:.*}", method = "GET", method = "HEAD")]
+pub async fn get_media(path: Path<String>, req: HttpRequest) -> HttpResponse {
+    StorageService::get_media_response(&path, &req).await
+}
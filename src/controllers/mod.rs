@@ -1,10 +1,24 @@
 pub mod api_doc;
+pub mod bookmark_controller;
+pub mod cleanup_controller;
 pub mod controller_utils;
+pub mod discovery_controller;
+pub mod library_controller;
+pub mod media_controller;
 pub mod notification_controller;
+pub mod personal_data_controller;
+pub mod playback_preference_controller;
+pub mod playlist_controller;
 pub mod podcast_controller;
 pub mod podcast_episode_controller;
+pub mod queue_controller;
+pub mod recommendation_controller;
+pub mod search_controller;
 pub mod settings_controller;
+pub mod share_controller;
+pub mod statistics_controller;
 pub mod sys_info_controller;
+pub mod tag_controller;
 pub mod watch_time_controller;
 pub mod web_socket;
 pub mod websocket_controller;
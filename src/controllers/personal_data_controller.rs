@@ -0,0 +1,30 @@
+use actix_web::web::Data;
+use actix_web::{get, web, HttpResponse, Responder, ResponseError};
+use std::sync::Mutex;
+use crate::DbPool;
+use crate::models::user::User;
+use crate::mutex::LockResultExt;
+use crate::service::mapping_service::MappingService;
+use crate::service::personal_data_service::PersonalDataService;
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Exports everything stored about the user, including the watch \
+history, tags, queue, playlists, playback preferences and bookmarks.", body =
+PersonalDataExport)),
+tag="info"
+)]
+#[get("/user/export")]
+pub async fn export_personal_data(conn: Data<DbPool>,
+                                  mapping_service: Data<Mutex<MappingService>>,
+                                  requester: Option<web::ReqData<User>>) -> impl Responder {
+    match PersonalDataService::export(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                      &mapping_service.lock().ignore_poison()) {
+        Ok(export) => HttpResponse::Ok()
+            .insert_header(("Content-Disposition",
+                            "attachment; filename=\"personal-data.json\""))
+            .json(export),
+        Err(e) => e.error_response()
+    }
+}
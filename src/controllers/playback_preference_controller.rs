@@ -0,0 +1,84 @@
+use actix_web::web::{Data, Path};
+use actix_web::{delete, get, put, web, HttpResponse, Responder, ResponseError};
+use crate::DbPool;
+use crate::models::playback_preference::PlaybackPreferencesUpdateModel;
+use crate::models::user::User;
+use crate::service::playback_preference_service::PlaybackPreferenceService;
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the playback preferences the user changed, one entry per \
+podcast. Other podcasts use the defaults.", body = [PlaybackPreferencesDto])),
+tag="playback"
+)]
+#[get("/podcasts/preferences")]
+pub async fn get_all_playback_preferences(conn: Data<DbPool>,
+                                          requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    let mut preferences = PlaybackPreferenceService::get_custom_preferences(
+        &mut conn.get().unwrap(), &requester.unwrap().username)
+        .into_values()
+        .collect::<Vec<_>>();
+    preferences.sort_by_key(|preference| preference.podcast_id);
+    HttpResponse::Ok().json(preferences)
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the playback preferences of the user for a podcast.", body =
+PlaybackPreferencesDto)),
+tag="playback"
+)]
+#[get("/podcast/{id}/preferences")]
+pub async fn get_playback_preferences(id: Path<i32>, conn: Data<DbPool>,
+                                      requester: Option<web::ReqData<User>>) -> impl Responder {
+    match PlaybackPreferenceService::get_preferences(&mut conn.get().unwrap(),
+                                                     &requester.unwrap().username,
+                                                     id.into_inner()) {
+        Ok(preferences) => HttpResponse::Ok().json(preferences),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=PlaybackPreferencesUpdateModel,
+responses(
+(status = 200, description = "Saves the playback speed, volume, skipped seconds and silence \
+trimming of the user for a podcast.", body = PlaybackPreferencesDto)),
+tag="playback"
+)]
+#[put("/podcast/{id}/preferences")]
+pub async fn update_playback_preferences(id: Path<i32>,
+                                         preferences: web::Json<PlaybackPreferencesUpdateModel>,
+                                         conn: Data<DbPool>,
+                                         requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    match PlaybackPreferenceService::save_preferences(&mut conn.get().unwrap(),
+                                                      &requester.unwrap().username,
+                                                      id.into_inner(), &preferences) {
+        Ok(preferences) => HttpResponse::Ok().json(preferences),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Resets the playback preferences of the user for a podcast to the \
+defaults.", body = PlaybackPreferencesDto)),
+tag="playback"
+)]
+#[delete("/podcast/{id}/preferences")]
+pub async fn reset_playback_preferences(id: Path<i32>, conn: Data<DbPool>,
+                                        requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    match PlaybackPreferenceService::reset_preferences(&mut conn.get().unwrap(),
+                                                       &requester.unwrap().username,
+                                                       id.into_inner()) {
+        Ok(preferences) => HttpResponse::Ok().json(preferences),
+        Err(e) => e.error_response()
+    }
+}
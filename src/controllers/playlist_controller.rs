@@ -0,0 +1,166 @@
+use actix_web::web::{Data, Path};
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder, ResponseError};
+use std::sync::Mutex;
+use crate::DbPool;
+use crate::models::playlist::PlaylistCreateModel;
+use crate::models::user::User;
+use crate::mutex::LockResultExt;
+use crate::service::mapping_service::MappingService;
+use crate::service::playlist_service::PlaylistService;
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the playlists of the user.", body = [PlaylistDto])),
+tag="playlists"
+)]
+#[get("/playlists")]
+pub async fn get_playlists(conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    let playlists = PlaylistService::get_playlists(&mut conn.get().unwrap(),
+                                                   &requester.unwrap().username);
+    HttpResponse::Ok().json(playlists)
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=PlaylistCreateModel,
+responses(
+(status = 200, description = "Creates a playlist. Playlists with rules are smart playlists, the \
+others contain the given episodes.", body = PlaylistDto)),
+tag="playlists"
+)]
+#[post("/playlists")]
+pub async fn create_playlist(playlist: web::Json<PlaylistCreateModel>, conn: Data<DbPool>,
+                             requester: Option<web::ReqData<User>>) -> impl Responder {
+    match PlaylistService::create_playlist(&mut conn.get().unwrap(),
+                                           &requester.unwrap().username, &playlist) {
+        Ok(playlist) => HttpResponse::Ok().json(playlist),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets a playlist of the user.", body = PlaylistDto)),
+tag="playlists"
+)]
+#[get("/playlists/{id}")]
+pub async fn get_playlist(id: Path<i32>, conn: Data<DbPool>,
+                          requester: Option<web::ReqData<User>>) -> impl Responder {
+    match PlaylistService::get_playlist(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                        id.into_inner()) {
+        Ok(playlist) => HttpResponse::Ok().json(playlist),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=PlaylistCreateModel,
+responses(
+(status = 200, description = "Renames a playlist and replaces its rules or episodes.", body =
+PlaylistDto)),
+tag="playlists"
+)]
+#[put("/playlists/{id}")]
+pub async fn update_playlist(id: Path<i32>, playlist: web::Json<PlaylistCreateModel>,
+                             conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    match PlaylistService::update_playlist(&mut conn.get().unwrap(),
+                                           &requester.unwrap().username, id.into_inner(),
+                                           &playlist) {
+        Ok(playlist) => HttpResponse::Ok().json(playlist),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Deletes a playlist.")),
+tag="playlists"
+)]
+#[delete("/playlists/{id}")]
+pub async fn delete_playlist(id: Path<i32>, conn: Data<DbPool>,
+                             requester: Option<web::ReqData<User>>) -> impl Responder {
+    match PlaylistService::delete_playlist(&mut conn.get().unwrap(),
+                                           &requester.unwrap().username, id.into_inner()) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the episodes of a playlist in playback order. Smart \
+playlists are evaluated on every request.", body = [PlaylistItemDto])),
+tag="playlists"
+)]
+#[get("/playlists/{id}/episodes")]
+pub async fn get_playlist_episodes(id: Path<i32>, conn: Data<DbPool>,
+                                   mapping_service: Data<Mutex<MappingService>>,
+                                   requester: Option<web::ReqData<User>>) -> impl Responder {
+    match PlaylistService::get_playlist_episodes(&mut conn.get().unwrap(),
+                                                 &requester.unwrap().username, id.into_inner(),
+                                                 &mapping_service.lock().ignore_poison()) {
+        Ok(episodes) => HttpResponse::Ok().json(episodes),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("episode_id", description="The episode id (uuid) of the podcast episode")),
+responses(
+(status = 200, description = "Appends an episode to a manual playlist.")),
+tag="playlists"
+)]
+#[put("/playlists/{id}/episodes/{episode_id}")]
+pub async fn add_episode_to_playlist(path: Path<(i32, String)>, conn: Data<DbPool>,
+                                     requester: Option<web::ReqData<User>>) -> impl Responder {
+    let (id, episode_id) = path.into_inner();
+    match PlaylistService::add_episode(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                       id, &episode_id) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("episode_id", description="The episode id (uuid) of the podcast episode")),
+responses(
+(status = 200, description = "Removes an episode from a manual playlist.")),
+tag="playlists"
+)]
+#[delete("/playlists/{id}/episodes/{episode_id}")]
+pub async fn remove_episode_from_playlist(path: Path<(i32, String)>, conn: Data<DbPool>,
+                                          requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    let (id, episode_id) = path.into_inner();
+    match PlaylistService::remove_episode(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                          id, &episode_id) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Creates new RSS, M3U and PLS links of a playlist. The old links \
+stop working.", body = PlaylistDto)),
+tag="playlists"
+)]
+#[put("/playlists/{id}/feed")]
+pub async fn reset_playlist_feed_urls(id: Path<i32>, conn: Data<DbPool>,
+                                      requester: Option<web::ReqData<User>>) -> impl Responder {
+    match PlaylistService::reset_feed_urls(&mut conn.get().unwrap(),
+                                           &requester.unwrap().username, id.into_inner()) {
+        Ok(playlist) => HttpResponse::Ok().json(playlist),
+        Err(e) => e.error_response()
+    }
+}
@@ -1,26 +1,23 @@
 use crate::models::dto_models::PodcastFavorUpdateModel;
 use crate::models::models::{PodcastAddModel, PodcastInsertModel};
 use crate::models::opml_model::OpmlModel;
-use crate::models::search_type::SearchType::{ITUNES, PODINDEX};
+use crate::models::search_type::SearchType;
 use crate::models::web_socket_message::Lobby;
 use crate::service::environment_service::EnvironmentService;
 use crate::service::mapping_service::MappingService;
 use crate::service::podcast_episode_service::PodcastEpisodeService;
 use crate::service::rust_service::PodcastService;
-use crate::{DbConnection, DbPool, get_default_image, unwrap_string};
+use crate::{DbConnection, DbPool, get_default_image};
 use actix::Addr;
 use actix_web::web::{Data, Path};
-use actix_web::{get, post, put, delete, HttpRequest, error, Error};
+use actix_web::{get, post, put, delete, HttpRequest, error, Error, ResponseError};
 use actix_web::{web, HttpResponse, Responder};
 use async_recursion::async_recursion;
 use futures::{executor};
 use opml::{Outline, OPML};
-use rand::rngs::ThreadRng;
 use rand::Rng;
-use reqwest::blocking::{Client, ClientBuilder as SyncClientBuilder};
-use reqwest::{ClientBuilder as AsyncClientBuilder};
-use rss::Channel;
-use serde_json::{from_str, Value};
+use reqwest::blocking::Client;
+use serde_json::from_str;
 use std::sync::{Mutex};
 use std::thread;
 use actix_web::dev::PeerAddr;
@@ -31,18 +28,33 @@ use crate::exception::exceptions::PodFetchError;
 use crate::models::user::User;
 use crate::mutex::LockResultExt;
 use crate::service::file_service::FileService;
-use futures_util::{StreamExt};
-use reqwest::header::HeaderMap;
-use tokio::sync::mpsc;
+use reqwest::header::{CONTENT_TYPE, HeaderMap};
 use crate::models::filter::Filter;
+use crate::models::feed_fetch_report::FeedFetchReport;
+use crate::models::podcast_credential::PodcastCredentials;
+use crate::service::credential_service::{CredentialScope, CredentialService};
+use crate::service::http_client::HttpClientFactory;
+use crate::service::outbound_policy::OutboundPolicy;
+use crate::models::feed::Feed;
+use crate::service::discovery::DiscoveryService;
+use crate::service::tag_service::TagService;
+use crate::service::playback_preference_service::PlaybackPreferenceService;
+use crate::models::tag::TagsPodcast;
+use crate::models::queue::{QueueAutoAddPodcast, QueueItem};
+use crate::models::playlist::PlaylistItem;
+use crate::models::episode_state::EpisodeState;
+use crate::models::listening_stats::ListeningRollup;
+use crate::models::playback_preference::PlaybackPreference;
+use crate::models::cleanup::CleanupPolicy;
+use crate::models::bookmark::Bookmark;
+use crate::models::episode_share::EpisodeShare;
 use crate::models::podcasts::Podcast;
 use crate::models::messages::BroadcastMessage;
 use crate::models::order_criteria::{OrderCriteria, OrderOption};
 use crate::models::podcast_rssadd_model::PodcastRSSAddModel;
-use tokio_stream::wrappers::UnboundedReceiverStream;
 use crate::models::podcast_episode::PodcastEpisode;
 use crate::models::podcast_history_item::PodcastHistoryItem;
-use crate::utils::append_to_header::add_basic_auth_headers_conditionally;
+use crate::service::feed_format::parse_feed;
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -50,7 +62,8 @@ pub struct PodcastSearchModel{
     order: Option<OrderCriteria>,
     title: Option<String>,
     order_option: Option<OrderOption>,
-    favored_only: bool
+    favored_only: bool,
+    tag_id: Option<i32>
 }
 
 #[utoipa::path(
@@ -79,52 +92,44 @@ tag="podcasts"
 )]
 #[get("/podcasts/search")]
 pub async fn search_podcasts(query: web::Query<PodcastSearchModel>, conn:Data<DbPool>,
-                             _podcast_service: Data<Mutex<PodcastService>>,
                              _mapping_service:Data<Mutex<MappingService>>, requester: Option<web::ReqData<User>>)
                              ->impl Responder{
     let query = query.into_inner();
     let _order = query.order.unwrap_or(OrderCriteria::ASC);
     let _latest_pub = query.order_option.unwrap_or(OrderOption::Title);
-    let only_favored;
     let opt_filter = Filter::get_filter_by_username(requester.clone().unwrap().username.clone(),
                                                     &mut conn.get().unwrap()).await.unwrap();
 
-    match opt_filter {
-        Some(filter)=>{
-            only_favored = filter.only_favored;
-        },
-        None=>{
-            only_favored = true
-        }
-    }
+    let only_favored = match opt_filter {
+        Some(filter)=>filter.only_favored,
+        None=>true
+    };
 
     let username = requester.unwrap().username.clone();
-    let filter = Filter::new(username.clone(), query.title.clone(), _order.clone().to_bool(),Some
+    let filter = Filter::new(username.clone(), query.title.clone(), _order.to_bool(),Some
                 (_latest_pub.clone()
-                .to_string()),only_favored);
-    Filter::save_filter(filter, &mut *conn.get().unwrap()).expect("Error saving filter");
+                .to_string()),only_favored, query.tag_id);
+    Filter::save_filter(filter, &mut conn.get().unwrap()).expect("Error saving filter");
+    let tagged_podcast_ids = query.tag_id.map(|tag_id| TagService::get_podcast_ids_of_tag(
+        &mut conn.get().unwrap(), &username, tag_id));
 
     match query.favored_only {
         true => {
-            let podcasts = _podcast_service.lock().ignore_poison().search_podcasts_favored( _order
-                                                                                               .clone(), query.title,
-                                                                                   _latest_pub
-                                                                                       .clone(),
-                                                                                   _mapping_service.lock()
-                                                                                       .ignore_poison(),
-                                                                                   &mut conn.get().unwrap
-                                                                                   (),username)
+            let podcasts = PodcastService::search_podcasts_favored(_order, query.title,
+                                                                   _latest_pub.clone(),
+                                                                   _mapping_service.lock()
+                                                                       .ignore_poison(),
+                                                                   &mut conn.get().unwrap(),
+                                                                   username, tagged_podcast_ids)
                 .unwrap();
             HttpResponse::Ok().json(podcasts)
         }
         false => {
-            let podcasts = _podcast_service.lock().ignore_poison().search_podcasts( _order.clone(),
-                                                                                   _mapping_service.lock().ignore_poison(),
-                                                                                   query.title,
-                                                                                   _latest_pub
-                                                                                        .clone(),
-                                                                                   &mut conn.get().unwrap
-                                                                                   (), username)
+            let podcasts = PodcastService::search_podcasts(_order,
+                                                           _mapping_service.lock().ignore_poison(),
+                                                           query.title, _latest_pub.clone(),
+                                                           &mut conn.get().unwrap(), username,
+                                                           tagged_podcast_ids)
                 .unwrap();
             HttpResponse::Ok().json(podcasts)
         }
@@ -137,7 +142,8 @@ pub async fn search_podcasts(query: web::Query<PodcastSearchModel>, conn:Data<Db
 #[utoipa::path(
 context_path="/api/v1",
 responses(
-(status = 200, description = "Find a podcast by its collection id", body = [Podcast])
+(status = 200, description = "Find a podcast by its collection id. Includes the playback \
+preferences of the user.", body = PodcastWithPreferences)
 ),
 tag="podcasts"
 )]
@@ -145,13 +151,17 @@ tag="podcasts"
 pub async fn find_podcast_by_id(
     id: Path<String>,
     mapping_service: Data<Mutex<MappingService>>,
-    conn: Data<DbPool>
+    conn: Data<DbPool>,
+    requester: Option<web::ReqData<User>>
 ) -> impl Responder {
     let id_num = from_str::<i32>(&id).unwrap();
     let podcast = PodcastService::get_podcast(&mut conn.get().unwrap(), id_num)
         .expect("Error getting podcast");
+    let playback_preferences = PlaybackPreferenceService::get_preferences_of_podcast(
+        &mut conn.get().unwrap(), &requester.unwrap().username, id_num);
     let mapping_service = mapping_service.lock().ignore_poison();
-    let mapped_podcast = mapping_service.map_podcast_to_podcast_dto(&podcast);
+    let mapped_podcast = mapping_service.map_podcast_to_podcast_dto_with_preferences(
+        &podcast, playback_preferences);
     HttpResponse::Ok().json(mapped_podcast)
 }
 
@@ -171,24 +181,24 @@ pub async fn find_all_podcasts(
         .lock()
         .ignore_poison();
     let username = requester.unwrap().username.clone();
-    let podcasts;
+    
 
 
-    podcasts = PodcastService::get_podcasts(&mut conn.get().unwrap(), username, mapping_service).unwrap();
+    let podcasts = PodcastService::get_podcasts(&mut conn.get().unwrap(), username, mapping_service).unwrap();
     HttpResponse::Ok().json(podcasts)
 }
 
 #[utoipa::path(
 context_path="/api/v1",
 responses(
-(status = 200, description = "Finds a podcast from the itunes url.", body = [ItunesModel])
+(status = 200, description = "Searches iTunes (0) or Podcast Index (1) for podcasts.", body =
+[DiscoveredPodcast])
 ),
 tag="podcasts"
 )]
 #[get("/podcasts/{type_of}/{podcast}/search")]
 pub async fn find_podcast(
     podcast_col: Path<(i32, String)>,
-    podcast_service: Data<Mutex<PodcastService>>,
     requester: Option<web::ReqData<User>>
 ) -> impl Responder {
     if !requester.unwrap().is_privileged_user(){
@@ -196,28 +206,19 @@ pub async fn find_podcast(
     }
 
     let (type_of, podcast) = podcast_col.into_inner();
-    match type_of.try_into() {
-        Ok(ITUNES) => {
-            let mut podcast_service = podcast_service
-                .lock()
-                .ignore_poison();
-            log::debug!("Searching for podcast: {}", podcast);
-            let res = podcast_service.find_podcast(&podcast).await;
-            HttpResponse::Ok().json(res)
-        }
-        Ok(PODINDEX) => {
-            let mut environment = EnvironmentService::new();
-
-            if !environment.get_config().podindex_configured {
-                return HttpResponse::BadRequest().json("Podindex is not configured");
-            }
-            let mut podcast_service = podcast_service
-                .lock()
-                .expect("Error locking podcastservice");
-
-            HttpResponse::Ok().json(podcast_service.find_podcast_on_podindex(&podcast).await)
-        }
-        Err(_) => HttpResponse::BadRequest().json("Invalid search type"),
+    let search_type: SearchType = match type_of.try_into() {
+        Ok(search_type) => search_type,
+        Err(_) => return HttpResponse::BadRequest().json("Invalid search type"),
+    };
+    let discovery_service = DiscoveryService::new();
+    let provider = match discovery_service.get_provider(search_type.provider_id()) {
+        Ok(provider) => provider,
+        Err(e) => return HttpResponse::BadRequest().json(e),
+    };
+    log::debug!("Searching for podcast: {}", podcast);
+    match provider.search(&podcast).await {
+        Ok(podcasts) => HttpResponse::Ok().json(podcasts),
+        Err(e) => HttpResponse::BadGateway().json(e),
     }
 }
 
@@ -236,33 +237,14 @@ pub async fn add_podcast(
     if !requester.unwrap().is_privileged_user(){
         return HttpResponse::Unauthorized().json("Unauthorized");
     }
-    let client = AsyncClientBuilder::new().build().unwrap();
-    let res = client
-              .get("https://itunes.apple.com/lookup?id=".to_owned() + &track_id.track_id
-                  .to_string()+ "&entity=podcast")
-              .send()
-              .await
-              .unwrap();
-
-    let res = res.json::<Value>().await.unwrap();
 
     let mut podcast_service = PodcastService::new();
-    let mapping_service = MappingService::new();
-          podcast_service
-              .handle_insert_of_podcast(&mut conn.get().unwrap(),
-                                        PodcastInsertModel {
-                                            feed_url: unwrap_string(&res["results"][0]["feedUrl"]),
-                                            title: unwrap_string(&res["results"][0]["collectionName"]),
-                                            id: unwrap_string(&res["results"][0]["collectionId"])
-                                                .parse()
-                                                .unwrap(),
-                                            image_url: unwrap_string(&res["results"][0]["artworkUrl600"]),
-                                        },
-                                        mapping_service,
-                                        lobby,
-              )
-              .await.expect("Error handling insert of podcast");
-    HttpResponse::Ok().into()
+    match podcast_service.insert_discovered_podcast(&mut conn.get().unwrap(), "itunes",
+                                                    &track_id.track_id.to_string(), lobby)
+        .await {
+        Ok(_) => HttpResponse::Ok().into(),
+        Err(e) => e.error_response()
+    }
 }
 
 #[utoipa::path(
@@ -275,36 +257,51 @@ tag="podcasts"
 pub async fn add_podcast_by_feed(
     rss_feed: web::Json<PodcastRSSAddModel>,
     lobby: Data<Addr<Lobby>>,
-    podcast_service: Data<Mutex<PodcastService>>,
     conn: Data<DbPool>,
     requester: Option<web::ReqData<User>>) -> impl Responder {
-    let mut podcast_service = podcast_service
-        .lock()
-        .ignore_poison();
-
     if !requester.unwrap().is_privileged_user(){
         return HttpResponse::Unauthorized().json("Unauthorized");
     }
-    let client = AsyncClientBuilder::new().build().unwrap();
-    let mut header_map = HeaderMap::new();
-    add_basic_auth_headers_conditionally(rss_feed.clone().rss_feed_url, &mut header_map);
-    let result = client.get(rss_feed.clone().rss_feed_url)
-        .headers(header_map)
-        .send()
-        .await
-        .unwrap();
+    let outbound_policy = OutboundPolicy::new();
+    if let Err(e) = outbound_policy.check_url(&rss_feed.rss_feed_url) {
+        return HttpResponse::BadRequest().json(e);
+    }
+    let credential_scope = CredentialScope::new(&rss_feed.rss_feed_url,
+                                                rss_feed.credentials.clone());
+    let result = match HttpClientFactory::new()
+        .get_with_credentials_async(&rss_feed.rss_feed_url, &HeaderMap::new(), &credential_scope)
+        .await {
+        Ok(result) => result,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(format!("Error requesting feed: {}", e));
+        }
+    };
 
-    let bytes = result.bytes().await.unwrap();
-    let channel = Channel::read_from(&*bytes).unwrap();
+    let content_type = result.headers().get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| content_type.to_string());
+    let bytes = match outbound_policy.read_response_async(result).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(e);
+        }
+    };
+    let feed = match parse_feed(&bytes, content_type.as_deref()) {
+        Ok(feed) => feed,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(e);
+        }
+    };
     let num = rand::thread_rng().gen_range(100..10000000);
 
-    let res = podcast_service.handle_insert_of_podcast(
+    let res = PodcastService::new().handle_insert_of_podcast(
                         &mut conn.get().unwrap(),
                         PodcastInsertModel {
                             feed_url: rss_feed.clone().rss_feed_url.clone(),
-                            title: channel.title.clone(),
+                            title: feed.title.clone(),
                             id: num,
-                            image_url: channel.image.map(|i| i.url).unwrap_or(get_default_image()),
+                            image_url: feed.image_url.unwrap_or(get_default_image()),
+                            credentials: rss_feed.credentials.clone(),
                         },
                         MappingService::new(),
                         lobby,
@@ -327,20 +324,21 @@ pub async fn import_podcasts_from_opml(
     conn: Data<DbPool>,
     requester: Option<web::ReqData<User>>
 ) -> impl Responder {
-    if !requester.unwrap().is_privileged_user(){
+    let requester = requester.unwrap();
+    if !requester.is_privileged_user(){
         return HttpResponse::Unauthorized().json("Unauthorized");
     }
+    let username = requester.username.clone();
 
 
    spawn_blocking(move || {
-                        let rng = rand::thread_rng();
                         let environment = EnvironmentService::new();
                         let document = OPML::from_str(&opml.content).unwrap();
 
                         for outline in document.body.outlines {
-                            let client = SyncClientBuilder::new().build().unwrap();
-                            executor::block_on(insert_outline(outline.clone(), client.clone(), lobby.clone(), rng
-                                .clone(), environment.clone(), conn.clone()));
+                            let client = HttpClientFactory::new().build_sync_client();
+                            executor::block_on(insert_outline(outline.clone(), client.clone(), lobby.clone(),
+                                environment.clone(), conn.clone(), username.clone(), None));
                         }
                     });
 
@@ -387,7 +385,7 @@ fn start_download_podindex(id: i32, lobby: Data<Addr<Lobby>>, conn: &mut DbConne
     rt.block_on(async {
         let mut podcast_service = PodcastService::new();
         podcast_service
-            .insert_podcast_from_podindex(conn, id, lobby)
+            .insert_discovered_podcast(conn, "podindex", &id.to_string(), lobby)
             .await
     })
 }
@@ -472,6 +470,119 @@ pub async fn download_podcast(
     HttpResponse::Ok().json("Refreshing podcast")
 }
 
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Fetches the full history of a podcast by following the pages of \
+paged and archived feeds")),
+tag="podcasts"
+)]
+#[post("/podcast/{id}/history")]
+pub async fn fetch_podcast_history(
+    id: Path<String>,
+    lobby: Data<Addr<Lobby>>,
+    podcast_service: Data<Mutex<PodcastService>>,
+    conn: Data<DbPool>,
+    requester: Option<web::ReqData<User>>
+) -> impl Responder {
+    if !requester.unwrap().is_privileged_user(){
+        return HttpResponse::Unauthorized().json("Unauthorized");
+    }
+
+    let id_num = from_str::<i32>(&id).unwrap();
+    let mut podcast_service = podcast_service.lock()
+        .ignore_poison();
+    let podcast = podcast_service.get_podcast_by_id(&mut conn.get().unwrap(),id_num);
+    thread::spawn(move || {
+        let mut podcast_service = PodcastService::new();
+        podcast_service.fetch_full_history(podcast.clone(), lobby, &mut conn.get().unwrap());
+    });
+    HttpResponse::Ok().json("Fetching full history of podcast")
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the outcome of the last refresh of a podcast including the \
+feed items that were skipped.", body=Option<FeedFetchReportDto>)),
+tag="podcasts"
+)]
+#[get("/podcast/{id}/fetch-report")]
+pub async fn get_fetch_report(
+    id: Path<i32>,
+    conn: Data<DbPool>
+) -> impl Responder {
+    let report = FeedFetchReport::get_report_by_podcast_id(&mut conn.get().unwrap(), id
+        .into_inner());
+    HttpResponse::Ok().json(report)
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets which credentials are stored for a podcast. Secrets are never \
+returned.", body=Option<PodcastCredentialsInfo>)),
+tag="podcasts"
+)]
+#[get("/podcast/{id}/credentials")]
+pub async fn get_podcast_credentials(
+    id: Path<i32>,
+    conn: Data<DbPool>,
+    requester: Option<web::ReqData<User>>
+) -> impl Responder {
+    if !requester.unwrap().is_privileged_user(){
+        return HttpResponse::Unauthorized().json("Unauthorized");
+    }
+    let credentials = CredentialService::get_credentials(&mut conn.get().unwrap(), id
+        .into_inner());
+    HttpResponse::Ok().json(credentials.map(|credentials| credentials.to_info()))
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=PodcastCredentials,
+responses(
+(status = 200, description = "Stores the credentials of a private feed. They are used for the feed, \
+episode downloads, images and the proxy.", body=PodcastCredentialsInfo)),
+tag="podcasts"
+)]
+#[put("/podcast/{id}/credentials")]
+pub async fn update_podcast_credentials(
+    id: Path<i32>,
+    credentials: web::Json<PodcastCredentials>,
+    conn: Data<DbPool>,
+    requester: Option<web::ReqData<User>>
+) -> impl Responder {
+    if !requester.unwrap().is_privileged_user(){
+        return HttpResponse::Unauthorized().json("Unauthorized");
+    }
+    let conn = &mut *conn.get().unwrap();
+    if Podcast::get_podcast(conn, *id).is_err() {
+        return HttpResponse::NotFound().json("Podcast not found");
+    }
+    CredentialService::save_credentials(conn, id.into_inner(), &credentials);
+    HttpResponse::Ok().json(credentials.to_info())
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Removes the stored credentials of a podcast.")),
+tag="podcasts"
+)]
+#[delete("/podcast/{id}/credentials")]
+pub async fn delete_podcast_credentials(
+    id: Path<i32>,
+    conn: Data<DbPool>,
+    requester: Option<web::ReqData<User>>
+) -> impl Responder {
+    if !requester.unwrap().is_privileged_user(){
+        return HttpResponse::Unauthorized().json("Unauthorized");
+    }
+    CredentialService::delete_credentials(&mut conn.get().unwrap(), id.into_inner());
+    HttpResponse::Ok().finish()
+}
+
 #[utoipa::path(
 context_path="/api/v1",
 request_body=PodcastFavorUpdateModel,
@@ -541,19 +652,27 @@ async fn insert_outline(
     podcast: Outline,
     client: Client,
     lobby: Data<Addr<Lobby>>,
-    mut rng: ThreadRng,
     environment: EnvironmentService,
-    conn: Data<DbPool>
+    conn: Data<DbPool>,
+    username: String,
+    tag_id: Option<i32>
 ) {
-    if podcast.outlines.len() > 0 {
+    if !podcast.outlines.is_empty() {
+        // Nested outlines are folders. They become (nested) tags of the importing user.
+        let folder_name = podcast.title.clone().unwrap_or(podcast.text.clone());
+        let folder_tag_id = TagService::find_or_create_tag(&mut conn.get().unwrap(), &username,
+                                                           &folder_name, tag_id)
+            .map(|tag| tag.id)
+            .or(tag_id);
         for outline_nested in podcast.clone().outlines {
             insert_outline(
                 outline_nested,
                 client.clone(),
                 lobby.clone(),
-                rng.clone(),
                 environment.clone(),
-                conn.clone()
+                conn.clone(),
+                username.clone(),
+                folder_tag_id
             )
             .await;
         }
@@ -561,17 +680,32 @@ async fn insert_outline(
     }
     let feed_url = podcast.clone().xml_url.expect("No feed url");
 
-    let content = client.get(feed_url).send().unwrap().bytes().unwrap();
+    // Podcasts that are already in the library are only put into the folder
+    let (existing_feed_url, _) = CredentialService::split_credentials_from_url(&feed_url);
+    if let Some(existing_podcast) = Podcast::find_by_rss_feed_url(&mut conn.get().unwrap(),
+                                                                  &existing_feed_url) {
+        if let Some(tag_id) = tag_id {
+            TagsPodcast::add_podcast_to_tag(&mut conn.get().unwrap(), tag_id, existing_podcast.id);
+        }
+        lobby.do_send(BroadcastMessage{
+            type_of: PodcastType::OpmlErrored,
+            message: PodFetchError::podcast_already_exists().to_string(),
+            podcast: None,
+            podcast_episodes: None,
+            podcast_episode: None,
+        });
+        return;
+    }
 
-    let channel = Channel::read_from(&content[..]);
+    let feed = fetch_outline_feed(&client, &feed_url);
 
-    match channel{
-        Ok(channel)=>{
+    match feed{
+        Ok(feed)=>{
             let mut podcast_service = PodcastService::new();
             let mapping_service = MappingService::new();
 
-            let image_url = match channel.image {
-                Some(image) => image.url,
+            let image_url = match feed.image_url {
+                Some(image_url) => image_url,
                 None => {
                     println!("No image found for podcast. Downloading from {}",environment.server_url
                         .clone().to_owned() + "ui/default.jpg");
@@ -584,9 +718,10 @@ async fn insert_outline(
                     &mut conn.get().unwrap(),
                     PodcastInsertModel {
                         feed_url: podcast.clone().xml_url.expect("No feed url"),
-                        title: channel.title,
-                        id: rng.gen::<i32>(),
+                        title: feed.title,
+                        id: rand::thread_rng().gen::<i32>(),
                         image_url,
+                        credentials: None,
                     },
                     mapping_service,
                     lobby.clone(),
@@ -594,6 +729,10 @@ async fn insert_outline(
                 .await;
             match inserted_podcast {
                 Ok(podcast)=>{
+                    if let Some(tag_id) = tag_id {
+                        TagsPodcast::add_podcast_to_tag(&mut conn.get().unwrap(), tag_id,
+                                                        podcast.id);
+                    }
                     lobby.do_send(BroadcastMessage{
                         type_of: PodcastType::OpmlAdded,
                         message: "Refreshed podcasts".to_string(),
@@ -626,12 +765,97 @@ async fn insert_outline(
 
 
 }
+fn fetch_outline_feed(client: &Client, feed_url: &str) -> Result<Feed, String> {
+    let outbound_policy = OutboundPolicy::new();
+    outbound_policy.check_url(feed_url)?;
+    let response = client.get(feed_url)
+        .send()
+        .map_err(|e| format!("Error requesting feed: {}", e))?;
+    let content_type = response.headers().get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| content_type.to_string());
+    let content = outbound_policy.read_response(response)?;
+
+    parse_feed(&content, content_type.as_deref())
+}
+
 use utoipa::ToSchema;
 #[derive(Deserialize,ToSchema)]
 pub struct DeletePodcast {
     pub delete_files: bool
 }
 
+#[derive(Deserialize,ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PodcastProxyModel {
+    pub proxy_url: Option<String>
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=PodcastProxyModel,
+responses(
+(status = 200, description = "Sets the http or socks proxy used for all requests of a podcast. \
+Without one the global proxy is used.")),
+tag="podcasts"
+)]
+#[put("/podcast/{id}/proxy")]
+pub async fn update_podcast_proxy(
+    id: Path<i32>,
+    proxy: web::Json<PodcastProxyModel>,
+    conn: Data<DbPool>,
+    requester: Option<web::ReqData<User>>
+) -> impl Responder {
+    if !requester.unwrap().is_privileged_user(){
+        return HttpResponse::Unauthorized().json("Unauthorized");
+    }
+    let proxy_url = proxy.into_inner().proxy_url.filter(|proxy_url| !proxy_url.trim().is_empty());
+    if let Some(proxy_url) = &proxy_url {
+        if let Err(e) = HttpClientFactory::validate_proxy_url(proxy_url) {
+            return HttpResponse::BadRequest().json(e);
+        }
+    }
+    let conn = &mut *conn.get().unwrap();
+    if Podcast::get_podcast(conn, *id).is_err() {
+        return HttpResponse::NotFound().json("Podcast not found");
+    }
+    Podcast::update_proxy_url(conn, id.into_inner(), proxy_url);
+    HttpResponse::Ok().finish()
+}
+
+#[derive(Deserialize,ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PodcastMetadataTagsModel {
+    pub write_metadata_tags: Option<bool>
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=PodcastMetadataTagsModel,
+responses(
+(status = 200, description = "Sets whether metadata tags are written into the downloaded files \
+of a podcast. Without a value the setting is used.")),
+tag="podcasts"
+)]
+#[put("/podcast/{id}/metadata-tags")]
+pub async fn update_podcast_metadata_tags(
+    id: Path<i32>,
+    metadata_tags: web::Json<PodcastMetadataTagsModel>,
+    conn: Data<DbPool>,
+    requester: Option<web::ReqData<User>>
+) -> impl Responder {
+    if !requester.unwrap().is_privileged_user(){
+        return HttpResponse::Unauthorized().json("Unauthorized");
+    }
+    let conn = &mut *conn.get().unwrap();
+    if Podcast::get_podcast(conn, *id).is_err() {
+        return HttpResponse::NotFound().json("Podcast not found");
+    }
+    Podcast::update_write_metadata_tags(conn, id.into_inner(),
+                                        metadata_tags.into_inner().write_metadata_tags);
+    HttpResponse::Ok().finish()
+}
+
 #[utoipa::path(
 context_path="/api/v1",
 request_body=DeletePodcast,
@@ -647,20 +871,37 @@ pub async fn delete_podcast(data: web::Json<DeletePodcast>, db: Data<DbPool>, id
     }
 
 
-    let podcast = Podcast::get_podcast(&mut *db.get().unwrap(), id.clone()).expect("Error \
+    let podcast = Podcast::get_podcast(&mut db.get().unwrap(), *id).expect("Error \
         finding podcast");
     if data.delete_files{
-        FileService::delete_podcast_files(&podcast.directory_name);
+        let directory_name = podcast.directory_name.clone();
+        web::block(move || FileService::delete_podcast_files(&directory_name)).await
+            .expect("Error deleting podcast directory");
     }
 
-    PodcastHistoryItem::delete_watchtime(&mut *db.get().unwrap(), id.clone()).expect("Error deleting \
+    PodcastHistoryItem::delete_watchtime(&mut db.get().unwrap(), *id).expect("Error deleting \
     watchtime");
-    PodcastEpisode::delete_episodes_of_podcast(&mut *db.get().unwrap(), id.clone()).expect("Error \
+    QueueItem::delete_by_podcast_id(&mut db.get().unwrap(), *id);
+    QueueAutoAddPodcast::delete_by_podcast_id(&mut db.get().unwrap(), *id);
+    PlaylistItem::delete_by_podcast_id(&mut db.get().unwrap(), *id);
+    EpisodeState::delete_by_podcast_id(&mut db.get().unwrap(), *id);
+    ListeningRollup::delete_by_podcast_id(&mut db.get().unwrap(), *id);
+    PlaybackPreference::delete_by_podcast_id(&mut db.get().unwrap(), *id);
+    CleanupPolicy::delete_by_podcast_id(&mut db.get().unwrap(), *id);
+    Bookmark::delete_by_podcast_id(&mut db.get().unwrap(), *id);
+    EpisodeShare::delete_by_podcast_id(&mut db.get().unwrap(), *id);
+    PodcastEpisode::delete_episodes_of_podcast(&mut db.get().unwrap(), *id).expect("Error \
     deleting \
     episodes of podcast");
-    Podcast::delete_podcast(&mut *db.get().unwrap(), id.clone());
+    FeedFetchReport::delete_report_by_podcast_id(&mut db.get().unwrap(), *id);
+    CredentialService::delete_credentials(&mut db.get().unwrap(), *id);
+    TagsPodcast::delete_by_podcast_id(&mut db.get().unwrap(), *id);
+    Podcast::delete_podcast(&mut db.get().unwrap(), *id);
     HttpResponse::Ok().into()
 }
+const PROXY_FORWARDED_HEADERS: [&str; 7] = ["accept", "accept-language", "range", "if-range",
+    "if-match", "if-none-match", "if-modified-since"];
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Params {
@@ -676,7 +917,6 @@ tag="podcasts"
 )]
 #[get("/proxy/podcast")]
 pub(crate) async fn proxy_podcast(
-    mut payload: web::Payload,
     params: web::Query<Params>,
     peer_addr: Option<PeerAddr>,
     method: Method,
@@ -690,41 +930,36 @@ pub(crate) async fn proxy_podcast(
         return Ok(HttpResponse::NotFound().finish());
     }
     let episode = opt_res.unwrap().unwrap();
-    let (tx, rx) = mpsc::unbounded_channel();
-
-    actix_web::rt::spawn(async move {
-        while let Some(chunk) = payload.next().await {
-            tx.send(chunk).unwrap();
-        }
-    });
-
+    if let Err(e) = OutboundPolicy::new().check_url(&episode.url) {
+        log::error!("Refusing to proxy episode {}: {}", episode.name, e);
+        return Ok(HttpResponse::Forbidden().json(e));
+    }
     let mut header_map = HeaderMap::new();
 
+    // Cookies and the authorization of the PodFetch user must not reach the podcast server
     for x in rq.headers() {
-        if x.0 == "host"||x.0 == "referer"||x.0 == "sec-fetch-site"||x.0 == "sec-fetch-mode" {
+        if !PROXY_FORWARDED_HEADERS.contains(&x.0.as_str()) {
             continue;
         }
         header_map.append(x.0.clone(), x.1.clone());
     }
 
-    add_basic_auth_headers_conditionally(episode.clone().url,&mut header_map);
     // Required to not generate a 302 redirect
     header_map.append("sec-fetch-mode", "no-cors".parse().unwrap());
     header_map.append("sec-fetch-site", "cross-site".parse().unwrap());
+    if let Some(PeerAddr(addr)) = peer_addr {
+        header_map.append("x-forwarded-for", addr.ip().to_string().parse().unwrap());
+    }
 
-    let forwarded_req = reqwest::Client::new()
-        .request(method, episode.url)
-        .headers(header_map)
-        .fetch_mode_no_cors()
-        .body(reqwest::Body::wrap_stream(UnboundedReceiverStream::new(rx)));
-
-    let forwarded_req = match peer_addr {
-        Some(PeerAddr(addr)) => forwarded_req.header("x-forwarded-for", addr.ip().to_string()),
-        None => forwarded_req,
+    // Only GET requests are proxied, they have no body
+    let (http_client_factory, credential_scope) = match Podcast::get_podcast(conn,
+                                                                             episode.podcast_id) {
+        Ok(podcast) => (HttpClientFactory::for_podcast(&podcast),
+                        CredentialService::get_credential_scope(conn, &podcast)),
+        Err(_) => (HttpClientFactory::new(), CredentialScope::default())
     };
-
-    let res = forwarded_req
-        .send()
+    let res = http_client_factory
+        .stream_with_credentials(method, &episode.url, &header_map, &credential_scope)
         .await
         .map_err(error::ErrorInternalServerError)?;
 
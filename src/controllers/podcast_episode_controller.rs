@@ -2,17 +2,20 @@ use crate::service::mapping_service::MappingService;
 use crate::service::podcast_episode_service::PodcastEpisodeService;
 use actix_web::web::{Data, Query};
 use actix_web::{get, put};
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpResponse, Responder, ResponseError};
 use serde_json::from_str;
 use std::sync::Mutex;
 use std::thread;
 use crate::db::TimelineItem;
 use crate::DbPool;
+use crate::models::episode_state::{EpisodeStateBulkModel, EpisodeStateType,
+                                   EpisodeStateUpdateModel};
 use crate::models::favorites::Favorite;
 use crate::models::podcast_episode::PodcastEpisode;
 use crate::models::podcasts::Podcast;
 use crate::models::user::User;
 use crate::mutex::LockResultExt;
+use crate::service::episode_state_service::EpisodeStateService;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OptionalId {
@@ -66,7 +69,9 @@ pub struct TimeLinePodcastItem{
 #[serde(rename_all = "camelCase")]
 pub struct TimelineQueryParams {
     pub favored_only: bool,
-    pub last_timestamp: Option<String>
+    pub last_timestamp: Option<String>,
+    pub tag_id: Option<i32>,
+    pub state: Option<EpisodeStateType>
 }
 
 #[utoipa::path(
@@ -90,11 +95,11 @@ Responder {
         let (podcast_episode, podcast, favorite) = podcast_episode.clone();
         let mapped_podcast_episode = mapping_service.map_podcastepisode_to_dto(&podcast_episode);
 
-        return TimeLinePodcastEpisode {
+        TimeLinePodcastEpisode {
             podcast_episode: mapped_podcast_episode,
             podcast,
             favorite
-        };
+        }
         }).collect::<Vec<TimeLinePodcastEpisode>>();
     HttpResponse::Ok().json(TimeLinePodcastItem{
         data: mapped_timeline,
@@ -123,20 +128,95 @@ Responder {
     thread::spawn(move || {
         let res = PodcastEpisode::get_podcast_episode_by_id(&mut conn.get().unwrap(), &id.into_inner())
             .unwrap();
-        match res {
-            Some(podcast_episode) => {
-                let podcast = Podcast::get_podcast(&mut conn.get().unwrap(),podcast_episode
-                    .podcast_id).unwrap();
-                PodcastEpisodeService::perform_download(
-                    &podcast_episode,
-                    podcast_episode.clone(),
-                    podcast,
-                    &mut conn.get().unwrap()
-                );
+        if let Some(podcast_episode) = res {
+            let podcast = Podcast::get_podcast(&mut conn.get().unwrap(),podcast_episode
+                .podcast_id).unwrap();
+            if let Err(e) = PodcastEpisodeService::perform_download(
+                &podcast_episode,
+                podcast_episode.clone(),
+                podcast,
+                &mut conn.get().unwrap()
+            ) {
+                log::error!("Error downloading episode {}: {}", podcast_episode.name, e);
             }
-            None => {}
         }
     });
 
     HttpResponse::Ok().json("Download started")
 }
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the state of every episode of a podcast for the user.", body =
+[EpisodeStateDto])),
+tag="podcast_episodes"
+)]
+#[get("/podcast/{id}/episodes/state")]
+pub async fn get_episode_states_of_podcast(id: web::Path<i32>, conn: Data<DbPool>,
+                                           requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    match EpisodeStateService::get_states_of_podcast(&mut conn.get().unwrap(),
+                                                     &requester.unwrap().username,
+                                                     id.into_inner()) {
+        Ok(episode_states) => HttpResponse::Ok().json(episode_states),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("id", description="The episode id (uuid) of the podcast episode")),
+responses(
+(status = 200, description = "Gets the state of an episode for the user.", body =
+EpisodeStateDto)),
+tag="podcast_episodes"
+)]
+#[get("/podcast/episode/{id}/state")]
+pub async fn get_episode_state(id: web::Path<String>, conn: Data<DbPool>,
+                               requester: Option<web::ReqData<User>>) -> impl Responder {
+    match EpisodeStateService::get_state(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                         &id) {
+        Ok(episode_state) => HttpResponse::Ok().json(episode_state),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=EpisodeStateUpdateModel,
+params(("id", description="The episode id (uuid) of the podcast episode")),
+responses(
+(status = 200, description = "Marks an episode as new, in progress, played or archived.", body =
+EpisodeStateDto)),
+tag="podcast_episodes"
+)]
+#[put("/podcast/episode/{id}/state")]
+pub async fn update_episode_state(id: web::Path<String>,
+                                  episode_state: web::Json<EpisodeStateUpdateModel>,
+                                  conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    match EpisodeStateService::set_state(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                         &id, episode_state.state) {
+        Ok(episode_state) => HttpResponse::Ok().json(episode_state),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=EpisodeStateBulkModel,
+responses(
+(status = 200, description = "Sets the state of many episodes, e.g. of every episode of a \
+podcast published before a date. Returns the number of episodes.", body = usize)),
+tag="podcast_episodes"
+)]
+#[put("/podcast/episodes/state")]
+pub async fn update_episode_states(filter: web::Json<EpisodeStateBulkModel>, conn: Data<DbPool>,
+                                   requester: Option<web::ReqData<User>>) -> impl Responder {
+    match EpisodeStateService::set_states(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                          &filter) {
+        Ok(updated_episodes) => HttpResponse::Ok().json(updated_episodes),
+        Err(e) => e.error_response()
+    }
+}
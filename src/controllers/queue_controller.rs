@@ -0,0 +1,187 @@
+use actix_web::web::{Data, Path};
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder, ResponseError};
+use std::sync::Mutex;
+use crate::DbPool;
+use crate::models::queue::{QueueAddModel, QueueOrderModel};
+use crate::models::user::User;
+use crate::mutex::LockResultExt;
+use crate::service::mapping_service::MappingService;
+use crate::service::queue_service::QueueService;
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the queue of the user in playback order.", body =
+[QueueItemDto])),
+tag="queue"
+)]
+#[get("/queue")]
+pub async fn get_queue(conn: Data<DbPool>, mapping_service: Data<Mutex<MappingService>>,
+                       requester: Option<web::ReqData<User>>) -> impl Responder {
+    let queue = QueueService::get_queue(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                        &mapping_service.lock().ignore_poison());
+    HttpResponse::Ok().json(queue)
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=QueueAddModel,
+responses(
+(status = 200, description = "Adds an episode to the queue. An episode that is already queued is \
+moved to the given position.")),
+tag="queue"
+)]
+#[post("/queue")]
+pub async fn add_to_queue(episode: web::Json<QueueAddModel>, conn: Data<DbPool>,
+                          requester: Option<web::ReqData<User>>) -> impl Responder {
+    match QueueService::add_episode(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                    &episode.episode_id, episode.position) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Removes all episodes from the queue.")),
+tag="queue"
+)]
+#[delete("/queue")]
+pub async fn clear_queue(conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    QueueService::clear(&mut conn.get().unwrap(), &requester.unwrap().username);
+    HttpResponse::Ok().finish()
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=QueueOrderModel,
+responses(
+(status = 200, description = "Reorders the queue. The episode ids have to be the ones of the \
+queue.")),
+tag="queue"
+)]
+#[put("/queue/order")]
+pub async fn reorder_queue(order: web::Json<QueueOrderModel>, conn: Data<DbPool>,
+                           requester: Option<web::ReqData<User>>) -> impl Responder {
+    match QueueService::reorder(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                &order.episode_ids) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("episode_id", description="The episode id (uuid) of the podcast episode")),
+responses(
+(status = 200, description = "Removes an episode from the queue.")),
+tag="queue"
+)]
+#[delete("/queue/{episode_id}")]
+pub async fn remove_from_queue(episode_id: Path<String>, conn: Data<DbPool>,
+                               requester: Option<web::ReqData<User>>) -> impl Responder {
+    match QueueService::remove_episode(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                       &episode_id) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("episode_id", description="The episode id (uuid) of the podcast episode")),
+responses(
+(status = 200, description = "Moves an episode to the top of the queue.")),
+tag="queue"
+)]
+#[put("/queue/{episode_id}/top")]
+pub async fn move_to_top_of_queue(episode_id: Path<String>, conn: Data<DbPool>,
+                                  requester: Option<web::ReqData<User>>) -> impl Responder {
+    match QueueService::move_to_top(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                    &episode_id) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the ids of the podcasts whose new episodes are added to the \
+queue.", body = [i32])),
+tag="queue"
+)]
+#[get("/queue/auto-add")]
+pub async fn get_queue_auto_add_podcasts(conn: Data<DbPool>,
+                                         requester: Option<web::ReqData<User>>) -> impl Responder {
+    let podcast_ids = QueueService::get_auto_add_podcasts(&mut conn.get().unwrap(),
+                                                          &requester.unwrap().username);
+    HttpResponse::Ok().json(podcast_ids)
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Adds new episodes of the podcast to the queue from now on.")),
+tag="queue"
+)]
+#[put("/queue/auto-add/{podcast_id}")]
+pub async fn add_queue_auto_add_podcast(podcast_id: Path<i32>, conn: Data<DbPool>,
+                                        requester: Option<web::ReqData<User>>) -> impl Responder {
+    match QueueService::set_auto_add_podcast(&mut conn.get().unwrap(),
+                                             &requester.unwrap().username,
+                                             podcast_id.into_inner(), true) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Stops adding new episodes of the podcast to the queue.")),
+tag="queue"
+)]
+#[delete("/queue/auto-add/{podcast_id}")]
+pub async fn remove_queue_auto_add_podcast(podcast_id: Path<i32>, conn: Data<DbPool>,
+                                           requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    match QueueService::set_auto_add_podcast(&mut conn.get().unwrap(),
+                                             &requester.unwrap().username,
+                                             podcast_id.into_inner(), false) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the private RSS and M3U links of the queue for podcast apps.",
+body = QueueFeedUrls)),
+tag="queue"
+)]
+#[get("/queue/feed")]
+pub async fn get_queue_feed_urls(conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    let feed_urls = QueueService::get_feed_urls(&mut conn.get().unwrap(),
+                                                &requester.unwrap().username);
+    HttpResponse::Ok().json(feed_urls)
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Creates new private links of the queue. The old links stop \
+working.", body = QueueFeedUrls)),
+tag="queue"
+)]
+#[put("/queue/feed")]
+pub async fn reset_queue_feed_urls(conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    let feed_urls = QueueService::reset_feed_urls(&mut conn.get().unwrap(),
+                                                  &requester.unwrap().username);
+    HttpResponse::Ok().json(feed_urls)
+}
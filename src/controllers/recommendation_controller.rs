@@ -0,0 +1,35 @@
+use actix_web::web::{Data, Query};
+use actix_web::{get, web, HttpResponse, Responder};
+use std::sync::Mutex;
+use crate::constants::constants::{DEFAULT_RECOMMENDATION_LIMIT, MAX_RECOMMENDATION_LIMIT};
+use crate::DbPool;
+use crate::models::user::User;
+use crate::mutex::LockResultExt;
+use crate::service::mapping_service::MappingService;
+use crate::service::recommendation_service::RecommendationService;
+
+#[derive(Debug, Deserialize)]
+pub struct RecommendationQuery {
+    limit: Option<usize>
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("limit", description="Maximum number of episodes and of podcasts. At most 100.")),
+responses(
+(status = 200, description = "Recommends unplayed episodes of the followed podcasts and similar \
+podcasts of the library. Every recommendation explains why it was made.", body =
+Recommendations)),
+tag="recommendations"
+)]
+#[get("/recommendations")]
+pub async fn get_recommendations(query: Query<RecommendationQuery>, conn: Data<DbPool>,
+                                 mapping_service: Data<Mutex<MappingService>>,
+                                 requester: Option<web::ReqData<User>>) -> impl Responder {
+    let limit = query.limit.unwrap_or(DEFAULT_RECOMMENDATION_LIMIT)
+        .clamp(1, MAX_RECOMMENDATION_LIMIT);
+    let recommendations = RecommendationService::get_recommendations(
+        &mut conn.get().unwrap(), &requester.unwrap().username, limit,
+        &mapping_service.lock().ignore_poison());
+    HttpResponse::Ok().json(recommendations)
+}
@@ -0,0 +1,94 @@
+use actix_web::web::{Data, Query};
+use actix_web::{get, web, HttpResponse, Responder};
+use chrono::{Days, NaiveDate};
+use std::sync::Mutex;
+use crate::constants::constants::DEFAULT_SEARCH_PAGE_SIZE;
+use crate::DbPool;
+use crate::models::search::EpisodeSearchFilter;
+use crate::models::user::User;
+use crate::mutex::LockResultExt;
+use crate::service::mapping_service::MappingService;
+use crate::service::search_service::SearchService;
+use crate::utils::time::get_start_of_day;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeSearchModel {
+    q: String,
+    podcast_id: Option<i32>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    downloaded: Option<bool>,
+    played: Option<bool>,
+    offset: Option<i64>,
+    limit: Option<i64>
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PodcastFullTextSearchModel {
+    q: String,
+    offset: Option<i64>,
+    limit: Option<i64>
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("q", description="The search terms. All of them have to match."),
+("podcastId", description="Only episodes of this podcast"),
+("from", description="Only episodes published on or after this date (YYYY-MM-DD, UTC)"),
+("to", description="Only episodes published on or before this date (YYYY-MM-DD, UTC)"),
+("downloaded", description="Only downloaded or only not downloaded episodes"),
+("played", description="Only episodes the user has or hasn't listened to"),
+("offset", description="Number of results to skip"),
+("limit", description="Maximum number of results. At most 100.")),
+responses(
+(status = 200, description = "Searches the names and show notes of all episodes. The results are \
+ranked by relevance.", body = EpisodeSearchPage)),
+tag="search"
+)]
+#[get("/search/episodes")]
+pub async fn search_episodes(query: Query<EpisodeSearchModel>, conn: Data<DbPool>,
+                             mapping_service: Data<Mutex<MappingService>>,
+                             requester: Option<web::ReqData<User>>) -> impl Responder {
+    let query = query.into_inner();
+    let filter = EpisodeSearchFilter {
+        podcast_id: query.podcast_id,
+        from: query.from.map(get_start_of_day),
+        // Episodes published at any time of the last day are included
+        until: query.to
+            .and_then(|to| to.checked_add_days(Days::new(1)))
+            .map(get_start_of_day),
+        downloaded: query.downloaded,
+        played: query.played,
+        username: requester.unwrap().username.clone()
+    };
+    let page = SearchService::search_episodes(&mut conn.get().unwrap(), &query.q, &filter,
+                                              query.offset.unwrap_or(0),
+                                              query.limit.unwrap_or(DEFAULT_SEARCH_PAGE_SIZE),
+                                              &mapping_service.lock().ignore_poison());
+    HttpResponse::Ok().json(page)
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("q", description="The search terms. All of them have to match."),
+("offset", description="Number of results to skip"),
+("limit", description="Maximum number of results. At most 100.")),
+responses(
+(status = 200, description = "Searches the names, authors, summaries and keywords of all \
+podcasts. The results are ranked by relevance.", body = PodcastSearchPage)),
+tag="search"
+)]
+#[get("/search/podcasts")]
+pub async fn search_podcasts_full_text(query: Query<PodcastFullTextSearchModel>,
+                                       conn: Data<DbPool>,
+                                       mapping_service: Data<Mutex<MappingService>>)
+    -> impl Responder {
+    let query = query.into_inner();
+    let page = SearchService::search_podcasts(&mut conn.get().unwrap(), &query.q,
+                                              query.offset.unwrap_or(0),
+                                              query.limit.unwrap_or(DEFAULT_SEARCH_PAGE_SIZE),
+                                              &mapping_service.lock().ignore_poison());
+    HttpResponse::Ok().json(page)
+}
@@ -1,18 +1,22 @@
 use std::str::FromStr;
 use crate::models::settings::Setting;
-use crate::service::podcast_episode_service::PodcastEpisodeService;
 use actix_web::web::{Data, Path};
-use actix_web::{get, put};
+use actix_web::{get, post, put};
 use actix_web::{web, HttpResponse, Responder};
+use std::collections::HashMap;
 use std::sync::{Mutex, MutexGuard};
 use chrono::Local;
 use xml_builder::{XMLBuilder, XMLElement, XMLVersion};
 use crate::DbPool;
 use crate::models::podcasts::Podcast;
+use crate::models::tag::TagDto;
 use crate::models::user::User;
 use crate::mutex::LockResultExt;
+use crate::service::credential_service::CredentialService;
 use crate::service::environment_service::EnvironmentService;
 use crate::service::settings_service::SettingsService;
+use crate::service::tag_service::TagService;
+use crate::service::cleanup_service::CleanupService;
 
 #[utoipa::path(
 context_path="/api/v1",
@@ -55,38 +59,26 @@ web::Json<Setting>, requester: Option<web::ReqData<User>>,conn:Data<DbPool>) ->
 #[utoipa::path(
 context_path="/api/v1",
 responses(
-(status = 200, description = "Runs a cleanup of old episodes")),
+(status = 200, description = "Runs a cleanup of old episodes and of the episodes the cleanup \
+policies select. Returns the deleted episodes.", body = CleanupReport)),
 tag="settings"
 )]
 #[put("/settings/runcleanup")]
-pub async fn run_cleanup(
-    pdservice: Data<Mutex<PodcastEpisodeService>>,
-    settings_service: Data<Mutex<SettingsService>>,conn: Data<DbPool>,
-    requester: Option<web::ReqData<User>>,
-) -> impl Responder {
+pub async fn run_cleanup(conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
 
     if !requester.unwrap().is_admin() {
         return HttpResponse::Unauthorized().finish();
     }
-    let settings = settings_service.lock().ignore_poison().get_settings( &mut conn.get
-    ().unwrap());
-    match settings {
-        Some(settings) => {
-            pdservice
-                .lock()
-                .ignore_poison()
-                .cleanup_old_episodes(settings.auto_cleanup_days,&mut conn.get().unwrap());
-            HttpResponse::Ok().finish()
-        }
-        None => {
-            log::error!("Error getting settings");
-            HttpResponse::InternalServerError().finish()
-        }
+    match web::block(move || CleanupService::run_cleanup(&mut conn.get().unwrap(), true)).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string())
     }
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "lowercase")]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Mode{
     LOCAL,ONLINE
 }
@@ -98,11 +90,13 @@ responses(
 tag="podcasts"
 )]
 #[get("/settings/opml/{type_of}")]
-pub async fn get_opml(conn: Data<DbPool>, type_of: Path<Mode>, env_service: Data<Mutex<EnvironmentService>>) ->
+pub async fn get_opml(conn: Data<DbPool>, type_of: Path<Mode>, env_service: Data<Mutex<EnvironmentService>>,
+                      requester: Option<web::ReqData<User>>) ->
                                                                                              impl
 Responder {
     let env_service = env_service.lock().ignore_poison();
     let podcasts_found = Podcast::get_all_podcasts(&mut conn.get().unwrap()).unwrap();
+    let tags = TagService::get_tags(&mut conn.get().unwrap(), &requester.unwrap().username);
 
     let mut xml = XMLBuilder::new().version(XMLVersion::XML1_1)
         .encoding("UTF-8".to_string())
@@ -110,7 +104,7 @@ Responder {
     let mut opml = XMLElement::new("opml");
     opml.add_attribute("version", "2.0");
     opml.add_child(add_header()).expect("TODO: panic message");
-    opml.add_child(add_podcasts(podcasts_found, env_service,type_of.into_inner() )).expect("TODO: panic \
+    opml.add_child(add_podcasts(podcasts_found, tags, env_service,type_of.into_inner() )).expect("TODO: panic \
     message");
 
     xml.set_root_element(opml);
@@ -140,26 +134,111 @@ fn add_body()->XMLElement {
 }
 
 
-fn add_podcasts(podcasts_found: Vec<Podcast>, env_service: MutexGuard<EnvironmentService>,
-                type_of: Mode) -> XMLElement {
-    let mut body = add_body();
-    for podcast in podcasts_found {
-        let mut outline = XMLElement::new("outline");
-        if podcast.summary.is_some(){
-            outline.add_attribute("text", &*podcast.summary.unwrap());
+/// Tagged podcasts are written into nested outlines for their tags. A podcast with several tags
+/// is only written once into its most deeply nested tag so that importing the file doesn't
+/// subscribe twice.
+fn add_podcasts(podcasts_found: Vec<Podcast>, tags: Vec<TagDto>,
+                env_service: MutexGuard<EnvironmentService>, type_of: Mode) -> XMLElement {
+    let tag_paths = tags.iter()
+        .map(|tag| (tag.id, get_tag_path(&tags, tag)))
+        .collect::<HashMap<i32, Vec<String>>>();
+    let mut folder_of_podcast = HashMap::<i32, i32>::new();
+    for tag in &tags {
+        for podcast_id in &tag.podcast_ids {
+            let is_preferred = match folder_of_podcast.get(podcast_id) {
+                Some(current_tag_id) => {
+                    let current_path = &tag_paths[current_tag_id];
+                    let path = &tag_paths[&tag.id];
+                    path.len() > current_path.len()
+                        || (path.len() == current_path.len() && path < current_path)
+                }
+                None => true
+            };
+            if is_preferred {
+                folder_of_podcast.insert(*podcast_id, tag.id);
+            }
         }
-        outline.add_attribute("title", &*podcast.name);
-        outline.add_attribute("type", "rss");
-        match type_of {
-            Mode::LOCAL => outline.add_attribute("xmlUrl", &*format!("{}rss/{}", &*env_service
-                .get_server_url(), podcast.id)),
-            Mode::ONLINE => outline.add_attribute("xmlUrl", &*podcast.rssfeed),
+    }
+
+    let mut podcasts_of_tag = HashMap::<Option<i32>, Vec<&Podcast>>::new();
+    for podcast in &podcasts_found {
+        podcasts_of_tag.entry(folder_of_podcast.get(&podcast.id).cloned())
+            .or_default()
+            .push(podcast);
+    }
+
+    let mut body = add_body();
+    for tag in tags.iter().filter(|tag| tag.parent_id.is_none()) {
+        if let Some(outline) = create_tag_outline(tag, &tags, &podcasts_of_tag, &env_service,
+                                                  &type_of) {
+            body.add_child(outline).expect("TODO: panic message");
         }
-        body.add_child(outline).expect("TODO: panic message");
+    }
+    for podcast in podcasts_of_tag.remove(&None).unwrap_or_default() {
+        body.add_child(create_podcast_outline(podcast, &env_service, &type_of))
+            .expect("TODO: panic message");
     }
     body
 }
 
+fn get_tag_path(tags: &[TagDto], tag: &TagDto) -> Vec<String> {
+    let mut path = vec![tag.name.clone()];
+    let mut parent_id = tag.parent_id;
+    while let Some(parent) = parent_id.and_then(|parent_id| tags.iter().find(|tag| tag.id ==
+        parent_id)) {
+        // Guards against cycles in the stored tags
+        if path.len() > tags.len() {
+            break;
+        }
+        path.insert(0, parent.name.clone());
+        parent_id = parent.parent_id;
+    }
+    path
+}
+
+/// Folders without any podcasts are left out.
+fn create_tag_outline(tag: &TagDto, tags: &[TagDto],
+                      podcasts_of_tag: &HashMap<Option<i32>, Vec<&Podcast>>,
+                      env_service: &EnvironmentService, type_of: &Mode) -> Option<XMLElement> {
+    let mut outline = XMLElement::new("outline");
+    outline.add_attribute("text", &tag.name);
+    outline.add_attribute("title", &tag.name);
+    let mut is_empty = true;
+    for nested_tag in tags.iter().filter(|nested_tag| nested_tag.parent_id == Some(tag.id)) {
+        if let Some(nested_outline) = create_tag_outline(nested_tag, tags, podcasts_of_tag,
+                                                         env_service, type_of) {
+            outline.add_child(nested_outline).expect("TODO: panic message");
+            is_empty = false;
+        }
+    }
+    for podcast in podcasts_of_tag.get(&Some(tag.id)).into_iter().flatten() {
+        outline.add_child(create_podcast_outline(podcast, env_service, type_of))
+            .expect("TODO: panic message");
+        is_empty = false;
+    }
+    match is_empty {
+        true => None,
+        false => Some(outline)
+    }
+}
+
+fn create_podcast_outline(podcast: &Podcast, env_service: &EnvironmentService, type_of: &Mode)
+    -> XMLElement {
+    let mut outline = XMLElement::new("outline");
+    if let Some(summary) = &podcast.summary {
+        outline.add_attribute("text", summary);
+    }
+    outline.add_attribute("title", &podcast.name);
+    outline.add_attribute("type", "rss");
+    match type_of {
+        Mode::LOCAL => outline.add_attribute("xmlUrl", &format!("{}rss/{}", env_service
+            .get_server_url(), podcast.id)),
+        Mode::ONLINE => outline.add_attribute("xmlUrl", &CredentialService::redact_url(&podcast
+            .rssfeed)),
+    }
+    outline
+}
+
 #[utoipa::path(
 context_path="/api/v1",
 responses(
@@ -183,6 +262,35 @@ pub async fn update_name(settings_service: Data<Mutex<SettingsService>>,
     HttpResponse::Ok().json(settings)
 }
 
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Renders the podcast and episode format for the newest episodes \
+without saving them. Paths that are already taken get a numeric suffix when downloading.",
+body = [NamingPreview])),
+tag="podcasts",
+request_body=UpdateNameSettings
+)]
+#[post("/settings/name/preview")]
+pub async fn preview_name(settings_service: Data<Mutex<SettingsService>>,
+                          update_information: web::Json<UpdateNameSettings>, requester:
+                          Option<web::ReqData<User>>,
+                          conn: Data<DbPool>)
+    -> impl Responder {
+    if !requester.unwrap().is_admin() {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    let mut settings_service = settings_service.lock().ignore_poison();
+
+    match settings_service.preview_name(update_information.into_inner(),
+                                        &mut conn.get().unwrap()) {
+        Ok(previews) => HttpResponse::Ok().json(previews),
+        Err(e) => HttpResponse::BadRequest().json(e)
+    }
+}
+
+use std::fmt::{Display, Formatter};
 use utoipa::ToSchema;
 #[derive(Deserialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -194,6 +302,15 @@ pub struct UpdateNameSettings{
     pub podcast_format: String
 }
 
+/// Where an episode would be downloaded to with the name settings
+#[derive(Serialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NamingPreview {
+    pub podcast_name: String,
+    pub episode_name: String,
+    pub path: String
+}
+
 #[derive(Deserialize, Clone)]
 #[serde(rename_all="kebab-case")]
 pub enum ReplacementStrategy{
@@ -202,13 +319,13 @@ pub enum ReplacementStrategy{
     ReplaceWithDash
 }
 
-impl ToString for ReplacementStrategy{
-    fn to_string(&self) -> String {
+impl Display for ReplacementStrategy{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            ReplacementStrategy::ReplaceWithDashAndUnderscore => "replace-with-dash-and-underscore"
-                .to_string(),
-            ReplacementStrategy::Remove => "remove".to_string(),
-            ReplacementStrategy::ReplaceWithDash => "replace-with-dash".to_string()
+            ReplacementStrategy::ReplaceWithDashAndUnderscore =>
+                write!(f, "replace-with-dash-and-underscore"),
+            ReplacementStrategy::Remove => write!(f, "remove"),
+            ReplacementStrategy::ReplaceWithDash => write!(f, "replace-with-dash")
         }
     }
 }
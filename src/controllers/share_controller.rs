@@ -0,0 +1,123 @@
+use actix_web::web::{Data, Path, Query};
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder, ResponseError};
+use std::sync::Mutex;
+use crate::DbPool;
+use crate::models::episode_share::EpisodeShareCreateModel;
+use crate::models::user::User;
+use crate::mutex::LockResultExt;
+use crate::service::credential_service::CredentialService;
+use crate::service::http_client::HttpClientFactory;
+use crate::service::mapping_service::MappingService;
+use crate::service::share_service::ShareService;
+use crate::service::storage::StorageService;
+
+#[derive(Debug, Deserialize)]
+pub struct SignedMediaQuery {
+    expires: i64,
+    signature: String
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the share links of the user, newest first. Expired links are \
+deleted once a day.", body = [EpisodeShareDto])),
+tag="shares"
+)]
+#[get("/shares")]
+pub async fn get_shares(conn: Data<DbPool>, mapping_service: Data<Mutex<MappingService>>,
+                        requester: Option<web::ReqData<User>>) -> impl Responder {
+    let shares = ShareService::get_shares(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                          &mapping_service.lock().ignore_poison());
+    HttpResponse::Ok().json(shares)
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("id", description="The episode id (uuid) of the podcast episode")),
+request_body=EpisodeShareCreateModel,
+responses(
+(status = 200, description = "Creates a public link to an episode that starts playing at the \
+given position.", body = EpisodeShareDto)),
+tag="shares"
+)]
+#[post("/podcast/episode/{id}/shares")]
+pub async fn create_share(id: Path<String>, share: web::Json<EpisodeShareCreateModel>,
+                          conn: Data<DbPool>, mapping_service: Data<Mutex<MappingService>>,
+                          requester: Option<web::ReqData<User>>) -> impl Responder {
+    match ShareService::create_share(&mut conn.get().unwrap(), &requester.unwrap().username, &id,
+                                     &share, &mapping_service.lock().ignore_poison()) {
+        Ok(share) => HttpResponse::Ok().json(share),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Revokes a share link.")),
+tag="shares"
+)]
+#[delete("/shares/{id}")]
+pub async fn revoke_share(id: Path<i32>, conn: Data<DbPool>,
+                          requester: Option<web::ReqData<User>>) -> impl Responder {
+    match ShareService::revoke_share(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                     id.into_inner()) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+responses(
+(status = 200, description = "Gets the public player page of a share link. The token is part of \
+the link.")),
+tag="shares"
+)]
+#[get("/share/{token}")]
+pub async fn get_share_page(token: Path<String>, conn: Data<DbPool>) -> HttpResponse {
+    match ShareService::get_active_share(&mut conn.get().unwrap(), &token) {
+        Some((share, podcast_episode, podcast)) => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .insert_header(("Referrer-Policy", "no-referrer"))
+            .insert_header(("X-Robots-Tag", "noindex"))
+            .body(ShareService::render_share_page(&share, &podcast_episode, &podcast)),
+        None => HttpResponse::NotFound()
+            .content_type("text/html; charset=utf-8")
+            .body(ShareService::render_not_found_page())
+    }
+}
+
+#[utoipa::path(
+params(("expires", description="Unix time the media url expires at"),
+("signature", description="Signature of the media url")),
+responses(
+(status = 200, description = "Streams the episode of a share link. Episodes that are not \
+downloaded redirect to their original url, unless it needs credentials.")),
+tag="shares"
+)]
+#[get("/share/{token}/media")]
+pub async fn get_share_media(token: Path<String>, query: Query<SignedMediaQuery>,
+                             conn: Data<DbPool>, req: HttpRequest) -> HttpResponse {
+    if !ShareService::verify_media_signature(&token, query.expires, &query.signature) {
+        return HttpResponse::Forbidden().finish();
+    }
+    let conn = &mut conn.get().unwrap();
+    let (podcast_episode, podcast) = match ShareService::get_active_share(conn, &token) {
+        Some((_, podcast_episode, podcast)) => (podcast_episode, podcast),
+        None => return HttpResponse::NotFound().finish()
+    };
+    if !podcast_episode.is_downloaded() {
+        // Episodes that need credentials are streamed, visitors mustn't see them
+        let credential_scope = CredentialService::get_credential_scope(conn, &podcast);
+        return match CredentialService::split_credentials_from_url(&podcast_episode.url) {
+            (episode_url, None) if !credential_scope.has_credentials() => HttpResponse::Found()
+                .insert_header(("Location", episode_url))
+                .finish(),
+            _ => StorageService::proxy_remote_media(&podcast_episode.url,
+                                                    &HttpClientFactory::for_podcast(&podcast),
+                                                    &credential_scope, &req).await
+        };
+    }
+    StorageService::get_media_response(&podcast_episode.local_url, &req).await
+}
@@ -0,0 +1,107 @@
+use actix_web::web::{Data, Path, Query};
+use actix_web::{get, web, HttpResponse, Responder, ResponseError};
+use chrono::NaiveDate;
+use crate::DbPool;
+use crate::models::listening_stats::StatsExportFormat;
+use crate::models::user::User;
+use crate::service::statistics_service::StatisticsService;
+
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatsExportQuery {
+    from: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    format: Option<StatsExportFormat>
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("from", description="First day (YYYY-MM-DD) of the statistics"),
+("until", description="Last day (YYYY-MM-DD) of the statistics")),
+responses(
+(status = 200, description = "Gets the listening statistics of the user. Days and hours are in \
+UTC.", body = ListeningStats)),
+tag="statistics"
+)]
+#[get("/stats")]
+pub async fn get_listening_stats(query: Query<StatsQuery>, conn: Data<DbPool>,
+                                 requester: Option<web::ReqData<User>>) -> impl Responder {
+    match StatisticsService::get_stats(&mut conn.get().unwrap(),
+                                       Some(&requester.unwrap().username), query.from,
+                                       query.until) {
+        Ok(stats) => HttpResponse::Ok().json(stats),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("from", description="First day (YYYY-MM-DD) of the statistics"),
+("until", description="Last day (YYYY-MM-DD) of the statistics")),
+responses(
+(status = 200, description = "Gets the listening statistics of every user of the instance. Only \
+for admins.", body = ListeningStats)),
+tag="statistics"
+)]
+#[get("/stats/instance")]
+pub async fn get_instance_listening_stats(query: Query<StatsQuery>, conn: Data<DbPool>,
+                                          requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    if !requester.unwrap().is_admin(){
+        return HttpResponse::Forbidden()
+            .body("You are not authorized to perform this action")
+    }
+    match StatisticsService::get_stats(&mut conn.get().unwrap(), None, query.from, query.until) {
+        Ok(stats) => HttpResponse::Ok().json(stats),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the year in review of the user.", body = WrappedReport)),
+tag="statistics"
+)]
+#[get("/stats/wrapped/{year}")]
+pub async fn get_wrapped(year: Path<i32>, conn: Data<DbPool>,
+                         requester: Option<web::ReqData<User>>) -> impl Responder {
+    match StatisticsService::get_wrapped(&mut conn.get().unwrap(),
+                                         &requester.unwrap().username, year.into_inner()) {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+params(("from", description="First day (YYYY-MM-DD) of the export"),
+("until", description="Last day (YYYY-MM-DD) of the export"),
+("format", description="csv or json, defaults to csv")),
+responses(
+(status = 200, description = "Exports the listening time of the user per hour and episode.")),
+tag="statistics"
+)]
+#[get("/stats/export")]
+pub async fn export_listening_stats(query: Query<StatsExportQuery>, conn: Data<DbPool>,
+                                    requester: Option<web::ReqData<User>>) -> impl Responder {
+    let format = query.format.unwrap_or(StatsExportFormat::Csv);
+    let (content_type, extension) = match format {
+        StatsExportFormat::Csv => ("text/csv", "csv"),
+        StatsExportFormat::Json => ("application/json", "json")
+    };
+    match StatisticsService::export(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                    query.from, query.until, format) {
+        Ok(export) => HttpResponse::Ok()
+            .content_type(content_type)
+            .insert_header(("Content-Disposition",
+                            format!("attachment; filename=\"listening-stats.{}\"", extension)))
+            .body(export),
+        Err(e) => e.error_response()
+    }
+}
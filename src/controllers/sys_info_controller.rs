@@ -3,13 +3,13 @@ use crate::service::environment_service::EnvironmentService;
 use actix_web::web::Data;
 use actix_web::{get, post};
 use actix_web::{web, HttpResponse, Responder};
-use fs_extra::dir::get_size;
 use std::sync::{Mutex};
 use sysinfo::{System, SystemExt};
 use crate::models::user::User;
 use crate::mutex::LockResultExt;
 use sha256::{digest};
 use crate::DbPool;
+use crate::service::storage::StorageService;
 pub mod built_info {
     // The file has been placed there by the build script.
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
@@ -27,7 +27,12 @@ pub async fn get_sys_info() -> impl Responder {
     let mut sys = System::new_all();
     sys.refresh_all();
 
-    let podcast_byte_size = get_size("podcasts").unwrap();
+    let podcast_byte_size = match web::block(|| StorageService::get_backend().dir_size(""))
+        .await {
+        Ok(Ok(podcast_byte_size)) => podcast_byte_size,
+        Ok(Err(e)) => return HttpResponse::InternalServerError().json(e.to_string()),
+        Err(e) => return HttpResponse::InternalServerError().json(e.to_string())
+    };
     HttpResponse::Ok().json(SysExtraInfo {
         system: sys,
         podcast_directory: podcast_byte_size,
@@ -73,9 +78,9 @@ pub async fn login(
     if auth.0.username == env_service.username && auth.0.password == env_service.password {
         return HttpResponse::Ok().json("Login successful");
     }
-    let db_user = User::find_by_username(&auth.0.username, &mut *db.get().unwrap());
+    let db_user = User::find_by_username(&auth.0.username, &mut db.get().unwrap());
 
-    return match db_user {
+    match db_user {
         Some(user) => {
             if user.password.unwrap() == digest(auth.0.password) {
                 return HttpResponse::Ok().json("Login successful");
@@ -85,7 +90,7 @@ pub async fn login(
         None => {
             HttpResponse::Unauthorized().json(ERROR_LOGIN_MESSAGE)
         }
-    };
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -0,0 +1,104 @@
+use actix_web::web::{Data, Path};
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder, ResponseError};
+use crate::DbPool;
+use crate::models::tag::TagCreateModel;
+use crate::models::user::User;
+use crate::service::tag_service::TagService;
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the tags of the user with the ids of their podcasts.", body =
+[TagDto])),
+tag="tags"
+)]
+#[get("/tags")]
+pub async fn get_tags(conn: Data<DbPool>, requester: Option<web::ReqData<User>>)
+    -> impl Responder {
+    let tags = TagService::get_tags(&mut conn.get().unwrap(), &requester.unwrap().username);
+    HttpResponse::Ok().json(tags)
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=TagCreateModel,
+responses(
+(status = 200, description = "Creates a tag. Tags with a parent are nested inside it like \
+folders.", body = Tag)),
+tag="tags"
+)]
+#[post("/tags")]
+pub async fn create_tag(tag: web::Json<TagCreateModel>, conn: Data<DbPool>,
+                        requester: Option<web::ReqData<User>>) -> impl Responder {
+    match TagService::create_tag(&mut conn.get().unwrap(), &requester.unwrap().username, &tag) {
+        Ok(tag) => HttpResponse::Ok().json(tag),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+request_body=TagCreateModel,
+responses(
+(status = 200, description = "Renames, moves or recolors a tag.", body = Tag)),
+tag="tags"
+)]
+#[put("/tags/{id}")]
+pub async fn update_tag(id: Path<i32>, tag: web::Json<TagCreateModel>, conn: Data<DbPool>,
+                        requester: Option<web::ReqData<User>>) -> impl Responder {
+    match TagService::update_tag(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                 id.into_inner(), &tag) {
+        Ok(tag) => HttpResponse::Ok().json(tag),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Deletes a tag. Tags nested inside it are moved to its parent.")),
+tag="tags"
+)]
+#[delete("/tags/{id}")]
+pub async fn delete_tag(id: Path<i32>, conn: Data<DbPool>,
+                        requester: Option<web::ReqData<User>>) -> impl Responder {
+    match TagService::delete_tag(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                 id.into_inner()) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Adds a podcast to a tag.")),
+tag="tags"
+)]
+#[put("/tags/{id}/podcasts/{podcast_id}")]
+pub async fn add_podcast_to_tag(path: Path<(i32, i32)>, conn: Data<DbPool>,
+                                requester: Option<web::ReqData<User>>) -> impl Responder {
+    let (id, podcast_id) = path.into_inner();
+    match TagService::add_podcast_to_tag(&mut conn.get().unwrap(), &requester.unwrap().username,
+                                         id, podcast_id) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Removes a podcast from a tag.")),
+tag="tags"
+)]
+#[delete("/tags/{id}/podcasts/{podcast_id}")]
+pub async fn remove_podcast_from_tag(path: Path<(i32, i32)>, conn: Data<DbPool>,
+                                     requester: Option<web::ReqData<User>>) -> impl Responder {
+    let (id, podcast_id) = path.into_inner();
+    match TagService::remove_podcast_from_tag(&mut conn.get().unwrap(),
+                                              &requester.unwrap().username, id, podcast_id) {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => e.error_response()
+    }
+}
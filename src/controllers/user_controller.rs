@@ -44,13 +44,13 @@ pub async fn onboard_user(user_onboarding: web::Json<UserOnboardingModel>, conn:
 
     let res = UserManagementService::onboard_user(user_to_onboard.username, user_to_onboard
         .password,
-                                                  user_to_onboard.invite_id, &mut *conn.get().unwrap());
+                                                  user_to_onboard.invite_id, &mut conn.get().unwrap());
 
-    return match res {
+    match res {
         Ok(user) => HttpResponse::Ok().json(User::map_to_dto(user)),
         Err(e) => HttpResponse::BadRequest()
-            .body(e.name().clone())
-    };
+            .body(e.name())
+    }
 }
 
 #[utoipa::path(
@@ -62,11 +62,11 @@ tag="info"
 #[get("")]
 pub async fn get_users(conn: Data<DbPool>, requester: Option<web::ReqData<User>>)->impl Responder{
 
-    let res = UserManagementService::get_users(requester.unwrap().into_inner(),&mut *conn.get().unwrap());
+    let res = UserManagementService::get_users(requester.unwrap().into_inner(),&mut conn.get().unwrap());
 
     if res.is_err() {
         return HttpResponse::Forbidden()
-            .body(res.err().unwrap().name().clone())
+            .body(res.err().unwrap().name())
     }
 
     HttpResponse::Ok().json(res.unwrap())
@@ -81,12 +81,12 @@ tag="info"
 #[get("/users/{username}")]
 pub async fn get_user(req: HttpRequest, conn: Data<DbPool>)->impl Responder{
     let username = get_user_from_request(req);
-    let user = User::find_by_username(&username, &mut *conn.get().unwrap());
-    return match user {
+    let user = User::find_by_username(&username, &mut conn.get().unwrap());
+    match user {
         Some(user) => HttpResponse::Ok().json(User::map_to_dto(user)),
         None => HttpResponse::NotFound()
             .body("User not found")
-    };
+    }
 }
 
 #[utoipa::path(
@@ -105,7 +105,7 @@ web::Path<String>, requester: Option<web::ReqData<User>>)
         return HttpResponse::Forbidden()
             .body("You are not authorized to perform this action")
     }
-    let user_to_update = User::find_by_username(&username, &mut *conn.get().unwrap());
+    let user_to_update = User::find_by_username(&username, &mut conn.get().unwrap());
 
     if user_to_update.is_none() {
         return HttpResponse::NotFound()
@@ -117,7 +117,7 @@ web::Path<String>, requester: Option<web::ReqData<User>>)
     found_user.role = role.role.to_string();
     found_user.explicit_consent = role.explicit_consent;
 
-    let res = UserManagementService::update_user(found_user, &mut *conn.get()
+    let res = UserManagementService::update_user(found_user, &mut conn.get()
         .unwrap());
 
     match res {
@@ -125,7 +125,7 @@ web::Path<String>, requester: Option<web::ReqData<User>>)
             HttpResponse::Ok().into()
         },
         Err(e) => HttpResponse::BadRequest()
-            .body(e.name().clone())
+            .body(e.name())
     }
 }
 
@@ -144,7 +144,7 @@ Responder{
     let invite = invite.into_inner();
 
     let created_invite = UserManagementService::create_invite(invite.role, invite
-        .explicit_consent,&mut *conn.get()
+        .explicit_consent,&mut conn.get()
         .unwrap(), requester.unwrap().into_inner()).expect("Error creating invite");
     HttpResponse::Ok().json(created_invite)
 }
@@ -161,10 +161,10 @@ pub async fn get_invites(conn: Data<DbPool>, requester: Option<web::ReqData<User
         return HttpResponse::Forbidden().body("You are not authorized to perform this action")
     }
 
-    let invites = UserManagementService::get_invites( &mut *conn.get().unwrap());
+    let invites = UserManagementService::get_invites( &mut conn.get().unwrap());
 
     if invites.is_err(){
-        return HttpResponse::BadRequest().body(invites.err().unwrap().name().clone())
+        return HttpResponse::BadRequest().body(invites.err().unwrap().name())
     }
 
     HttpResponse::Ok().json(invites.unwrap())
@@ -179,7 +179,7 @@ tag="info"
 #[get("/users/invites/{invite_id}")]
 pub async fn get_invite(conn: Data<DbPool>, invite_id: web::Path<String>)->
     impl Responder{
-    match UserManagementService::get_invite(invite_id.into_inner(), &mut *conn.get().unwrap()){
+    match UserManagementService::get_invite(invite_id.into_inner(), &mut conn.get().unwrap()){
         Ok(invite) => HttpResponse::Ok().json(invite),
         Err(e) => HttpResponse::BadRequest().body(e.to_string())
     }
@@ -198,12 +198,12 @@ Responder{
         return HttpResponse::Forbidden().body("You are not authorized to perform this action")
     }
 
-    let user_to_delete = User::find_by_username(&username, &mut *conn.get().unwrap()).unwrap();
-    return match UserManagementService::delete_user(user_to_delete, &mut *conn.get().unwrap())
+    let user_to_delete = User::find_by_username(&username, &mut conn.get().unwrap()).unwrap();
+    match UserManagementService::delete_user(user_to_delete, &mut conn.get().unwrap())
     {
         Ok(_) => HttpResponse::Ok().into(),
         Err(e) => HttpResponse::BadRequest().body(e.to_string())
-    };
+    }
 }
 
 #[utoipa::path(
@@ -222,7 +222,7 @@ pub async fn get_invite_link(conn: Data<DbPool>, invite_id: web::Path<String>,
 
 
     match UserManagementService::get_invite_link(invite_id.into_inner(),
-                                                 environment_service,&mut *conn.get()
+                                                 environment_service,&mut conn.get()
         .unwrap()){
         Ok(invite) => HttpResponse::Ok().json(invite),
         Err(e) => HttpResponse::BadRequest().body(e.to_string())
@@ -240,13 +240,15 @@ pub async fn delete_invite(conn:Data<DbPool>, invite_id: web::Path<String>,reque
         return HttpResponse::Forbidden().body("You are not authorized to perform this action")
     }
 
-    return match UserManagementService::delete_invite(invite_id.into_inner(), &mut *conn.get().unwrap())
+    match UserManagementService::delete_invite(invite_id.into_inner(), &mut conn.get().unwrap())
     {
         Ok(_) => HttpResponse::Ok().into(),
         Err(e) => HttpResponse::BadRequest().body(e.to_string())
-    };
+    }
 }
 
+// Only used by get_user, which isn't registered as a service yet
+#[allow(dead_code)]
 fn get_user_from_request(req: HttpRequest)->String{
     req.clone().headers().get(USERNAME).unwrap().to_str().unwrap().to_string()
-}
\ No newline at end of file
+}
@@ -8,7 +8,9 @@ use crate::models::episode::Episode;
 use crate::models::podcast_history_item::PodcastHistoryItem;
 use crate::models::user::User;
 use crate::mutex::LockResultExt;
+use crate::service::episode_state_service::EpisodeStateService;
 use crate::service::mapping_service::MappingService;
+use crate::service::statistics_service::StatisticsService;
 
 #[utoipa::path(
 context_path="/api/v1",
@@ -24,9 +26,12 @@ Responder {
 
 
     let podcast_episode_id = podcast_watch.0.podcast_episode_id.clone();
-    PodcastHistoryItem::log_watchtime(&mut conn.get().unwrap(),podcast_watch.0, requester.unwrap().username
-        .clone())
+    let username = requester.unwrap().username.clone();
+    StatisticsService::record_listening(&mut conn.get().unwrap(), &username, &podcast_episode_id,
+                                        podcast_watch.time);
+    PodcastHistoryItem::log_watchtime(&mut conn.get().unwrap(),podcast_watch.0, username.clone())
         .expect("Error logging watchtime");
+    EpisodeStateService::on_listened(&mut conn.get().unwrap(), &username, &podcast_episode_id);
     log::debug!("Logged watchtime for episode: {}", podcast_episode_id);
     HttpResponse::Ok()
 }
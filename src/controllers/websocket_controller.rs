@@ -1,3 +1,4 @@
+use crate::constants::constants::DEFAULT_PODCAST_SUFFIX;
 use crate::controllers::web_socket::WsConn;
 
 use crate::models::podcast_episode::PodcastEpisode;
@@ -5,6 +6,9 @@ use crate::models::podcasts::Podcast;
 use crate::models::web_socket_message::Lobby;
 use crate::service::environment_service::EnvironmentService;
 use crate::service::podcast_episode_service::PodcastEpisodeService;
+use crate::service::playlist_service::PlaylistService;
+use crate::service::queue_service::QueueService;
+use crate::service::storage::StorageService;
 use actix::Addr;
 use actix_web::{get, web, web::Data, web::Payload, Error, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
@@ -80,11 +84,11 @@ fn generate_itunes_extension_conditionally(mut itunes_ext: ITunesChannelExtensio
                                            mut channel_builder: ChannelBuilder,
                                            podcast: Option<Podcast>,
                                            env: EnvironmentService) ->Channel{
-    return if items.len() == 0 {
-        if podcast.is_some() {
-            let unwrapped_podcast = podcast.unwrap();
-            match unwrapped_podcast.image_url.len() > 0{
-                true => itunes_ext.set_image(env.server_url+ &*unwrapped_podcast.image_url),
+    if items.is_empty() {
+        if let Some(unwrapped_podcast) = podcast {
+            match !unwrapped_podcast.image_url.is_empty(){
+                true => itunes_ext.set_image(env.server_url + &StorageService::get_media_path(
+                    &unwrapped_podcast.image_url)),
                 false => itunes_ext.set_image(env.server_url+ &*unwrapped_podcast.original_image_url)
             }
         }
@@ -94,11 +98,10 @@ fn generate_itunes_extension_conditionally(mut itunes_ext: ITunesChannelExtensio
             .build()
     } else {
 
-        if podcast.is_some() {
-            let unwrapped_podcast = podcast.unwrap();
-
-            match unwrapped_podcast.image_url.len() > 0{
-                true => itunes_ext.set_image(env.server_url+ &*unwrapped_podcast.image_url),
+        if let Some(unwrapped_podcast) = podcast {
+            match !unwrapped_podcast.image_url.is_empty(){
+                true => itunes_ext.set_image(env.server_url + &StorageService::get_media_path(
+                    &unwrapped_podcast.image_url)),
                 false => itunes_ext.set_image(env.server_url+ &*unwrapped_podcast.original_image_url)
             }
         }
@@ -124,12 +127,12 @@ pub async fn get_rss_feed_for_podcast(
     let mut podcast_service = podcast_episode_service
         .lock()
         .ignore_poison();
-    let res = Podcast::get_podcast(&mut conn.get().unwrap(),id.clone());
+    let res = Podcast::get_podcast(&mut conn.get().unwrap(),*id);
 
     match res {
         Ok(podcast) => {
             let downloaded_episodes =
-                podcast_service.find_all_downloaded_podcast_episodes_by_podcast_id(id.clone(),
+                podcast_service.find_all_downloaded_podcast_episodes_by_podcast_id(*id,
                                                                                    &mut conn.get().unwrap());
 
             let mut itunes_owner = get_itunes_owner("", "");
@@ -184,11 +187,196 @@ pub async fn get_rss_feed_for_podcast(
             HttpResponse::Ok().body(channel.to_string())
         }
         Err(..) => {
-            return HttpResponse::NotFound().finish();
+            HttpResponse::NotFound().finish()
         }
     }
 }
 
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the queue of a user as rss feed. The token is part of the \
+private link of the queue."))
+,tag="queue")]
+#[get("/rss/queue/{token}")]
+pub async fn get_rss_feed_for_queue(
+    podcast_episode_service: Data<Mutex<PodcastEpisodeService>>,
+    token: web::Path<String>,
+    conn: Data<DbPool>,
+) -> HttpResponse {
+    let queue = match QueueService::get_queue_by_feed_token(&mut conn.get().unwrap(), &token) {
+        Some(queue) => queue,
+        None => return HttpResponse::NotFound().finish()
+    };
+    let queued_episodes = map_playlist_episodes(&podcast_episode_service, queue);
+    let channel = get_episode_list_channel(queued_episodes, "Podfetch Up Next",
+                                           "Your queue of podcast episodes",
+                                           &format!("rss/queue/{}", &token));
+
+    HttpResponse::Ok().body(channel.to_string())
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets the queue of a user as m3u playlist. The token is part of \
+the private link of the queue."))
+,tag="queue")]
+#[get("/m3u/queue/{token}")]
+pub async fn get_m3u_playlist_for_queue(
+    podcast_episode_service: Data<Mutex<PodcastEpisodeService>>,
+    token: web::Path<String>,
+    conn: Data<DbPool>,
+) -> HttpResponse {
+    let queue = match QueueService::get_queue_by_feed_token(&mut conn.get().unwrap(), &token) {
+        Some(queue) => queue,
+        None => return HttpResponse::NotFound().finish()
+    };
+    let queued_episodes = map_playlist_episodes(&podcast_episode_service, queue);
+
+    HttpResponse::Ok()
+        .content_type("audio/x-mpegurl")
+        .body(get_m3u_playlist(&queued_episodes))
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets a playlist of a user as rss feed. The token is part of the \
+private link of the playlist."))
+,tag="playlists")]
+#[get("/rss/playlist/{token}")]
+pub async fn get_rss_feed_for_playlist(
+    podcast_episode_service: Data<Mutex<PodcastEpisodeService>>,
+    token: web::Path<String>,
+    conn: Data<DbPool>,
+) -> HttpResponse {
+    let (playlist, episodes) = match PlaylistService::get_playlist_by_feed_token(&mut conn.get()
+        .unwrap(), &token) {
+        Some(playlist) => playlist,
+        None => return HttpResponse::NotFound().finish()
+    };
+    let episodes = map_playlist_episodes(&podcast_episode_service, episodes);
+    let channel = get_episode_list_channel(episodes, &playlist.name,
+                                           &format!("Your playlist {}", playlist.name),
+                                           &format!("rss/playlist/{}", &token));
+
+    HttpResponse::Ok().body(channel.to_string())
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets a playlist of a user as m3u playlist. The token is part of \
+the private link of the playlist."))
+,tag="playlists")]
+#[get("/m3u/playlist/{token}")]
+pub async fn get_m3u_playlist_for_playlist(
+    podcast_episode_service: Data<Mutex<PodcastEpisodeService>>,
+    token: web::Path<String>,
+    conn: Data<DbPool>,
+) -> HttpResponse {
+    let (_, episodes) = match PlaylistService::get_playlist_by_feed_token(&mut conn.get()
+        .unwrap(), &token) {
+        Some(playlist) => playlist,
+        None => return HttpResponse::NotFound().finish()
+    };
+    let episodes = map_playlist_episodes(&podcast_episode_service, episodes);
+
+    HttpResponse::Ok()
+        .content_type("audio/x-mpegurl")
+        .body(get_m3u_playlist(&episodes))
+}
+
+#[utoipa::path(
+context_path="/api/v1",
+responses(
+(status = 200, description = "Gets a playlist of a user as pls playlist. The token is part of \
+the private link of the playlist."))
+,tag="playlists")]
+#[get("/pls/playlist/{token}")]
+pub async fn get_pls_playlist_for_playlist(
+    podcast_episode_service: Data<Mutex<PodcastEpisodeService>>,
+    token: web::Path<String>,
+    conn: Data<DbPool>,
+) -> HttpResponse {
+    let (_, episodes) = match PlaylistService::get_playlist_by_feed_token(&mut conn.get()
+        .unwrap(), &token) {
+        Some(playlist) => playlist,
+        None => return HttpResponse::NotFound().finish()
+    };
+    let episodes = map_playlist_episodes(&podcast_episode_service, episodes);
+
+    HttpResponse::Ok()
+        .content_type("audio/x-scpls")
+        .body(get_pls_playlist(&episodes))
+}
+
+/// Points the episodes to the local files if they are downloaded
+fn map_playlist_episodes(podcast_episode_service: &Mutex<PodcastEpisodeService>,
+                         episodes: Vec<(PodcastEpisode, Podcast)>) -> Vec<(PodcastEpisode, Podcast)> {
+    let (episodes, podcasts): (Vec<PodcastEpisode>, Vec<Podcast>) = episodes
+        .into_iter()
+        .unzip();
+    let episodes = podcast_episode_service
+        .lock()
+        .ignore_poison()
+        .map_rss_podcast_episodes(EnvironmentService::new(), episodes);
+    episodes.into_iter().zip(podcasts).collect()
+}
+
+fn get_episode_list_channel(episodes: Vec<(PodcastEpisode, Podcast)>, title: &str,
+                            description: &str, path: &str) -> Channel {
+    let env = EnvironmentService::new();
+    let feed_url = format!("{}{}", env.get_server_url(), path);
+    let itunes_ext = ITunesChannelExtensionBuilder::default()
+        .owner(Some(get_itunes_owner("Podfetch", "dev@podfetch.com")))
+        .categories(vec![get_category("Technology".to_string())])
+        .explicit(Some("no".to_string()))
+        .author(Some("Podfetch".to_string()))
+        .new_feed_url(feed_url.clone())
+        .summary(Some(description.to_string()))
+        .build();
+
+    let items = get_podcast_items_rss(episodes.into_iter()
+        .map(|(podcast_episode, _)| podcast_episode)
+        .collect());
+    let channel_builder = ChannelBuilder::default()
+        .language("en".to_string())
+        .title(title)
+        .link(feed_url)
+        .description(description)
+        .items(items.clone()).clone();
+
+    generate_itunes_extension_conditionally(itunes_ext, items, channel_builder, None, env)
+}
+
+fn get_playlist_entry_title(episode: &PodcastEpisode, podcast: &Podcast) -> String {
+    format!("{} - {}", podcast.name, episode.name).replace(['\r', '\n'], " ")
+}
+
+fn get_m3u_playlist(episodes: &[(PodcastEpisode, Podcast)]) -> String {
+    let mut playlist = "#EXTM3U\n".to_string();
+    for (episode, podcast) in episodes {
+        playlist.push_str(&format!("#EXTINF:{},{}\n{}\n", episode.total_time,
+                                   get_playlist_entry_title(episode, podcast), episode.local_url));
+    }
+    playlist
+}
+
+fn get_pls_playlist(episodes: &[(PodcastEpisode, Podcast)]) -> String {
+    let mut playlist = "[playlist]\n".to_string();
+    for (index, (episode, podcast)) in episodes.iter().enumerate() {
+        let number = index + 1;
+        playlist.push_str(&format!("File{}={}\nTitle{}={}\nLength{}={}\n", number,
+                                   episode.local_url, number,
+                                   get_playlist_entry_title(episode, podcast), number,
+                                   episode.total_time));
+    }
+    playlist.push_str(&format!("NumberOfEntries={}\nVersion=2\n", episodes.len()));
+    playlist
+}
+
 fn get_podcast_items_rss(downloaded_episodes: Vec<PodcastEpisode>) -> Vec<Item> {
 
     downloaded_episodes
@@ -200,7 +388,8 @@ fn get_podcast_items_rss(downloaded_episodes: Vec<PodcastEpisode>) -> Vec<Item>
                 .mime_type(format!(
                     "{}/{}",
                     "audio",
-                    &*PodcastEpisodeService::get_url_file_suffix(&episode.clone().local_url)
+                    PodcastEpisodeService::get_file_suffix(&episode.local_url, None,
+                                                           DEFAULT_PODCAST_SUFFIX)
                 ))
                 .build();
 
@@ -221,7 +410,7 @@ fn get_podcast_items_rss(downloaded_episodes: Vec<PodcastEpisode>) -> Vec<Item>
                 .enclosure(Some(enclosure))
                 .itunes_ext(itunes_extension)
                 .build();
-            return item;
+            item
         })
         .collect::<Vec<Item>>()
 }
@@ -4,8 +4,10 @@ use diesel::prelude::*;
 use diesel::{debug_query, RunQueryDsl};
 use crate::controllers::podcast_episode_controller::TimelineQueryParams;
 use crate::{DbConnection};
+use crate::models::episode_state::EpisodeState;
 use crate::models::favorites::Favorite;
 use crate::models::filter::Filter;
+use crate::service::tag_service::TagService;
 
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -42,11 +44,8 @@ impl TimelineItem{
 
         match favored_only.favored_only {
             true=>{
-                match favored_only.last_timestamp {
-                    Some(last_id) => {
-                        query = query.filter(date_of_recording.lt(last_id.clone()));
-                    }
-                    None => {}
+                if let Some(last_id) = favored_only.last_timestamp {
+                    query = query.filter(date_of_recording.lt(last_id.clone()));
                 }
 
                 query = query.filter(f_username.eq(username_to_search.clone()));
@@ -54,15 +53,27 @@ impl TimelineItem{
 
             }
             false=>{
-                match favored_only.last_timestamp {
-                    Some(last_id) => {
-                        query = query.filter(date_of_recording.lt(last_id));
-                    }
-                    None => {}
+                if let Some(last_id) = favored_only.last_timestamp {
+                    query = query.filter(date_of_recording.lt(last_id));
                 }
             }
         }
-        println!("{}",debug_query(&total_count).to_string());
+        if let Some(tag_id) = favored_only.tag_id {
+            use crate::dbconfig::schema::tags_podcasts;
+
+            let tag_ids = TagService::get_tag_ids(conn, &username_to_search, tag_id);
+            let tagged_podcast_ids = tags_podcasts::table
+                .filter(tags_podcasts::tag_id.eq_any(tag_ids))
+                .select(tags_podcasts::podcast_id);
+            query = query.filter(pid.eq_any(tagged_podcast_ids.clone()));
+            total_count = total_count.filter(pid.eq_any(tagged_podcast_ids));
+        }
+        if let Some(state) = favored_only.state {
+            query = query.filter(EpisodeState::has_state(&username_to_search, &[state]));
+            total_count = total_count.filter(EpisodeState::has_state(&username_to_search,
+                                                                     &[state]));
+        }
+        println!("{}",debug_query(&total_count));
         let results = total_count.get_result::<i64>(conn).expect("Error counting results");
         let result = query.load::<(PodcastEpisode, Podcast, Option<Favorite>)>(conn).expect("Error \
         loading podcast episode by id");
@@ -1,3 +1,6 @@
+// The schema of the database in use is also loaded under the name of its backend
+#![allow(clippy::duplicate_mod)]
+
 #[cfg(sqlite)]
 #[path = "schemas/sqlite/schema.rs"]
 pub mod schema;
@@ -1,5 +1,27 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    bookmarks (id) {
+        id -> Int4,
+        username -> Text,
+        podcast_episode_id -> Int4,
+        position -> Int4,
+        end_position -> Nullable<Int4>,
+        note -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    cleanup_policies (id) {
+        id -> Integer,
+        podcast_id -> Nullable<Integer>,
+        keep_latest -> Nullable<Integer>,
+        delete_played_after_days -> Nullable<Integer>,
+        max_bytes -> Nullable<BigInt>,
+    }
+}
+
 diesel::table! {
     devices (id) {
         id -> Int4,
@@ -10,6 +32,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    episode_shares (id) {
+        id -> Int4,
+        token -> Text,
+        username -> Text,
+        podcast_episode_id -> Int4,
+        start_position -> Int4,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    episode_states (username, podcast_episode_id) {
+        username -> Text,
+        podcast_episode_id -> Int4,
+        state -> Text,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     episodes (id) {
         id -> Int4,
@@ -34,6 +77,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    feed_fetch_reports (podcast_id) {
+        podcast_id -> Int4,
+        fetched_at -> Timestamp,
+        successful -> Bool,
+        error -> Nullable<Text>,
+        new_episodes -> Int4,
+        updated_episodes -> Int4,
+        skipped_items -> Text,
+    }
+}
+
 diesel::table! {
     filters (username) {
         username -> Text,
@@ -41,6 +96,7 @@ diesel::table! {
         ascending -> Bool,
         filter -> Nullable<Text>,
         only_favored -> Bool,
+        tag_id -> Nullable<Int4>,
     }
 }
 
@@ -55,6 +111,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    listening_rollups (username, day, hour, podcast_episode_id) {
+        username -> Text,
+        day -> Date,
+        hour -> Int4,
+        podcast_id -> Int4,
+        podcast_episode_id -> Int4,
+        listened_seconds -> Int4,
+    }
+}
+
 diesel::table! {
     notifications (id) {
         id -> Int4,
@@ -65,6 +132,45 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    playback_preferences (username, podcast_id) {
+        username -> Text,
+        podcast_id -> Int4,
+        playback_speed -> Float4,
+        volume -> Int4,
+        skip_intro_seconds -> Int4,
+        skip_outro_seconds -> Int4,
+        trim_silence -> Bool,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    playlist_items (playlist_id, podcast_episode_id) {
+        playlist_id -> Int4,
+        podcast_episode_id -> Int4,
+        position -> Int4,
+    }
+}
+
+diesel::table! {
+    playlists (id) {
+        id -> Int4,
+        username -> Text,
+        name -> Text,
+        rules -> Nullable<Text>,
+        feed_token -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    podcast_credentials (podcast_id) {
+        podcast_id -> Int4,
+        credentials -> Text,
+    }
+}
+
 diesel::table! {
     podcast_episodes (id) {
         id -> Int4,
@@ -80,7 +186,13 @@ diesel::table! {
         description -> Text,
         status -> Bpchar,
         download_time -> Nullable<Timestamp>,
-        guid -> Varchar
+        guid -> Varchar,
+        removed_upstream -> Bool,
+        season -> Nullable<Integer>,
+        episode_number -> Nullable<Integer>,
+        pinned -> Bool,
+        chapters_url -> Nullable<Text>,
+        file_size -> Nullable<BigInt>,
     }
 }
 
@@ -111,6 +223,32 @@ diesel::table! {
         active -> Bool,
         original_image_url -> Varchar,
         directory_name -> Varchar,
+        proxy_url -> Nullable<Text>,
+        write_metadata_tags -> Nullable<Bool>,
+    }
+}
+
+diesel::table! {
+    queue_auto_add_podcasts (username, podcast_id) {
+        username -> Text,
+        podcast_id -> Int4,
+    }
+}
+
+diesel::table! {
+    queue_feed_tokens (username) {
+        username -> Text,
+        token -> Text,
+    }
+}
+
+diesel::table! {
+    queue_items (id) {
+        id -> Int4,
+        username -> Text,
+        podcast_episode_id -> Int4,
+        position -> Int4,
+        added_at -> Timestamp,
     }
 }
 
@@ -135,6 +273,7 @@ diesel::table! {
         replacement_strategy -> Text,
         episode_format -> Text,
         podcast_format -> Text,
+        write_metadata_tags -> Bool,
     }
 }
 
@@ -149,6 +288,24 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    tags (id) {
+        id -> Int4,
+        username -> Text,
+        name -> Text,
+        parent_id -> Nullable<Int4>,
+        color -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    tags_podcasts (tag_id, podcast_id) {
+        tag_id -> Int4,
+        podcast_id -> Int4,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Int4,
@@ -160,22 +317,52 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(bookmarks -> podcast_episodes (podcast_episode_id));
+diesel::joinable!(cleanup_policies -> podcasts (podcast_id));
+diesel::joinable!(episode_shares -> podcast_episodes (podcast_episode_id));
+diesel::joinable!(episode_states -> podcast_episodes (podcast_episode_id));
 diesel::joinable!(favorites -> podcasts (podcast_id));
+diesel::joinable!(feed_fetch_reports -> podcasts (podcast_id));
+diesel::joinable!(listening_rollups -> podcast_episodes (podcast_episode_id));
+diesel::joinable!(listening_rollups -> podcasts (podcast_id));
+diesel::joinable!(playback_preferences -> podcasts (podcast_id));
+diesel::joinable!(playlist_items -> playlists (playlist_id));
+diesel::joinable!(playlist_items -> podcast_episodes (podcast_episode_id));
+diesel::joinable!(podcast_credentials -> podcasts (podcast_id));
 diesel::joinable!(podcast_episodes -> podcasts (podcast_id));
 diesel::joinable!(podcast_history_items -> podcasts (podcast_id));
+diesel::joinable!(queue_auto_add_podcasts -> podcasts (podcast_id));
+diesel::joinable!(queue_items -> podcast_episodes (podcast_episode_id));
+diesel::joinable!(tags_podcasts -> podcasts (podcast_id));
+diesel::joinable!(tags_podcasts -> tags (tag_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    bookmarks,
+    cleanup_policies,
     devices,
+    episode_shares,
+    episode_states,
     episodes,
     favorites,
+    feed_fetch_reports,
     filters,
     invites,
+    listening_rollups,
     notifications,
+    playback_preferences,
+    playlist_items,
+    playlists,
+    podcast_credentials,
     podcast_episodes,
     podcast_history_items,
     podcasts,
+    queue_auto_add_podcasts,
+    queue_feed_tokens,
+    queue_items,
     sessions,
     settings,
     subscriptions,
+    tags,
+    tags_podcasts,
     users,
 );
@@ -1,5 +1,27 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    bookmarks (id) {
+        id -> Integer,
+        username -> Text,
+        podcast_episode_id -> Integer,
+        position -> Integer,
+        end_position -> Nullable<Integer>,
+        note -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    cleanup_policies (id) {
+        id -> Integer,
+        podcast_id -> Nullable<Integer>,
+        keep_latest -> Nullable<Integer>,
+        delete_played_after_days -> Nullable<Integer>,
+        max_bytes -> Nullable<BigInt>,
+    }
+}
+
 diesel::table! {
     devices (id) {
         id -> Integer,
@@ -10,6 +32,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    episode_shares (id) {
+        id -> Integer,
+        token -> Text,
+        username -> Text,
+        podcast_episode_id -> Integer,
+        start_position -> Integer,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    episode_states (username, podcast_episode_id) {
+        username -> Text,
+        podcast_episode_id -> Integer,
+        state -> Text,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     episodes (id) {
         id -> Integer,
@@ -34,6 +77,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    feed_fetch_reports (podcast_id) {
+        podcast_id -> Integer,
+        fetched_at -> Timestamp,
+        successful -> Bool,
+        error -> Nullable<Text>,
+        new_episodes -> Integer,
+        updated_episodes -> Integer,
+        skipped_items -> Text,
+    }
+}
+
 diesel::table! {
     filters (username) {
         username -> Text,
@@ -41,6 +96,7 @@ diesel::table! {
         ascending -> Bool,
         filter -> Nullable<Text>,
         only_favored -> Bool,
+        tag_id -> Nullable<Integer>,
     }
 }
 
@@ -55,6 +111,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    listening_rollups (username, day, hour, podcast_episode_id) {
+        username -> Text,
+        day -> Date,
+        hour -> Integer,
+        podcast_id -> Integer,
+        podcast_episode_id -> Integer,
+        listened_seconds -> Integer,
+    }
+}
+
 diesel::table! {
     notifications (id) {
         id -> Integer,
@@ -65,6 +132,45 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    playback_preferences (username, podcast_id) {
+        username -> Text,
+        podcast_id -> Integer,
+        playback_speed -> Float,
+        volume -> Integer,
+        skip_intro_seconds -> Integer,
+        skip_outro_seconds -> Integer,
+        trim_silence -> Bool,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    playlist_items (playlist_id, podcast_episode_id) {
+        playlist_id -> Integer,
+        podcast_episode_id -> Integer,
+        position -> Integer,
+    }
+}
+
+diesel::table! {
+    playlists (id) {
+        id -> Integer,
+        username -> Text,
+        name -> Text,
+        rules -> Nullable<Text>,
+        feed_token -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    podcast_credentials (podcast_id) {
+        podcast_id -> Integer,
+        credentials -> Text,
+    }
+}
+
 diesel::table! {
     podcast_episodes (id) {
         id -> Integer,
@@ -81,6 +187,12 @@ diesel::table! {
         status -> Text,
         download_time -> Nullable<Timestamp>,
         guid -> Text,
+        removed_upstream -> Bool,
+        season -> Nullable<Integer>,
+        episode_number -> Nullable<Integer>,
+        pinned -> Bool,
+        chapters_url -> Nullable<Text>,
+        file_size -> Nullable<BigInt>,
     }
 }
 
@@ -111,6 +223,32 @@ diesel::table! {
         active -> Bool,
         original_image_url -> Text,
         directory_name -> Text,
+        proxy_url -> Nullable<Text>,
+        write_metadata_tags -> Nullable<Bool>,
+    }
+}
+
+diesel::table! {
+    queue_auto_add_podcasts (username, podcast_id) {
+        username -> Text,
+        podcast_id -> Integer,
+    }
+}
+
+diesel::table! {
+    queue_feed_tokens (username) {
+        username -> Text,
+        token -> Text,
+    }
+}
+
+diesel::table! {
+    queue_items (id) {
+        id -> Integer,
+        username -> Text,
+        podcast_episode_id -> Integer,
+        position -> Integer,
+        added_at -> Timestamp,
     }
 }
 
@@ -135,6 +273,7 @@ diesel::table! {
         replacement_strategy -> Text,
         episode_format -> Text,
         podcast_format -> Text,
+        write_metadata_tags -> Bool,
     }
 }
 
@@ -149,6 +288,24 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    tags (id) {
+        id -> Integer,
+        username -> Text,
+        name -> Text,
+        parent_id -> Nullable<Integer>,
+        color -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    tags_podcasts (tag_id, podcast_id) {
+        tag_id -> Integer,
+        podcast_id -> Integer,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Integer,
@@ -160,22 +317,52 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(bookmarks -> podcast_episodes (podcast_episode_id));
+diesel::joinable!(cleanup_policies -> podcasts (podcast_id));
+diesel::joinable!(episode_shares -> podcast_episodes (podcast_episode_id));
+diesel::joinable!(episode_states -> podcast_episodes (podcast_episode_id));
 diesel::joinable!(favorites -> podcasts (podcast_id));
+diesel::joinable!(feed_fetch_reports -> podcasts (podcast_id));
+diesel::joinable!(listening_rollups -> podcast_episodes (podcast_episode_id));
+diesel::joinable!(listening_rollups -> podcasts (podcast_id));
+diesel::joinable!(playback_preferences -> podcasts (podcast_id));
+diesel::joinable!(playlist_items -> playlists (playlist_id));
+diesel::joinable!(playlist_items -> podcast_episodes (podcast_episode_id));
+diesel::joinable!(podcast_credentials -> podcasts (podcast_id));
 diesel::joinable!(podcast_episodes -> podcasts (podcast_id));
 diesel::joinable!(podcast_history_items -> podcasts (podcast_id));
+diesel::joinable!(queue_auto_add_podcasts -> podcasts (podcast_id));
+diesel::joinable!(queue_items -> podcast_episodes (podcast_episode_id));
+diesel::joinable!(tags_podcasts -> podcasts (podcast_id));
+diesel::joinable!(tags_podcasts -> tags (tag_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    bookmarks,
+    cleanup_policies,
     devices,
+    episode_shares,
+    episode_states,
     episodes,
     favorites,
+    feed_fetch_reports,
     filters,
     invites,
+    listening_rollups,
     notifications,
+    playback_preferences,
+    playlist_items,
+    playlists,
+    podcast_credentials,
     podcast_episodes,
     podcast_history_items,
     podcasts,
+    queue_auto_add_podcasts,
+    queue_feed_tokens,
+    queue_items,
     sessions,
     settings,
     subscriptions,
+    tags,
+    tags_podcasts,
     users,
 );
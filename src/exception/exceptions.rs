@@ -41,7 +41,7 @@ impl PodFetchErrorTrait for PodFetchError {
 // Use default implementation for `error_response()` method
 impl error::ResponseError for PodFetchError {
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code)
+        HttpResponse::build(PodFetchErrorTrait::status_code(self))
             .body(self.name)
     }
 }
@@ -70,4 +70,104 @@ impl PodFetchError{
     pub fn podcast_directory_creation_error() -> PodFetchError {
         PodFetchError::new("Error creating podcast directory", StatusCode::INTERNAL_SERVER_ERROR)
     }
+
+    pub fn podcast_not_found_in_directory() -> PodFetchError {
+        PodFetchError::new("Podcast not found in the podcast directory", StatusCode::NOT_FOUND)
+    }
+
+    pub fn tag_not_found() -> PodFetchError {
+        PodFetchError::new("Tag not found", StatusCode::NOT_FOUND)
+    }
+
+    pub fn invalid_tag_name() -> PodFetchError {
+        PodFetchError::new("The name of a tag must not be empty", StatusCode::BAD_REQUEST)
+    }
+
+    pub fn tag_already_exists() -> PodFetchError {
+        PodFetchError::new("A tag with this name already exists in this folder", StatusCode::BAD_REQUEST)
+    }
+
+    pub fn invalid_tag_parent() -> PodFetchError {
+        PodFetchError::new("A tag cannot be nested inside itself", StatusCode::BAD_REQUEST)
+    }
+
+    pub fn podcast_not_found() -> PodFetchError {
+        PodFetchError::new("Podcast not found", StatusCode::NOT_FOUND)
+    }
+
+    pub fn episode_not_found() -> PodFetchError {
+        PodFetchError::new("Podcast episode not found", StatusCode::NOT_FOUND)
+    }
+
+    pub fn episode_not_in_queue() -> PodFetchError {
+        PodFetchError::new("The episode is not in the queue", StatusCode::NOT_FOUND)
+    }
+
+    pub fn invalid_queue_order() -> PodFetchError {
+        PodFetchError::new("The new order must contain every episode of the queue exactly once",
+                           StatusCode::BAD_REQUEST)
+    }
+
+    pub fn playlist_not_found() -> PodFetchError {
+        PodFetchError::new("Playlist not found", StatusCode::NOT_FOUND)
+    }
+
+    pub fn invalid_playlist_name() -> PodFetchError {
+        PodFetchError::new("The name of a playlist must not be empty", StatusCode::BAD_REQUEST)
+    }
+
+    pub fn playlist_already_exists() -> PodFetchError {
+        PodFetchError::new("A playlist with this name already exists", StatusCode::BAD_REQUEST)
+    }
+
+    pub fn invalid_smart_playlist_rules() -> PodFetchError {
+        PodFetchError::new("The rules of the smart playlist are invalid", StatusCode::BAD_REQUEST)
+    }
+
+    pub fn smart_playlist_not_editable() -> PodFetchError {
+        PodFetchError::new("The episodes of a smart playlist are chosen by its rules",
+                           StatusCode::BAD_REQUEST)
+    }
+
+    pub fn invalid_episode_state_filter() -> PodFetchError {
+        PodFetchError::new("Select the episodes by ids, podcast or publication date",
+                           StatusCode::BAD_REQUEST)
+    }
+
+    pub fn invalid_stats_range() -> PodFetchError {
+        PodFetchError::new("The start of the range must not be after its end",
+                           StatusCode::BAD_REQUEST)
+    }
+
+    pub fn invalid_playback_preferences() -> PodFetchError {
+        PodFetchError::new("The playback speed, volume or skipped seconds are out of range",
+                           StatusCode::BAD_REQUEST)
+    }
+
+    pub fn bookmark_not_found() -> PodFetchError {
+        PodFetchError::new("Bookmark not found", StatusCode::NOT_FOUND)
+    }
+
+    pub fn invalid_bookmark() -> PodFetchError {
+        PodFetchError::new("The position must not be negative, the end of a clip must be after \
+        its start and the note must not be too long", StatusCode::BAD_REQUEST)
+    }
+
+    pub fn user_not_found() -> PodFetchError {
+        PodFetchError::new("User not found", StatusCode::NOT_FOUND)
+    }
+
+    pub fn share_not_found() -> PodFetchError {
+        PodFetchError::new("Share link not found", StatusCode::NOT_FOUND)
+    }
+
+    pub fn invalid_share() -> PodFetchError {
+        PodFetchError::new("The start must not be negative and the share link must expire in the \
+        future, at most a year from now", StatusCode::BAD_REQUEST)
+    }
+
+    pub fn invalid_cleanup_policy() -> PodFetchError {
+        PodFetchError::new("At least one episode has to be kept and the days and the maximum \
+        size must not be negative", StatusCode::BAD_REQUEST)
+    }
 }
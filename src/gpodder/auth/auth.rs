@@ -18,16 +18,13 @@ pub async fn login(username:web::Path<String>, rq: HttpRequest, conn:Data<DbPool
 Responder {
     let env = env_service.lock().ignore_poison();
     let conn = &mut conn.get().unwrap();
-    match rq.clone().cookie("sessionid") {
-        Some(cookie) => {
-            let session = cookie.value();
-            let opt_session = Session::find_by_session_id(session, conn);
-                if opt_session.is_ok(){
-                    let user_cookie = create_session_cookie(opt_session.unwrap());
-                    return HttpResponse::Ok().cookie(user_cookie).finish();
-                }
-        }
-        None=>{}
+    if let Some(cookie) = rq.clone().cookie("sessionid") {
+        let session = cookie.value();
+        let opt_session = Session::find_by_session_id(session, conn);
+            if let Ok(session) = opt_session {
+                let user_cookie = create_session_cookie(session);
+                return HttpResponse::Ok().cookie(user_cookie).finish();
+            }
     }
 
     let authorization = rq.headers().get("Authorization").unwrap().to_str().unwrap();
@@ -37,7 +34,7 @@ Responder {
         return HttpResponse::Unauthorized().finish();
     }
     if unwrapped_username == env.username && password == env.password {
-        return HttpResponse::Ok().finish();
+        HttpResponse::Ok().finish()
     } else {
         match User::find_by_username(&unwrapped_username, conn) {
             Some(user) => {
@@ -50,9 +47,7 @@ Responder {
                     HttpResponse::Unauthorized().finish()
                 }
             }
-            None => {
-                return  HttpResponse::Unauthorized().finish()
-            }
+            None => HttpResponse::Unauthorized().finish()
         }
     }
 }
@@ -41,11 +41,11 @@ pub async fn get_episode_actions(username: web::Path<String>, pool: Data<DbPool>
                 return HttpResponse::Unauthorized().finish();
             }
 
-            let since_date = NaiveDateTime::from_timestamp_opt(since.since as i64, 0);
+            let since_date = NaiveDateTime::from_timestamp_opt(since.since, 0);
             println!("{}",since_date.unwrap());
-            let mut actions = Episode::get_actions_by_username(username.clone(), &mut *pool.get().unwrap(), since_date)
+            let mut actions = Episode::get_actions_by_username(username.clone(), &mut pool.get().unwrap(), since_date)
                 .await;
-            let watch_logs = PodcastHistoryItem::get_watch_logs_by_username(username.clone(), &mut *pool.get()
+            let watch_logs = PodcastHistoryItem::get_watch_logs_by_username(username.clone(), &mut pool.get()
                 .unwrap(), since_date.unwrap()).iter().map(|watch_log| {
                 Episode{
                     id: 0,
@@ -86,7 +86,7 @@ Responder {
             let mut inserted_episodes: Vec<Episode> = vec![];
             podcast_episode.iter().for_each(|episode| {
                 let episode = Episode::convert_to_episode(episode, username.clone());
-                inserted_episodes.push(Episode::insert_episode(episode.borrow(), &mut *conn.get().unwrap())
+                inserted_episodes.push(Episode::insert_episode(episode.borrow(), &mut conn.get().unwrap())
                     .expect("Unable to insert episode"));
 
                 if EpisodeAction::from_string(&episode.clone().action) == EpisodeAction::Play {
@@ -95,15 +95,14 @@ Responder {
                     let mut first_split = episode.episode.split("?");
                     let res = first_split.next();
 
-                    if res.is_some() {
-                        episode_url = res.unwrap().parse().unwrap()
+                    if let Some(res) = res {
+                        episode_url = res.parse().unwrap()
                     }
 
-                    let podcast_episode = PodcastEpisode::query_podcast_episode_by_url(&mut *conn.get()
+                    let podcast_episode = PodcastEpisode::query_podcast_episode_by_url(&mut conn.get()
                         .unwrap(),
-                                                                           &*episode_url);
+                                                                           &episode_url);
                     if podcast_episode.clone().unwrap().is_none() {
-                        return;
                     }
                 }
             });
@@ -38,7 +38,7 @@ Responder {
 
             let res = SubscriptionChangesToClient::get_device_subscriptions(&deviceid, &username, query
                 .since,
-                                                                            &mut *conn.get().unwrap()).await;
+                                                                            &mut conn.get().unwrap()).await;
 
             match res {
                 Ok(res) => {
@@ -66,7 +66,7 @@ pub async fn upload_subscription_changes(upload_request: web::Json<SubscriptionU
             }
             SubscriptionChangesToClient::update_subscriptions(&deviceid, &username,
                                                               upload_request,
-                                                              &mut *conn.get().unwrap()).await.expect("TODO: panic message");
+                                                              &mut conn.get().unwrap()).await.expect("TODO: panic message");
 
             HttpResponse::Ok().json(SubscriptionPostResponse {
                 update_urls: vec![],
@@ -6,7 +6,7 @@ extern crate core;
 extern crate serde_json;
 
 use actix::{Actor};
-use actix_files::{Files, NamedFile};
+use actix_files::NamedFile;
 use actix_web::dev::{fn_service, ServiceFactory, ServiceRequest, ServiceResponse};
 use actix_web::middleware::{Condition, Logger};
 use actix_web::web::{redirect, Data};
@@ -34,17 +34,58 @@ use crate::controllers::api_doc::ApiDoc;
 use crate::controllers::notification_controller::{
     dismiss_notifications, get_unread_notifications,
 };
-use crate::controllers::podcast_controller::{add_podcast, add_podcast_by_feed, delete_podcast, find_all_podcasts, find_podcast, find_podcast_by_id, get_filter, proxy_podcast, refresh_all_podcasts, search_podcasts};
+use crate::controllers::podcast_controller::{add_podcast, add_podcast_by_feed, delete_podcast, find_all_podcasts, find_podcast, fetch_podcast_history, find_podcast_by_id, get_fetch_report, get_filter, get_podcast_credentials, update_podcast_credentials, delete_podcast_credentials, update_podcast_proxy, update_podcast_metadata_tags, proxy_podcast, refresh_all_podcasts, search_podcasts};
 use crate::controllers::podcast_controller::{
     add_podcast_from_podindex, download_podcast, favorite_podcast, get_favored_podcasts,
     import_podcasts_from_opml, query_for_podcast, update_active_podcast,
 };
 use crate::controllers::podcast_episode_controller::{download_podcast_episodes_of_podcast, find_all_podcast_episodes_of_podcast, get_timeline};
-use crate::controllers::settings_controller::{get_opml, get_settings, run_cleanup, update_name, update_settings};
+use crate::controllers::playback_preference_controller::{get_all_playback_preferences,
+                                                         get_playback_preferences,
+                                                         reset_playback_preferences,
+                                                         update_playback_preferences};
+use crate::controllers::statistics_controller::{export_listening_stats,
+                                                get_instance_listening_stats,
+                                                get_listening_stats, get_wrapped};
+use crate::controllers::bookmark_controller::{create_bookmark, delete_bookmark,
+                                              export_bookmarks, get_bookmarks,
+                                              get_episode_bookmarks, update_bookmark};
+use crate::controllers::personal_data_controller::export_personal_data;
+use crate::controllers::share_controller::{create_share, get_share_media, get_share_page,
+                                           get_shares, revoke_share};
+use crate::controllers::media_controller::get_media;
+use crate::controllers::library_controller::{check_library, get_reorganization_plan,
+                                             reorganize_library, repair_library};
+use crate::controllers::cleanup_controller::{delete_podcast_cleanup_policy, get_cleanup_policy,
+                                             get_podcast_cleanup_policy, pin_episode,
+                                             preview_cleanup, update_cleanup_policy,
+                                             update_podcast_cleanup_policy};
+use crate::controllers::podcast_episode_controller::{get_episode_state,
+                                                     get_episode_states_of_podcast,
+                                                     update_episode_state, update_episode_states};
+use crate::controllers::discovery_controller::{get_discovery_providers, get_trending_podcasts, lookup_discovered_podcast, search_discovery_provider, subscribe_discovered_podcast};
+use crate::controllers::recommendation_controller::get_recommendations;
+use crate::controllers::tag_controller::{add_podcast_to_tag, create_tag, delete_tag, get_tags,
+                                           remove_podcast_from_tag, update_tag};
+use crate::controllers::queue_controller::{add_queue_auto_add_podcast, add_to_queue, clear_queue,
+                                             get_queue, get_queue_auto_add_podcasts,
+                                             get_queue_feed_urls, move_to_top_of_queue,
+                                             remove_from_queue, remove_queue_auto_add_podcast,
+                                             reorder_queue, reset_queue_feed_urls};
+use crate::controllers::playlist_controller::{add_episode_to_playlist, create_playlist,
+                                                delete_playlist, get_playlist,
+                                                get_playlist_episodes, get_playlists,
+                                                remove_episode_from_playlist,
+                                                reset_playlist_feed_urls, update_playlist};
+use crate::controllers::search_controller::{search_episodes, search_podcasts_full_text};
+use crate::controllers::settings_controller::{get_opml, get_settings, preview_name, run_cleanup,
+                                              update_name, update_settings};
 use crate::controllers::sys_info_controller::{get_info, get_public_config, get_sys_info, login};
 use crate::controllers::watch_time_controller::{get_last_watched, get_watchtime, log_watchtime};
 use crate::controllers::websocket_controller::{
-    get_rss_feed, get_rss_feed_for_podcast, start_connection,
+    get_m3u_playlist_for_playlist, get_m3u_playlist_for_queue, get_pls_playlist_for_playlist,
+    get_rss_feed, get_rss_feed_for_playlist, get_rss_feed_for_podcast, get_rss_feed_for_queue,
+    start_connection,
 };
 pub use controllers::controller_utils::*;
 use crate::auth_middleware::{AuthFilter};
@@ -61,8 +102,13 @@ use crate::models::podcasts::Podcast;
 use crate::models::session::Session;
 use crate::models::settings::Setting;
 use crate::models::web_socket_message::Lobby;
+use crate::service::credential_service::CredentialService;
+use crate::service::statistics_service::StatisticsService;
+use crate::service::share_service::ShareService;
+use crate::service::cleanup_service::CleanupService;
 use crate::service::environment_service::EnvironmentService;
 use crate::service::file_service::FileService;
+use crate::service::http_client::HttpClientFactory;
 use crate::service::jwkservice::JWKService;
 use crate::service::logging_service::init_logging;
 use crate::service::mapping_service::MappingService;
@@ -165,6 +211,8 @@ async fn main() -> std::io::Result<()> {
     }
 
     insert_default_settings_if_not_present();
+    CredentialService::migrate_credentials_from_feed_urls(&mut connection);
+    StatisticsService::backfill_rollups(&mut connection);
 
     thread::spawn(|| {
         let mut scheduler = Scheduler::new();
@@ -194,14 +242,12 @@ async fn main() -> std::io::Result<()> {
             let conn= &mut establish_connection();
             Session::cleanup_sessions(conn).expect("Error clearing old \
             sessions");
-            let mut podcast_episode_service = PodcastEpisodeService::new();
+            ShareService::delete_expired_shares(conn);
             let settings = Setting::get_settings(conn);
             match settings {
                 Some(settings) => {
-                    if settings.auto_cleanup {
-                        podcast_episode_service.cleanup_old_episodes(settings.auto_cleanup_days,
-                                                                     &mut establish_connection());
-                    }
+                    // The cleanup policies apply even if the age based auto cleanup is disabled
+                    CleanupService::run_cleanup(conn, settings.auto_cleanup);
                 }
                 None => {
                     log::error!("Could not get settings from database");
@@ -263,8 +309,7 @@ pub fn get_global_scope() -> Scope {
         .service(get_client_parametrization)
         .service(proxy_podcast)
         .service(get_ui_config())
-        .service(Files::new("/podcasts", "podcasts")
-            .disable_content_disposition())
+        .service(get_media)
         .service(redirect("/swagger-ui", "/swagger-ui/"))
         .service(SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-doc/openapi.json", openapi))
         .service(redirect("/", "./ui/"))
@@ -272,6 +317,13 @@ pub fn get_global_scope() -> Scope {
         .service(start_connection)
         .service(get_rss_feed)
         .service(get_rss_feed_for_podcast)
+        .service(get_rss_feed_for_queue)
+        .service(get_m3u_playlist_for_queue)
+        .service(get_rss_feed_for_playlist)
+        .service(get_m3u_playlist_for_playlist)
+        .service(get_pls_playlist_for_playlist)
+        .service(get_share_page)
+        .service(get_share_media)
 }
 
 fn get_private_api() -> Scope<impl ServiceFactory<ServiceRequest, Config = (), Response = ServiceResponse<EitherBody<BoxBody>>, Error = actix_web::Error, InitError = ()>> {
@@ -279,6 +331,7 @@ fn get_private_api() -> Scope<impl ServiceFactory<ServiceRequest, Config = (), R
     web::scope("")
         .wrap(middleware)
         .service(update_name)
+        .service(preview_name)
         .service(get_filter)
         .service(search_podcasts)
         .service(add_podcast_by_feed)
@@ -297,7 +350,81 @@ fn get_private_api() -> Scope<impl ServiceFactory<ServiceRequest, Config = (), R
         .service(get_unread_notifications)
         .service(dismiss_notifications)
         .service(download_podcast)
+        .service(get_fetch_report)
+        .service(fetch_podcast_history)
+        .service(get_podcast_credentials)
+        .service(update_podcast_credentials)
+        .service(delete_podcast_credentials)
+        .service(update_podcast_proxy)
+        .service(update_podcast_metadata_tags)
         .service(query_for_podcast)
+        .service(search_episodes)
+        .service(search_podcasts_full_text)
+        .service(get_discovery_providers)
+        .service(search_discovery_provider)
+        .service(get_trending_podcasts)
+        .service(lookup_discovered_podcast)
+        .service(subscribe_discovered_podcast)
+        .service(get_recommendations)
+        .service(get_tags)
+        .service(create_tag)
+        .service(update_tag)
+        .service(delete_tag)
+        .service(add_podcast_to_tag)
+        .service(remove_podcast_from_tag)
+        .service(get_queue)
+        .service(add_to_queue)
+        .service(clear_queue)
+        .service(reorder_queue)
+        .service(get_queue_auto_add_podcasts)
+        .service(add_queue_auto_add_podcast)
+        .service(remove_queue_auto_add_podcast)
+        .service(get_queue_feed_urls)
+        .service(reset_queue_feed_urls)
+        .service(remove_from_queue)
+        .service(move_to_top_of_queue)
+        .service(get_playlists)
+        .service(create_playlist)
+        .service(get_playlist)
+        .service(update_playlist)
+        .service(delete_playlist)
+        .service(get_playlist_episodes)
+        .service(add_episode_to_playlist)
+        .service(remove_episode_from_playlist)
+        .service(reset_playlist_feed_urls)
+        .service(get_episode_states_of_podcast)
+        .service(get_episode_state)
+        .service(update_episode_state)
+        .service(update_episode_states)
+        .service(get_listening_stats)
+        .service(get_instance_listening_stats)
+        .service(get_wrapped)
+        .service(export_listening_stats)
+        .service(get_all_playback_preferences)
+        .service(get_playback_preferences)
+        .service(update_playback_preferences)
+        .service(reset_playback_preferences)
+        .service(get_bookmarks)
+        .service(export_bookmarks)
+        .service(update_bookmark)
+        .service(delete_bookmark)
+        .service(get_episode_bookmarks)
+        .service(create_bookmark)
+        .service(export_personal_data)
+        .service(get_shares)
+        .service(create_share)
+        .service(revoke_share)
+        .service(get_reorganization_plan)
+        .service(reorganize_library)
+        .service(check_library)
+        .service(repair_library)
+        .service(get_cleanup_policy)
+        .service(update_cleanup_policy)
+        .service(get_podcast_cleanup_policy)
+        .service(update_podcast_cleanup_policy)
+        .service(delete_podcast_cleanup_policy)
+        .service(preview_cleanup)
+        .service(pin_episode)
         .service(download_podcast_episodes_of_podcast)
         .service(get_sys_info)
         .service(get_favored_podcasts)
@@ -342,10 +469,7 @@ pub fn get_ui_config() -> Scope {
                     return Ok(ServiceResponse::new(req.clone(), file.into_response(&req)))
                 }
             }
-            if type_of.contains("css"){
-                content  = fix_links(&content)
-            }
-            else if type_of.contains("javascript"){
+            if type_of.contains("css") || type_of.contains("javascript"){
                 content = fix_links(&content)
             }
             let res = HttpResponse::Ok()
@@ -403,30 +527,32 @@ pub fn check_server_config(service1: EnvironmentService) {
         exit(1);
     }
 
-    if service1.http_basic {
-        if service1.password.is_empty() || service1.username.is_empty() {
+    if service1.http_basic
+        && (service1.password.is_empty() || service1.username.is_empty()) {
             log::error!("BASIC_AUTH activated but no username or password set. Please set username and password in the .env file.");
             exit(1);
         }
+
+    if let Err(e) = HttpClientFactory::new().validate() {
+        log::error!("Invalid outbound http configuration: {}", e);
+        exit(1);
     }
 
-    if service1.gpodder_integration_enabled{
-        if !(service1.http_basic || service1.oidc_configured){
+    if service1.gpodder_integration_enabled
+        && !(service1.http_basic || service1.oidc_configured){
             eprintln!("GPODDER_INTEGRATION_ENABLED activated but no BASIC_AUTH or OIDC_AUTH set. Please set BASIC_AUTH or OIDC_AUTH in the .env file.");
             exit(1);
         }
-    }
 
     if service1.http_basic && service1.oidc_configured{
         log::error!("You cannot have oidc and basic auth enabled at the same time. Please disable one of them.");
     }
 
-    if var(TELEGRAM_API_ENABLED).is_ok(){
-        if !var(TELEGRAM_BOT_TOKEN).is_ok() || !var(TELEGRAM_BOT_CHAT_ID).is_ok() {
+    if var(TELEGRAM_API_ENABLED).is_ok()
+        && (var(TELEGRAM_BOT_TOKEN).is_err() || var(TELEGRAM_BOT_CHAT_ID).is_err()) {
             eprintln!("TELEGRAM_API_ENABLED activated but no TELEGRAM_API_TOKEN or TELEGRAM_API_CHAT_ID set. Please set TELEGRAM_API_TOKEN and TELEGRAM_API_CHAT_ID in the .env file.");
             exit(1);
         }
-    }
 }
 
 #[cfg(sqlite)]
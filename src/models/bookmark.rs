@@ -0,0 +1,150 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::{ExpressionMethods, JoinOnDsl, OptionalExtension, QueryDsl, Queryable, RunQueryDsl};
+use diesel::insert_into;
+use utoipa::ToSchema;
+use crate::DbConnection;
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcasts::Podcast;
+
+/// A position in an episode the user wants to come back to. Bookmarks with an end position are
+/// clips.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    pub id: i32,
+    pub username: String,
+    pub podcast_episode_id: i32,
+    pub position: i32,
+    pub end_position: Option<i32>,
+    pub note: Option<String>,
+    pub created_at: NaiveDateTime
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkDto {
+    pub id: i32,
+    /// Seconds from the start of the episode
+    pub position: i32,
+    pub end_position: Option<i32>,
+    pub note: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub podcast_episode: PodcastEpisode,
+    pub podcast: Podcast
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkCreateModel {
+    pub position: i32,
+    pub end_position: Option<i32>,
+    pub note: Option<String>
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BookmarkExportFormat {
+    Markdown,
+    Json
+}
+
+impl Bookmark {
+    pub fn insert_bookmark(conn: &mut DbConnection, username_1: &str, podcast_episode_id_1: i32,
+                           bookmark: &BookmarkCreateModel) -> Bookmark {
+        use crate::dbconfig::schema::bookmarks::dsl::*;
+
+        insert_into(bookmarks)
+            .values((
+                username.eq(username_1),
+                podcast_episode_id.eq(podcast_episode_id_1),
+                position.eq(bookmark.position),
+                end_position.eq(bookmark.end_position),
+                note.eq(&bookmark.note),
+                created_at.eq(Utc::now().naive_utc())
+            ))
+            .get_result::<Bookmark>(conn)
+            .expect("Error inserting bookmark")
+    }
+
+    pub fn update_bookmark(conn: &mut DbConnection, bookmark_id: i32,
+                           bookmark: &BookmarkCreateModel) -> Bookmark {
+        use crate::dbconfig::schema::bookmarks::dsl::*;
+
+        diesel::update(bookmarks.filter(id.eq(bookmark_id)))
+            .set((
+                position.eq(bookmark.position),
+                end_position.eq(bookmark.end_position),
+                note.eq(&bookmark.note)
+            ))
+            .get_result::<Bookmark>(conn)
+            .expect("Error updating bookmark")
+    }
+
+    pub fn get_bookmark_of_user(conn: &mut DbConnection, bookmark_id: i32, username_1: &str)
+        -> Option<Bookmark> {
+        use crate::dbconfig::schema::bookmarks::dsl::*;
+
+        bookmarks
+            .filter(id.eq(bookmark_id))
+            .filter(username.eq(username_1))
+            .first::<Bookmark>(conn)
+            .optional()
+            .expect("Error loading bookmark")
+    }
+
+    /// The bookmarks of the user with their episodes, optionally only those of an episode or a
+    /// podcast. Ordered by podcast, episode and position.
+    pub fn get_bookmarks_of_user(conn: &mut DbConnection, username_1: &str,
+                                 podcast_episode_id_1: Option<i32>, podcast_id_1: Option<i32>)
+        -> Vec<(Bookmark, PodcastEpisode, Podcast)> {
+        use crate::dbconfig::schema::bookmarks::dsl::*;
+        use crate::dbconfig::schema::podcast_episodes;
+        use crate::dbconfig::schema::podcasts;
+
+        let mut query = bookmarks
+            .inner_join(podcast_episodes::table.on(podcast_episode_id.eq(podcast_episodes::id)))
+            .inner_join(podcasts::table.on(podcast_episodes::podcast_id.eq(podcasts::id)))
+            .filter(username.eq(username_1.to_string()))
+            .into_boxed();
+        if let Some(podcast_episode_id_1) = podcast_episode_id_1 {
+            query = query.filter(podcast_episode_id.eq(podcast_episode_id_1));
+        }
+        if let Some(podcast_id_1) = podcast_id_1 {
+            query = query.filter(podcast_episodes::podcast_id.eq(podcast_id_1));
+        }
+        query
+            .order((podcasts::name.asc(), podcasts::id.asc(),
+                    podcast_episodes::date_of_recording.desc(), podcast_episodes::id.asc(),
+                    position.asc(), id.asc()))
+            .load::<(Bookmark, PodcastEpisode, Podcast)>(conn)
+            .expect("Error loading bookmarks")
+    }
+
+    pub fn delete_bookmark(conn: &mut DbConnection, bookmark_id: i32) {
+        use crate::dbconfig::schema::bookmarks::dsl::*;
+
+        diesel::delete(bookmarks.filter(id.eq(bookmark_id)))
+            .execute(conn)
+            .expect("Error deleting bookmark");
+    }
+
+    pub fn delete_by_username(username_1: &str, conn: &mut DbConnection) -> Result<(),
+        diesel::result::Error> {
+        use crate::dbconfig::schema::bookmarks::dsl::*;
+
+        diesel::delete(bookmarks.filter(username.eq(username_1))).execute(conn)?;
+        Ok(())
+    }
+
+    pub fn delete_by_podcast_id(conn: &mut DbConnection, podcast_id_1: i32) {
+        use crate::dbconfig::schema::bookmarks::dsl::*;
+        use crate::dbconfig::schema::podcast_episodes;
+
+        let episodes_of_podcast = podcast_episodes::table
+            .filter(podcast_episodes::podcast_id.eq(podcast_id_1))
+            .select(podcast_episodes::id);
+        diesel::delete(bookmarks.filter(podcast_episode_id.eq_any(episodes_of_podcast)))
+            .execute(conn)
+            .expect("Error deleting bookmarks of podcast");
+    }
+}
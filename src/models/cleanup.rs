@@ -0,0 +1,133 @@
+use diesel::{Connection, ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl};
+use diesel::insert_into;
+use utoipa::ToSchema;
+use crate::dbconfig::schema::cleanup_policies;
+use crate::DbConnection;
+
+/// Rules for deleting downloaded episodes. The policy without a podcast applies to every
+/// podcast, a policy of a podcast overrides the rules it sets.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupPolicy {
+    pub id: i32,
+    pub podcast_id: Option<i32>,
+    pub keep_latest: Option<i32>,
+    pub delete_played_after_days: Option<i32>,
+    pub max_bytes: Option<i64>
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = cleanup_policies)]
+struct NewCleanupPolicy {
+    podcast_id: Option<i32>,
+    keep_latest: Option<i32>,
+    delete_played_after_days: Option<i32>,
+    max_bytes: Option<i64>
+}
+
+/// Rules that are empty are not applied
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupPolicyDto {
+    /// Only the newest episodes of a podcast are kept
+    pub keep_latest: Option<i32>,
+    /// Episodes that were played are deleted after this many days
+    pub delete_played_after_days: Option<i32>,
+    /// The oldest episodes are deleted until the downloads take up less space
+    pub max_bytes: Option<i64>
+}
+
+impl From<CleanupPolicy> for CleanupPolicyDto {
+    fn from(policy: CleanupPolicy) -> Self {
+        CleanupPolicyDto {
+            keep_latest: policy.keep_latest,
+            delete_played_after_days: policy.delete_played_after_days,
+            max_bytes: policy.max_bytes
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum CleanupReason {
+    /// Downloaded longer ago than the auto cleanup days
+    Age,
+    KeepLatest,
+    Played,
+    PodcastQuota,
+    StorageQuota
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupEpisode {
+    pub podcast_episode_id: i32,
+    pub episode_id: String,
+    pub podcast_id: i32,
+    pub podcast_name: String,
+    pub name: String,
+    pub reason: CleanupReason,
+    pub bytes: u64
+}
+
+/// The episodes that are deleted by a cleanup and the space that is freed
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupReport {
+    pub episodes: Vec<CleanupEpisode>,
+    pub freed_bytes: u64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PinnedUpdateModel {
+    pub pinned: bool
+}
+
+impl CleanupPolicy {
+    pub fn get_policies(conn: &mut DbConnection) -> Vec<CleanupPolicy> {
+        use crate::dbconfig::schema::cleanup_policies::dsl::*;
+
+        cleanup_policies
+            .load::<CleanupPolicy>(conn)
+            .expect("Error loading cleanup policies")
+    }
+
+    /// Replaces the policy of the podcast or the global policy if no podcast is given
+    pub fn save_policy(conn: &mut DbConnection, podcast_id_1: Option<i32>,
+                       policy: &CleanupPolicyDto) -> CleanupPolicy {
+        use crate::dbconfig::schema::cleanup_policies::dsl::*;
+
+        let new_policy = NewCleanupPolicy {
+            podcast_id: podcast_id_1,
+            keep_latest: policy.keep_latest,
+            delete_played_after_days: policy.delete_played_after_days,
+            max_bytes: policy.max_bytes
+        };
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            Self::delete_policy(conn, podcast_id_1)?;
+            insert_into(cleanup_policies)
+                .values(&new_policy)
+                .get_result::<CleanupPolicy>(conn)
+        }).expect("Error saving cleanup policy")
+    }
+
+    pub fn delete_by_podcast_id(conn: &mut DbConnection, podcast_id_1: i32) {
+        Self::delete_policy(conn, Some(podcast_id_1))
+            .expect("Error deleting cleanup policy");
+    }
+
+    fn delete_policy(conn: &mut DbConnection, podcast_id_1: Option<i32>)
+        -> Result<usize, diesel::result::Error> {
+        use crate::dbconfig::schema::cleanup_policies::dsl::*;
+
+        match podcast_id_1 {
+            Some(podcast_id_1) => diesel::delete(cleanup_policies
+                .filter(podcast_id.eq(podcast_id_1)))
+                .execute(conn),
+            None => diesel::delete(cleanup_policies
+                .filter(podcast_id.is_null()))
+                .execute(conn)
+        }
+    }
+}
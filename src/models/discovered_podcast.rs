@@ -0,0 +1,28 @@
+use utoipa::ToSchema;
+
+/// A podcast found in a podcast directory. Every discovery provider maps its results to this
+/// model.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredPodcast {
+    pub provider: String,
+    /// Id of the podcast within the directory of the provider
+    pub id: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub feed_url: String,
+    pub image_url: Option<String>,
+    pub website_url: Option<String>,
+    pub categories: Vec<String>,
+    pub episode_count: Option<i64>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveryProviderInfo {
+    pub id: String,
+    pub name: String,
+    pub configured: bool,
+    pub supports_trending: bool
+}
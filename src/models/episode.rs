@@ -1,3 +1,4 @@
+use std::fmt;
 use std::collections::HashMap;
 use std::io::Error;
 use chrono::NaiveDateTime;
@@ -56,8 +57,8 @@ impl Episode{
             .optional()
             .expect("");
 
-        if res.is_some() {
-            return Ok(res.unwrap())
+        if let Some(res) = res {
+            return Ok(res)
         }
 
         diesel::insert_into(episodes)
@@ -80,7 +81,7 @@ impl Episode{
         EpisodeDto {
             podcast: self.podcast.clone(),
             episode: self.episode.clone(),
-            timestamp: self.timestamp.clone(),
+            timestamp: self.timestamp,
             guid: self.guid.clone(),
             action: EpisodeAction::from_string(&self.action),
             started: self.started,
@@ -97,7 +98,7 @@ impl Episode{
             device: episode_dto.device.clone(),
             podcast: episode_dto.podcast.clone(),
             episode: episode_dto.episode.clone(),
-            timestamp: episode_dto.timestamp.clone(),
+            timestamp: episode_dto.timestamp,
             guid: episode_dto.guid.clone(),
             action: episode_dto.action.clone().to_string(),
             started: episode_dto.started,
@@ -126,6 +127,19 @@ impl Episode{
         }
     }
 
+    /// Returns username, podcast id and episode id of every episode played with a gpodder client.
+    pub fn get_played_episodes(conn: &mut DbConnection) -> Vec<(String, i32, String)> {
+        use crate::dbconfig::schema::podcast_episodes;
+        use diesel::JoinOnDsl;
+
+        episodes::table
+            .inner_join(podcast_episodes::table.on(episodes::episode.eq(podcast_episodes::url)))
+            .filter(episodes::action.eq(EpisodeAction::Play.to_string()))
+            .select((episodes::username, podcast_episodes::podcast_id, podcast_episodes::episode_id))
+            .load::<(String, i32, String)>(conn)
+            .expect("Error loading played episodes")
+    }
+
     pub fn get_watch_log_by_username_and_episode(username1: String, conn: &mut DbConnection,
                                                  episode_1: String) ->Option<Episode>{
 
@@ -135,7 +149,8 @@ impl Episode{
         use diesel::JoinOnDsl;
         use diesel::Table;
 
-        let query = episodes
+        
+        episodes
             .inner_join(podcast_episode_table.on(podcast.eq(rssfeed)))
             .filter(username.eq(username1))
             .filter(episode.eq(episode_1))
@@ -143,8 +158,7 @@ impl Episode{
             .select(episodes::all_columns())
             .first::<Episode>(conn)
             .optional()
-            .expect("");
-        return query;
+            .expect("")
     }
 
     pub fn convert_to_podcast_history_item(&self, podcast_1: Podcast,pod_episode: PodcastEpisode)
@@ -241,21 +255,15 @@ impl EpisodeAction{
         }
     }
 
-    pub fn to_string(&self) -> String {
+}
+
+impl fmt::Display for EpisodeAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            EpisodeAction::New => "new".to_string(),
-            EpisodeAction::Download => "download".to_string(),
-            EpisodeAction::Play => "play".to_string(),
-            EpisodeAction::Delete => "delete".to_string(),
+            EpisodeAction::New => write!(f, "new"),
+            EpisodeAction::Download => write!(f, "download"),
+            EpisodeAction::Play => write!(f, "play"),
+            EpisodeAction::Delete => write!(f, "delete"),
         }
     }
 }
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename_all = "lowercase")]
-pub enum EpisodeActionRaw {
-    New,
-    Download,
-    Play,
-    Delete,
-}
\ No newline at end of file
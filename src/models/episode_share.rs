@@ -0,0 +1,145 @@
+use chrono::NaiveDateTime;
+use diesel::{ExpressionMethods, JoinOnDsl, OptionalExtension, QueryDsl, Queryable, RunQueryDsl};
+use diesel::insert_into;
+use utoipa::ToSchema;
+use crate::DbConnection;
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcasts::Podcast;
+
+/// A public link to a single episode. Whoever knows the token can listen to the episode until
+/// the link expires or is revoked.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeShare {
+    pub id: i32,
+    pub token: String,
+    pub username: String,
+    pub podcast_episode_id: i32,
+    pub start_position: i32,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeShareDto {
+    pub id: i32,
+    /// The public page of the share
+    pub url: String,
+    /// Seconds from the start of the episode
+    pub start_position: i32,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub expired: bool,
+    pub podcast_episode: PodcastEpisode,
+    pub podcast: Podcast
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeShareCreateModel {
+    pub start_position: Option<i32>,
+    /// Defaults to a week from now
+    pub expires_at: Option<NaiveDateTime>
+}
+
+impl EpisodeShare {
+    pub fn insert_share(conn: &mut DbConnection, token_1: &str, username_1: &str,
+                        podcast_episode_id_1: i32, start_position_1: i32,
+                        expires_at_1: NaiveDateTime, created_at_1: NaiveDateTime)
+        -> EpisodeShare {
+        use crate::dbconfig::schema::episode_shares::dsl::*;
+
+        insert_into(episode_shares)
+            .values((
+                token.eq(token_1),
+                username.eq(username_1),
+                podcast_episode_id.eq(podcast_episode_id_1),
+                start_position.eq(start_position_1),
+                expires_at.eq(expires_at_1),
+                created_at.eq(created_at_1)
+            ))
+            .get_result::<EpisodeShare>(conn)
+            .expect("Error inserting share link")
+    }
+
+    /// The share with its episode, expired or not
+    pub fn get_by_token(conn: &mut DbConnection, token_1: &str)
+        -> Option<(EpisodeShare, PodcastEpisode, Podcast)> {
+        use crate::dbconfig::schema::episode_shares::dsl::*;
+        use crate::dbconfig::schema::podcast_episodes;
+        use crate::dbconfig::schema::podcasts;
+
+        episode_shares
+            .inner_join(podcast_episodes::table.on(podcast_episode_id.eq(podcast_episodes::id)))
+            .inner_join(podcasts::table.on(podcast_episodes::podcast_id.eq(podcasts::id)))
+            .filter(token.eq(token_1))
+            .first::<(EpisodeShare, PodcastEpisode, Podcast)>(conn)
+            .optional()
+            .expect("Error loading share link")
+    }
+
+    pub fn get_share_of_user(conn: &mut DbConnection, share_id: i32, username_1: &str)
+        -> Option<EpisodeShare> {
+        use crate::dbconfig::schema::episode_shares::dsl::*;
+
+        episode_shares
+            .filter(id.eq(share_id))
+            .filter(username.eq(username_1))
+            .first::<EpisodeShare>(conn)
+            .optional()
+            .expect("Error loading share link")
+    }
+
+    /// The shares of the user with their episodes, newest first
+    pub fn get_shares_of_user(conn: &mut DbConnection, username_1: &str)
+        -> Vec<(EpisodeShare, PodcastEpisode, Podcast)> {
+        use crate::dbconfig::schema::episode_shares::dsl::*;
+        use crate::dbconfig::schema::podcast_episodes;
+        use crate::dbconfig::schema::podcasts;
+
+        episode_shares
+            .inner_join(podcast_episodes::table.on(podcast_episode_id.eq(podcast_episodes::id)))
+            .inner_join(podcasts::table.on(podcast_episodes::podcast_id.eq(podcasts::id)))
+            .filter(username.eq(username_1))
+            .order((created_at.desc(), id.desc()))
+            .load::<(EpisodeShare, PodcastEpisode, Podcast)>(conn)
+            .expect("Error loading share links")
+    }
+
+    pub fn delete_share(conn: &mut DbConnection, share_id: i32) {
+        use crate::dbconfig::schema::episode_shares::dsl::*;
+
+        diesel::delete(episode_shares.filter(id.eq(share_id)))
+            .execute(conn)
+            .expect("Error deleting share link");
+    }
+
+    pub fn delete_expired(conn: &mut DbConnection, expired_before: NaiveDateTime) -> usize {
+        use crate::dbconfig::schema::episode_shares::dsl::*;
+
+        diesel::delete(episode_shares.filter(expires_at.lt(expired_before)))
+            .execute(conn)
+            .expect("Error deleting expired share links")
+    }
+
+    pub fn delete_by_username(username_1: &str, conn: &mut DbConnection) -> Result<(),
+        diesel::result::Error> {
+        use crate::dbconfig::schema::episode_shares::dsl::*;
+
+        diesel::delete(episode_shares.filter(username.eq(username_1))).execute(conn)?;
+        Ok(())
+    }
+
+    pub fn delete_by_podcast_id(conn: &mut DbConnection, podcast_id_1: i32) {
+        use crate::dbconfig::schema::episode_shares::dsl::*;
+        use crate::dbconfig::schema::podcast_episodes;
+
+        let episodes_of_podcast = podcast_episodes::table
+            .filter(podcast_episodes::podcast_id.eq(podcast_id_1))
+            .select(podcast_episodes::id);
+        diesel::delete(episode_shares.filter(podcast_episode_id.eq_any(episodes_of_podcast)))
+            .execute(conn)
+            .expect("Error deleting share links of podcast");
+    }
+}
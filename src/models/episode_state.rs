@@ -0,0 +1,263 @@
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use diesel::{BoxableExpression, Connection, ExpressionMethods, Insertable, QueryDsl, Queryable,
+             RunQueryDsl};
+use diesel::dsl::sql;
+use diesel::insert_into;
+use diesel::sql_types::{Bool, Integer, Text};
+use utoipa::ToSchema;
+use crate::constants::constants::PLAYED_THRESHOLD_PERCENT;
+use crate::dbconfig::schema::episode_states;
+use crate::DbConnection;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum EpisodeStateType {
+    New,
+    InProgress,
+    Played,
+    Archived
+}
+
+impl EpisodeStateType {
+    pub fn from_string(s: &str) -> Self {
+        match s {
+            "new" => EpisodeStateType::New,
+            "in_progress" => EpisodeStateType::InProgress,
+            "played" => EpisodeStateType::Played,
+            "archived" => EpisodeStateType::Archived,
+            _ => panic!("Unknown episode state: {}", s),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EpisodeStateType::New => "new",
+            EpisodeStateType::InProgress => "in_progress",
+            EpisodeStateType::Played => "played",
+            EpisodeStateType::Archived => "archived",
+        }
+    }
+
+    /// Archived episodes count as played
+    pub fn is_played(&self) -> bool {
+        matches!(self, EpisodeStateType::Played | EpisodeStateType::Archived)
+    }
+}
+
+/// A state the user set explicitly. It takes precedence over the state inferred from the
+/// listening history.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeState {
+    pub username: String,
+    pub podcast_episode_id: i32,
+    pub state: String,
+    pub updated_at: NaiveDateTime
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeStateDto {
+    pub episode_id: String,
+    pub podcast_id: i32,
+    pub state: EpisodeStateType
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeStateUpdateModel {
+    pub state: EpisodeStateType
+}
+
+/// Sets the state of many episodes at once. The filters are combined, at least one is required.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeStateBulkModel {
+    pub state: EpisodeStateType,
+    pub episode_ids: Option<Vec<String>>,
+    pub podcast_id: Option<i32>,
+    /// Only episodes published before this date (YYYY-MM-DD, UTC)
+    pub published_before: Option<NaiveDate>
+}
+
+impl EpisodeState {
+    /// Whether the state of the podcast episode of the query is one of the states for the user.
+    /// Like in [`crate::service::episode_state_service::EpisodeStateService`] the explicit state
+    /// takes precedence over the state inferred from the listening history. The states are
+    /// looked up with subqueries, so large libraries don't run into the bind parameter limits.
+    pub fn has_state<'a, QS: 'a>(username_1: &str, states: &[EpisodeStateType])
+        -> Box<dyn BoxableExpression<QS, <DbConnection as Connection>::Backend, SqlType = Bool>
+        + 'a> {
+        let states = states.iter()
+            .map(|state| format!("'{}'", state.as_str()))
+            .collect::<Vec<String>>()
+            .join(", ");
+        Box::new(sql::<Bool>("coalesce((SELECT s.state FROM episode_states s \
+            WHERE s.podcast_episode_id = podcast_episodes.id AND s.username = ")
+            .bind::<Text, _>(username_1.to_string())
+            .sql("), CASE WHEN EXISTS (SELECT 1 FROM podcast_history_items h \
+            WHERE h.episode_id = podcast_episodes.episode_id AND h.username = ")
+            .bind::<Text, _>(username_1.to_string())
+            .sql(" AND podcast_episodes.total_time > 0 \
+            AND h.watched_time * 100 >= podcast_episodes.total_time * ")
+            .bind::<Integer, _>(PLAYED_THRESHOLD_PERCENT)
+            .sql(") THEN 'played' WHEN EXISTS (SELECT 1 FROM podcast_history_items h \
+            WHERE h.episode_id = podcast_episodes.episode_id AND h.username = ")
+            .bind::<Text, _>(username_1.to_string())
+            .sql(&format!(" AND h.watched_time > 0) THEN 'in_progress' ELSE 'new' END) IN ({})",
+                          states)))
+    }
+
+    /// The explicit states of the user or of every user
+    pub fn get_states(conn: &mut DbConnection, username_1: Option<&str>) -> Vec<EpisodeState> {
+        use crate::dbconfig::schema::episode_states::dsl::*;
+
+        let mut query = episode_states.into_boxed();
+        if let Some(username_1) = username_1 {
+            query = query.filter(username.eq(username_1.to_string()));
+        }
+        query
+            .load::<EpisodeState>(conn)
+            .expect("Error loading episode states")
+    }
+
+    pub fn get_state(conn: &mut DbConnection, username_1: &str, podcast_episode_id_1: i32)
+        -> Option<EpisodeState> {
+        use crate::dbconfig::schema::episode_states::dsl::*;
+        use diesel::OptionalExtension;
+
+        episode_states
+            .filter(username.eq(username_1))
+            .filter(podcast_episode_id.eq(podcast_episode_id_1))
+            .first::<EpisodeState>(conn)
+            .optional()
+            .expect("Error loading episode state")
+    }
+
+    pub fn set_states(conn: &mut DbConnection, username_1: &str, podcast_episode_ids: &[i32],
+                      state_1: EpisodeStateType) {
+        use crate::dbconfig::schema::episode_states::dsl::*;
+
+        let now = Utc::now().naive_utc();
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            // Keeps the number of bind parameters per statement below the database limits
+            for chunk in podcast_episode_ids.chunks(500) {
+                diesel::delete(episode_states
+                    .filter(username.eq(username_1))
+                    .filter(podcast_episode_id.eq_any(chunk)))
+                    .execute(conn)?;
+                let new_states = chunk.iter()
+                    .map(|episode_id| EpisodeState {
+                        username: username_1.to_string(),
+                        podcast_episode_id: *episode_id,
+                        state: state_1.as_str().to_string(),
+                        updated_at: now
+                    })
+                    .collect::<Vec<EpisodeState>>();
+                insert_into(episode_states)
+                    .values(&new_states)
+                    .execute(conn)?;
+            }
+            Ok(())
+        }).expect("Error saving episode states");
+    }
+
+    pub fn delete_state(conn: &mut DbConnection, username_1: &str, podcast_episode_id_1: i32) {
+        use crate::dbconfig::schema::episode_states::dsl::*;
+
+        diesel::delete(episode_states
+            .filter(username.eq(username_1))
+            .filter(podcast_episode_id.eq(podcast_episode_id_1)))
+            .execute(conn)
+            .expect("Error deleting episode state");
+    }
+
+    pub fn delete_by_username(username_1: &str, conn: &mut DbConnection) -> Result<(),
+        diesel::result::Error> {
+        use crate::dbconfig::schema::episode_states::dsl::*;
+
+        diesel::delete(episode_states.filter(username.eq(username_1))).execute(conn)?;
+        Ok(())
+    }
+
+    pub fn delete_by_podcast_id(conn: &mut DbConnection, podcast_id_1: i32) {
+        use crate::dbconfig::schema::episode_states::dsl::*;
+        use crate::dbconfig::schema::podcast_episodes;
+
+        let episodes_of_podcast = podcast_episodes::table
+            .filter(podcast_episodes::podcast_id.eq(podcast_id_1))
+            .select(podcast_episodes::id);
+        diesel::delete(episode_states.filter(podcast_episode_id.eq_any(episodes_of_podcast)))
+            .execute(conn)
+            .expect("Error deleting episode states of podcast");
+    }
+}
+
+#[cfg(all(test, sqlite))]
+mod tests {
+    use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl, SqliteConnection};
+    use diesel::sql_types::{Integer, Text};
+    use diesel_migrations::MigrationHarness;
+    use crate::MIGRATIONS;
+    use crate::dbconfig::schema::podcast_episodes;
+    use crate::models::episode_state::{EpisodeState, EpisodeStateType};
+
+    const USERNAME: &str = "listener";
+
+    // Episode 1 is untouched, 2 is started, 3 is finished, 4 is finished but marked as new again,
+    // 5 has no duration, 6 is archived and 7 was finished by another user
+    fn get_connection() -> SqliteConnection {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        diesel::sql_query("INSERT INTO podcasts (id, name, directory_id, rssfeed, image_url, \
+        active, original_image_url, directory_name) VALUES (1, 'Podcast', 'podcast', \
+        'https://example.com/feed.xml', '', 1, '', 'Podcast')")
+            .execute(&mut conn)
+            .unwrap();
+        for (id, total_time) in [(1, 100), (2, 100), (3, 100), (4, 100), (5, 0), (6, 100),
+            (7, 100)] {
+            diesel::sql_query("INSERT INTO podcast_episodes (id, podcast_id, episode_id, name, \
+            url, date_of_recording, image_url, total_time, local_url, local_image_url, \
+            description, status, guid, removed_upstream, pinned) VALUES (?, 1, ?, '', '', '', \
+            '', ?, '', '', '', 'N', '', 0, 0)")
+                .bind::<Integer, _>(id)
+                .bind::<Text, _>(format!("episode-{}", id))
+                .bind::<Integer, _>(total_time)
+                .execute(&mut conn)
+                .unwrap();
+        }
+        for (username, episode, watched_time) in [(USERNAME, 2, 10), (USERNAME, 3, 20),
+            (USERNAME, 3, 99), (USERNAME, 4, 100), (USERNAME, 5, 50), ("other", 7, 100)] {
+            diesel::sql_query("INSERT INTO podcast_history_items (podcast_id, episode_id, \
+            watched_time, date, username) VALUES (1, ?, ?, '2023-08-01 10:00:00', ?)")
+                .bind::<Text, _>(format!("episode-{}", episode))
+                .bind::<Integer, _>(watched_time)
+                .bind::<Text, _>(username)
+                .execute(&mut conn)
+                .unwrap();
+        }
+        EpisodeState::set_states(&mut conn, USERNAME, &[4], EpisodeStateType::New);
+        EpisodeState::set_states(&mut conn, USERNAME, &[6], EpisodeStateType::Archived);
+        conn
+    }
+
+    fn get_episode_ids(conn: &mut SqliteConnection, states: &[EpisodeStateType]) -> Vec<i32> {
+        podcast_episodes::table
+            .filter(EpisodeState::has_state(USERNAME, states))
+            .select(podcast_episodes::id)
+            .order(podcast_episodes::id.asc())
+            .load::<i32>(conn)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_has_state() {
+        let mut conn = get_connection();
+        assert_eq!(get_episode_ids(&mut conn, &[EpisodeStateType::New]), vec![1, 4, 7]);
+        assert_eq!(get_episode_ids(&mut conn, &[EpisodeStateType::InProgress]), vec![2, 5]);
+        assert_eq!(get_episode_ids(&mut conn, &[EpisodeStateType::Played]), vec![3]);
+        assert_eq!(get_episode_ids(&mut conn, &[EpisodeStateType::Archived]), vec![6]);
+        assert_eq!(get_episode_ids(&mut conn, &[EpisodeStateType::Played,
+            EpisodeStateType::Archived]), vec![3, 6]);
+    }
+}
@@ -65,6 +65,15 @@ impl Favorite{
         }
     }
 
+    pub fn get_all_favored(conn: &mut DbConnection) -> Vec<Favorite> {
+        use crate::dbconfig::schema::favorites::dsl::*;
+
+        favorites
+            .filter(favored.eq(true))
+            .load::<Favorite>(conn)
+            .expect("Error loading favorites")
+    }
+
     pub fn get_favored_podcasts(found_username: String,conn:&mut DbConnection,
                                 mapping_service:MappingService) ->
     Result<Vec<PodcastDto>,
@@ -73,9 +82,9 @@ impl Favorite{
         use crate::dbconfig::schema::favorites::dsl::favorites as f_db;
         use crate::dbconfig::schema::favorites::dsl::username as user_favor;
         use crate::dbconfig::schema::favorites::dsl::favored as favor_column;
-        let result:Vec<(Podcast, Favorite)>;
+        
 
-        result = dsl_podcast
+        let result:Vec<(Podcast, Favorite)> = dsl_podcast
             .inner_join(f_db)
             .filter(
                 favor_column.eq(true).and(
@@ -86,15 +95,16 @@ impl Favorite{
 
         let mapped_result = result
             .iter()
-            .map(|podcast| return mapping_service.map_podcast_to_podcast_dto_with_favorites_option
-            (&*podcast))
+            .map(|podcast| mapping_service.map_podcast_to_podcast_dto_with_favorites_option
+            (podcast))
             .collect::<Vec<PodcastDto>>();
         Ok(mapped_result)
     }
 
     pub fn search_podcasts_favored(conn: &mut DbConnection, order: OrderCriteria, title: Option<String>,
                                    latest_pub: OrderOption,
-                                   designated_username: String) ->Vec<(Podcast, Favorite)>{
+                                   designated_username: String,
+                                   tagged_podcast_ids: Option<Vec<i32>>) ->Vec<(Podcast, Favorite)>{
         use crate::dbconfig::schema::podcasts::dsl::*;
         use crate::dbconfig::schema::podcast_episodes::dsl::*;
         use crate::dbconfig::schema::podcasts::dsl::id as podcastsid;
@@ -130,10 +140,14 @@ impl Favorite{
             }
         }
 
-        if title.is_some() {
+        if let Some(title) = title {
             use crate::dbconfig::schema::podcasts::dsl::name as podcasttitle;
             query = query
-                .filter(podcasttitle.like(format!("%{}%", title.unwrap())));
+                .filter(podcasttitle.like(format!("%{}%", title)));
+        }
+
+        if let Some(tagged_podcast_ids) = tagged_podcast_ids {
+            query = query.filter(podcastsid.eq_any(tagged_podcast_ids));
         }
 
         let mut matching_podcast_ids = vec![];
@@ -155,7 +169,8 @@ impl Favorite{
 
     pub fn search_podcasts(conn: &mut DbConnection, order: OrderCriteria, title: Option<String>,
                            latest_pub: OrderOption,
-                           designated_username: String) -> Vec<(Podcast, Option<Favorite>)> {
+                           designated_username: String,
+                           tagged_podcast_ids: Option<Vec<i32>>) -> Vec<(Podcast, Option<Favorite>)> {
         use crate::dbconfig::schema::podcasts::dsl::*;
         use crate::dbconfig::schema::podcast_episodes::dsl::*;
         use crate::dbconfig::schema::podcasts::dsl::id as podcastsid;
@@ -192,10 +207,14 @@ impl Favorite{
             }
         }
 
-        if title.is_some() {
+        if let Some(title) = title {
             use crate::dbconfig::schema::podcasts::dsl::name as podcasttitle;
             query = query
-                .filter(podcasttitle.like(format!("%{}%", title.unwrap())));
+                .filter(podcasttitle.like(format!("%{}%", title)));
+        }
+
+        if let Some(tagged_podcast_ids) = tagged_podcast_ids {
+            query = query.filter(podcastsid.eq_any(tagged_podcast_ids));
         }
 
         let mut matching_podcast_ids = vec![];
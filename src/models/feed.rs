@@ -0,0 +1,37 @@
+/// Format independent representation of a podcast feed. RSS, Atom and JSON feeds are all parsed
+/// into this model before their episodes are stored.
+#[derive(Debug, Clone, Default)]
+pub struct Feed {
+    pub title: String,
+    pub description: String,
+    pub language: Option<String>,
+    pub last_build_date: Option<String>,
+    pub author: Option<String>,
+    pub keywords: Vec<String>,
+    pub image_url: Option<String>,
+    pub new_feed_url: Option<String>,
+    /// Link to the page with older items of a paged or archived feed (RFC 5005)
+    pub next_page_url: Option<String>,
+    pub items: Vec<FeedItem>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FeedItem {
+    pub title: Option<String>,
+    pub guid: Option<String>,
+    pub description: Option<String>,
+    pub pub_date: Option<String>,
+    pub enclosure: Option<FeedEnclosure>,
+    pub image_url: Option<String>,
+    pub duration: Option<String>,
+    pub season: Option<i32>,
+    pub episode_number: Option<i32>,
+    pub chapters_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FeedEnclosure {
+    pub url: String,
+    pub mime_type: Option<String>,
+    pub length: Option<String>,
+}
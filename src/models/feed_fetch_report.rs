@@ -0,0 +1,127 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, OptionalExtension, RunQueryDsl};
+use diesel::QueryDsl;
+use diesel::ExpressionMethods;
+use diesel::AsChangeset;
+use diesel::Queryable;
+use utoipa::ToSchema;
+use crate::dbconfig::schema::feed_fetch_reports;
+use crate::DbConnection;
+use crate::utils::time::get_current_timestamp_str;
+
+/// Outcome of the last refresh of a podcast feed.
+#[derive(Debug, Clone, Insertable, AsChangeset, Queryable)]
+#[diesel(treat_none_as_null = true)]
+pub struct FeedFetchReport {
+    pub podcast_id: i32,
+    pub fetched_at: NaiveDateTime,
+    pub successful: bool,
+    pub error: Option<String>,
+    pub new_episodes: i32,
+    pub updated_episodes: i32,
+    pub skipped_items: String
+}
+
+/// A feed item that could not be turned into an episode.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedFeedItem {
+    pub title: Option<String>,
+    pub guid: Option<String>,
+    pub reason: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedFetchReportDto {
+    pub podcast_id: i32,
+    pub fetched_at: NaiveDateTime,
+    pub successful: bool,
+    pub error: Option<String>,
+    pub new_episodes: i32,
+    pub updated_episodes: i32,
+    pub skipped_items: Vec<SkippedFeedItem>
+}
+
+impl FeedFetchReport {
+    pub fn succeeded(podcast_id: i32, new_episodes: i32, updated_episodes: i32,
+                     skipped_items: &[SkippedFeedItem]) -> Self {
+        FeedFetchReport {
+            podcast_id,
+            fetched_at: get_current_timestamp_str(),
+            successful: true,
+            error: None,
+            new_episodes,
+            updated_episodes,
+            skipped_items: serde_json::to_string(skipped_items).unwrap()
+        }
+    }
+
+    pub fn failed(podcast_id: i32, error: String) -> Self {
+        FeedFetchReport {
+            podcast_id,
+            fetched_at: get_current_timestamp_str(),
+            successful: false,
+            error: Some(error),
+            new_episodes: 0,
+            updated_episodes: 0,
+            skipped_items: "[]".to_string()
+        }
+    }
+
+    pub fn save_report(self, conn: &mut DbConnection) {
+        use crate::dbconfig::schema::feed_fetch_reports::dsl::*;
+
+        let existing_report = feed_fetch_reports
+            .filter(podcast_id.eq(self.podcast_id))
+            .first::<FeedFetchReport>(conn)
+            .optional()
+            .expect("Error loading feed fetch report");
+        match existing_report {
+            Some(_) => {
+                diesel::update(feed_fetch_reports.filter(podcast_id.eq(self.podcast_id)))
+                    .set(self)
+                    .execute(conn)
+                    .expect("Error updating feed fetch report");
+            }
+            None => {
+                diesel::insert_into(feed_fetch_reports)
+                    .values(self)
+                    .execute(conn)
+                    .expect("Error inserting feed fetch report");
+            }
+        }
+    }
+
+    pub fn get_report_by_podcast_id(conn: &mut DbConnection, podcast_id_to_search: i32)
+        -> Option<FeedFetchReportDto> {
+        use crate::dbconfig::schema::feed_fetch_reports::dsl::*;
+
+        feed_fetch_reports
+            .filter(podcast_id.eq(podcast_id_to_search))
+            .first::<FeedFetchReport>(conn)
+            .optional()
+            .expect("Error loading feed fetch report")
+            .map(|report| report.into_dto())
+    }
+
+    pub fn delete_report_by_podcast_id(conn: &mut DbConnection, podcast_id_to_delete: i32) {
+        use crate::dbconfig::schema::feed_fetch_reports::dsl::*;
+
+        diesel::delete(feed_fetch_reports.filter(podcast_id.eq(podcast_id_to_delete)))
+            .execute(conn)
+            .expect("Error deleting feed fetch report");
+    }
+
+    fn into_dto(self) -> FeedFetchReportDto {
+        FeedFetchReportDto {
+            podcast_id: self.podcast_id,
+            fetched_at: self.fetched_at,
+            successful: self.successful,
+            error: self.error,
+            new_episodes: self.new_episodes,
+            updated_episodes: self.updated_episodes,
+            skipped_items: serde_json::from_str(&self.skipped_items).unwrap_or_default()
+        }
+    }
+}
@@ -78,9 +78,9 @@ impl FilenameBuilder {
         let resulting_directory = self.clone().create_podcast_episode_dir(format!("{}/{}",self
             .directory.clone(), self.episode.clone()),conn);
 
-        return (format!("{}/{}.{}", resulting_directory,self.filename.clone(), self.suffix.clone
+        (format!("{}/{}.{}", resulting_directory,self.filename.clone(), self.suffix.clone
         ()),format!("{}/{}.{}", resulting_directory,self.image_filename.clone(), self.image_suffix
-            .clone()));
+            .clone()))
     }
 
 
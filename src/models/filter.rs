@@ -9,24 +9,27 @@ use utoipa::ToSchema;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Insertable, AsChangeset, Queryable,ToSchema)]
 #[serde(rename_all = "camelCase")]
+#[diesel(treat_none_as_null = true)]
 pub struct Filter{
     pub username: String,
     pub title: Option<String>,
     pub ascending: bool,
     pub filter: Option<String>,
-    pub only_favored: bool
+    pub only_favored: bool,
+    pub tag_id: Option<i32>
 }
 
 
 impl Filter{
     pub fn new(username: String, title: Option<String>, ascending: bool, filter: Option<String>,
-               only_favored: bool) -> Self{
+               only_favored: bool, tag_id: Option<i32>) -> Self{
         Filter{
             username,
             title,
             ascending,
             filter,
-            only_favored
+            only_favored,
+            tag_id
         }
     }
 
@@ -0,0 +1,83 @@
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum LibraryIssueKind {
+    /// The episode is marked as downloaded, but its file doesn't exist
+    MissingFile,
+    EmptyFile,
+    /// The file is smaller than after its download or ends before the episode does
+    TruncatedFile,
+    /// The downloaded image of a podcast or an episode doesn't exist
+    MissingArtwork,
+    /// A file no podcast or episode refers to, e.g. the rest of an interrupted download
+    OrphanedFile,
+    /// A directory without any file a podcast or episode refers to, e.g. the directory of a
+    /// podcast that was deleted without its files
+    OrphanedDirectory
+}
+
+/// A difference between the database and the files in the media root
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryIssue {
+    pub kind: LibraryIssueKind,
+    /// Relative to the media root
+    pub path: String,
+    pub podcast_id: Option<i32>,
+    pub podcast_episode_id: Option<i32>,
+    /// Name of the podcast or the episode
+    pub name: Option<String>,
+    /// Size of the file or of every file in the directory
+    pub bytes: u64,
+    pub details: Option<String>
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryCheckReport {
+    pub checked_episodes: usize,
+    pub checked_files: usize,
+    pub issues: Vec<LibraryIssue>
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum LibraryRepairAction {
+    Redownloaded,
+    /// The episode couldn't be downloaded again and is no longer marked as downloaded
+    StatusReset,
+    ArtworkDownloaded,
+    /// Moved to the quarantine directory in the media root
+    Quarantined,
+    Deleted
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryRepair {
+    pub issue: LibraryIssue,
+    pub action: LibraryRepairAction
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryRepairFailure {
+    pub issue: LibraryIssue,
+    pub error: String
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryRepairResult {
+    pub repairs: Vec<LibraryRepair>,
+    pub failures: Vec<LibraryRepairFailure>
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryRepairModel {
+    /// Orphaned files are deleted instead of moved to the quarantine directory
+    #[serde(default)]
+    pub delete_orphans: bool
+}
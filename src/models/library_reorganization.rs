@@ -0,0 +1,58 @@
+use utoipa::ToSchema;
+
+/// A file or directory that is moved, both paths are relative to the media root
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PathChange {
+    pub from: String,
+    pub to: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeReorganization {
+    pub podcast_episode_id: i32,
+    pub name: String,
+    pub directory: PathChange,
+    pub file: PathChange,
+    pub image: Option<PathChange>,
+    /// The directory of the naming settings is already taken, a suffix was added
+    pub collision: bool
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PodcastReorganization {
+    pub podcast_id: i32,
+    pub podcast_name: String,
+    /// Empty if only episodes of the podcast are renamed
+    pub directory: Option<PathChange>,
+    pub image: Option<PathChange>,
+    pub collision: bool,
+    pub episodes: Vec<EpisodeReorganization>
+}
+
+/// The files that don't match the current naming settings and where they are moved to
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReorganizationPlan {
+    pub podcasts: Vec<PodcastReorganization>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReorganizationFailure {
+    /// Name of the podcast or the episode
+    pub name: String,
+    pub path: String,
+    pub error: String
+}
+
+/// Files that couldn't be moved keep their old path, everything else is moved
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReorganizationResult {
+    pub moved_podcasts: usize,
+    pub moved_episodes: usize,
+    pub failures: Vec<ReorganizationFailure>
+}
@@ -0,0 +1,248 @@
+use chrono::NaiveDate;
+use diesel::{Connection, ExpressionMethods, Insertable, JoinOnDsl, OptionalExtension, QueryDsl,
+             Queryable, RunQueryDsl};
+use diesel::insert_into;
+use utoipa::ToSchema;
+use crate::dbconfig::schema::listening_rollups;
+use crate::DbConnection;
+
+/// The seconds a user listened to an episode within one hour (UTC). The watch logs only contain
+/// positions, the rollups contain the listening time derived from them.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[serde(rename_all = "camelCase")]
+pub struct ListeningRollup {
+    pub username: String,
+    pub day: NaiveDate,
+    pub hour: i32,
+    pub podcast_id: i32,
+    pub podcast_episode_id: i32,
+    pub listened_seconds: i32
+}
+
+/// A rollup together with the names of its episode and podcast
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListeningRecord {
+    pub username: String,
+    pub day: NaiveDate,
+    pub hour: i32,
+    pub podcast_id: i32,
+    #[serde(skip)]
+    pub podcast_episode_id: i32,
+    pub listened_seconds: i32,
+    pub episode_id: String,
+    pub episode_name: String,
+    pub podcast_name: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListeningPeriod {
+    /// The day (2023-09-16), ISO week (2023-W37) or month (2023-09)
+    pub period: String,
+    pub listened_seconds: i64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TopPodcast {
+    pub podcast_id: i32,
+    pub name: String,
+    pub listened_seconds: i64,
+    pub episodes: i64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TopEpisode {
+    pub episode_id: String,
+    pub podcast_id: i32,
+    pub name: String,
+    pub podcast_name: String,
+    pub listened_seconds: i64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionStats {
+    pub started_episodes: i64,
+    pub completed_episodes: i64,
+    /// Share of the started episodes that are played, between 0 and 1
+    pub completion_rate: f64
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListeningStreaks {
+    /// Consecutive days with listening time up to today or yesterday
+    pub current_days: i64,
+    pub longest_days: i64,
+    pub longest_from: Option<NaiveDate>,
+    pub longest_until: Option<NaiveDate>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ListeningStats {
+    pub from: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    pub total_seconds: i64,
+    pub listeners: i64,
+    pub days: Vec<ListeningPeriod>,
+    pub weeks: Vec<ListeningPeriod>,
+    pub months: Vec<ListeningPeriod>,
+    /// Listening time per hour of the day (UTC), starting at midnight
+    pub hours: Vec<i64>,
+    pub top_podcasts: Vec<TopPodcast>,
+    pub top_episodes: Vec<TopEpisode>,
+    pub completion: CompletionStats,
+    pub streaks: ListeningStreaks
+}
+
+/// The year in review of a user
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WrappedReport {
+    pub year: i32,
+    pub total_seconds: i64,
+    pub listening_days: i64,
+    pub episodes_started: i64,
+    pub episodes_completed: i64,
+    /// Podcasts the user listened to for the first time this year
+    pub new_podcasts: i64,
+    pub top_podcasts: Vec<TopPodcast>,
+    pub top_episodes: Vec<TopEpisode>,
+    pub longest_streak: ListeningStreaks,
+    pub busiest_day: Option<ListeningPeriod>,
+    pub busiest_month: Option<ListeningPeriod>,
+    pub favorite_hour: Option<u32>
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsExportFormat {
+    Csv,
+    Json
+}
+
+impl ListeningRollup {
+    /// Adds listening time to the rollup of the hour
+    pub fn add_listened_seconds(conn: &mut DbConnection, rollup: &ListeningRollup) {
+        use crate::dbconfig::schema::listening_rollups::dsl::*;
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let existing_rollup = listening_rollups
+                .filter(username.eq(&rollup.username))
+                .filter(day.eq(rollup.day))
+                .filter(hour.eq(rollup.hour))
+                .filter(podcast_episode_id.eq(rollup.podcast_episode_id))
+                .first::<ListeningRollup>(conn)
+                .optional()?;
+            match existing_rollup {
+                Some(existing_rollup) => {
+                    diesel::update(listening_rollups
+                        .filter(username.eq(&rollup.username))
+                        .filter(day.eq(rollup.day))
+                        .filter(hour.eq(rollup.hour))
+                        .filter(podcast_episode_id.eq(rollup.podcast_episode_id)))
+                        .set(listened_seconds.eq(existing_rollup.listened_seconds
+                            + rollup.listened_seconds))
+                        .execute(conn)?;
+                }
+                None => {
+                    insert_into(listening_rollups)
+                        .values(rollup)
+                        .execute(conn)?;
+                }
+            }
+            Ok(())
+        }).expect("Error saving listening rollup");
+    }
+
+    /// Replaces every rollup, used to rebuild them from the watch logs
+    pub fn replace_rollups(conn: &mut DbConnection, rollups: &[ListeningRollup]) {
+        use crate::dbconfig::schema::listening_rollups::dsl::*;
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::delete(listening_rollups).execute(conn)?;
+            // Keeps the number of bind parameters per statement below the database limits
+            for chunk in rollups.chunks(500) {
+                insert_into(listening_rollups)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            Ok(())
+        }).expect("Error replacing listening rollups");
+    }
+
+    pub fn has_rollups(conn: &mut DbConnection) -> bool {
+        use crate::dbconfig::schema::listening_rollups::dsl::*;
+
+        listening_rollups
+            .select(username)
+            .first::<String>(conn)
+            .optional()
+            .expect("Error loading listening rollups")
+            .is_some()
+    }
+
+    /// The rollups of the user or of every user between the dates, both inclusive
+    pub fn get_records(conn: &mut DbConnection, username_1: Option<&str>, from: Option<NaiveDate>,
+                       until: Option<NaiveDate>) -> Vec<ListeningRecord> {
+        use crate::dbconfig::schema::podcast_episodes;
+        use crate::dbconfig::schema::podcasts;
+
+        let mut query = listening_rollups::table
+            .inner_join(podcast_episodes::table.on(listening_rollups::podcast_episode_id
+                .eq(podcast_episodes::id)))
+            .inner_join(podcasts::table.on(listening_rollups::podcast_id.eq(podcasts::id)))
+            .select((listening_rollups::username, listening_rollups::day,
+                     listening_rollups::hour, listening_rollups::podcast_id,
+                     listening_rollups::podcast_episode_id, listening_rollups::listened_seconds,
+                     podcast_episodes::episode_id, podcast_episodes::name, podcasts::name))
+            .into_boxed();
+        if let Some(username_1) = username_1 {
+            query = query.filter(listening_rollups::username.eq(username_1.to_string()));
+        }
+        if let Some(from) = from {
+            query = query.filter(listening_rollups::day.ge(from));
+        }
+        if let Some(until) = until {
+            query = query.filter(listening_rollups::day.le(until));
+        }
+        query
+            .order((listening_rollups::day, listening_rollups::hour,
+                    listening_rollups::username, listening_rollups::podcast_episode_id))
+            .load::<ListeningRecord>(conn)
+            .expect("Error loading listening rollups")
+    }
+
+    /// The day the user first listened to each podcast
+    pub fn get_first_listening_days(conn: &mut DbConnection, username_1: &str)
+        -> Vec<(i32, Option<NaiveDate>)> {
+        use crate::dbconfig::schema::listening_rollups::dsl::*;
+
+        listening_rollups
+            .filter(username.eq(username_1))
+            .group_by(podcast_id)
+            .select((podcast_id, diesel::dsl::min(day)))
+            .load::<(i32, Option<NaiveDate>)>(conn)
+            .expect("Error loading first listening days")
+    }
+
+    pub fn delete_by_username(username_1: &str, conn: &mut DbConnection) -> Result<(),
+        diesel::result::Error> {
+        use crate::dbconfig::schema::listening_rollups::dsl::*;
+
+        diesel::delete(listening_rollups.filter(username.eq(username_1))).execute(conn)?;
+        Ok(())
+    }
+
+    pub fn delete_by_podcast_id(conn: &mut DbConnection, podcast_id_1: i32) {
+        use crate::dbconfig::schema::listening_rollups::dsl::*;
+
+        diesel::delete(listening_rollups.filter(podcast_id.eq(podcast_id_1)))
+            .execute(conn)
+            .expect("Error deleting listening rollups of podcast");
+    }
+}
@@ -0,0 +1,88 @@
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use crate::DbConnection;
+
+/// The columns that store paths of downloaded files
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaPathColumn {
+    PodcastDirectory,
+    PodcastImage,
+    EpisodeFile,
+    EpisodeImage
+}
+
+/// A path of a downloaded file or directory stored in the database, relative to the media root
+#[derive(Debug, Clone)]
+pub struct MediaPath {
+    pub column: MediaPathColumn,
+    /// Id of the podcast or the podcast episode
+    pub id: i32,
+    pub path: String
+}
+
+impl MediaPath {
+    /// Every stored path of a downloaded file. Images that weren't downloaded are urls and
+    /// aren't included.
+    pub fn get_media_paths(conn: &mut DbConnection) -> Vec<MediaPath> {
+        use crate::dbconfig::schema::podcast_episodes;
+        use crate::dbconfig::schema::podcasts;
+
+        let podcasts = podcasts::table
+            .select((podcasts::id, podcasts::directory_name, podcasts::image_url))
+            .load::<(i32, String, String)>(conn)
+            .expect("Error loading podcast paths");
+        let podcast_episodes = podcast_episodes::table
+            .select((podcast_episodes::id, podcast_episodes::local_url,
+                     podcast_episodes::local_image_url))
+            .load::<(i32, String, String)>(conn)
+            .expect("Error loading podcast episode paths");
+
+        let podcast_paths = podcasts
+            .into_iter()
+            .flat_map(|(id, directory_name, image_url)| [
+                (MediaPathColumn::PodcastDirectory, id, directory_name),
+                (MediaPathColumn::PodcastImage, id, image_url)
+            ]);
+        let episode_paths = podcast_episodes
+            .into_iter()
+            .flat_map(|(id, local_url, local_image_url)| [
+                (MediaPathColumn::EpisodeFile, id, local_url),
+                (MediaPathColumn::EpisodeImage, id, local_image_url)
+            ]);
+        podcast_paths
+            .chain(episode_paths)
+            .filter(|(_, _, path)| !path.trim().is_empty() && !path.contains("://"))
+            .map(|(column, id, path)| MediaPath { column, id, path })
+            .collect()
+    }
+
+    /// Saves the paths in one transaction, either all of them change or none
+    pub fn update_media_paths(conn: &mut DbConnection, media_paths: &[MediaPath])
+        -> Result<(), diesel::result::Error> {
+        use crate::dbconfig::schema::podcast_episodes;
+        use crate::dbconfig::schema::podcasts;
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            for media_path in media_paths {
+                match media_path.column {
+                    MediaPathColumn::PodcastDirectory => diesel::update(podcasts::table
+                        .filter(podcasts::id.eq(media_path.id)))
+                        .set(podcasts::directory_name.eq(&media_path.path))
+                        .execute(conn)?,
+                    MediaPathColumn::PodcastImage => diesel::update(podcasts::table
+                        .filter(podcasts::id.eq(media_path.id)))
+                        .set(podcasts::image_url.eq(&media_path.path))
+                        .execute(conn)?,
+                    MediaPathColumn::EpisodeFile => diesel::update(podcast_episodes::table
+                        .filter(podcast_episodes::id.eq(media_path.id)))
+                        .set(podcast_episodes::local_url.eq(&media_path.path))
+                        .execute(conn)?,
+                    MediaPathColumn::EpisodeImage => diesel::update(podcast_episodes::table
+                        .filter(podcast_episodes::id.eq(media_path.id)))
+                        .set(podcast_episodes::local_image_url.eq(&media_path.path))
+                        .execute(conn)?
+                };
+            }
+            Ok(())
+        })
+    }
+}
@@ -31,10 +31,3 @@ pub struct Disconnect {
     pub id: Uuid,
 }
 
-#[derive(Message)]
-#[rtype(result = "()")]
-pub struct ClientActorMessage {
-    pub id: Uuid,
-    pub msg: String,
-    pub room_id: Uuid,
-}
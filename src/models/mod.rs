@@ -1,6 +1,6 @@
 pub mod dto_models;
-pub mod itunes_models;
 pub mod messages;
+#[allow(clippy::module_inception)]
 pub mod models;
 pub mod opml_model;
 pub mod search_type;
@@ -25,3 +25,22 @@ pub mod podcast_dto;
 pub mod podcast_episode;
 pub mod podcast_history_item;
 pub mod notification;
+pub mod feed_fetch_report;
+pub mod feed;
+pub mod podcast_credential;
+pub mod search;
+pub mod discovered_podcast;
+pub mod recommendation;
+pub mod tag;
+pub mod queue;
+pub mod playlist;
+pub mod episode_state;
+pub mod listening_stats;
+pub mod playback_preference;
+pub mod bookmark;
+pub mod personal_data;
+pub mod episode_share;
+pub mod media_path;
+pub mod library_reorganization;
+pub mod library_check;
+pub mod cleanup;
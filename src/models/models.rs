@@ -1,5 +1,6 @@
 use crate::models::podcast_episode::PodcastEpisode;
 use crate::models::podcasts::Podcast;
+use crate::models::podcast_credential::PodcastCredentials;
 
 
 use utoipa::ToSchema;
@@ -7,18 +8,6 @@ use chrono::NaiveDateTime;
 
 
 
-// decode request data
-#[derive(Deserialize)]
-pub struct UserData {
-    pub username: String,
-}
-// this is to insert users to database
-#[derive(Serialize, Deserialize)]
-pub struct NewUser {
-    pub username: String,
-    pub password: String,
-    pub first_name: String,
-}
 
 #[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -32,6 +21,7 @@ pub struct PodcastInsertModel {
     pub id: i32,
     pub feed_url: String,
     pub image_url: String,
+    pub credentials: Option<PodcastCredentials>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -1,20 +1,6 @@
 use std::fmt;
 use std::fmt::{Formatter, write};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Jwks {
-    keys: Vec<Jwk>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Jwk {
-    kid: String,
-    kty: String,
-    alg: String,
-    use_: String,
-    n: String,
-    e: String,
-}
 
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -1,4 +1,7 @@
-#[derive(Serialize, Deserialize, Debug,Clone)]
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug,Clone,Copy)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum OrderCriteria {
     ASC,
     DESC
@@ -20,13 +23,6 @@ pub enum OrderOption{
 }
 
 impl OrderOption{
-    pub fn to_string(self)->String{
-        match self{
-            OrderOption::PublishedDate=>"PublishedDate".to_string(),
-            OrderOption::Title=>"Title".to_string()
-        }
-    }
-
     pub fn from_string(s: String)->Self{
         match s.as_str(){
             "PublishedDate"=>OrderOption::PublishedDate,
@@ -35,3 +31,12 @@ impl OrderOption{
         }
     }
 }
+
+impl fmt::Display for OrderOption{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            OrderOption::PublishedDate=>write!(f, "PublishedDate"),
+            OrderOption::Title=>write!(f, "Title")
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use chrono::NaiveDateTime;
+use utoipa::ToSchema;
+use crate::models::bookmark::BookmarkDto;
+use crate::models::episode_state::EpisodeState;
+use crate::models::playback_preference::PlaybackPreferencesDto;
+use crate::models::playlist::PlaylistDto;
+use crate::models::podcast_history_item::PodcastHistoryItem;
+use crate::models::tag::TagDto;
+use crate::models::user::UserWithoutPassword;
+
+/// Everything PodFetch stores about a user
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PersonalDataExport {
+    pub user: UserWithoutPassword,
+    pub exported_at: NaiveDateTime,
+    pub favorite_podcast_ids: Vec<i32>,
+    pub watch_history: Vec<PodcastHistoryItem>,
+    /// The states the user set explicitly
+    pub episode_states: Vec<EpisodeState>,
+    pub tags: Vec<TagDto>,
+    /// The episode ids (uuid) in queue order
+    pub queue: Vec<String>,
+    pub playlists: Vec<PlaylistDto>,
+    pub playback_preferences: Vec<PlaybackPreferencesDto>,
+    pub bookmarks: Vec<BookmarkDto>
+}
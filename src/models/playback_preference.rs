@@ -0,0 +1,150 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::{Connection, ExpressionMethods, Insertable, OptionalExtension, QueryDsl, Queryable,
+             RunQueryDsl};
+use diesel::insert_into;
+use utoipa::ToSchema;
+use crate::constants::constants::{DEFAULT_PLAYBACK_SPEED, DEFAULT_VOLUME};
+use crate::dbconfig::schema::playback_preferences;
+use crate::DbConnection;
+
+/// How a user wants to listen to the episodes of a podcast
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackPreference {
+    pub username: String,
+    pub podcast_id: i32,
+    pub playback_speed: f32,
+    pub volume: i32,
+    pub skip_intro_seconds: i32,
+    pub skip_outro_seconds: i32,
+    pub trim_silence: bool,
+    pub updated_at: NaiveDateTime
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackPreferencesDto {
+    pub podcast_id: i32,
+    pub playback_speed: f32,
+    /// Volume in percent, values above 100 boost the volume
+    pub volume: i32,
+    pub skip_intro_seconds: i32,
+    pub skip_outro_seconds: i32,
+    pub trim_silence: bool,
+    /// Whether the user changed the defaults
+    pub custom: bool
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackPreferencesUpdateModel {
+    pub playback_speed: f32,
+    pub volume: i32,
+    pub skip_intro_seconds: i32,
+    pub skip_outro_seconds: i32,
+    pub trim_silence: bool
+}
+
+impl PlaybackPreferencesDto {
+    pub fn default_for(podcast_id: i32) -> Self {
+        PlaybackPreferencesDto {
+            podcast_id,
+            playback_speed: DEFAULT_PLAYBACK_SPEED,
+            volume: DEFAULT_VOLUME,
+            skip_intro_seconds: 0,
+            skip_outro_seconds: 0,
+            trim_silence: false,
+            custom: false
+        }
+    }
+}
+
+impl From<PlaybackPreference> for PlaybackPreferencesDto {
+    fn from(preference: PlaybackPreference) -> Self {
+        PlaybackPreferencesDto {
+            podcast_id: preference.podcast_id,
+            playback_speed: preference.playback_speed,
+            volume: preference.volume,
+            skip_intro_seconds: preference.skip_intro_seconds,
+            skip_outro_seconds: preference.skip_outro_seconds,
+            trim_silence: preference.trim_silence,
+            custom: true
+        }
+    }
+}
+
+impl PlaybackPreference {
+    pub fn get_preference(conn: &mut DbConnection, username_1: &str, podcast_id_1: i32)
+        -> Option<PlaybackPreference> {
+        use crate::dbconfig::schema::playback_preferences::dsl::*;
+
+        playback_preferences
+            .filter(username.eq(username_1))
+            .filter(podcast_id.eq(podcast_id_1))
+            .first::<PlaybackPreference>(conn)
+            .optional()
+            .expect("Error loading playback preferences")
+    }
+
+    pub fn get_preferences_of_user(conn: &mut DbConnection, username_1: &str)
+        -> Vec<PlaybackPreference> {
+        use crate::dbconfig::schema::playback_preferences::dsl::*;
+
+        playback_preferences
+            .filter(username.eq(username_1))
+            .order(podcast_id)
+            .load::<PlaybackPreference>(conn)
+            .expect("Error loading playback preferences")
+    }
+
+    pub fn save_preference(conn: &mut DbConnection, username_1: &str, podcast_id_1: i32,
+                           preference: &PlaybackPreferencesUpdateModel) -> PlaybackPreference {
+        use crate::dbconfig::schema::playback_preferences::dsl::*;
+
+        let new_preference = PlaybackPreference {
+            username: username_1.to_string(),
+            podcast_id: podcast_id_1,
+            playback_speed: preference.playback_speed,
+            volume: preference.volume,
+            skip_intro_seconds: preference.skip_intro_seconds,
+            skip_outro_seconds: preference.skip_outro_seconds,
+            trim_silence: preference.trim_silence,
+            updated_at: Utc::now().naive_utc()
+        };
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::delete(playback_preferences
+                .filter(username.eq(username_1))
+                .filter(podcast_id.eq(podcast_id_1)))
+                .execute(conn)?;
+            insert_into(playback_preferences)
+                .values(&new_preference)
+                .get_result::<PlaybackPreference>(conn)
+        }).expect("Error saving playback preferences")
+    }
+
+    pub fn delete_preference(conn: &mut DbConnection, username_1: &str, podcast_id_1: i32) {
+        use crate::dbconfig::schema::playback_preferences::dsl::*;
+
+        diesel::delete(playback_preferences
+            .filter(username.eq(username_1))
+            .filter(podcast_id.eq(podcast_id_1)))
+            .execute(conn)
+            .expect("Error deleting playback preferences");
+    }
+
+    pub fn delete_by_username(username_1: &str, conn: &mut DbConnection) -> Result<(),
+        diesel::result::Error> {
+        use crate::dbconfig::schema::playback_preferences::dsl::*;
+
+        diesel::delete(playback_preferences.filter(username.eq(username_1))).execute(conn)?;
+        Ok(())
+    }
+
+    pub fn delete_by_podcast_id(conn: &mut DbConnection, podcast_id_1: i32) {
+        use crate::dbconfig::schema::playback_preferences::dsl::*;
+
+        diesel::delete(playback_preferences.filter(podcast_id.eq(podcast_id_1)))
+            .execute(conn)
+            .expect("Error deleting playback preferences of podcast");
+    }
+}
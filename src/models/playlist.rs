@@ -0,0 +1,341 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::{Connection, ExpressionMethods, JoinOnDsl, OptionalExtension, QueryDsl, Queryable,
+             RunQueryDsl};
+use diesel::dsl::not;
+use diesel::insert_into;
+use utoipa::ToSchema;
+use crate::DbConnection;
+use crate::models::episode_state::{EpisodeState, EpisodeStateType};
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcasts::Podcast;
+
+/// A named playlist of a user. Manual playlists contain hand-picked episodes, smart playlists
+/// are filled by their saved rules whenever they are read.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+#[serde(rename_all = "camelCase")]
+pub struct Playlist {
+    pub id: i32,
+    pub username: String,
+    pub name: String,
+    // The rules of smart playlists as json
+    pub rules: Option<String>,
+    pub feed_token: String,
+    pub created_at: NaiveDateTime
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistItem {
+    pub playlist_id: i32,
+    pub podcast_episode_id: i32,
+    pub position: i32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum PlaylistOrder {
+    Newest,
+    Oldest
+}
+
+/// The rules of a smart playlist. Rules that aren't set match every episode.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SmartPlaylistRules {
+    /// Only episodes of podcasts with this tag or one of its nested tags
+    pub tag_id: Option<i32>,
+    pub podcast_ids: Option<Vec<i32>>,
+    pub played: Option<bool>,
+    /// Only episodes of podcasts the user marked as favorite or of the other podcasts
+    pub favored: Option<bool>,
+    pub downloaded: Option<bool>,
+    /// In seconds
+    pub min_duration: Option<i32>,
+    /// In seconds
+    pub max_duration: Option<i32>,
+    pub published_within_days: Option<i32>,
+    pub order: Option<PlaylistOrder>,
+    pub limit: Option<i32>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistDto {
+    pub id: i32,
+    pub name: String,
+    pub smart: bool,
+    pub rules: Option<SmartPlaylistRules>,
+    pub created_at: NaiveDateTime,
+    pub rss_url: String,
+    pub m3u_url: String,
+    pub pls_url: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistItemDto {
+    pub podcast_episode: PodcastEpisode,
+    pub podcast: Podcast
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistCreateModel {
+    pub name: String,
+    /// Makes the playlist a smart playlist
+    pub rules: Option<SmartPlaylistRules>,
+    /// The episodes of a manual playlist in playback order
+    pub episode_ids: Option<Vec<String>>
+}
+
+impl Playlist {
+    pub fn get_rules(&self) -> Option<SmartPlaylistRules> {
+        self.rules.as_ref().map(|rules| serde_json::from_str(rules)
+            .expect("Error parsing smart playlist rules"))
+    }
+
+    pub fn insert_playlist(conn: &mut DbConnection, username_1: &str, name_1: &str,
+                           rules_1: Option<&SmartPlaylistRules>, feed_token_1: &str) -> Playlist {
+        use crate::dbconfig::schema::playlists::dsl::*;
+
+        insert_into(playlists)
+            .values((
+                username.eq(username_1),
+                name.eq(name_1),
+                rules.eq(rules_1.map(|rules_1| serde_json::to_string(rules_1).unwrap())),
+                feed_token.eq(feed_token_1)
+            ))
+            .get_result::<Playlist>(conn)
+            .expect("Error inserting playlist")
+    }
+
+    pub fn update_playlist(conn: &mut DbConnection, playlist_id: i32, name_1: &str,
+                           rules_1: Option<&SmartPlaylistRules>) -> Playlist {
+        use crate::dbconfig::schema::playlists::dsl::*;
+
+        diesel::update(playlists.filter(id.eq(playlist_id)))
+            .set((
+                name.eq(name_1),
+                rules.eq(rules_1.map(|rules_1| serde_json::to_string(rules_1).unwrap()))
+            ))
+            .get_result::<Playlist>(conn)
+            .expect("Error updating playlist")
+    }
+
+    pub fn update_feed_token(conn: &mut DbConnection, playlist_id: i32, feed_token_1: &str)
+        -> Playlist {
+        use crate::dbconfig::schema::playlists::dsl::*;
+
+        diesel::update(playlists.filter(id.eq(playlist_id)))
+            .set(feed_token.eq(feed_token_1))
+            .get_result::<Playlist>(conn)
+            .expect("Error updating playlist feed token")
+    }
+
+    pub fn get_playlist_of_user(conn: &mut DbConnection, playlist_id: i32, username_1: &str)
+        -> Option<Playlist> {
+        use crate::dbconfig::schema::playlists::dsl::*;
+
+        playlists
+            .filter(id.eq(playlist_id))
+            .filter(username.eq(username_1))
+            .first::<Playlist>(conn)
+            .optional()
+            .expect("Error loading playlist")
+    }
+
+    pub fn get_playlists_of_user(conn: &mut DbConnection, username_1: &str) -> Vec<Playlist> {
+        use crate::dbconfig::schema::playlists::dsl::*;
+
+        playlists
+            .filter(username.eq(username_1))
+            .order(name.asc())
+            .load::<Playlist>(conn)
+            .expect("Error loading playlists")
+    }
+
+    pub fn find_by_name(conn: &mut DbConnection, username_1: &str, name_1: &str)
+        -> Option<Playlist> {
+        use crate::dbconfig::schema::playlists::dsl::*;
+
+        playlists
+            .filter(username.eq(username_1))
+            .filter(name.eq(name_1))
+            .first::<Playlist>(conn)
+            .optional()
+            .expect("Error loading playlist")
+    }
+
+    pub fn get_by_feed_token(conn: &mut DbConnection, feed_token_1: &str) -> Option<Playlist> {
+        use crate::dbconfig::schema::playlists::dsl::*;
+
+        playlists
+            .filter(feed_token.eq(feed_token_1))
+            .first::<Playlist>(conn)
+            .optional()
+            .expect("Error loading playlist")
+    }
+
+    /// The episodes matching the rules of a smart playlist. The tag ids restrict the episodes to
+    /// the podcasts tagged with one of them.
+    pub fn find_smart_playlist_episodes(conn: &mut DbConnection, username_1: &str,
+                                        rules: &SmartPlaylistRules, tag_ids: Option<Vec<i32>>)
+        -> Vec<(PodcastEpisode, Podcast)> {
+        use crate::dbconfig::schema::favorites;
+        use crate::dbconfig::schema::podcast_episodes;
+        use crate::dbconfig::schema::podcasts;
+        use crate::dbconfig::schema::tags_podcasts;
+
+        let mut query = podcast_episodes::table
+            .inner_join(podcasts::table.on(podcast_episodes::podcast_id.eq(podcasts::id)))
+            .into_boxed();
+        if let Some(podcast_ids) = rules.podcast_ids.clone() {
+            query = query.filter(podcast_episodes::podcast_id.eq_any(podcast_ids));
+        }
+        if let Some(tag_ids) = tag_ids {
+            let tagged_podcast_ids = tags_podcasts::table
+                .filter(tags_podcasts::tag_id.eq_any(tag_ids))
+                .select(tags_podcasts::podcast_id);
+            query = query.filter(podcast_episodes::podcast_id.eq_any(tagged_podcast_ids));
+        }
+        if let Some(played) = rules.played {
+            let states = match played {
+                true => [EpisodeStateType::Played, EpisodeStateType::Archived],
+                false => [EpisodeStateType::New, EpisodeStateType::InProgress]
+            };
+            query = query.filter(EpisodeState::has_state(username_1, &states));
+        }
+        if let Some(favored) = rules.favored {
+            let favored_podcasts = favorites::table
+                .filter(favorites::username.eq(username_1.to_string()))
+                .filter(favorites::favored.eq(true))
+                .select(favorites::podcast_id);
+            query = match favored {
+                true => query.filter(podcast_episodes::podcast_id.eq_any(favored_podcasts)),
+                false => query.filter(not(podcast_episodes::podcast_id.eq_any(favored_podcasts)))
+            };
+        }
+        if let Some(downloaded) = rules.downloaded {
+            query = match downloaded {
+                true => query.filter(podcast_episodes::status.eq("D")),
+                false => query.filter(podcast_episodes::status.ne("D"))
+            };
+        }
+        if let Some(min_duration) = rules.min_duration {
+            query = query.filter(podcast_episodes::total_time.ge(min_duration));
+        }
+        if let Some(max_duration) = rules.max_duration {
+            query = query.filter(podcast_episodes::total_time.le(max_duration));
+        }
+        if let Some(published_within_days) = rules.published_within_days {
+            // Publication dates are RFC 3339 strings, so the date prefix compares correctly
+            let published_since = (Utc::now() - Duration::days(published_within_days as i64))
+                .date_naive()
+                .to_string();
+            query = query.filter(podcast_episodes::date_of_recording.ge(published_since));
+        }
+        query = match rules.order {
+            Some(PlaylistOrder::Oldest) => query
+                .order((podcast_episodes::date_of_recording.asc(), podcast_episodes::id.asc())),
+            _ => query
+                .order((podcast_episodes::date_of_recording.desc(), podcast_episodes::id.desc()))
+        };
+        if let Some(limit) = rules.limit {
+            query = query.limit(limit as i64);
+        }
+        query
+            .load::<(PodcastEpisode, Podcast)>(conn)
+            .expect("Error loading episodes of smart playlist")
+    }
+
+    pub fn delete_playlist(conn: &mut DbConnection, playlist_id: i32) {
+        use crate::dbconfig::schema::playlists::dsl::*;
+
+        PlaylistItem::delete_by_playlist_ids(conn, &[playlist_id]);
+        diesel::delete(playlists.filter(id.eq(playlist_id)))
+            .execute(conn)
+            .expect("Error deleting playlist");
+    }
+
+    pub fn delete_by_username(username_1: &str, conn: &mut DbConnection) -> Result<(),
+        diesel::result::Error> {
+        use crate::dbconfig::schema::playlists::dsl::*;
+
+        let playlist_ids = playlists
+            .filter(username.eq(username_1))
+            .select(id)
+            .load::<i32>(conn)?;
+        PlaylistItem::delete_by_playlist_ids(conn, &playlist_ids);
+        diesel::delete(playlists.filter(username.eq(username_1))).execute(conn)?;
+        Ok(())
+    }
+}
+
+impl PlaylistItem {
+    /// The episodes of a manual playlist in playback order
+    pub fn get_items(conn: &mut DbConnection, playlist_id_1: i32)
+        -> Vec<(PodcastEpisode, Podcast)> {
+        use crate::dbconfig::schema::playlist_items::dsl::*;
+        use crate::dbconfig::schema::podcast_episodes;
+        use crate::dbconfig::schema::podcasts;
+
+        playlist_items
+            .inner_join(podcast_episodes::table.on(podcast_episode_id.eq(podcast_episodes::id)))
+            .inner_join(podcasts::table.on(podcast_episodes::podcast_id.eq(podcasts::id)))
+            .filter(playlist_id.eq(playlist_id_1))
+            .order(position.asc())
+            .select((podcast_episodes::all_columns, podcasts::all_columns))
+            .load::<(PodcastEpisode, Podcast)>(conn)
+            .expect("Error loading playlist items")
+    }
+
+    pub fn get_episode_ids(conn: &mut DbConnection, playlist_id_1: i32) -> Vec<i32> {
+        use crate::dbconfig::schema::playlist_items::dsl::*;
+
+        playlist_items
+            .filter(playlist_id.eq(playlist_id_1))
+            .order(position.asc())
+            .select(podcast_episode_id)
+            .load::<i32>(conn)
+            .expect("Error loading playlist items")
+    }
+
+    /// Replaces the episodes of the playlist with the given episodes in this order
+    pub fn save_items(conn: &mut DbConnection, playlist_id_1: i32, podcast_episode_ids: &[i32]) {
+        use crate::dbconfig::schema::playlist_items::dsl::*;
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::delete(playlist_items.filter(playlist_id.eq(playlist_id_1))).execute(conn)?;
+            for (index, episode_id) in podcast_episode_ids.iter().enumerate() {
+                insert_into(playlist_items)
+                    .values((
+                        playlist_id.eq(playlist_id_1),
+                        podcast_episode_id.eq(episode_id),
+                        position.eq(index as i32)
+                    ))
+                    .execute(conn)?;
+            }
+            Ok(())
+        }).expect("Error saving playlist items");
+    }
+
+    pub fn delete_by_playlist_ids(conn: &mut DbConnection, playlist_ids: &[i32]) {
+        use crate::dbconfig::schema::playlist_items::dsl::*;
+
+        diesel::delete(playlist_items.filter(playlist_id.eq_any(playlist_ids)))
+            .execute(conn)
+            .expect("Error deleting playlist items");
+    }
+
+    pub fn delete_by_podcast_id(conn: &mut DbConnection, podcast_id_1: i32) {
+        use crate::dbconfig::schema::playlist_items::dsl::*;
+        use crate::dbconfig::schema::podcast_episodes;
+
+        let episodes_of_podcast = podcast_episodes::table
+            .filter(podcast_episodes::podcast_id.eq(podcast_id_1))
+            .select(podcast_episodes::id);
+        diesel::delete(playlist_items.filter(podcast_episode_id.eq_any(episodes_of_podcast)))
+            .execute(conn)
+            .expect("Error deleting playlist items of podcast");
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use base64::Engine;
+use base64::engine::general_purpose;
+use diesel::{Insertable, OptionalExtension, RunQueryDsl};
+use diesel::QueryDsl;
+use diesel::ExpressionMethods;
+use diesel::AsChangeset;
+use diesel::Queryable;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use utoipa::ToSchema;
+use crate::dbconfig::schema::podcast_credentials;
+use crate::DbConnection;
+
+/// Encrypted credentials of a podcast. The plain credentials never reach the database.
+#[derive(Clone, Insertable, AsChangeset, Queryable)]
+pub struct PodcastCredential {
+    pub podcast_id: i32,
+    pub credentials: String
+}
+
+/// Credentials needed to access a private feed and its media. They are sent with every request
+/// made for the podcast.
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PodcastCredentials {
+    Basic {
+        username: String,
+        password: String
+    },
+    Bearer {
+        token: String
+    },
+    Headers {
+        headers: BTreeMap<String, String>
+    }
+}
+
+/// What the API reveals about stored credentials. Passwords, tokens and header values are left
+/// out.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PodcastCredentialsInfo {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub username: Option<String>,
+    pub header_names: Vec<String>
+}
+
+// Only the redacted form may end up in logs
+impl fmt::Debug for PodcastCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_info().fmt(f)
+    }
+}
+
+impl PodcastCredentials {
+    /// Adds the credentials to the headers. Existing headers of the same name are replaced so
+    /// that e.g. the Authorization header of the PodFetch user isn't passed on.
+    pub fn apply_to_headers(&self, header_map: &mut HeaderMap) {
+        match self {
+            PodcastCredentials::Basic { username, password } => {
+                let b64_auth = general_purpose::STANDARD.encode(format!("{}:{}", username,
+                                                                        password));
+                header_map.insert(AUTHORIZATION, ("Basic ".to_owned() + &b64_auth).parse()
+                    .unwrap());
+            }
+            PodcastCredentials::Bearer { token } => {
+                match HeaderValue::from_str(&format!("Bearer {}", token)) {
+                    Ok(value) => {
+                        header_map.insert(AUTHORIZATION, value);
+                    }
+                    Err(_) => log::error!("Bearer token contains invalid characters")
+                }
+            }
+            PodcastCredentials::Headers { headers } => {
+                for (name, value) in headers {
+                    match (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+                        (Ok(name), Ok(value)) => {
+                            header_map.insert(name, value);
+                        }
+                        _ => log::error!("Skipping invalid credential header {}", name)
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn to_info(&self) -> PodcastCredentialsInfo {
+        match self {
+            PodcastCredentials::Basic { username, .. } => PodcastCredentialsInfo {
+                credential_type: "basic".to_string(),
+                username: Some(username.clone()),
+                header_names: vec![AUTHORIZATION.to_string()]
+            },
+            PodcastCredentials::Bearer { .. } => PodcastCredentialsInfo {
+                credential_type: "bearer".to_string(),
+                username: None,
+                header_names: vec![AUTHORIZATION.to_string()]
+            },
+            PodcastCredentials::Headers { headers } => PodcastCredentialsInfo {
+                credential_type: "headers".to_string(),
+                username: None,
+                header_names: headers.keys().cloned().collect()
+            }
+        }
+    }
+}
+
+impl PodcastCredential {
+    pub fn save_credential(self, conn: &mut DbConnection) {
+        use crate::dbconfig::schema::podcast_credentials::dsl::*;
+
+        let existing_credential = Self::get_credential(conn, self.podcast_id);
+        match existing_credential {
+            Some(_) => {
+                diesel::update(podcast_credentials.filter(podcast_id.eq(self.podcast_id)))
+                    .set(self)
+                    .execute(conn)
+                    .expect("Error updating podcast credentials");
+            }
+            None => {
+                diesel::insert_into(podcast_credentials)
+                    .values(self)
+                    .execute(conn)
+                    .expect("Error inserting podcast credentials");
+            }
+        }
+    }
+
+    pub fn get_credential(conn: &mut DbConnection, podcast_id_to_search: i32)
+        -> Option<PodcastCredential> {
+        use crate::dbconfig::schema::podcast_credentials::dsl::*;
+
+        podcast_credentials
+            .filter(podcast_id.eq(podcast_id_to_search))
+            .first::<PodcastCredential>(conn)
+            .optional()
+            .expect("Error loading podcast credentials")
+    }
+
+    pub fn delete_credential(conn: &mut DbConnection, podcast_id_to_delete: i32) {
+        use crate::dbconfig::schema::podcast_credentials::dsl::*;
+
+        diesel::delete(podcast_credentials.filter(podcast_id.eq(podcast_id_to_delete)))
+            .execute(conn)
+            .expect("Error deleting podcast credentials");
+    }
+}
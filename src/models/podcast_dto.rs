@@ -1,3 +1,7 @@
+use utoipa::ToSchema;
+use crate::models::playback_preference::PlaybackPreferencesDto;
+use crate::models::podcasts::Podcast;
+
 #[derive(Serialize, Deserialize)]
 pub struct PodcastDto {
     pub(crate) id: i32,
@@ -13,5 +17,17 @@ pub struct PodcastDto {
     pub author: Option<String>,
     pub active: bool,
     pub original_image_url: String,
-    pub favorites: bool
+    pub favorites: bool,
+    /// Episodes the user hasn't played yet
+    pub unplayed_episodes: i64,
+    pub playback_preferences: PlaybackPreferencesDto
+}
+
+/// A podcast together with the playback preferences of the user. Uses the field names of the
+/// podcast.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PodcastWithPreferences {
+    #[serde(flatten)]
+    pub podcast: Podcast,
+    pub playback_preferences: PlaybackPreferencesDto
 }
\ No newline at end of file
@@ -1,17 +1,18 @@
+use std::collections::HashSet;
 use crate::dbconfig::schema::*;
-use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use diesel::prelude::{Queryable, Identifiable, Selectable, QueryableByName};
-use diesel::{BoolExpressionMethods, delete, insert_into, OptionalExtension, RunQueryDsl, TextExpressionMethods};
+use diesel::{delete, insert_into, JoinOnDsl, OptionalExtension, RunQueryDsl, TextExpressionMethods};
 use diesel::dsl::sql;
 use utoipa::ToSchema;
-use diesel::sql_types::{Integer, Text, Nullable, Timestamp};
+use diesel::sql_types::{Integer, Text, Nullable, Timestamp, Bool, BigInt};
 use diesel::QueryDsl;
 use diesel::ExpressionMethods;
-use rss::{Guid, Item};
+use crate::models::feed::FeedItem;
 use crate::DbConnection;
 use crate::models::podcasts::Podcast;
 use crate::utils::do_retry::do_retry;
-use crate::utils::time::opt_or_empty_string;
+use crate::utils::time::{opt_or_empty_string, parse_feed_date};
 use diesel::AsChangeset;
 
 #[derive(Queryable, Identifiable,QueryableByName, Selectable, Debug, PartialEq, Clone, ToSchema,
@@ -44,7 +45,22 @@ pub struct PodcastEpisode {
     #[diesel(sql_type = Nullable<Timestamp>)]
     pub(crate) download_time: Option<NaiveDateTime>,
     #[diesel(sql_type = Text)]
-    pub(crate) guid: String
+    pub(crate) guid: String,
+    #[diesel(sql_type = Bool)]
+    pub(crate) removed_upstream: bool,
+    #[diesel(sql_type = Nullable<Integer>)]
+    pub season: Option<i32>,
+    #[diesel(sql_type = Nullable<Integer>)]
+    pub episode_number: Option<i32>,
+    /// Pinned episodes are never deleted by the cleanup
+    #[diesel(sql_type = Bool)]
+    pub pinned: bool,
+    /// Podcasting 2.0 chapters of the episode in the JSON chapters format
+    #[diesel(sql_type = Nullable<Text>)]
+    pub chapters_url: Option<String>,
+    /// Size of the stored file after the download, unknown for older downloads
+    #[diesel(sql_type = Nullable<BigInt>)]
+    pub file_size: Option<i64>
 }
 
 impl PodcastEpisode{
@@ -67,33 +83,6 @@ impl PodcastEpisode{
         Ok(found_podcast_episode)
     }
 
-    pub fn get_podcast_episode_by_url(
-        conn: &mut DbConnection,
-        podcas_episode_url_to_be_found: &str,
-        i: Option<i32>,
-    ) -> Result<Option<PodcastEpisode>, String> {
-        use crate::dbconfig::schema::podcast_episodes::dsl::*;
-        let found_podcast_episode;
-        if i.is_some(){
-            found_podcast_episode = podcast_episodes
-                .filter(url.eq(podcas_episode_url_to_be_found)
-                    .and(podcast_id.eq(i.unwrap())))
-                .first::<PodcastEpisode>(conn)
-                .optional()
-                .expect("Error loading podcast by id");
-        }
-        else{
-            found_podcast_episode = podcast_episodes
-                .filter(url.eq(podcas_episode_url_to_be_found))
-                .first::<PodcastEpisode>(conn)
-                .optional()
-                .expect("Error loading podcast by id");
-        }
-
-
-        Ok(found_podcast_episode)
-    }
-
     pub fn query_podcast_episode_by_url(
         conn: &mut DbConnection,
         podcas_episode_url_to_be_found: &str,
@@ -112,7 +101,7 @@ impl PodcastEpisode{
     pub fn insert_podcast_episodes(
         conn: &mut DbConnection,
         podcast: Podcast,
-        item: Item,
+        item: FeedItem,
         optional_image: Option<String>,
         duration: i32,
     ) -> PodcastEpisode {
@@ -120,40 +109,40 @@ impl PodcastEpisode{
         let uuid_podcast = uuid::Uuid::new_v4();
 
         let mut inserted_date = "".to_string();
-        let inserted_image_url;
-        match &item.pub_date {
+        match item.pub_date.as_deref().and_then(parse_feed_date) {
             Some(date) => {
-                let date = DateTime::parse_from_rfc2822(date).expect("Error parsing date");
                 inserted_date = date.to_rfc3339()
             }
-            None => {}
-        }
-
-        match optional_image {
-            Some(image_url_podcast_episode) => {
-                inserted_image_url = image_url_podcast_episode;
-            }
             None => {
-                inserted_image_url = podcast.original_image_url;
+                log::info!("Episode {:?} has no valid publication date.", item.title);
             }
         }
 
-        let inserted_podcast = insert_into(podcast_episodes)
+        let inserted_image_url = match optional_image {
+            Some(image_url_podcast_episode) => image_url_podcast_episode,
+            None => podcast.original_image_url
+        };
+
+        
+
+
+        insert_into(podcast_episodes)
             .values((
                 total_time.eq(duration),
                 podcast_id.eq(podcast.id),
                 episode_id.eq(uuid_podcast.to_string()),
-                name.eq(item.title.as_ref().unwrap().clone()),
-                url.eq(item.enclosure.unwrap().url),
+                name.eq(item.title.clone().unwrap_or_default()),
+                url.eq(item.enclosure.map(|enclosure| enclosure.url).unwrap_or_default()),
                 date_of_recording.eq(inserted_date),
                 image_url.eq(inserted_image_url),
                 description.eq(opt_or_empty_string(item.description)),
+                guid.eq(item.guid.unwrap_or_default()),
+                season.eq(item.season),
+                episode_number.eq(item.episode_number),
+                chapters_url.eq(item.chapters_url),
             ))
             .get_result::<PodcastEpisode>(conn)
-            .expect("Error inserting podcast episode");
-
-
-        return inserted_podcast;
+            .expect("Error inserting podcast episode")
     }
 
 
@@ -285,7 +274,7 @@ impl PodcastEpisode{
             .expect("Error loading podcast episode by id");
         match result {
             Some(podcast_episode) => {
-                return match podcast_episode.status.as_str() {
+                match podcast_episode.status.as_str() {
                     "N" => Ok(false),
                     "D" => Ok(true),
                     "P" => Ok(false),
@@ -325,17 +314,6 @@ impl PodcastEpisode{
             .expect("Error loading podcast episode by id")
     }
 
-    pub fn get_podcast_episodes_older_than_days(days: i32,conn: &mut DbConnection) ->
-    Vec<PodcastEpisode> {
-        use crate::dbconfig::schema::podcast_episodes::dsl::*;
-
-        podcast_episodes
-            .filter(download_time.lt(Utc::now().naive_utc() - Duration::days(days as i64)))
-            .load::<PodcastEpisode>(conn)
-            .expect("Error loading podcast episode by id")
-    }
-
-
     pub fn update_download_status_of_episode(id_to_find: i32, conn: &mut DbConnection) {
         use crate::dbconfig::schema::podcast_episodes::dsl::*;
         do_retry(||{diesel::update(podcast_episodes.filter(id.eq(id_to_find)))
@@ -344,6 +322,35 @@ impl PodcastEpisode{
         ).expect("Error updating podcast episode");
     }
 
+    pub fn update_file_size(conn: &mut DbConnection, episode_id_to_find: &str,
+                            file_size_1: i64) {
+        use crate::dbconfig::schema::podcast_episodes::dsl::*;
+        diesel::update(podcast_episodes.filter(episode_id.eq(episode_id_to_find)))
+            .set(file_size.eq(file_size_1))
+            .execute(conn)
+            .expect("Error updating file size of podcast episode");
+    }
+
+    pub fn update_pinned(conn: &mut DbConnection, id_to_find: i32, pinned_1: bool) {
+        use crate::dbconfig::schema::podcast_episodes::dsl::*;
+        diesel::update(podcast_episodes.filter(id.eq(id_to_find)))
+            .set(pinned.eq(pinned_1))
+            .execute(conn)
+            .expect("Error updating pinned state of podcast episode");
+    }
+
+    /// The downloaded episodes together with their podcast
+    pub fn get_downloaded_episodes(conn: &mut DbConnection) -> Vec<(PodcastEpisode, Podcast)> {
+        use crate::dbconfig::schema::podcast_episodes::dsl::*;
+        use crate::dbconfig::schema::podcasts;
+
+        podcast_episodes
+            .inner_join(podcasts::table.on(podcast_id.eq(podcasts::id)))
+            .filter(status.eq("D"))
+            .load::<(PodcastEpisode, Podcast)>(conn)
+            .expect("Error loading downloaded podcast episodes")
+    }
+
     pub fn get_episodes_by_podcast_id(
         id_to_search: i32,
         conn: &mut DbConnection
@@ -355,24 +362,120 @@ impl PodcastEpisode{
             .expect("Error loading podcast episode by id")
     }
 
-    pub fn update_guid(conn: &mut DbConnection, guid_to_update:Guid, podcast_episode_id_to_update:
-    &str) {
+    /// The enclosure and image urls of the episodes of a podcast
+    pub fn get_media_urls(conn: &mut DbConnection, podcast_id_to_search: i32)
+        -> Vec<(String, String)> {
         use crate::dbconfig::schema::podcast_episodes::dsl::*;
-        use crate::dbconfig::schema::podcast_episodes::dsl::episode_id as podcast_episode_id;
+        podcast_episodes
+            .filter(podcast_id.eq(podcast_id_to_search))
+            .select((url, image_url))
+            .load::<(String, String)>(conn)
+            .expect("Error loading podcast episode urls")
+    }
 
-        diesel::update(podcast_episodes.filter(podcast_episode_id.eq(podcast_episode_id_to_update)))
-            .set(guid.eq(guid_to_update.value))
-            .execute(conn)
-            .expect("Error updating guide");
+    /// The newest episodes of all podcasts
+    pub fn get_latest_episodes(conn: &mut DbConnection, limit: i64)
+        -> Vec<(PodcastEpisode, Podcast)> {
+        use crate::dbconfig::schema::podcast_episodes::dsl::*;
+        use crate::dbconfig::schema::podcasts;
+
+        podcast_episodes
+            .inner_join(podcasts::table.on(podcast_id.eq(podcasts::id)))
+            .order((date_of_recording.desc(), id.desc()))
+            .limit(limit)
+            .load::<(PodcastEpisode, Podcast)>(conn)
+            .expect("Error loading the latest podcast episodes")
+    }
+
+    /// Returns id and podcast id of the episodes of the podcast or of every episode. Dates are
+    /// compared as points in time, episodes without a valid date aren't published before any.
+    pub fn get_episode_ids(conn: &mut DbConnection, podcast_id_to_search: Option<i32>,
+                           published_before: Option<DateTime<Utc>>) -> Vec<(i32, i32)> {
+        use crate::dbconfig::schema::podcast_episodes::dsl::*;
+
+        let mut query = podcast_episodes
+            .select((id, podcast_id, date_of_recording))
+            .into_boxed();
+        if let Some(podcast_id_to_search) = podcast_id_to_search {
+            query = query.filter(podcast_id.eq(podcast_id_to_search));
+        }
+        query
+            .load::<(i32, i32, String)>(conn)
+            .expect("Error loading podcast episode ids")
+            .into_iter()
+            .filter(|(_, _, date)| published_before.is_none_or(|published_before|
+                Self::is_published_before(date, &published_before)))
+            .map(|(podcast_episode_id, episode_podcast_id, _)| (podcast_episode_id,
+                                                                episode_podcast_id))
+            .collect()
+    }
+
+    fn is_published_before(date: &str, published_before: &DateTime<Utc>) -> bool {
+        parse_feed_date(date).is_some_and(|date| date < *published_before)
     }
 
     pub fn update_podcast_episode(conn: &mut DbConnection, episode_to_update:PodcastEpisode) ->
     PodcastEpisode {
         use crate::dbconfig::schema::podcast_episodes::dsl::*;
 
-        diesel::update(podcast_episodes.find(episode_to_update.id.clone()))
+        diesel::update(podcast_episodes.find(episode_to_update.id))
             .set(episode_to_update)
             .get_result::<PodcastEpisode>(conn)
             .expect("Error updating podcast episode")
     }
-}
\ No newline at end of file
+
+    /// Flags all episodes of a podcast that are no longer part of its feed. Episodes that show up
+    /// in the feed again are unflagged when their metadata is updated.
+    pub fn mark_removed_upstream(conn: &mut DbConnection, podcast_id_to_update: i32,
+                                 episode_ids_in_feed: &[i32]) {
+        use crate::dbconfig::schema::podcast_episodes::dsl::*;
+
+        let episode_ids_in_feed = episode_ids_in_feed.iter().collect::<HashSet<_>>();
+        let removed_episode_ids = podcast_episodes
+            .filter(podcast_id.eq(podcast_id_to_update))
+            .filter(removed_upstream.eq(false))
+            .select(id)
+            .load::<i32>(conn)
+            .expect("Error loading podcast episodes")
+            .into_iter()
+            .filter(|episode| !episode_ids_in_feed.contains(episode))
+            .collect::<Vec<_>>();
+        // Keeps the number of bind parameters per statement below the database limits
+        for chunk in removed_episode_ids.chunks(500) {
+            do_retry(||{diesel::update(podcast_episodes
+                .filter(id.eq_any(chunk)))
+                .set(removed_upstream.eq(true))
+                .execute(conn)})
+                .expect("Error flagging podcast episodes removed upstream");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use crate::models::podcast_episode::PodcastEpisode;
+    use crate::utils::time::get_start_of_day;
+
+    #[test]
+    fn test_is_published_before() {
+        let published_before = get_start_of_day(NaiveDate::from_ymd_opt(2023, 6, 1).unwrap());
+        assert!(PodcastEpisode::is_published_before("2023-05-31T23:59:59+00:00",
+                                                    &published_before));
+        assert!(!PodcastEpisode::is_published_before("2023-06-01T00:00:00Z", &published_before));
+        // Same day in another offset, but already June 1st in UTC
+        assert!(!PodcastEpisode::is_published_before("2023-05-31T23:30:00-02:00",
+                                                     &published_before));
+        assert!(PodcastEpisode::is_published_before("2023-06-01T01:00:00+02:00",
+                                                    &published_before));
+        assert!(PodcastEpisode::is_published_before("Wed, 31 May 2023 10:00:00 GMT",
+                                                    &published_before));
+    }
+
+    #[test]
+    fn test_is_published_before_without_date() {
+        let published_before = get_start_of_day(NaiveDate::from_ymd_opt(2023, 6, 1).unwrap());
+        assert!(!PodcastEpisode::is_published_before("", &published_before));
+        assert!(!PodcastEpisode::is_published_before("not a date", &published_before));
+    }
+}
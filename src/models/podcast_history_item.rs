@@ -98,12 +98,11 @@ impl PodcastHistoryItem{
                     .optional()
                     .expect("Error loading podcast episode by id");
 
-                return match history_item {
+                match history_item {
                     Some(found_history_item) => {
                         let option_episode = Episode::get_watch_log_by_username_and_episode
                             (username_to_find.clone(), conn, found_podcast.clone().url);
-                        if option_episode.is_some(){
-                            let episode = option_episode.unwrap();
+                        if let Some(episode) = option_episode {
                             if episode.action == EpisodeAction::Play.to_string() && episode
                                 .position.unwrap()>found_history_item.watched_time && episode.timestamp>found_history_item.date{
 
@@ -125,7 +124,7 @@ impl PodcastHistoryItem{
                         username: STANDARD_USER.to_string(),
                         date: Utc::now().naive_utc()
                     }),
-                };
+                }
             }
             None => {
                 panic!("Podcast episode not found");
@@ -177,13 +176,13 @@ impl PodcastHistoryItem{
                             .map_podcastepisode_to_dto(&podcast_episode);
                         let podcast = Podcast::get_podcast(conn, podcast_episode.podcast_id)
                             .unwrap();
-                        let podcast_watch_model = mapping_service
+                        
+                        mapping_service
                             .map_podcast_history_item_to_with_podcast_episode(
                                 &podcast_watch_model.clone(),
                                 podcast_dto,
                                 podcast,
-                            );
-                        return podcast_watch_model;
+                            )
                     }
                     None => {
                         panic!("Podcast episode not found");
@@ -194,6 +193,69 @@ impl PodcastHistoryItem{
         Ok(podcast_watch_episode)
     }
 
+    /// Returns username, podcast id and episode id of every listened episode.
+    pub fn get_listened_episodes(conn: &mut DbConnection) -> Vec<(String, i32, String)> {
+        use crate::dbconfig::schema::podcast_history_items::dsl::*;
+
+        podcast_history_items
+            .select((username, podcast_id, episode_id))
+            .distinct()
+            .load::<(String, i32, String)>(conn)
+            .expect("Error loading listened episodes")
+    }
+
+    /// Returns username, podcast episode id, duration and listened position of every logged
+    /// watchtime of the user or of every user.
+    pub fn get_listening_progress(conn: &mut DbConnection, username_1: Option<&str>)
+        -> Vec<(String, i32, i32, i32)> {
+        use crate::dbconfig::schema::podcast_history_items;
+        use crate::dbconfig::schema::podcast_episodes;
+
+        let mut query = podcast_history_items::table
+            .inner_join(podcast_episodes::table.on(podcast_history_items::episode_id
+                .eq(podcast_episodes::episode_id)))
+            .select((podcast_history_items::username, podcast_episodes::id,
+                     podcast_episodes::total_time, podcast_history_items::watched_time))
+            .into_boxed();
+        if let Some(username_1) = username_1 {
+            query = query.filter(podcast_history_items::username.eq(username_1.to_string()));
+        }
+        query
+            .load::<(String, i32, i32, i32)>(conn)
+            .expect("Error loading listening progress")
+    }
+
+    /// The last logged position of the user in the episode
+    pub fn get_latest_watch_log(conn: &mut DbConnection, username_1: &str, episode_id_1: &str)
+        -> Option<PodcastHistoryItem> {
+        use crate::dbconfig::schema::podcast_history_items::dsl::*;
+
+        podcast_history_items
+            .filter(username.eq(username_1))
+            .filter(episode_id.eq(episode_id_1))
+            .order((date.desc(), id.desc()))
+            .first::<PodcastHistoryItem>(conn)
+            .optional()
+            .expect("Error loading latest watch log")
+    }
+
+    /// Every watch log together with the id of its podcast episode, ordered by user, episode
+    /// and date.
+    pub fn get_watch_logs_with_episode_ids(conn: &mut DbConnection)
+        -> Vec<(PodcastHistoryItem, i32)> {
+        use crate::dbconfig::schema::podcast_history_items;
+        use crate::dbconfig::schema::podcast_episodes;
+
+        podcast_history_items::table
+            .inner_join(podcast_episodes::table.on(podcast_history_items::episode_id
+                .eq(podcast_episodes::episode_id)))
+            .select((podcast_history_items::all_columns, podcast_episodes::id))
+            .order((podcast_history_items::username, podcast_history_items::episode_id,
+                    podcast_history_items::date, podcast_history_items::id))
+            .load::<(PodcastHistoryItem, i32)>(conn)
+            .expect("Error loading watch logs")
+    }
+
     pub fn get_watch_logs_by_username(username_to_search: String, conn: &mut DbConnection,
                                       since: NaiveDateTime)
                                       ->
@@ -1,5 +1,9 @@
+use crate::models::podcast_credential::PodcastCredentials;
+
 #[derive(Debug, Serialize, Deserialize,Clone)]
 pub struct PodcastRSSAddModel{
     #[serde(rename = "rssFeedUrl")]
     pub rss_feed_url: String,
+    #[serde(default)]
+    pub credentials: Option<PodcastCredentials>,
 }
\ No newline at end of file
@@ -10,7 +10,6 @@ use diesel::ExpressionMethods;
 use crate::DbConnection;
 use crate::models::favorites::Favorite;
 use crate::models::podcast_dto::PodcastDto;
-use crate::models::podcast_episode::PodcastEpisode;
 use crate::service::mapping_service::MappingService;
 use crate::utils::do_retry::do_retry;
 use crate::utils::podcast_builder::PodcastExtra;
@@ -47,7 +46,12 @@ pub struct Podcast {
     #[diesel(sql_type = Text)]
     pub original_image_url: String,
     #[diesel(sql_type = Text)]
-    pub directory_name:String
+    pub directory_name:String,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub proxy_url: Option<String>,
+    /// Overrides the setting to write metadata tags into downloaded files
+    #[diesel(sql_type = Nullable<Bool>)]
+    pub write_metadata_tags: Option<bool>
 }
 
 impl Podcast{
@@ -75,8 +79,8 @@ impl Podcast{
 
         let mapped_result = result
             .iter()
-            .map(|podcast| return mapping_service.map_podcast_to_podcast_dto_with_favorites
-            (&*podcast))
+            .map(|podcast| mapping_service.map_podcast_to_podcast_dto_with_favorites
+            (podcast))
             .collect::<Vec<PodcastDto>>();
         Ok(mapped_result)
     }
@@ -139,7 +143,8 @@ impl Podcast{
         use crate::dbconfig::schema::podcasts::{directory_id, image_url, name as podcast_name, rssfeed};
         use crate::dbconfig::schema::podcasts::{original_image_url, directory_name};
 
-        let inserted_podcast = insert_into(podcasts::table)
+        
+        insert_into(podcasts::table)
             .values((
                 directory_id.eq(collection_id.to_string()),
                 podcast_name.eq(collection_name.to_string()),
@@ -149,8 +154,7 @@ impl Podcast{
                 directory_name.eq(directory_name_to_insert.to_string())
             ))
             .get_result::<Podcast>(conn)
-            .expect("Error inserting podcast");
-        return inserted_podcast;
+            .expect("Error inserting podcast")
     }
 
     pub fn get_podcast_by_rss_feed(rss_feed_1:String, conn: &mut DbConnection) -> Podcast {
@@ -174,19 +178,6 @@ impl Podcast{
         Ok(result)
     }
 
-    pub fn query_for_podcast(query: &str, conn: &mut DbConnection) -> Result<Vec<PodcastEpisode>, String> {
-        use crate::dbconfig::schema::podcast_episodes::dsl::*;
-        use diesel::TextExpressionMethods;
-        let result = podcast_episodes
-            .filter(
-                name.like(format!("%{}%", query))
-                    .or(description.like(format!("%{}%", query))),
-            )
-            .load::<PodcastEpisode>(conn)
-            .expect("Error loading podcast episode by id");
-        Ok(result)
-    }
-
     pub fn update_podcast_fields(podcast_extra: PodcastExtra,  conn: &mut DbConnection) {
         use crate::dbconfig::schema::podcasts::dsl::*;
 
@@ -234,6 +225,28 @@ impl Podcast{
             .expect("Error updating podcast episode");
     }
 
+    pub fn update_proxy_url(conn: &mut DbConnection, podcast_id_to_update: i32,
+                            proxy_url_to_set: Option<String>) {
+        use crate::dbconfig::schema::podcasts::dsl::*;
+        use crate::dbconfig::schema::podcasts::dsl::id as pid;
+
+        diesel::update(podcasts.filter(pid.eq(podcast_id_to_update)))
+            .set(proxy_url.eq(proxy_url_to_set))
+            .execute(conn)
+            .expect("Error updating proxy of podcast");
+    }
+
+    pub fn update_write_metadata_tags(conn: &mut DbConnection, podcast_id_to_update: i32,
+                                      write_metadata_tags_to_set: Option<bool>) {
+        use crate::dbconfig::schema::podcasts::dsl::*;
+        use crate::dbconfig::schema::podcasts::dsl::id as pid;
+
+        diesel::update(podcasts.filter(pid.eq(podcast_id_to_update)))
+            .set(write_metadata_tags.eq(write_metadata_tags_to_set))
+            .execute(conn)
+            .expect("Error updating metadata tag setting of podcast");
+    }
+
     pub fn update_podcast_urls_on_redirect(podcast_id_to_update: i32, new_url: String, conn: &mut DbConnection) {
         use crate::dbconfig::schema::podcasts::dsl::*;
         use crate::dbconfig::schema::podcasts::dsl::id as pid;
@@ -0,0 +1,260 @@
+use chrono::NaiveDateTime;
+use diesel::{Connection, ExpressionMethods, Insertable, OptionalExtension, QueryDsl, Queryable,
+             RunQueryDsl};
+use diesel::insert_into;
+use utoipa::ToSchema;
+use crate::dbconfig::schema::{queue_auto_add_podcasts, queue_feed_tokens};
+use crate::DbConnection;
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcasts::Podcast;
+
+/// An episode in the "Up Next" queue of a user. The queue is ordered by position.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueItem {
+    pub id: i32,
+    pub username: String,
+    pub podcast_episode_id: i32,
+    pub position: i32,
+    pub added_at: NaiveDateTime
+}
+
+/// A podcast whose new episodes are appended to the queue of the user.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+pub struct QueueAutoAddPodcast {
+    pub username: String,
+    pub podcast_id: i32
+}
+
+/// The secret that gives podcast apps access to the queue feeds of a user.
+#[derive(Debug, Clone, Queryable, Insertable)]
+pub struct QueueFeedToken {
+    pub username: String,
+    pub token: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueItemDto {
+    pub position: i32,
+    pub added_at: NaiveDateTime,
+    pub podcast_episode: PodcastEpisode,
+    pub podcast: Podcast
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueAddModel {
+    pub episode_id: String,
+    /// 0 puts the episode at the top. Without a position it is appended.
+    pub position: Option<usize>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueOrderModel {
+    pub episode_ids: Vec<String>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueFeedUrls {
+    pub rss_url: String,
+    pub m3u_url: String
+}
+
+impl QueueItem {
+    pub fn get_queue(conn: &mut DbConnection, username_1: &str)
+        -> Vec<(QueueItem, PodcastEpisode, Podcast)> {
+        use crate::dbconfig::schema::queue_items::dsl::*;
+        use crate::dbconfig::schema::podcast_episodes;
+        use crate::dbconfig::schema::podcasts;
+        use diesel::JoinOnDsl;
+
+        queue_items
+            .inner_join(podcast_episodes::table.on(podcast_episode_id.eq(podcast_episodes::id)))
+            .inner_join(podcasts::table.on(podcast_episodes::podcast_id.eq(podcasts::id)))
+            .filter(username.eq(username_1))
+            .order(position.asc())
+            .load::<(QueueItem, PodcastEpisode, Podcast)>(conn)
+            .expect("Error loading queue")
+    }
+
+    /// The ids of the queued podcast episodes in queue order
+    pub fn get_queued_episode_ids(conn: &mut DbConnection, username_1: &str) -> Vec<i32> {
+        use crate::dbconfig::schema::queue_items::dsl::*;
+
+        queue_items
+            .filter(username.eq(username_1))
+            .order(position.asc())
+            .select(podcast_episode_id)
+            .load::<i32>(conn)
+            .expect("Error loading queue")
+    }
+
+    /// Stores the queue in the given order. Episodes that are no longer in it are removed.
+    pub fn save_queue(conn: &mut DbConnection, username_1: &str, podcast_episode_ids: &[i32]) {
+        use crate::dbconfig::schema::queue_items::dsl::*;
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::delete(queue_items
+                .filter(username.eq(username_1))
+                .filter(podcast_episode_id.ne_all(podcast_episode_ids)))
+                .execute(conn)?;
+            let queued_episode_ids = queue_items
+                .filter(username.eq(username_1))
+                .select(podcast_episode_id)
+                .load::<i32>(conn)?;
+            for (index, episode_id) in podcast_episode_ids.iter().enumerate() {
+                match queued_episode_ids.contains(episode_id) {
+                    true => {
+                        diesel::update(queue_items
+                            .filter(username.eq(username_1))
+                            .filter(podcast_episode_id.eq(episode_id)))
+                            .set(position.eq(index as i32))
+                            .execute(conn)?;
+                    }
+                    false => {
+                        insert_into(queue_items)
+                            .values((
+                                username.eq(username_1),
+                                podcast_episode_id.eq(episode_id),
+                                position.eq(index as i32)
+                            ))
+                            .execute(conn)?;
+                    }
+                }
+            }
+            Ok(())
+        }).expect("Error saving queue");
+    }
+
+    pub fn delete_by_username(username_1: &str, conn: &mut DbConnection) -> Result<(),
+        diesel::result::Error> {
+        use crate::dbconfig::schema::queue_items::dsl::*;
+
+        diesel::delete(queue_items.filter(username.eq(username_1))).execute(conn)?;
+        Ok(())
+    }
+
+    pub fn delete_by_podcast_id(conn: &mut DbConnection, podcast_id_1: i32) {
+        use crate::dbconfig::schema::queue_items::dsl::*;
+        use crate::dbconfig::schema::podcast_episodes;
+
+        let episodes_of_podcast = podcast_episodes::table
+            .filter(podcast_episodes::podcast_id.eq(podcast_id_1))
+            .select(podcast_episodes::id);
+        diesel::delete(queue_items.filter(podcast_episode_id.eq_any(episodes_of_podcast)))
+            .execute(conn)
+            .expect("Error deleting queued episodes of podcast");
+    }
+}
+
+impl QueueAutoAddPodcast {
+    pub fn get_podcast_ids(conn: &mut DbConnection, username_1: &str) -> Vec<i32> {
+        use crate::dbconfig::schema::queue_auto_add_podcasts::dsl::*;
+
+        queue_auto_add_podcasts
+            .filter(username.eq(username_1))
+            .select(podcast_id)
+            .load::<i32>(conn)
+            .expect("Error loading podcasts that are added to the queue")
+    }
+
+    pub fn get_usernames(conn: &mut DbConnection, podcast_id_1: i32) -> Vec<String> {
+        use crate::dbconfig::schema::queue_auto_add_podcasts::dsl::*;
+
+        queue_auto_add_podcasts
+            .filter(podcast_id.eq(podcast_id_1))
+            .select(username)
+            .load::<String>(conn)
+            .expect("Error loading users that add the podcast to their queue")
+    }
+
+    pub fn add_podcast(conn: &mut DbConnection, username_1: &str, podcast_id_1: i32) {
+        use crate::dbconfig::schema::queue_auto_add_podcasts::dsl::*;
+
+        let existing = queue_auto_add_podcasts
+            .filter(username.eq(username_1))
+            .filter(podcast_id.eq(podcast_id_1))
+            .first::<QueueAutoAddPodcast>(conn)
+            .optional()
+            .expect("Error loading podcast that is added to the queue");
+        if existing.is_none() {
+            insert_into(queue_auto_add_podcasts)
+                .values(QueueAutoAddPodcast {
+                    username: username_1.to_string(),
+                    podcast_id: podcast_id_1
+                })
+                .execute(conn)
+                .expect("Error adding podcast to the queue");
+        }
+    }
+
+    pub fn remove_podcast(conn: &mut DbConnection, username_1: &str, podcast_id_1: i32) {
+        use crate::dbconfig::schema::queue_auto_add_podcasts::dsl::*;
+
+        diesel::delete(queue_auto_add_podcasts
+            .filter(username.eq(username_1))
+            .filter(podcast_id.eq(podcast_id_1)))
+            .execute(conn)
+            .expect("Error removing podcast from the queue");
+    }
+
+    pub fn delete_by_username(username_1: &str, conn: &mut DbConnection) -> Result<(),
+        diesel::result::Error> {
+        use crate::dbconfig::schema::queue_auto_add_podcasts::dsl::*;
+
+        diesel::delete(queue_auto_add_podcasts.filter(username.eq(username_1))).execute(conn)?;
+        Ok(())
+    }
+
+    pub fn delete_by_podcast_id(conn: &mut DbConnection, podcast_id_1: i32) {
+        use crate::dbconfig::schema::queue_auto_add_podcasts::dsl::*;
+
+        diesel::delete(queue_auto_add_podcasts.filter(podcast_id.eq(podcast_id_1)))
+            .execute(conn)
+            .expect("Error deleting podcast that is added to the queue");
+    }
+}
+
+impl QueueFeedToken {
+    pub fn get_by_username(conn: &mut DbConnection, username_1: &str) -> Option<QueueFeedToken> {
+        use crate::dbconfig::schema::queue_feed_tokens::dsl::*;
+
+        queue_feed_tokens
+            .filter(username.eq(username_1))
+            .first::<QueueFeedToken>(conn)
+            .optional()
+            .expect("Error loading queue feed token")
+    }
+
+    pub fn get_by_token(conn: &mut DbConnection, token_1: &str) -> Option<QueueFeedToken> {
+        use crate::dbconfig::schema::queue_feed_tokens::dsl::*;
+
+        queue_feed_tokens
+            .filter(token.eq(token_1))
+            .first::<QueueFeedToken>(conn)
+            .optional()
+            .expect("Error loading queue feed token")
+    }
+
+    /// Replaces the token of the user. Links with the old token stop working.
+    pub fn save_token(&self, conn: &mut DbConnection) {
+        use crate::dbconfig::schema::queue_feed_tokens::dsl::*;
+
+        Self::delete_by_username(&self.username, conn).expect("Error deleting queue feed token");
+        insert_into(queue_feed_tokens)
+            .values(self.clone())
+            .execute(conn)
+            .expect("Error saving queue feed token");
+    }
+
+    pub fn delete_by_username(username_1: &str, conn: &mut DbConnection) -> Result<(),
+        diesel::result::Error> {
+        use crate::dbconfig::schema::queue_feed_tokens::dsl::*;
+
+        diesel::delete(queue_feed_tokens.filter(username.eq(username_1))).execute(conn)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use utoipa::ToSchema;
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcasts::Podcast;
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Recommendations {
+    pub episodes: Vec<EpisodeRecommendation>,
+    pub podcasts: Vec<PodcastRecommendation>
+}
+
+/// An unplayed episode of a podcast the user follows.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeRecommendation {
+    pub podcast_episode: PodcastEpisode,
+    pub podcast: Podcast,
+    pub score: f64,
+    pub reasons: Vec<RecommendationReason>
+}
+
+/// A podcast of the library the user doesn't follow yet.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PodcastRecommendation {
+    pub podcast: Podcast,
+    pub score: f64,
+    pub reasons: Vec<RecommendationReason>
+}
+
+/// Explains why something was recommended.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RecommendationReason {
+    #[serde(rename = "type")]
+    pub reason_type: RecommendationReasonType,
+    pub message: String,
+    /// The podcast of the user the recommendation is based on
+    pub related_podcast_id: Option<i32>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum RecommendationReasonType {
+    Favorite,
+    Subscription,
+    ListeningHistory,
+    CoListening,
+    Keywords,
+    Language,
+    Popular
+}
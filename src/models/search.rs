@@ -0,0 +1,272 @@
+use chrono::{DateTime, Utc};
+use diesel::{QueryableByName, RunQueryDsl};
+use diesel::sql_types::{BigInt, Bool, Double, Integer, Nullable, Text};
+use utoipa::ToSchema;
+use crate::constants::constants::PLAYED_THRESHOLD_PERCENT;
+use crate::DbConnection;
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcasts::Podcast;
+use crate::utils::feed_sanitizer::to_plain_text;
+use crate::utils::search_highlight::{highlight, snippet};
+
+// Number of words of the description and summary snippets
+const SNIPPET_WORDS: usize = 32;
+
+/// Filters of the episode search. Filters that aren't set match every episode.
+#[derive(Debug, Clone, Default)]
+pub struct EpisodeSearchFilter {
+    pub podcast_id: Option<i32>,
+    // The publication dates of the episodes keep the offset of the feed, they are compared as
+    // points in time
+    pub from: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub downloaded: Option<bool>,
+    pub played: Option<bool>,
+    pub username: String
+}
+
+/// An episode matching a search. The snippets are HTML escaped plain text, only the matched
+/// terms are marked with `<mark>` tags.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeSearchResult {
+    pub podcast_episode: PodcastEpisode,
+    pub score: f64,
+    pub name_snippet: String,
+    pub description_snippet: String
+}
+
+/// A podcast matching a search. The snippets are HTML escaped plain text, only the matched terms
+/// are marked with `<mark>` tags.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PodcastSearchResult {
+    pub podcast: Podcast,
+    pub score: f64,
+    pub name_snippet: String,
+    pub summary_snippet: String
+}
+
+#[derive(QueryableByName)]
+struct EpisodeMatch {
+    #[diesel(embed)]
+    podcast_episode: PodcastEpisode,
+    #[diesel(sql_type = Double)]
+    score: f64
+}
+
+#[derive(QueryableByName)]
+struct PodcastMatch {
+    #[diesel(embed)]
+    podcast: Podcast,
+    #[diesel(sql_type = Double)]
+    score: f64
+}
+
+#[cfg(sqlite)]
+const EPISODE_SEARCH_QUERY: &str = "SELECT e.*, -bm25(podcast_episodes_fts, 10.0, 1.0) AS score
+    FROM podcast_episodes_fts
+    JOIN podcast_episodes e ON e.id = podcast_episodes_fts.rowid
+    WHERE podcast_episodes_fts MATCH ?1
+    AND (?2 IS NULL OR e.podcast_id = ?2)
+    AND (?3 IS NULL OR julianday(e.date_of_recording) >= julianday(?3))
+    AND (?4 IS NULL OR julianday(e.date_of_recording) < julianday(?4))
+    AND (?5 IS NULL OR (e.status = 'D') = ?5)
+    AND (?6 IS NULL OR coalesce((SELECT s.state IN ('played', 'archived') FROM episode_states s
+        WHERE s.podcast_episode_id = e.id AND s.username = ?7),
+        EXISTS (SELECT 1 FROM podcast_history_items h
+        WHERE h.episode_id = e.episode_id AND h.username = ?7 AND e.total_time > 0
+        AND h.watched_time * 100 >= e.total_time * ?10)) = ?6)
+    ORDER BY score DESC, e.id DESC
+    LIMIT ?8 OFFSET ?9";
+
+#[cfg(sqlite)]
+const PODCAST_SEARCH_QUERY: &str = "SELECT p.*, -bm25(podcasts_fts, 10.0, 5.0, 1.0, 5.0) AS score
+    FROM podcasts_fts
+    JOIN podcasts p ON p.id = podcasts_fts.rowid
+    WHERE podcasts_fts MATCH ?1
+    ORDER BY score DESC, p.id DESC
+    LIMIT ?2 OFFSET ?3";
+
+// The search vectors are the expressions of the indexes created in the full_text_search
+// migration. Only RFC 3339 dates are cast, as a failing cast would fail the whole search.
+#[cfg(postgresql)]
+const EPISODE_SEARCH_QUERY: &str = "SELECT e.*,
+    ts_rank(setweight(to_tsvector('simple', e.name), 'A') ||
+        setweight(to_tsvector('simple', e.description), 'C'), q)::float8 AS score
+    FROM podcast_episodes e, to_tsquery('simple', $1) q
+    WHERE (setweight(to_tsvector('simple', e.name), 'A') ||
+        setweight(to_tsvector('simple', e.description), 'C')) @@ q
+    AND ($2::int4 IS NULL OR e.podcast_id = $2)
+    AND ($3::timestamptz IS NULL OR (CASE WHEN e.date_of_recording ~ '^[0-9]{4}-[0-9]{2}-\
+    [0-9]{2}T[0-9]{2}:[0-9]{2}:[0-9]{2}(\\.[0-9]+)?([+-][0-9]{2}:[0-9]{2}|Z)$'
+        THEN e.date_of_recording::timestamptz END) >= $3::timestamptz)
+    AND ($4::timestamptz IS NULL OR (CASE WHEN e.date_of_recording ~ '^[0-9]{4}-[0-9]{2}-\
+    [0-9]{2}T[0-9]{2}:[0-9]{2}:[0-9]{2}(\\.[0-9]+)?([+-][0-9]{2}:[0-9]{2}|Z)$'
+        THEN e.date_of_recording::timestamptz END) < $4::timestamptz)
+    AND ($5::bool IS NULL OR (e.status = 'D') = $5)
+    AND ($6::bool IS NULL OR coalesce((SELECT s.state IN ('played', 'archived')
+        FROM episode_states s WHERE s.podcast_episode_id = e.id AND s.username = $7),
+        EXISTS (SELECT 1 FROM podcast_history_items h
+        WHERE h.episode_id = e.episode_id AND h.username = $7 AND e.total_time > 0
+        AND h.watched_time * 100 >= e.total_time * $10::int4)) = $6)
+    ORDER BY score DESC, e.id DESC
+    LIMIT $8 OFFSET $9";
+
+#[cfg(postgresql)]
+const PODCAST_SEARCH_QUERY: &str = "SELECT p.*,
+    ts_rank(setweight(to_tsvector('simple', p.name), 'A') ||
+        setweight(to_tsvector('simple', coalesce(p.author, '')), 'B') ||
+        setweight(to_tsvector('simple', coalesce(p.keywords, '')), 'B') ||
+        setweight(to_tsvector('simple', coalesce(p.summary, '')), 'C'), q)::float8 AS score
+    FROM podcasts p, to_tsquery('simple', $1) q
+    WHERE (setweight(to_tsvector('simple', p.name), 'A') ||
+        setweight(to_tsvector('simple', coalesce(p.author, '')), 'B') ||
+        setweight(to_tsvector('simple', coalesce(p.keywords, '')), 'B') ||
+        setweight(to_tsvector('simple', coalesce(p.summary, '')), 'C')) @@ q
+    ORDER BY score DESC, p.id DESC
+    LIMIT $2 OFFSET $3";
+
+impl EpisodeSearchResult {
+    /// Searches episode names and descriptions. Every term has to match, either completely or as
+    /// the prefix of a word.
+    pub fn search(conn: &mut DbConnection, terms: &[String], filter: &EpisodeSearchFilter,
+                  limit: i64, offset: i64) -> Vec<EpisodeSearchResult> {
+        diesel::sql_query(EPISODE_SEARCH_QUERY)
+            .bind::<Text, _>(to_match_query(terms))
+            .bind::<Nullable<Integer>, _>(filter.podcast_id)
+            .bind::<Nullable<Text>, _>(filter.from.map(|from| from.to_rfc3339()))
+            .bind::<Nullable<Text>, _>(filter.until.map(|until| until.to_rfc3339()))
+            .bind::<Nullable<Bool>, _>(filter.downloaded)
+            .bind::<Nullable<Bool>, _>(filter.played)
+            .bind::<Text, _>(filter.username.clone())
+            .bind::<BigInt, _>(limit)
+            .bind::<BigInt, _>(offset)
+            .bind::<Integer, _>(PLAYED_THRESHOLD_PERCENT)
+            .load::<EpisodeMatch>(conn)
+            .expect("Error searching podcast episodes")
+            .into_iter()
+            .map(|episode_match| EpisodeSearchResult {
+                name_snippet: highlight(&episode_match.podcast_episode.name, terms),
+                description_snippet: snippet(&to_plain_text(&episode_match.podcast_episode
+                    .description), terms, SNIPPET_WORDS),
+                podcast_episode: episode_match.podcast_episode,
+                score: episode_match.score
+            })
+            .collect()
+    }
+}
+
+impl PodcastSearchResult {
+    /// Searches podcast names, authors, summaries and keywords.
+    pub fn search(conn: &mut DbConnection, terms: &[String], limit: i64, offset: i64)
+        -> Vec<PodcastSearchResult> {
+        diesel::sql_query(PODCAST_SEARCH_QUERY)
+            .bind::<Text, _>(to_match_query(terms))
+            .bind::<BigInt, _>(limit)
+            .bind::<BigInt, _>(offset)
+            .load::<PodcastMatch>(conn)
+            .expect("Error searching podcasts")
+            .into_iter()
+            .map(|podcast_match| PodcastSearchResult {
+                name_snippet: highlight(&podcast_match.podcast.name, terms),
+                summary_snippet: snippet(&to_plain_text(&podcast_match.podcast.summary.clone()
+                    .unwrap_or_default()), terms, SNIPPET_WORDS),
+                podcast: podcast_match.podcast,
+                score: podcast_match.score
+            })
+            .collect()
+    }
+}
+
+// The terms only contain letters and digits, so they can't break the query syntax
+#[cfg(sqlite)]
+fn to_match_query(terms: &[String]) -> String {
+    terms.iter()
+        .map(|term| format!("\"{}\"*", term))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(postgresql)]
+fn to_match_query(terms: &[String]) -> String {
+    terms.iter()
+        .map(|term| format!("{}:*", term))
+        .collect::<Vec<String>>()
+        .join(" & ")
+}
+
+#[cfg(all(test, sqlite))]
+mod tests {
+    use diesel::{Connection, RunQueryDsl, SqliteConnection};
+    use diesel::sql_types::{Integer, Text};
+    use diesel_migrations::MigrationHarness;
+    use crate::MIGRATIONS;
+    use crate::models::search::{EpisodeSearchFilter, EpisodeSearchResult, PodcastSearchResult};
+
+    fn get_connection() -> SqliteConnection {
+        let mut conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        diesel::sql_query("INSERT INTO podcasts (id, name, directory_id, rssfeed, image_url, \
+        summary, active, original_image_url, directory_name) VALUES (1, 'Rust <i>Weekly</i>', \
+        'rust-weekly', 'https://example.com/feed.xml', '', '<p>All about <b>Rust</b> \
+        <img src=x onerror=alert(1)></p>', 1, '', 'Rust Weekly')")
+            .execute(&mut conn)
+            .unwrap();
+        conn
+    }
+
+    fn insert_episode(conn: &mut SqliteConnection, id: i32, name: &str, description: &str) {
+        diesel::sql_query("INSERT INTO podcast_episodes (id, podcast_id, episode_id, name, url, \
+        date_of_recording, image_url, total_time, local_url, local_image_url, description, \
+        status, guid, removed_upstream, pinned) VALUES (?, 1, ?, ?, '', \
+        '2023-08-01T10:00:00+00:00', '', 0, '', '', ?, 'N', '', 0, 0)")
+            .bind::<Integer, _>(id)
+            .bind::<Text, _>(format!("episode-{}", id))
+            .bind::<Text, _>(name)
+            .bind::<Text, _>(description)
+            .execute(conn)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_search_episodes_ranks_names_above_descriptions() {
+        let mut conn = get_connection();
+        insert_episode(&mut conn, 1, "Weekly news", "<p>This week in <b>Rust</b></p>");
+        insert_episode(&mut conn, 2, "Rust 1.72 released", "<p>The release notes</p>");
+        insert_episode(&mut conn, 3, "Go generics", "<p>Nothing to see</p>");
+
+        let results = EpisodeSearchResult::search(&mut conn, &["rust".to_string()],
+                                                  &EpisodeSearchFilter::default(), 10, 0);
+        assert_eq!(results.iter().map(|result| result.podcast_episode.id).collect::<Vec<i32>>(),
+                   vec![2, 1]);
+        assert!(results[0].score > results[1].score);
+        assert_eq!(results[0].name_snippet, "<mark>Rust</mark> 1.72 released");
+        assert_eq!(results[1].description_snippet, "This week in <mark>Rust</mark>");
+    }
+
+    #[test]
+    fn test_search_episodes_escapes_feed_markup() {
+        let mut conn = get_connection();
+        insert_episode(&mut conn, 1, "<script>alert(1)</script> Rust", "<p>Rust &amp; \
+        <img src=x onerror=alert(1)>&lt;script&gt;alert(2)&lt;/script&gt; <a href=\"https://\
+        example.com/rust\">Show notes</a></p>");
+
+        let results = EpisodeSearchResult::search(&mut conn, &["rust".to_string()],
+                                                  &EpisodeSearchFilter::default(), 10, 0);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name_snippet,
+                   "&lt;script&gt;alert(1)&lt;/script&gt; <mark>Rust</mark>");
+        assert_eq!(results[0].description_snippet,
+                   "<mark>Rust</mark> &amp; &lt;script&gt;alert(2)&lt;/script&gt; Show notes");
+    }
+
+    #[test]
+    fn test_search_podcasts_escapes_feed_markup() {
+        let mut conn = get_connection();
+        let results = PodcastSearchResult::search(&mut conn, &["rust".to_string()], 10, 0);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name_snippet, "<mark>Rust</mark> &lt;i&gt;Weekly&lt;/i&gt;");
+        assert_eq!(results[0].summary_snippet, "All about <mark>Rust</mark>");
+    }
+}
@@ -1,3 +1,4 @@
+#[allow(clippy::upper_case_acronyms)]
 pub enum SearchType {
     ITUNES,
     PODINDEX,
@@ -14,3 +15,13 @@ impl TryFrom<i32> for SearchType {
         }
     }
 }
+
+impl SearchType {
+    /// The discovery provider that searches the directory
+    pub fn provider_id(&self) -> &'static str {
+        match self {
+            SearchType::ITUNES => "itunes",
+            SearchType::PODINDEX => "podindex",
+        }
+    }
+}
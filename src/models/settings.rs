@@ -23,7 +23,9 @@ pub struct Setting {
     pub use_existing_filename: bool,
     pub replacement_strategy: String,
     pub episode_format: String,
-    pub podcast_format: String
+    pub podcast_format: String,
+    /// Writes the podcast and episode metadata into downloaded audio files
+    pub write_metadata_tags: bool
 }
 
 #[derive(Serialize, Deserialize)]
@@ -65,8 +67,9 @@ impl Setting{
         use diesel::ExpressionMethods;
         do_retry(||{insert_into(settings)
             .values((
-                id.eq(1),
+                id.eq(DEFAULT_SETTINGS.id),
                 auto_download.eq(DEFAULT_SETTINGS.auto_download),
+                auto_update.eq(DEFAULT_SETTINGS.auto_update),
                 auto_cleanup.eq(DEFAULT_SETTINGS.auto_cleanup),
                 auto_cleanup_days.eq(DEFAULT_SETTINGS.auto_cleanup_days),
                 podcast_prefill.eq(DEFAULT_SETTINGS.podcast_prefill))
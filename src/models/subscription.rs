@@ -49,6 +49,21 @@ impl Subscription{
             .execute(conn).expect("Error deleting subscriptions of user");
         Ok(())
     }
+
+    /// Ids of the podcasts the user subscribed to with a gpodder client
+    pub fn get_subscribed_podcast_ids(conn: &mut DbConnection, username_1: &str) -> Vec<i32> {
+        use crate::dbconfig::schema::podcasts;
+        use diesel::JoinOnDsl;
+
+        subscriptions::table
+            .inner_join(podcasts::table.on(subscriptions::podcast.eq(podcasts::rssfeed)))
+            .filter(subscriptions::username.eq(username_1))
+            .filter(subscriptions::deleted.is_null())
+            .select(podcasts::id)
+            .distinct()
+            .load::<i32>(conn)
+            .expect("Error loading subscribed podcasts")
+    }
 }
 
 
@@ -129,14 +144,11 @@ impl SubscriptionChangesToClient {
             let opt_sub = Self::find_by_podcast(username.to_string(), device_id.to_string(), c
                 .to_string(), conn).expect("Error retrieving \
                                              subscription");
-            match opt_sub {
-                Some(s)=>{
-                    diesel::update(subscriptions.
-                        filter(dsl_types::id.eq(s.id)))
-                        .set(dsl_types::deleted.eq(Some(Utc::now().naive_utc())))
-                        .execute(conn).unwrap();
-                },
-                None=>{}
+            if let Some(s) = opt_sub {
+                diesel::update(subscriptions.
+                    filter(dsl_types::id.eq(s.id)))
+                    .set(dsl_types::deleted.eq(Some(Utc::now().naive_utc())))
+                    .execute(conn).unwrap();
             }
         });
 
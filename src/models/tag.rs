@@ -0,0 +1,215 @@
+use chrono::NaiveDateTime;
+use diesel::{ExpressionMethods, Insertable, OptionalExtension, QueryDsl, Queryable, RunQueryDsl};
+use diesel::insert_into;
+use utoipa::ToSchema;
+use crate::dbconfig::schema::tags_podcasts;
+use crate::DbConnection;
+
+/// A user defined label for podcasts. Tags with a parent are nested like folders.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub id: i32,
+    pub username: String,
+    pub name: String,
+    pub parent_id: Option<i32>,
+    pub color: Option<String>,
+    pub created_at: NaiveDateTime
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+pub struct TagsPodcast {
+    pub tag_id: i32,
+    pub podcast_id: i32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagDto {
+    pub id: i32,
+    pub name: String,
+    pub parent_id: Option<i32>,
+    pub color: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub podcast_ids: Vec<i32>
+}
+
+impl Tag {
+    pub fn insert_tag(conn: &mut DbConnection, username_1: &str, name_1: &str,
+                      parent_id_1: Option<i32>, color_1: Option<String>) -> Tag {
+        use crate::dbconfig::schema::tags::dsl::*;
+
+        insert_into(tags)
+            .values((
+                username.eq(username_1),
+                name.eq(name_1),
+                parent_id.eq(parent_id_1),
+                color.eq(color_1)
+            ))
+            .get_result::<Tag>(conn)
+            .expect("Error inserting tag")
+    }
+
+    pub fn update_tag(conn: &mut DbConnection, tag_id: i32, name_1: &str,
+                      parent_id_1: Option<i32>, color_1: Option<String>) -> Tag {
+        use crate::dbconfig::schema::tags::dsl::*;
+
+        diesel::update(tags.filter(id.eq(tag_id)))
+            .set((
+                name.eq(name_1),
+                parent_id.eq(parent_id_1),
+                color.eq(color_1)
+            ))
+            .get_result::<Tag>(conn)
+            .expect("Error updating tag")
+    }
+
+    pub fn get_tag_of_user(conn: &mut DbConnection, tag_id: i32, username_1: &str)
+        -> Option<Tag> {
+        use crate::dbconfig::schema::tags::dsl::*;
+
+        tags
+            .filter(id.eq(tag_id))
+            .filter(username.eq(username_1))
+            .first::<Tag>(conn)
+            .optional()
+            .expect("Error loading tag")
+    }
+
+    pub fn get_tags_of_user(conn: &mut DbConnection, username_1: &str) -> Vec<Tag> {
+        use crate::dbconfig::schema::tags::dsl::*;
+
+        tags
+            .filter(username.eq(username_1))
+            .order(name.asc())
+            .load::<Tag>(conn)
+            .expect("Error loading tags")
+    }
+
+    pub fn find_by_name(conn: &mut DbConnection, username_1: &str, name_1: &str,
+                        parent_id_1: Option<i32>) -> Option<Tag> {
+        use crate::dbconfig::schema::tags::dsl::*;
+
+        let query = tags
+            .filter(username.eq(username_1))
+            .filter(name.eq(name_1))
+            .into_boxed();
+        let query = match parent_id_1 {
+            Some(parent_id_1) => query.filter(parent_id.eq(parent_id_1)),
+            None => query.filter(parent_id.is_null())
+        };
+        query
+            .first::<Tag>(conn)
+            .optional()
+            .expect("Error loading tag")
+    }
+
+    /// Deletes the tag. Its nested tags are moved up to its parent.
+    pub fn delete_tag(conn: &mut DbConnection, tag: &Tag) {
+        use crate::dbconfig::schema::tags::dsl::*;
+
+        diesel::update(tags.filter(parent_id.eq(tag.id)))
+            .set(parent_id.eq(tag.parent_id))
+            .execute(conn)
+            .expect("Error moving nested tags");
+        TagsPodcast::delete_by_tag_ids(conn, &[tag.id]);
+        diesel::delete(tags.filter(id.eq(tag.id)))
+            .execute(conn)
+            .expect("Error deleting tag");
+    }
+
+    pub fn delete_by_username(username_1: &str, conn: &mut DbConnection) -> Result<(),
+        diesel::result::Error> {
+        use crate::dbconfig::schema::tags::dsl::*;
+
+        let tag_ids = tags
+            .filter(username.eq(username_1))
+            .select(id)
+            .load::<i32>(conn)?;
+        TagsPodcast::delete_by_tag_ids(conn, &tag_ids);
+        // Nested tags reference their parent
+        diesel::update(tags.filter(username.eq(username_1)))
+            .set(parent_id.eq(None::<i32>))
+            .execute(conn)?;
+        diesel::delete(tags.filter(username.eq(username_1))).execute(conn)?;
+        Ok(())
+    }
+}
+
+impl TagsPodcast {
+    pub fn add_podcast_to_tag(conn: &mut DbConnection, tag_id_1: i32, podcast_id_1: i32) {
+        use crate::dbconfig::schema::tags_podcasts::dsl::*;
+
+        let existing = tags_podcasts
+            .filter(tag_id.eq(tag_id_1))
+            .filter(podcast_id.eq(podcast_id_1))
+            .first::<TagsPodcast>(conn)
+            .optional()
+            .expect("Error loading tagged podcast");
+        if existing.is_none() {
+            insert_into(tags_podcasts)
+                .values(TagsPodcast {
+                    tag_id: tag_id_1,
+                    podcast_id: podcast_id_1
+                })
+                .execute(conn)
+                .expect("Error tagging podcast");
+        }
+    }
+
+    pub fn remove_podcast_from_tag(conn: &mut DbConnection, tag_id_1: i32, podcast_id_1: i32) {
+        use crate::dbconfig::schema::tags_podcasts::dsl::*;
+
+        diesel::delete(tags_podcasts
+            .filter(tag_id.eq(tag_id_1))
+            .filter(podcast_id.eq(podcast_id_1)))
+            .execute(conn)
+            .expect("Error removing podcast from tag");
+    }
+
+    pub fn get_tagged_podcasts(conn: &mut DbConnection, tag_ids: &[i32]) -> Vec<TagsPodcast> {
+        use crate::dbconfig::schema::tags_podcasts::dsl::*;
+
+        tags_podcasts
+            .filter(tag_id.eq_any(tag_ids))
+            .load::<TagsPodcast>(conn)
+            .expect("Error loading tagged podcasts")
+    }
+
+    pub fn delete_by_tag_ids(conn: &mut DbConnection, tag_ids: &[i32]) {
+        use crate::dbconfig::schema::tags_podcasts::dsl::*;
+
+        diesel::delete(tags_podcasts.filter(tag_id.eq_any(tag_ids)))
+            .execute(conn)
+            .expect("Error deleting tagged podcasts");
+    }
+
+    pub fn delete_by_podcast_id(conn: &mut DbConnection, podcast_id_1: i32) {
+        use crate::dbconfig::schema::tags_podcasts::dsl::*;
+
+        diesel::delete(tags_podcasts.filter(podcast_id.eq(podcast_id_1)))
+            .execute(conn)
+            .expect("Error deleting tags of podcast");
+    }
+}
+
+impl From<(Tag, Vec<i32>)> for TagDto {
+    fn from((tag, podcast_ids): (Tag, Vec<i32>)) -> Self {
+        TagDto {
+            id: tag.id,
+            name: tag.name,
+            parent_id: tag.parent_id,
+            color: tag.color,
+            created_at: tag.created_at,
+            podcast_ids
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCreateModel {
+    pub name: String,
+    pub parent_id: Option<i32>,
+    pub color: Option<String>
+}
@@ -49,13 +49,10 @@ impl User{
 
     pub fn find_by_username(username_to_find: &str, conn: &mut DbConnection) -> Option<User> {
         use crate::dbconfig::schema::users::dsl::*;
-        match var(USERNAME) {
-             Ok(res)=> {
-                if res==username_to_find {
-                    return Some(User::create_admin_user());
-                }
+        if let Ok(res) = var(USERNAME) {
+            if res==username_to_find {
+                return Some(User::create_admin_user());
             }
-            _ => {}
         }
 
         users.filter(username.eq(username_to_find))
@@ -67,13 +64,10 @@ impl User{
     pub fn insert_user(&mut self, conn: &mut DbConnection) -> Result<User, Error> {
         use crate::dbconfig::schema::users::dsl::*;
 
-        match  var(USERNAME){
-            Ok(res) => {
-                if res==self.username {
-                    return Err(Error::new(std::io::ErrorKind::Other, "Username already exists"));
-                }
-            },
-            Err(_) => {}
+        if let Ok(res) = var(USERNAME) {
+            if res==self.username {
+                return Err(Error::other("Username already exists"));
+            }
         }
 
         let res = diesel::insert_into(users::table())
@@ -144,7 +138,7 @@ impl User{
         use crate::dbconfig::schema::users::dsl::*;
 
         let loaded_users = users.load::<User>(conn).unwrap();
-        loaded_users.into_iter().map(|user| User::map_to_dto(user)).collect()
+        loaded_users.into_iter().map(User::map_to_dto).collect()
     }
 
     /**
@@ -155,7 +149,7 @@ impl User{
         if var(BASIC_AUTH).is_ok()|| var(OIDC_AUTH).is_ok() {
             let auth_header = req.headers().get(USERNAME);
             if auth_header.is_none() {
-                return Err(Error::new(std::io::ErrorKind::Other, "Username not found"));
+                return Err(Error::other("Username not found"));
             }
             return Ok(Some(auth_header.unwrap().to_str().unwrap().parse().unwrap()))
         }
@@ -165,9 +159,9 @@ impl User{
     pub fn get_gpodder_req_header(req: &actix_web::HttpRequest) -> Result<String, Error>{
             let auth_header = req.headers().get(USERNAME);
             if auth_header.is_none() {
-                return Err(Error::new(std::io::ErrorKind::Other, "Username not found"));
+                return Err(Error::other("Username not found"));
             }
-            return Ok(auth_header.unwrap().to_str().unwrap().parse().unwrap())
+            Ok(auth_header.unwrap().to_str().unwrap().parse().unwrap())
     }
 
 
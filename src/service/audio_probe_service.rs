@@ -0,0 +1,221 @@
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+// Durations of feeds are rounded or leave out inserted ads
+const MIN_DURATION_RATIO: f64 = 0.9;
+// The stream size in the VBR header of an MP3 file is exact, apart from tags of some encoders
+const MIN_STREAM_SIZE_RATIO: f64 = 0.99;
+// The first frame and its VBR header are found in this many bytes after the ID3 tag
+const MP3_HEADER_SEARCH_SIZE: usize = 64 * 1024;
+const ID3V1_TAG_SIZE: u64 = 128;
+
+const MPEG1_BITRATES: [u32; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256,
+    320];
+const MPEG2_BITRATES: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+/// The header of an MPEG audio layer III frame
+#[derive(Debug, Clone, Copy)]
+struct Mp3Frame {
+    mpeg1: bool,
+    mono: bool,
+    bitrate_kbps: u32,
+    sample_rate: u32,
+    length: usize
+}
+
+/// Finds audio files that end before their content does, e.g. downloads that were interrupted.
+/// Files are only read as far as needed, formats other than MP3 and MP4 are never truncated.
+pub struct AudioProbeService;
+
+impl AudioProbeService {
+    /// MP4 files are truncated if an atom reaches beyond the end of the file or the movie atom
+    /// is missing. MP3 files are compared with the stream size of their VBR header, files
+    /// without one are compared by their bitrate with the duration of the feed.
+    pub fn is_truncated(path: &Path, suffix: &str, duration_seconds: i32)
+        -> Result<bool, String> {
+        let mut file = File::open(path)
+            .map_err(|e| format!("Error opening {}: {}", path.display(), e))?;
+        match suffix.to_lowercase().as_str() {
+            "mp3" => Self::is_mp3_truncated(&mut file, duration_seconds),
+            "m4a" | "m4b" | "mp4" => Self::is_mp4_truncated(&mut file),
+            _ => Ok(false)
+        }.map_err(|e| format!("Error reading {}: {}", path.display(), e))
+    }
+
+    fn is_mp4_truncated(file: &mut File) -> io::Result<bool> {
+        let file_size = file.metadata()?.len();
+        let mut position = 0;
+        let mut has_movie = false;
+        while position + 8 <= file_size {
+            let mut header = [0u8; 16];
+            file.seek(SeekFrom::Start(position))?;
+            let header_size = match position + 16 <= file_size {
+                true => 16,
+                false => 8
+            };
+            file.read_exact(&mut header[..header_size])?;
+            let atom_size = match u32::from_be_bytes(header[0..4].try_into().unwrap()) as u64 {
+                // The atom lasts until the end of the file
+                0 => file_size - position,
+                // The size follows the type as 64 bit number
+                1 if header_size == 16 => u64::from_be_bytes(header[8..16].try_into().unwrap()),
+                atom_size => atom_size
+            };
+            if atom_size < 8 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                          format!("Invalid atom size {}", atom_size)));
+            }
+            has_movie |= &header[4..8] == b"moov";
+            if position + atom_size > file_size {
+                return Ok(true);
+            }
+            position += atom_size;
+        }
+        // Players can't read the file without its movie atom, it's written last by some encoders
+        Ok(!has_movie)
+    }
+
+    fn is_mp3_truncated(file: &mut File, duration_seconds: i32) -> io::Result<bool> {
+        let file_size = file.metadata()?.len();
+        let mut id3_header = [0u8; 10];
+        let stream_start = match file.read_exact(&mut id3_header) {
+            Ok(..) if &id3_header[0..3] == b"ID3" => {
+                let tag_size = id3_header[6..10].iter()
+                    .fold(0u64, |size, byte| (size << 7) | (*byte & 0x7f) as u64);
+                // The flag of a footer, which has the size of the header
+                let footer_size = match id3_header[5] & 0x10 {
+                    0 => 0,
+                    _ => 10
+                };
+                10 + tag_size + footer_size
+            }
+            _ => 0
+        };
+        if stream_start >= file_size {
+            return Ok(true);
+        }
+        let mut buffer = Vec::new();
+        file.seek(SeekFrom::Start(stream_start))?;
+        file.take(MP3_HEADER_SEARCH_SIZE as u64).read_to_end(&mut buffer)?;
+        let (frame_start, frame) = match Self::find_mp3_frame(&buffer) {
+            Some(frame) => frame,
+            None => return Ok(false)
+        };
+
+        let mut stream_end = file_size;
+        if file_size >= stream_start + ID3V1_TAG_SIZE {
+            let mut id3v1_header = [0u8; 3];
+            file.seek(SeekFrom::Start(file_size - ID3V1_TAG_SIZE))?;
+            file.read_exact(&mut id3v1_header)?;
+            if &id3v1_header == b"TAG" {
+                stream_end -= ID3V1_TAG_SIZE;
+            }
+        }
+        let stream_size = stream_end.saturating_sub(stream_start + frame_start as u64);
+
+        match Self::get_vbr_stream_size(&buffer[frame_start..], &frame) {
+            Some(expected_stream_size) => Ok((stream_size as f64)
+                < expected_stream_size as f64 * MIN_STREAM_SIZE_RATIO),
+            // Without a VBR header the bitrate of the first frame is the bitrate of the file
+            None if duration_seconds > 0 && !Self::has_vbr_header(&buffer[frame_start..],
+                                                                  &frame) => {
+                let probed_duration = stream_size as f64 * 8.0
+                    / (frame.bitrate_kbps as f64 * 1000.0);
+                Ok(probed_duration < duration_seconds as f64 * MIN_DURATION_RATIO)
+            }
+            None => Ok(false)
+        }
+    }
+
+    /// The first frame whose successor starts where the frame ends, so that bytes of the
+    /// audio data that look like a header aren't taken for one
+    fn find_mp3_frame(buffer: &[u8]) -> Option<(usize, Mp3Frame)> {
+        (0..buffer.len().saturating_sub(4))
+            .filter_map(|offset| Self::parse_mp3_frame(&buffer[offset..])
+                .map(|frame| (offset, frame)))
+            .find(|(offset, frame)| match buffer.get(offset + frame.length..) {
+                Some(next_frame) if next_frame.len() >= 4 => Self::parse_mp3_frame(next_frame)
+                    .is_some_and(|next_frame| next_frame.sample_rate == frame.sample_rate),
+                // The file ends with the frame
+                _ => true
+            })
+    }
+
+    fn parse_mp3_frame(header: &[u8]) -> Option<Mp3Frame> {
+        if header.len() < 4 || header[0] != 0xff || header[1] & 0xe0 != 0xe0 {
+            return None;
+        }
+        let version = (header[1] >> 3) & 0x03;
+        let layer = (header[1] >> 1) & 0x03;
+        let bitrate_index = (header[2] >> 4) as usize;
+        let sample_rate_index = ((header[2] >> 2) & 0x03) as usize;
+        // Only layer III is used for MP3 files, version 1 is reserved
+        if layer != 1 || version == 1 || bitrate_index == 0 || bitrate_index == 15
+            || sample_rate_index == 3 {
+            return None;
+        }
+        let mpeg1 = version == 3;
+        let bitrate_kbps = match mpeg1 {
+            true => MPEG1_BITRATES[bitrate_index],
+            false => MPEG2_BITRATES[bitrate_index]
+        };
+        let sample_rate = [44100, 48000, 32000][sample_rate_index] >> match version {
+            3 => 0,
+            2 => 1,
+            _ => 2
+        };
+        let padding = ((header[2] >> 1) & 0x01) as u32;
+        let samples_per_byte = match mpeg1 {
+            true => 144,
+            false => 72
+        };
+        Some(Mp3Frame {
+            mpeg1,
+            mono: header[3] >> 6 == 3,
+            bitrate_kbps,
+            sample_rate,
+            length: (samples_per_byte * bitrate_kbps * 1000 / sample_rate + padding) as usize
+        })
+    }
+
+    /// The size of the stream from the Xing, Info or VBRI header in the first frame
+    fn get_vbr_stream_size(frame: &[u8], header: &Mp3Frame) -> Option<u64> {
+        let xing_offset = Self::get_xing_offset(header);
+        if let Some(xing) = frame.get(xing_offset..xing_offset + 16) {
+            if &xing[0..4] == b"Xing" || &xing[0..4] == b"Info" {
+                let flags = u32::from_be_bytes(xing[4..8].try_into().unwrap());
+                // The number of frames comes first if it's included
+                let bytes_offset = match flags & 0x01 {
+                    0 => 8,
+                    _ => 12
+                };
+                return match flags & 0x02 {
+                    0 => None,
+                    _ => xing.get(bytes_offset..bytes_offset + 4)
+                        .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()) as u64)
+                };
+            }
+        }
+        frame.get(36..50)
+            .filter(|vbri| &vbri[0..4] == b"VBRI")
+            .map(|vbri| u32::from_be_bytes(vbri[10..14].try_into().unwrap()) as u64)
+    }
+
+    fn has_vbr_header(frame: &[u8], header: &Mp3Frame) -> bool {
+        let xing_offset = Self::get_xing_offset(header);
+        frame.get(xing_offset..xing_offset + 4)
+            .is_some_and(|xing| xing == b"Xing" || xing == b"Info")
+            || frame.get(36..40).is_some_and(|vbri| vbri == b"VBRI")
+    }
+
+    // The VBR header follows the frame header and the side information
+    fn get_xing_offset(header: &Mp3Frame) -> usize {
+        4 + match (header.mpeg1, header.mono) {
+            (true, false) => 32,
+            (true, true) | (false, false) => 17,
+            (false, true) => 9
+        }
+    }
+}
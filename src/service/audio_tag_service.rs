@@ -0,0 +1,279 @@
+use std::path::Path;
+use chrono::{DateTime, FixedOffset, Utc};
+use id3::{Content, Frame, TagLike, Timestamp, Version};
+use id3::frame::{Chapter, Comment, Picture, PictureType, TableOfContents};
+use crate::models::podcasts::Podcast;
+use crate::models::settings::Setting;
+use crate::utils::feed_sanitizer::to_plain_text;
+
+const PODCAST_GENRE: &str = "Podcast";
+const TABLE_OF_CONTENTS_ID: &str = "toc";
+
+/// The metadata of an episode that is written into its audio file
+#[derive(Debug, Clone, Default)]
+pub struct AudioMetadata {
+    /// Used as artist and album
+    pub podcast_name: String,
+    pub title: String,
+    pub date: Option<DateTime<FixedOffset>>,
+    pub description: String,
+    pub chapters: Vec<AudioChapter>,
+    pub artwork: Option<Artwork>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioChapter {
+    pub start_ms: u32,
+    pub end_ms: u32,
+    pub title: String
+}
+
+#[derive(Debug, Clone)]
+pub struct Artwork {
+    pub mime_type: String,
+    pub data: Vec<u8>
+}
+
+// The JSON chapters format of the Podcasting 2.0 namespace
+#[derive(Deserialize)]
+struct JsonChapters {
+    chapters: Vec<JsonChapter>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonChapter {
+    start_time: f64,
+    end_time: Option<f64>,
+    title: Option<String>,
+    // Chapters that are hidden from the table of contents
+    toc: Option<bool>
+}
+
+/// Writes ID3v2 tags into MP3 files and metadata atoms into MP4 files, so that players without
+/// the feed show the podcast and episode. Other formats are left as they are.
+pub struct AudioTagService;
+
+impl AudioTagService {
+    /// The setting of the podcast overrides the global setting
+    pub fn is_enabled(podcast: &Podcast, settings: &Setting) -> bool {
+        podcast.write_metadata_tags.unwrap_or(settings.write_metadata_tags)
+    }
+
+    /// Whether tags can be written into files with the suffix
+    pub fn supports_suffix(suffix: &str) -> bool {
+        matches!(suffix.to_lowercase().as_str(), "mp3" | "m4a" | "m4b" | "mp4")
+    }
+
+    pub fn get_artwork(data: Vec<u8>, image_suffix: &str) -> Option<Artwork> {
+        let mime_type = match image_suffix.to_lowercase().as_str() {
+            "jpg" | "jpeg" => "image/jpeg",
+            "png" => "image/png",
+            _ => return None
+        };
+        Some(Artwork {
+            mime_type: mime_type.to_string(),
+            data
+        })
+    }
+
+    /// Parses a JSON chapters file. Chapters without an end last until the next chapter or the
+    /// end of the episode.
+    pub fn parse_chapters(document: &[u8], duration_seconds: i32)
+        -> Result<Vec<AudioChapter>, String> {
+        let json_chapters = serde_json::from_slice::<JsonChapters>(document)
+            .map_err(|e| format!("Error parsing chapters: {}", e))?;
+        let mut visible_chapters = json_chapters.chapters
+            .into_iter()
+            .filter(|chapter| chapter.toc.unwrap_or(true) && chapter.start_time >= 0.0)
+            .collect::<Vec<_>>();
+        visible_chapters.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+        let episode_end_ms = duration_seconds.max(0) as u32 * 1000;
+        let start_times = visible_chapters.iter()
+            .map(|chapter| Self::to_milliseconds(chapter.start_time))
+            .collect::<Vec<u32>>();
+        Ok(visible_chapters
+            .into_iter()
+            .enumerate()
+            .map(|(index, chapter)| {
+                let start_ms = start_times[index];
+                let end_ms = chapter.end_time
+                    .map(Self::to_milliseconds)
+                    .or(start_times.get(index + 1).copied())
+                    .unwrap_or(episode_end_ms)
+                    .max(start_ms);
+                AudioChapter {
+                    start_ms,
+                    end_ms,
+                    title: chapter.title.unwrap_or_default()
+                }
+            })
+            .collect())
+    }
+
+    /// Replaces the metadata of the file. Files of other formats than MP3 and MP4 are skipped.
+    pub fn write_tags(path: &Path, suffix: &str, metadata: &AudioMetadata) -> Result<(), String> {
+        match suffix.to_lowercase().as_str() {
+            "mp3" => Self::write_id3_tags(path, metadata),
+            "m4a" | "m4b" | "mp4" => Self::write_mp4_tags(path, metadata),
+            _ => Ok(())
+        }
+    }
+
+    fn write_id3_tags(path: &Path, metadata: &AudioMetadata) -> Result<(), String> {
+        let mut tag = match id3::Tag::read_from_path(path) {
+            Ok(tag) => tag,
+            Err(id3::Error { kind: id3::ErrorKind::NoTag, .. }) => id3::Tag::new(),
+            Err(e) => return Err(format!("Error reading ID3 tag: {}", e))
+        };
+        tag.set_artist(&metadata.podcast_name);
+        tag.set_album(&metadata.podcast_name);
+        tag.set_title(&metadata.title);
+        tag.set_genre(PODCAST_GENRE);
+        if let Some(date) = metadata.date {
+            if let Ok(timestamp) = date.format("%Y-%m-%dT%H:%M:%S").to_string()
+                .parse::<Timestamp>() {
+                tag.set_date_recorded(timestamp);
+            }
+        }
+
+        let description = to_plain_text(&metadata.description);
+        tag.remove("COMM");
+        tag.remove("TDES");
+        if !description.is_empty() {
+            tag.add_frame(Comment {
+                lang: "eng".to_string(),
+                description: String::new(),
+                text: description.clone()
+            });
+            // The podcast description frame of iTunes
+            tag.add_frame(Frame::with_content("TDES", Content::Text(description)));
+        }
+
+        if let Some(artwork) = &metadata.artwork {
+            tag.remove_all_pictures();
+            tag.add_frame(Picture {
+                mime_type: artwork.mime_type.clone(),
+                picture_type: PictureType::CoverFront,
+                description: String::new(),
+                data: artwork.data.clone()
+            });
+        }
+
+        if !metadata.chapters.is_empty() {
+            tag.remove_all_chapters();
+            tag.remove_all_tables_of_contents();
+            let element_ids = (0..metadata.chapters.len())
+                .map(|index| format!("chp{}", index))
+                .collect::<Vec<String>>();
+            for (element_id, chapter) in element_ids.iter().zip(&metadata.chapters) {
+                tag.add_frame(Chapter {
+                    element_id: element_id.clone(),
+                    start_time: chapter.start_ms,
+                    end_time: chapter.end_ms,
+                    // The chapters are located by time, not by byte offset
+                    start_offset: u32::MAX,
+                    end_offset: u32::MAX,
+                    frames: vec![Frame::text("TIT2", &chapter.title)]
+                });
+            }
+            tag.add_frame(TableOfContents {
+                element_id: TABLE_OF_CONTENTS_ID.to_string(),
+                top_level: true,
+                ordered: true,
+                elements: element_ids,
+                frames: Vec::new()
+            });
+        }
+
+        tag.write_to_path(path, Version::Id3v24)
+            .map_err(|e| format!("Error writing ID3 tag: {}", e))
+    }
+
+    // Chapters of MP4 files are a text track, they are not written
+    fn write_mp4_tags(path: &Path, metadata: &AudioMetadata) -> Result<(), String> {
+        let mut tag = mp4ameta::Tag::read_from_path(path)
+            .map_err(|e| format!("Error reading MP4 metadata: {}", e))?;
+        tag.set_artist(&metadata.podcast_name);
+        tag.set_album(&metadata.podcast_name);
+        tag.set_title(&metadata.title);
+        tag.set_genre(PODCAST_GENRE);
+        if let Some(date) = metadata.date {
+            tag.set_year(date.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%SZ").to_string());
+        }
+        let description = to_plain_text(&metadata.description);
+        if !description.is_empty() {
+            tag.set_description(&description);
+            tag.set_comment(description);
+        }
+        if let Some(artwork) = &metadata.artwork {
+            let image = match artwork.mime_type.as_str() {
+                "image/png" => mp4ameta::Img::png(artwork.data.clone()),
+                _ => mp4ameta::Img::jpeg(artwork.data.clone())
+            };
+            tag.set_artwork(image);
+        }
+        tag.write_to_path(path)
+            .map_err(|e| format!("Error writing MP4 metadata: {}", e))
+    }
+
+    fn to_milliseconds(seconds: f64) -> u32 {
+        (seconds.max(0.0) * 1000.0).round().min(u32::MAX as f64) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::audio_tag_service::{AudioChapter, AudioTagService};
+
+    fn get_chapter(start_ms: u32, end_ms: u32, title: &str) -> AudioChapter {
+        AudioChapter {
+            start_ms,
+            end_ms,
+            title: title.to_string()
+        }
+    }
+
+    #[test]
+    fn test_parse_chapters() {
+        let document = br#"{"version": "1.2.0", "chapters": [
+            {"startTime": 0, "title": "Intro"},
+            {"startTime": 62.5, "endTime": 120, "title": "News"},
+            {"startTime": 120, "title": "Sponsor", "toc": false},
+            {"startTime": 300, "title": "Interview", "img": "https://example.com/chapter.jpg"}
+        ]}"#;
+        assert_eq!(AudioTagService::parse_chapters(document, 600).unwrap(), vec![
+            get_chapter(0, 62500, "Intro"),
+            get_chapter(62500, 120000, "News"),
+            get_chapter(300000, 600000, "Interview")]);
+    }
+
+    #[test]
+    fn test_parse_chapters_sorts_and_clamps_times() {
+        let document = br#"{"chapters": [
+            {"startTime": 30, "endTime": 10, "title": "Ends before it starts"},
+            {"startTime": -5, "title": "Negative"},
+            {"startTime": 0},
+            {"startTime": 1e12, "title": "After the end of time"}
+        ]}"#;
+        assert_eq!(AudioTagService::parse_chapters(document, -1).unwrap(), vec![
+            get_chapter(0, 30000, ""),
+            get_chapter(30000, 30000, "Ends before it starts"),
+            get_chapter(u32::MAX, u32::MAX, "After the end of time")]);
+    }
+
+    #[test]
+    fn test_parse_chapters_with_invalid_document() {
+        assert!(AudioTagService::parse_chapters(b"<chapters/>", 600).is_err());
+        assert!(AudioTagService::parse_chapters(br#"{"version": "1.2.0"}"#, 600).is_err());
+    }
+
+    #[test]
+    fn test_get_artwork() {
+        assert_eq!(AudioTagService::get_artwork(vec![1], "JPEG").unwrap().mime_type,
+                   "image/jpeg");
+        assert_eq!(AudioTagService::get_artwork(vec![1], "png").unwrap().mime_type, "image/png");
+        assert!(AudioTagService::get_artwork(vec![1], "webp").is_none());
+    }
+}
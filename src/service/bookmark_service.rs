@@ -0,0 +1,176 @@
+use crate::constants::constants::MAX_BOOKMARK_NOTE_LENGTH;
+use crate::DbConnection;
+use crate::exception::exceptions::PodFetchError;
+use crate::models::bookmark::{Bookmark, BookmarkCreateModel, BookmarkDto, BookmarkExportFormat};
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcasts::Podcast;
+use crate::service::mapping_service::MappingService;
+
+/// Manages the timestamped bookmarks and clips of a user
+pub struct BookmarkService;
+
+impl BookmarkService {
+    /// The bookmarks of the user across all podcasts, newest first
+    pub fn get_bookmarks(conn: &mut DbConnection, username: &str, podcast_id: Option<i32>,
+                         mapping_service: &MappingService) -> Vec<BookmarkDto> {
+        let mut bookmarks = Self::map_bookmarks(Bookmark::get_bookmarks_of_user(
+            conn, username, None, podcast_id), mapping_service);
+        bookmarks.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+        bookmarks
+    }
+
+    /// The bookmarks of the user in an episode ordered by position
+    pub fn get_bookmarks_of_episode(conn: &mut DbConnection, username: &str, episode_id: &str,
+                                    mapping_service: &MappingService)
+        -> Result<Vec<BookmarkDto>, PodFetchError> {
+        let podcast_episode = Self::find_episode(conn, episode_id)?;
+        Ok(Self::map_bookmarks(Bookmark::get_bookmarks_of_user(conn, username,
+                                                               Some(podcast_episode.id), None),
+                               mapping_service))
+    }
+
+    pub fn create_bookmark(conn: &mut DbConnection, username: &str, episode_id: &str,
+                           bookmark: &BookmarkCreateModel, mapping_service: &MappingService)
+        -> Result<BookmarkDto, PodFetchError> {
+        let podcast_episode = Self::find_episode(conn, episode_id)?;
+        let bookmark = Self::validate_bookmark(bookmark)?;
+        let created_bookmark = Bookmark::insert_bookmark(conn, username, podcast_episode.id,
+                                                         &bookmark);
+        Self::get_bookmark(conn, username, created_bookmark.id, mapping_service)
+    }
+
+    pub fn update_bookmark(conn: &mut DbConnection, username: &str, bookmark_id: i32,
+                           bookmark: &BookmarkCreateModel, mapping_service: &MappingService)
+        -> Result<BookmarkDto, PodFetchError> {
+        Self::find_bookmark(conn, username, bookmark_id)?;
+        let bookmark = Self::validate_bookmark(bookmark)?;
+        Bookmark::update_bookmark(conn, bookmark_id, &bookmark);
+        Self::get_bookmark(conn, username, bookmark_id, mapping_service)
+    }
+
+    pub fn delete_bookmark(conn: &mut DbConnection, username: &str, bookmark_id: i32)
+        -> Result<(), PodFetchError> {
+        Self::find_bookmark(conn, username, bookmark_id)?;
+        Bookmark::delete_bookmark(conn, bookmark_id);
+        Ok(())
+    }
+
+    /// Exports every bookmark of the user, grouped by podcast and episode
+    pub fn export(conn: &mut DbConnection, username: &str, format: BookmarkExportFormat,
+                  mapping_service: &MappingService) -> String {
+        let bookmarks = Self::map_bookmarks(Bookmark::get_bookmarks_of_user(conn, username, None,
+                                                                            None),
+                                            mapping_service);
+        match format {
+            BookmarkExportFormat::Json => serde_json::to_string(&bookmarks).unwrap(),
+            BookmarkExportFormat::Markdown => Self::to_markdown(&bookmarks)
+        }
+    }
+
+    fn get_bookmark(conn: &mut DbConnection, username: &str, bookmark_id: i32,
+                    mapping_service: &MappingService) -> Result<BookmarkDto, PodFetchError> {
+        let bookmark = Self::find_bookmark(conn, username, bookmark_id)?;
+        Self::map_bookmarks(Bookmark::get_bookmarks_of_user(conn, username,
+                                                            Some(bookmark.podcast_episode_id),
+                                                            None), mapping_service)
+            .into_iter()
+            .find(|bookmark| bookmark.id == bookmark_id)
+            .ok_or(PodFetchError::bookmark_not_found())
+    }
+
+    /// Trims the note and checks the positions. An empty note is no note.
+    fn validate_bookmark(bookmark: &BookmarkCreateModel)
+        -> Result<BookmarkCreateModel, PodFetchError> {
+        let note = bookmark.note.as_ref()
+            .map(|note| note.trim().to_string())
+            .filter(|note| !note.is_empty());
+        if bookmark.position < 0
+            || bookmark.end_position.map(|end_position| end_position <= bookmark.position)
+            .unwrap_or(false)
+            || note.as_ref().map(|note| note.chars().count() > MAX_BOOKMARK_NOTE_LENGTH)
+            .unwrap_or(false) {
+            return Err(PodFetchError::invalid_bookmark());
+        }
+        Ok(BookmarkCreateModel {
+            position: bookmark.position,
+            end_position: bookmark.end_position,
+            note
+        })
+    }
+
+    fn find_bookmark(conn: &mut DbConnection, username: &str, bookmark_id: i32)
+        -> Result<Bookmark, PodFetchError> {
+        Bookmark::get_bookmark_of_user(conn, bookmark_id, username)
+            .ok_or(PodFetchError::bookmark_not_found())
+    }
+
+    fn find_episode(conn: &mut DbConnection, episode_id: &str)
+        -> Result<PodcastEpisode, PodFetchError> {
+        PodcastEpisode::get_podcast_episode_by_id(conn, episode_id)
+            .unwrap()
+            .ok_or(PodFetchError::episode_not_found())
+    }
+
+    fn map_bookmarks(bookmarks: Vec<(Bookmark, PodcastEpisode, Podcast)>,
+                     mapping_service: &MappingService) -> Vec<BookmarkDto> {
+        bookmarks
+            .into_iter()
+            .map(|(bookmark, podcast_episode, podcast)|
+                Self::map_bookmark(bookmark, &podcast_episode, &podcast, mapping_service))
+            .collect()
+    }
+
+    fn map_bookmark(bookmark: Bookmark, podcast_episode: &PodcastEpisode, podcast: &Podcast,
+                    mapping_service: &MappingService) -> BookmarkDto {
+        BookmarkDto {
+            id: bookmark.id,
+            position: bookmark.position,
+            end_position: bookmark.end_position,
+            note: bookmark.note,
+            created_at: bookmark.created_at,
+            podcast_episode: mapping_service.map_podcastepisode_to_dto(podcast_episode),
+            podcast: mapping_service.map_podcast_to_podcast_dto(podcast)
+        }
+    }
+
+    /// One section per podcast and episode. The bookmarks have to be grouped by episode.
+    fn to_markdown(bookmarks: &[BookmarkDto]) -> String {
+        let mut markdown = String::from("# Bookmarks\n");
+        let mut current_podcast_id = None;
+        let mut current_episode_id = None;
+        for bookmark in bookmarks {
+            if current_podcast_id != Some(bookmark.podcast.id) {
+                markdown.push_str(&format!("\n## {}\n", bookmark.podcast.name.trim()));
+                current_podcast_id = Some(bookmark.podcast.id);
+                current_episode_id = None;
+            }
+            if current_episode_id != Some(bookmark.podcast_episode.id) {
+                markdown.push_str(&format!("\n### {}\n\n", bookmark.podcast_episode.name.trim()));
+                current_episode_id = Some(bookmark.podcast_episode.id);
+            }
+            let timestamp = match bookmark.end_position {
+                Some(end_position) => format!("{}–{}", Self::format_position(bookmark.position),
+                                              Self::format_position(end_position)),
+                None => Self::format_position(bookmark.position)
+            };
+            match &bookmark.note {
+                // Continuation lines are indented so multi line notes stay in the list item
+                Some(note) => markdown.push_str(&format!("- **{}** {}\n", timestamp,
+                                                         note.replace('\n', "\n  "))),
+                None => markdown.push_str(&format!("- **{}**\n", timestamp))
+            }
+        }
+        markdown
+    }
+
+    /// Formats seconds as 37:12 or 1:02:03
+    pub fn format_position(position: i32) -> String {
+        let hours = position / 3600;
+        let minutes = (position % 3600) / 60;
+        let seconds = position % 60;
+        match hours {
+            0 => format!("{}:{:02}", minutes, seconds),
+            _ => format!("{}:{:02}:{:02}", hours, minutes, seconds)
+        }
+    }
+}
@@ -0,0 +1,458 @@
+use std::collections::{HashMap, HashSet};
+use chrono::{Duration, NaiveDateTime, Utc};
+use crate::DbConnection;
+use crate::exception::exceptions::PodFetchError;
+use crate::models::cleanup::{CleanupEpisode, CleanupPolicy, CleanupPolicyDto, CleanupReason,
+                             CleanupReport};
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcasts::Podcast;
+use crate::models::settings::Setting;
+use crate::service::episode_state_service::EpisodeStateService;
+use crate::mutex::LockResultExt;
+use crate::service::file_service::FileService;
+use crate::service::library_service::LIBRARY_LOCK;
+use crate::service::storage::{StorageBackend, StorageService};
+use crate::utils::time::parse_feed_date;
+
+/// Deletes downloaded episodes by the auto cleanup setting and the cleanup policies. Pinned
+/// episodes are never deleted, but they count towards the quotas.
+pub struct CleanupService;
+
+/// The policies and the state of the library a cleanup is planned with
+struct CleanupRules {
+    global_policy: CleanupPolicyDto,
+    podcast_policies: HashMap<i32, CleanupPolicyDto>,
+    last_played_dates: HashMap<i32, NaiveDateTime>,
+    /// Only set if the age based auto cleanup applies
+    auto_cleanup_days: Option<i32>,
+    now: NaiveDateTime
+}
+
+struct CleanupPlanner<'a> {
+    episode_size: &'a dyn Fn(&PodcastEpisode) -> u64,
+    sizes: HashMap<i32, u64>,
+    selected: Vec<(&'a PodcastEpisode, &'a Podcast, CleanupReason)>,
+    selected_ids: HashSet<i32>
+}
+
+impl CleanupService {
+    pub fn get_policy(conn: &mut DbConnection, podcast_id: Option<i32>)
+        -> Result<CleanupPolicyDto, PodFetchError> {
+        if let Some(podcast_id) = podcast_id {
+            Self::find_podcast(conn, podcast_id)?;
+        }
+        Ok(CleanupPolicy::get_policies(conn)
+            .into_iter()
+            .find(|policy| policy.podcast_id == podcast_id)
+            .map(CleanupPolicyDto::from)
+            .unwrap_or_default())
+    }
+
+    pub fn save_policy(conn: &mut DbConnection, podcast_id: Option<i32>,
+                       policy: &CleanupPolicyDto) -> Result<CleanupPolicyDto, PodFetchError> {
+        if let Some(podcast_id) = podcast_id {
+            Self::find_podcast(conn, podcast_id)?;
+        }
+        if policy.keep_latest.is_some_and(|keep_latest| keep_latest < 1)
+            || policy.delete_played_after_days.is_some_and(|days| days < 0)
+            || policy.max_bytes.is_some_and(|max_bytes| max_bytes < 0) {
+            return Err(PodFetchError::invalid_cleanup_policy());
+        }
+        Ok(CleanupPolicyDto::from(CleanupPolicy::save_policy(conn, podcast_id, policy)))
+    }
+
+    /// The podcast uses the global policy again
+    pub fn delete_policy(conn: &mut DbConnection, podcast_id: i32)
+        -> Result<CleanupPolicyDto, PodFetchError> {
+        Self::find_podcast(conn, podcast_id)?;
+        CleanupPolicy::delete_by_podcast_id(conn, podcast_id);
+        Ok(CleanupPolicyDto::default())
+    }
+
+    pub fn set_pinned(conn: &mut DbConnection, episode_id: &str, pinned: bool)
+        -> Result<PodcastEpisode, PodFetchError> {
+        let podcast_episode = PodcastEpisode::get_podcast_episode_by_id(conn, episode_id)
+            .unwrap()
+            .ok_or(PodFetchError::episode_not_found())?;
+        PodcastEpisode::update_pinned(conn, podcast_episode.id, pinned);
+        Ok(PodcastEpisode {
+            pinned,
+            ..podcast_episode
+        })
+    }
+
+    /// The episodes a cleanup would delete without deleting anything. Episodes older than the
+    /// auto cleanup days are only included if `apply_age` is set.
+    pub fn plan_cleanup(conn: &mut DbConnection, apply_age: bool) -> CleanupReport {
+        let auto_cleanup_days = match apply_age {
+            true => Setting::get_settings(conn).map(|settings| settings.auto_cleanup_days),
+            false => None
+        };
+        let policies = CleanupPolicy::get_policies(conn);
+        let global_policy = policies.iter()
+            .find(|policy| policy.podcast_id.is_none())
+            .cloned()
+            .map(CleanupPolicyDto::from)
+            .unwrap_or_default();
+        let podcast_policies = policies.into_iter()
+            .filter_map(|policy| policy.podcast_id.map(|podcast_id| (podcast_id,
+                                                                     CleanupPolicyDto::from(policy))))
+            .collect::<HashMap<i32, CleanupPolicyDto>>();
+        let rules = CleanupRules {
+            global_policy,
+            podcast_policies,
+            last_played_dates: EpisodeStateService::get_last_played_dates(conn),
+            auto_cleanup_days,
+            now: Utc::now().naive_utc()
+        };
+        let backend = StorageService::get_backend();
+        Self::plan_episodes(PodcastEpisode::get_downloaded_episodes(conn), &rules,
+                            &|podcast_episode| Self::get_episode_size(backend.as_ref(),
+                                                                      podcast_episode))
+    }
+
+    fn plan_episodes(mut episodes: Vec<(PodcastEpisode, Podcast)>, rules: &CleanupRules,
+                     episode_size: &dyn Fn(&PodcastEpisode) -> u64) -> CleanupReport {
+        // Oldest first, the quotas evict episodes in this order. Dates that can't be parsed
+        // count as the oldest.
+        episodes.sort_by_cached_key(|(podcast_episode, _)| (
+            parse_feed_date(&podcast_episode.date_of_recording), podcast_episode.id));
+        let mut episodes_by_podcast = HashMap::<i32, Vec<&(PodcastEpisode, Podcast)>>::new();
+        for episode in &episodes {
+            episodes_by_podcast.entry(episode.1.id).or_default().push(episode);
+        }
+
+        let mut planner = CleanupPlanner {
+            episode_size,
+            sizes: HashMap::new(),
+            selected: Vec::new(),
+            selected_ids: HashSet::new()
+        };
+        let global_policy = &rules.global_policy;
+        let mut podcast_ids = episodes_by_podcast.keys().copied().collect::<Vec<i32>>();
+        podcast_ids.sort();
+        for podcast_id in podcast_ids {
+            let podcast_episodes = &episodes_by_podcast[&podcast_id];
+            let podcast_policy = rules.podcast_policies.get(&podcast_id).cloned()
+                .unwrap_or_default();
+            let keep_latest = podcast_policy.keep_latest.or(global_policy.keep_latest);
+            let delete_played_after_days = podcast_policy.delete_played_after_days
+                .or(global_policy.delete_played_after_days);
+
+            for (podcast_episode, podcast) in podcast_episodes.iter().map(|e| (&e.0, &e.1)) {
+                let downloaded_too_long_ago = rules.auto_cleanup_days
+                    .zip(podcast_episode.download_time)
+                    .is_some_and(|(days, download_time)| download_time < rules.now
+                        - Duration::days(days as i64));
+                let played_too_long_ago = delete_played_after_days
+                    .zip(rules.last_played_dates.get(&podcast_episode.id))
+                    .is_some_and(|(days, played_date)| *played_date < rules.now
+                        - Duration::days(days as i64));
+                if downloaded_too_long_ago {
+                    planner.select(podcast_episode, podcast, CleanupReason::Age);
+                } else if played_too_long_ago {
+                    planner.select(podcast_episode, podcast, CleanupReason::Played);
+                }
+            }
+
+            if let Some(keep_latest) = keep_latest {
+                // Pinned episodes are kept anyway, they don't take the place of another episode
+                podcast_episodes.iter()
+                    .rev()
+                    .filter(|(podcast_episode, _)| !podcast_episode.pinned)
+                    .skip(keep_latest.max(0) as usize)
+                    .for_each(|(podcast_episode, podcast)| planner.select(podcast_episode,
+                                                                          podcast,
+                                                                          CleanupReason::KeepLatest));
+            }
+
+            if let Some(max_bytes) = podcast_policy.max_bytes {
+                planner.enforce_quota(podcast_episodes, max_bytes,
+                                      CleanupReason::PodcastQuota);
+            }
+        }
+        if let Some(max_bytes) = global_policy.max_bytes {
+            planner.enforce_quota(&episodes.iter().collect::<Vec<_>>(), max_bytes,
+                                  CleanupReason::StorageQuota);
+        }
+        planner.into_report()
+    }
+
+    /// The directory of the episode holds the audio file and its image
+    fn get_episode_size(backend: &dyn StorageBackend, podcast_episode: &PodcastEpisode) -> u64 {
+        match podcast_episode.local_url.rsplit_once('/') {
+            Some((episode_directory, _)) => backend.dir_size(episode_directory)
+                .unwrap_or_else(|e| {
+                    log::error!("Error getting the size of {}: {}", episode_directory, e);
+                    0
+                }),
+            None => 0
+        }
+    }
+
+    /// Deletes the episodes of the plan. Episodes that couldn't be deleted are left out of the
+    /// report.
+    pub fn run_cleanup(conn: &mut DbConnection, apply_age: bool) -> CleanupReport {
+        let _library_lock = LIBRARY_LOCK.read().ignore_poison();
+        let plan = Self::plan_cleanup(conn, apply_age);
+        log::info!("Cleaning up {} episodes", plan.episodes.len());
+        let mut report = CleanupReport::default();
+        for cleanup_episode in plan.episodes {
+            let podcast_episode = match PodcastEpisode::get_podcast_episode_by_id(conn,
+                                                                                  &cleanup_episode.episode_id).unwrap() {
+                Some(podcast_episode) => podcast_episode,
+                None => continue
+            };
+            let podcast = match Podcast::get_podcast(conn, podcast_episode.podcast_id) {
+                Ok(podcast) => podcast,
+                Err(_) => continue
+            };
+            match FileService::cleanup_old_episode(podcast_episode, &podcast) {
+                Ok(_) => {
+                    PodcastEpisode::update_download_status_of_episode(cleanup_episode
+                        .podcast_episode_id, conn);
+                    report.freed_bytes += cleanup_episode.bytes;
+                    report.episodes.push(cleanup_episode);
+                }
+                Err(e) => {
+                    log::error!("Error deleting podcast episode {}: {}", cleanup_episode.name, e);
+                }
+            }
+        }
+        report
+    }
+
+    fn find_podcast(conn: &mut DbConnection, podcast_id: i32) -> Result<Podcast, PodFetchError> {
+        Podcast::get_podcast(conn, podcast_id).map_err(|_| PodFetchError::podcast_not_found())
+    }
+}
+
+impl<'a> CleanupPlanner<'a> {
+    fn select(&mut self, podcast_episode: &'a PodcastEpisode, podcast: &'a Podcast,
+              reason: CleanupReason) {
+        if podcast_episode.pinned || !self.selected_ids.insert(podcast_episode.id) {
+            return;
+        }
+        self.selected.push((podcast_episode, podcast, reason));
+    }
+
+    /// Selects the oldest episodes until the episodes that are kept fit into `max_bytes`
+    fn enforce_quota(&mut self, episodes: &[&'a (PodcastEpisode, Podcast)], max_bytes: i64,
+                     reason: CleanupReason) {
+        let kept = episodes.iter()
+            .filter(|(podcast_episode, _)| !self.selected_ids.contains(&podcast_episode.id))
+            .copied()
+            .collect::<Vec<_>>();
+        let mut used_bytes = kept.iter()
+            .map(|(podcast_episode, _)| self.get_size(podcast_episode))
+            .sum::<u64>();
+        for (podcast_episode, podcast) in kept.iter().map(|e| (&e.0, &e.1)) {
+            if used_bytes <= max_bytes.max(0) as u64 {
+                break;
+            }
+            if !podcast_episode.pinned {
+                used_bytes -= self.get_size(podcast_episode);
+                self.select(podcast_episode, podcast, reason);
+            }
+        }
+    }
+
+    fn get_size(&mut self, podcast_episode: &PodcastEpisode) -> u64 {
+        if let Some(size) = self.sizes.get(&podcast_episode.id) {
+            return *size;
+        }
+        let size = (self.episode_size)(podcast_episode);
+        self.sizes.insert(podcast_episode.id, size);
+        size
+    }
+
+    fn into_report(mut self) -> CleanupReport {
+        let mut report = CleanupReport::default();
+        for (podcast_episode, podcast, reason) in std::mem::take(&mut self.selected) {
+            let bytes = self.get_size(podcast_episode);
+            report.freed_bytes += bytes;
+            report.episodes.push(CleanupEpisode {
+                podcast_episode_id: podcast_episode.id,
+                episode_id: podcast_episode.episode_id.clone(),
+                podcast_id: podcast.id,
+                podcast_name: podcast.name.clone(),
+                name: podcast_episode.name.clone(),
+                reason,
+                bytes
+            });
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use chrono::{Duration, NaiveDate};
+    use crate::models::cleanup::{CleanupPolicyDto, CleanupReason, CleanupReport};
+    use crate::models::podcast_episode::PodcastEpisode;
+    use crate::models::podcasts::Podcast;
+    use crate::service::cleanup_service::{CleanupRules, CleanupService};
+
+    const EPISODE_SIZE: u64 = 100;
+
+    fn rules() -> CleanupRules {
+        CleanupRules {
+            global_policy: CleanupPolicyDto::default(),
+            podcast_policies: HashMap::new(),
+            last_played_dates: HashMap::new(),
+            auto_cleanup_days: None,
+            now: NaiveDate::from_ymd_opt(2023, 11, 1).unwrap().and_hms_opt(0, 0, 0).unwrap()
+        }
+    }
+
+    fn episode(id: i32, podcast_id: i32, date_of_recording: &str) -> (PodcastEpisode, Podcast) {
+        (PodcastEpisode {
+            id,
+            podcast_id,
+            episode_id: id.to_string(),
+            name: format!("Episode {}", id),
+            date_of_recording: date_of_recording.to_string(),
+            local_url: format!("Podcast {}/'Episode {}'/podcast.mp3", podcast_id, id),
+            ..PodcastEpisode::default()
+        }, Podcast {
+            id: podcast_id,
+            name: format!("Podcast {}", podcast_id),
+            ..Podcast::default()
+        })
+    }
+
+    fn pinned(mut episode: (PodcastEpisode, Podcast)) -> (PodcastEpisode, Podcast) {
+        episode.0.pinned = true;
+        episode
+    }
+
+    fn plan(episodes: Vec<(PodcastEpisode, Podcast)>, rules: &CleanupRules) -> CleanupReport {
+        CleanupService::plan_episodes(episodes, rules, &|_| EPISODE_SIZE)
+    }
+
+    fn selected(report: &CleanupReport) -> Vec<(i32, CleanupReason)> {
+        report.episodes.iter()
+            .map(|episode| (episode.podcast_episode_id, episode.reason))
+            .collect()
+    }
+
+    #[test]
+    fn test_keep_latest_sorts_by_parsed_date() {
+        let mut rules = rules();
+        rules.global_policy.keep_latest = Some(1);
+        // As text the first date is the older one, but it is three hours later
+        let report = plan(vec![
+            episode(1, 1, "2023-10-01T23:00:00-05:00"),
+            episode(2, 1, "2023-10-02T01:00:00+00:00"),
+            episode(3, 1, "Sun, 01 Oct 2023 12:00:00 +0000")
+        ], &rules);
+        assert_eq!(selected(&report), vec![(2, CleanupReason::KeepLatest),
+                                           (3, CleanupReason::KeepLatest)]);
+        assert_eq!(report.freed_bytes, 2 * EPISODE_SIZE);
+    }
+
+    #[test]
+    fn test_unparseable_dates_count_as_oldest() {
+        let mut rules = rules();
+        rules.global_policy.keep_latest = Some(1);
+        let report = plan(vec![
+            episode(1, 1, "2023-10-01T00:00:00+00:00"),
+            episode(2, 1, "yesterday")
+        ], &rules);
+        assert_eq!(selected(&report), vec![(2, CleanupReason::KeepLatest)]);
+    }
+
+    #[test]
+    fn test_pinned_episodes_are_kept_and_take_no_place() {
+        let mut rules = rules();
+        rules.global_policy.keep_latest = Some(1);
+        rules.global_policy.delete_played_after_days = Some(7);
+        rules.auto_cleanup_days = Some(30);
+        let mut old_download = pinned(episode(1, 1, "2023-09-01T00:00:00+00:00"));
+        old_download.0.download_time = Some(rules.now - Duration::days(60));
+        rules.last_played_dates.insert(3, rules.now - Duration::days(30));
+        let report = plan(vec![
+            old_download,
+            episode(2, 1, "2023-10-01T00:00:00+00:00"),
+            pinned(episode(3, 1, "2023-10-03T00:00:00+00:00")),
+            episode(4, 1, "2023-10-02T00:00:00+00:00")
+        ], &rules);
+        assert_eq!(selected(&report), vec![(2, CleanupReason::KeepLatest)]);
+    }
+
+    #[test]
+    fn test_age_and_played_come_before_keep_latest() {
+        let mut rules = rules();
+        rules.global_policy.keep_latest = Some(1);
+        rules.global_policy.delete_played_after_days = Some(7);
+        rules.auto_cleanup_days = Some(30);
+        let mut old_download = episode(1, 1, "2023-10-01T00:00:00+00:00");
+        old_download.0.download_time = Some(rules.now - Duration::days(31));
+        rules.last_played_dates.insert(2, rules.now - Duration::days(8));
+        rules.last_played_dates.insert(3, rules.now - Duration::days(6));
+        let report = plan(vec![
+            old_download,
+            episode(2, 1, "2023-10-02T00:00:00+00:00"),
+            episode(3, 1, "2023-10-03T00:00:00+00:00"),
+            episode(4, 1, "2023-10-04T00:00:00+00:00")
+        ], &rules);
+        assert_eq!(selected(&report), vec![(1, CleanupReason::Age), (2, CleanupReason::Played),
+                                           (3, CleanupReason::KeepLatest)]);
+    }
+
+    #[test]
+    fn test_podcast_quota_after_keep_latest_and_pinned() {
+        let mut rules = rules();
+        rules.global_policy.keep_latest = Some(3);
+        rules.podcast_policies.insert(1, CleanupPolicyDto {
+            max_bytes: Some(2 * EPISODE_SIZE as i64),
+            ..CleanupPolicyDto::default()
+        });
+        let report = plan(vec![
+            episode(1, 1, "2023-10-01T00:00:00+00:00"),
+            pinned(episode(2, 1, "2023-10-02T00:00:00+00:00")),
+            episode(3, 1, "2023-10-03T00:00:00+00:00"),
+            episode(4, 1, "2023-10-04T00:00:00+00:00"),
+            episode(5, 1, "2023-10-05T00:00:00+00:00")
+        ], &rules);
+        // The pinned episode counts towards the quota, but only the others are deleted
+        assert_eq!(selected(&report), vec![(1, CleanupReason::KeepLatest),
+                                           (3, CleanupReason::PodcastQuota),
+                                           (4, CleanupReason::PodcastQuota)]);
+    }
+
+    #[test]
+    fn test_storage_quota_spans_podcasts() {
+        let mut rules = rules();
+        rules.global_policy.max_bytes = Some(2 * EPISODE_SIZE as i64);
+        rules.podcast_policies.insert(2, CleanupPolicyDto {
+            keep_latest: Some(1),
+            ..CleanupPolicyDto::default()
+        });
+        let report = plan(vec![
+            episode(1, 1, "2023-10-01T00:00:00+00:00"),
+            episode(2, 2, "2023-10-02T00:00:00+00:00"),
+            episode(3, 1, "2023-10-03T00:00:00+00:00"),
+            episode(4, 2, "2023-10-04T00:00:00+00:00"),
+            episode(5, 1, "2023-10-05T00:00:00+00:00")
+        ], &rules);
+        // Episode 2 already freed its space, the oldest of the remaining four go until two are left
+        assert_eq!(selected(&report), vec![(2, CleanupReason::KeepLatest),
+                                           (1, CleanupReason::StorageQuota),
+                                           (3, CleanupReason::StorageQuota)]);
+        assert_eq!(report.freed_bytes, 3 * EPISODE_SIZE);
+    }
+
+    #[test]
+    fn test_quota_with_only_pinned_episodes() {
+        let mut rules = rules();
+        rules.global_policy.max_bytes = Some(0);
+        let report = plan(vec![
+            pinned(episode(1, 1, "2023-10-01T00:00:00+00:00")),
+            pinned(episode(2, 1, "2023-10-02T00:00:00+00:00"))
+        ], &rules);
+        assert!(report.episodes.is_empty());
+        assert_eq!(report.freed_bytes, 0);
+    }
+}
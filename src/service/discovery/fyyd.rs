@@ -0,0 +1,102 @@
+use futures::future::BoxFuture;
+use reqwest::Client;
+use crate::models::discovered_podcast::DiscoveredPodcast;
+use crate::service::discovery::{get_json, DiscoveryProvider};
+use crate::service::http_client::HttpClientFactory;
+
+/// The fyyd.de podcast directory. It needs no credentials.
+pub struct FyydProvider {
+    client: Client,
+    base_url: String
+}
+
+#[derive(Deserialize)]
+struct FyydResponse<T> {
+    data: Option<T>
+}
+
+#[derive(Deserialize)]
+struct FyydPodcast {
+    id: i64,
+    title: String,
+    author: Option<String>,
+    description: Option<String>,
+    #[serde(rename = "xmlURL")]
+    xml_url: String,
+    #[serde(rename = "imgURL")]
+    img_url: Option<String>,
+    #[serde(rename = "htmlURL")]
+    html_url: Option<String>,
+    episode_count: Option<i64>
+}
+
+impl FyydProvider {
+    pub fn new(base_url: String) -> Self {
+        FyydProvider {
+            client: HttpClientFactory::new().build_async_client(),
+            base_url
+        }
+    }
+
+    fn map_podcast(podcast: FyydPodcast) -> DiscoveredPodcast {
+        DiscoveredPodcast {
+            provider: "fyyd".to_string(),
+            id: podcast.id.to_string(),
+            title: podcast.title,
+            author: podcast.author.filter(|author| !author.is_empty()),
+            description: podcast.description.filter(|description| !description.is_empty()),
+            feed_url: podcast.xml_url,
+            image_url: podcast.img_url.filter(|image| !image.is_empty()),
+            website_url: podcast.html_url.filter(|link| !link.is_empty()),
+            // fyyd only returns the ids of its categories
+            categories: vec![],
+            episode_count: podcast.episode_count
+        }
+    }
+
+    async fn get_podcasts(&self, path: &str, query: &[(&str, String)])
+        -> Result<Vec<DiscoveredPodcast>, String> {
+        let request = self.client.get(format!("{}{}", self.base_url, path)).query(query);
+        get_json::<FyydResponse<Vec<FyydPodcast>>>(request).await
+            .map(|response| response.data
+                .unwrap_or_default()
+                .into_iter()
+                .map(Self::map_podcast)
+                .collect())
+    }
+}
+
+impl DiscoveryProvider for FyydProvider {
+    fn id(&self) -> &'static str {
+        "fyyd"
+    }
+
+    fn name(&self) -> &'static str {
+        "fyyd"
+    }
+
+    fn supports_trending(&self) -> bool {
+        true
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<Vec<DiscoveredPodcast>, String>> {
+        Box::pin(async move {
+            self.get_podcasts("/search/podcast", &[("term", query.to_string())]).await
+        })
+    }
+
+    fn lookup<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<DiscoveredPodcast>, String>> {
+        Box::pin(async move {
+            let request = self.client.get(format!("{}/podcast", self.base_url))
+                .query(&[("podcast_id", id)]);
+            get_json::<FyydResponse<FyydPodcast>>(request).await
+                .map(|response| response.data.map(Self::map_podcast))
+        })
+    }
+
+    fn trending(&self, limit: u32) -> BoxFuture<'_, Result<Vec<DiscoveredPodcast>, String>> {
+        Box::pin(async move {
+            self.get_podcasts("/feature/podcast/hot", &[("count", limit.to_string())]).await
+        })
+    }
+}
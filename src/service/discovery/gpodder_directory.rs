@@ -0,0 +1,85 @@
+use futures::future::BoxFuture;
+use reqwest::Client;
+use crate::models::discovered_podcast::DiscoveredPodcast;
+use crate::service::discovery::{get_json, DiscoveryProvider};
+use crate::service::http_client::HttpClientFactory;
+
+/// The public directory of gpodder.net. Podcasts are identified by their feed url there.
+pub struct GpodderDirectoryProvider {
+    client: Client,
+    base_url: String
+}
+
+#[derive(Deserialize)]
+struct GpodderPodcast {
+    url: String,
+    title: String,
+    author: Option<String>,
+    description: Option<String>,
+    logo_url: Option<String>,
+    website: Option<String>
+}
+
+impl GpodderDirectoryProvider {
+    pub fn new(base_url: String) -> Self {
+        GpodderDirectoryProvider {
+            client: HttpClientFactory::new().build_async_client(),
+            base_url
+        }
+    }
+
+    fn map_podcast(podcast: GpodderPodcast) -> DiscoveredPodcast {
+        DiscoveredPodcast {
+            provider: "gpodder".to_string(),
+            id: podcast.url.clone(),
+            title: podcast.title,
+            author: podcast.author.filter(|author| !author.is_empty()),
+            description: podcast.description.filter(|description| !description.is_empty()),
+            feed_url: podcast.url,
+            image_url: podcast.logo_url.filter(|logo_url| !logo_url.is_empty()),
+            website_url: podcast.website.filter(|website| !website.is_empty()),
+            categories: vec![],
+            episode_count: None
+        }
+    }
+}
+
+impl DiscoveryProvider for GpodderDirectoryProvider {
+    fn id(&self) -> &'static str {
+        "gpodder"
+    }
+
+    fn name(&self) -> &'static str {
+        "gpodder.net"
+    }
+
+    fn supports_trending(&self) -> bool {
+        true
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<Vec<DiscoveredPodcast>, String>> {
+        Box::pin(async move {
+            let request = self.client.get(format!("{}/search.json", self.base_url))
+                .query(&[("q", query)]);
+            get_json::<Vec<GpodderPodcast>>(request).await
+                .map(|podcasts| podcasts.into_iter().map(Self::map_podcast).collect())
+        })
+    }
+
+    fn lookup<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<DiscoveredPodcast>, String>> {
+        Box::pin(async move {
+            let request = self.client.get(format!("{}/api/2/data/podcast.json", self.base_url))
+                .query(&[("url", id)]);
+            get_json::<GpodderPodcast>(request).await
+                .map(|podcast| Some(Self::map_podcast(podcast)))
+        })
+    }
+
+    fn trending(&self, limit: u32) -> BoxFuture<'_, Result<Vec<DiscoveredPodcast>, String>> {
+        Box::pin(async move {
+            let request = self.client.get(format!("{}/toplist/{}.json", self.base_url, limit));
+            get_json::<Vec<GpodderPodcast>>(request).await
+                .map(|podcasts| podcasts.into_iter().map(Self::map_podcast).collect())
+        })
+    }
+}
@@ -0,0 +1,87 @@
+use futures::future::BoxFuture;
+use reqwest::Client;
+use crate::models::discovered_podcast::DiscoveredPodcast;
+use crate::service::discovery::{get_json, DiscoveryProvider};
+use crate::service::http_client::HttpClientFactory;
+
+pub struct ItunesProvider {
+    client: Client,
+    base_url: String
+}
+
+#[derive(Deserialize)]
+struct ItunesResponse {
+    results: Vec<ItunesPodcast>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItunesPodcast {
+    collection_id: i64,
+    collection_name: String,
+    artist_name: Option<String>,
+    // Podcasts without a public feed can't be subscribed to
+    feed_url: Option<String>,
+    artwork_url600: Option<String>,
+    collection_view_url: Option<String>,
+    #[serde(default)]
+    genres: Vec<String>,
+    track_count: Option<i64>
+}
+
+impl ItunesProvider {
+    pub fn new(base_url: String) -> Self {
+        ItunesProvider {
+            client: HttpClientFactory::new().build_async_client(),
+            base_url
+        }
+    }
+
+    fn map_podcasts(response: ItunesResponse) -> Vec<DiscoveredPodcast> {
+        response.results
+            .into_iter()
+            .filter_map(|podcast| Some(DiscoveredPodcast {
+                provider: "itunes".to_string(),
+                id: podcast.collection_id.to_string(),
+                title: podcast.collection_name,
+                author: podcast.artist_name,
+                description: None,
+                feed_url: podcast.feed_url?,
+                image_url: podcast.artwork_url600,
+                website_url: podcast.collection_view_url,
+                categories: podcast.genres
+                    .into_iter()
+                    .filter(|genre| genre != "Podcasts")
+                    .collect(),
+                episode_count: podcast.track_count
+            }))
+            .collect()
+    }
+}
+
+impl DiscoveryProvider for ItunesProvider {
+    fn id(&self) -> &'static str {
+        "itunes"
+    }
+
+    fn name(&self) -> &'static str {
+        "iTunes"
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<Vec<DiscoveredPodcast>, String>> {
+        Box::pin(async move {
+            let request = self.client.get(format!("{}/search", self.base_url))
+                .query(&[("media", "podcast"), ("entity", "podcast"), ("term", query)]);
+            get_json::<ItunesResponse>(request).await.map(Self::map_podcasts)
+        })
+    }
+
+    fn lookup<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<DiscoveredPodcast>, String>> {
+        Box::pin(async move {
+            let request = self.client.get(format!("{}/lookup", self.base_url))
+                .query(&[("entity", "podcast"), ("id", id)]);
+            get_json::<ItunesResponse>(request).await
+                .map(|response| Self::map_podcasts(response).into_iter().next())
+        })
+    }
+}
@@ -0,0 +1,97 @@
+pub mod fyyd;
+pub mod gpodder_directory;
+pub mod itunes;
+pub mod podcast_index;
+
+use futures::future::BoxFuture;
+use reqwest::RequestBuilder;
+use serde::de::DeserializeOwned;
+use crate::models::discovered_podcast::{DiscoveredPodcast, DiscoveryProviderInfo};
+use crate::service::discovery::fyyd::FyydProvider;
+use crate::service::discovery::gpodder_directory::GpodderDirectoryProvider;
+use crate::service::discovery::itunes::ItunesProvider;
+use crate::service::discovery::podcast_index::PodcastIndexProvider;
+use crate::service::environment_service::EnvironmentService;
+use crate::service::outbound_policy::OutboundPolicy;
+
+/// A podcast directory that can be searched for podcasts to subscribe to.
+pub trait DiscoveryProvider: Send + Sync {
+    /// Identifies the provider in the api, e.g. `itunes`
+    fn id(&self) -> &'static str;
+
+    fn name(&self) -> &'static str;
+
+    /// Providers that need credentials aren't usable without them.
+    fn is_configured(&self) -> bool {
+        true
+    }
+
+    fn supports_trending(&self) -> bool {
+        false
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<Vec<DiscoveredPodcast>, String>>;
+
+    fn lookup<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<DiscoveredPodcast>, String>>;
+
+    fn trending(&self, _limit: u32) -> BoxFuture<'_, Result<Vec<DiscoveredPodcast>, String>> {
+        let error = format!("{} has no trending podcasts", self.name());
+        Box::pin(async move { Err(error) })
+    }
+}
+
+/// Knows every available [`DiscoveryProvider`].
+pub struct DiscoveryService {
+    providers: Vec<Box<dyn DiscoveryProvider>>
+}
+
+impl DiscoveryService {
+    pub fn new() -> Self {
+        let environment_service = EnvironmentService::new();
+        let discovery_config = environment_service.discovery_config.clone();
+        DiscoveryService {
+            providers: vec![
+                Box::new(ItunesProvider::new(discovery_config.itunes_url)),
+                Box::new(PodcastIndexProvider::new(discovery_config.podindex_url,
+                                                   environment_service.podindex_api_key,
+                                                   environment_service.podindex_api_secret)),
+                Box::new(FyydProvider::new(discovery_config.fyyd_url)),
+                Box::new(GpodderDirectoryProvider::new(discovery_config.gpodder_directory_url)),
+            ]
+        }
+    }
+
+    pub fn get_providers(&self) -> Vec<DiscoveryProviderInfo> {
+        self.providers.iter()
+            .map(|provider| DiscoveryProviderInfo {
+                id: provider.id().to_string(),
+                name: provider.name().to_string(),
+                configured: provider.is_configured(),
+                supports_trending: provider.supports_trending()
+            })
+            .collect()
+    }
+
+    /// Returns the provider if it exists and is configured.
+    pub fn get_provider(&self, provider_id: &str) -> Result<&dyn DiscoveryProvider, String> {
+        let provider = self.providers.iter()
+            .find(|provider| provider.id() == provider_id)
+            .ok_or(format!("Unknown discovery provider {}", provider_id))?;
+        if !provider.is_configured() {
+            return Err(format!("{} is not configured", provider.name()));
+        }
+        Ok(provider.as_ref())
+    }
+}
+
+/// Sends a request to a directory and parses the json response. The response size is limited
+/// like every other outbound response.
+pub async fn get_json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, String> {
+    let response = request.send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Error requesting podcast directory: {}", e))?;
+    let body = OutboundPolicy::new().read_response_async(response).await?;
+    serde_json::from_slice(&body)
+        .map_err(|e| format!("Invalid response of podcast directory: {}", e))
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+use futures::future::BoxFuture;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::Client;
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use crate::models::discovered_podcast::DiscoveredPodcast;
+use crate::service::discovery::{get_json, DiscoveryProvider};
+use crate::service::http_client::HttpClientFactory;
+
+pub struct PodcastIndexProvider {
+    client: Client,
+    base_url: String,
+    api_key: String,
+    api_secret: String
+}
+
+#[derive(Deserialize)]
+struct PodcastIndexFeeds {
+    #[serde(default)]
+    feeds: Vec<PodcastIndexFeed>
+}
+
+// An unknown feed id is answered with an empty array instead of a feed
+#[derive(Deserialize)]
+struct PodcastIndexFeedResponse {
+    feed: Value
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PodcastIndexFeed {
+    id: i64,
+    title: String,
+    url: String,
+    author: Option<String>,
+    description: Option<String>,
+    artwork: Option<String>,
+    image: Option<String>,
+    link: Option<String>,
+    categories: Option<BTreeMap<String, String>>,
+    episode_count: Option<i64>
+}
+
+impl PodcastIndexProvider {
+    pub fn new(base_url: String, api_key: String, api_secret: String) -> Self {
+        PodcastIndexProvider {
+            client: HttpClientFactory::new().build_async_client(),
+            base_url,
+            api_key,
+            api_secret
+        }
+    }
+
+    fn get(&self, path: &str) -> reqwest::RequestBuilder {
+        self.client.get(format!("{}{}", self.base_url, path))
+            .headers(self.compute_auth_headers())
+    }
+
+    fn compute_auth_headers(&self) -> HeaderMap {
+        let seconds = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut hasher = Sha1::new();
+        hasher.update(self.api_key.clone() + &self.api_secret + &seconds.to_string());
+        let hashed_auth_key = format!("{:x}", hasher.finalize());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Auth-Key", HeaderValue::from_str(&self.api_key).unwrap());
+        headers.insert("X-Auth-Date", HeaderValue::from_str(&seconds.to_string()).unwrap());
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&hashed_auth_key).unwrap());
+        headers
+    }
+
+    fn map_feed(feed: PodcastIndexFeed) -> DiscoveredPodcast {
+        DiscoveredPodcast {
+            provider: "podindex".to_string(),
+            id: feed.id.to_string(),
+            title: feed.title,
+            author: feed.author.filter(|author| !author.is_empty()),
+            description: feed.description.filter(|description| !description.is_empty()),
+            feed_url: feed.url,
+            image_url: feed.artwork.or(feed.image).filter(|image| !image.is_empty()),
+            website_url: feed.link.filter(|link| !link.is_empty()),
+            categories: feed.categories.unwrap_or_default().into_values().collect(),
+            episode_count: feed.episode_count
+        }
+    }
+}
+
+impl DiscoveryProvider for PodcastIndexProvider {
+    fn id(&self) -> &'static str {
+        "podindex"
+    }
+
+    fn name(&self) -> &'static str {
+        "Podcast Index"
+    }
+
+    fn is_configured(&self) -> bool {
+        !self.api_key.is_empty() && !self.api_secret.is_empty()
+    }
+
+    fn supports_trending(&self) -> bool {
+        true
+    }
+
+    fn search<'a>(&'a self, query: &'a str) -> BoxFuture<'a, Result<Vec<DiscoveredPodcast>, String>> {
+        Box::pin(async move {
+            let request = self.get("/search/byterm").query(&[("q", query)]);
+            get_json::<PodcastIndexFeeds>(request).await
+                .map(|response| response.feeds.into_iter().map(Self::map_feed).collect())
+        })
+    }
+
+    fn lookup<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<DiscoveredPodcast>, String>> {
+        Box::pin(async move {
+            let request = self.get("/podcasts/byfeedid").query(&[("id", id)]);
+            let response = get_json::<PodcastIndexFeedResponse>(request).await?;
+            if !response.feed.is_object() {
+                return Ok(None);
+            }
+            serde_json::from_value::<PodcastIndexFeed>(response.feed)
+                .map(|feed| Some(Self::map_feed(feed)))
+                .map_err(|e| format!("Invalid response of podcast directory: {}", e))
+        })
+    }
+
+    fn trending(&self, limit: u32) -> BoxFuture<'_, Result<Vec<DiscoveredPodcast>, String>> {
+        Box::pin(async move {
+            let request = self.get("/podcasts/trending").query(&[("max", limit)]);
+            get_json::<PodcastIndexFeeds>(request).await
+                .map(|response| response.feeds.into_iter().map(Self::map_feed).collect())
+        })
+    }
+}
@@ -1,45 +1,85 @@
 use crate::models::podcast_episode::PodcastEpisode;
 use crate::models::podcasts::Podcast;
 use crate::service::file_service::{FileService};
-use crate::service::mapping_service::MappingService;
 
 use crate::service::podcast_episode_service::PodcastEpisodeService;
-use reqwest::blocking::ClientBuilder;
+use reqwest::blocking::Response;
 
-use std::io;
-use reqwest::header::HeaderMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use reqwest::header::{CONTENT_TYPE, HeaderMap};
 
 use crate::config::dbconfig::establish_connection;
-use crate::constants::constants::{PODCAST_FILENAME, PODCAST_IMAGENAME};
+use crate::mutex::LockResultExt;
+use crate::constants::constants::{DEFAULT_IMAGE_SUFFIX, DEFAULT_PODCAST_SUFFIX, PODCAST_FILENAME,
+                                   PODCAST_IMAGENAME};
 use crate::models::file_path::FilenameBuilder;
+use crate::service::credential_service::{CredentialScope, CredentialService};
+use crate::service::environment_service::EnvironmentService;
+use crate::service::http_client::HttpClientFactory;
+use crate::service::outbound_policy::OutboundPolicy;
+use crate::service::library_service::LIBRARY_LOCK;
 use crate::service::settings_service::SettingsService;
-use crate::utils::append_to_header::add_basic_auth_headers_conditionally;
+use crate::service::audio_tag_service::{AudioChapter, AudioMetadata, AudioTagService};
+use crate::service::storage::{StorageBackend, StorageService};
+use crate::utils::time::parse_feed_date;
+
+// Downloads for object stores are tagged in this directory of the local media root
+const TEMPORARY_DIRECTORY: &str = ".download";
 
 pub struct DownloadService {
-    pub mappingservice: MappingService,
-    pub client_builder: ClientBuilder,
     pub file_service: FileService,
 }
 
 impl DownloadService {
     pub fn new() -> Self {
         DownloadService {
-            mappingservice: MappingService::new(),
-            client_builder: ClientBuilder::new(),
             file_service: FileService::new(),
         }
     }
 
-    pub fn download_podcast_episode(&mut self, podcast_episode: PodcastEpisode, podcast: Podcast) {
+    pub fn download_podcast_episode(&mut self, podcast_episode: PodcastEpisode, podcast: Podcast)
+        -> Result<(), String> {
+        let _library_lock = LIBRARY_LOCK.read().ignore_poison();
         let conn = &mut establish_connection();
-        let suffix = PodcastEpisodeService::get_url_file_suffix(&podcast_episode.url);
+        let outbound_policy = OutboundPolicy::new();
+        outbound_policy.check_url(&podcast_episode.url)?;
+        outbound_policy.check_url(&podcast_episode.image_url)?;
+        let http_client_factory = HttpClientFactory::for_podcast(&podcast);
+        let credential_scope = CredentialService::get_credential_scope(conn, &podcast);
+
+        let mut resp = http_client_factory.get_with_credentials(&podcast_episode.url,
+                                                                &HeaderMap::new(),
+                                                                &credential_scope)
+            .map_err(|e| format!("Error requesting episode: {}", e))?;
+        outbound_policy.check_download_size(resp.headers())?;
+        let image_response = http_client_factory.get_with_credentials(&podcast_episode.image_url,
+                                                                      &HeaderMap::new(),
+                                                                      &credential_scope)
+            .map_err(|e| format!("Error requesting episode image: {}", e))?;
+
+        // Urls without a file extension are stored with the suffix of the reported content type
+        let suffix = PodcastEpisodeService::get_file_suffix(&podcast_episode.url,
+                                                            Self::get_content_type(&resp).as_deref(),
+                                                            DEFAULT_PODCAST_SUFFIX);
         let settings_in_db = SettingsService::new().get_settings(conn).unwrap();
-        let image_suffix = PodcastEpisodeService::get_url_file_suffix(&podcast_episode.image_url);
+        let image_suffix = PodcastEpisodeService::get_file_suffix(&podcast_episode.image_url,
+                                                                  Self::get_content_type(&image_response)
+                                                                      .as_deref(),
+                                                                  DEFAULT_IMAGE_SUFFIX);
+        let image = outbound_policy.read_response(image_response)?;
+        let metadata = match AudioTagService::is_enabled(&podcast, &settings_in_db)
+            && AudioTagService::supports_suffix(&suffix) {
+            true => Some(Self::get_metadata(&podcast_episode, &podcast, &image, &image_suffix,
+                                            &http_client_factory, &credential_scope,
+                                            &outbound_policy)),
+            false => None
+        };
 
-        let paths;
-        match settings_in_db.use_existing_filename {
+        let paths = match settings_in_db.use_existing_filename {
             true=>{
-                paths = FilenameBuilder::default()
+                FilenameBuilder::default()
                     .with_podcast(podcast.clone())
                     .with_suffix(&suffix)
                     .with_episode(podcast_episode.clone(), conn)
@@ -47,10 +87,10 @@ impl DownloadService {
                     .with_image_filename(PODCAST_IMAGENAME)
                     .with_image_suffix(&image_suffix)
                     .with_raw_directory(conn)
-                    .build(conn);
+                    .build(conn)
             },
             false=>{
-                paths = FilenameBuilder::default()
+                FilenameBuilder::default()
                     .with_suffix(&suffix)
                     .with_image_suffix(&image_suffix)
                     .with_episode(podcast_episode.clone(), conn)
@@ -58,35 +98,43 @@ impl DownloadService {
                     .with_podcast(podcast.clone())
                     .with_image_filename(PODCAST_IMAGENAME)
                     .with_filename(PODCAST_FILENAME)
-                    .build(conn);
+                    .build(conn)
             }
-        }
-
-
-        let client = ClientBuilder::new()
-            .build()
-            .unwrap();
+        };
 
-        let mut header_map = HeaderMap::new();
-        add_basic_auth_headers_conditionally(podcast_episode.clone().url, &mut header_map);
-        let mut resp = client.get(podcast_episode.clone().url)
-            .headers(header_map.clone())
-            .send()
-            .unwrap();
-        let mut image_response = client.get(podcast_episode.image_url.clone()).headers(header_map).send()
-            .unwrap();
-
-        let mut podcast_out = std::fs::File::create(paths.0.clone()).unwrap();
-        let mut image_out = std::fs::File::create(paths.1.clone()).unwrap();
 
+        let storage = StorageService::get_backend();
         if !self.file_service.check_if_podcast_main_image_downloaded(&podcast.clone()
             .directory_id,  conn)
         {
-            let mut image_podcast = std::fs::File::create(paths.1.clone()).unwrap();
-            io::copy(&mut image_response, &mut image_podcast).expect("failed to copy content");
+            storage.write(&paths.1, &mut image.as_slice())
+                .map_err(|e| format!("Error saving episode image: {}", e))?;
         }
 
-        io::copy(&mut resp, &mut podcast_out).expect("failed to copy content");
+        let max_download_size = match outbound_policy.max_download_size {
+            0 => u64::MAX,
+            max_download_size => max_download_size
+        };
+        let content_length = resp.content_length();
+        let mut content = (&mut resp).take(max_download_size.saturating_add(1));
+        let (downloaded_size, file_size) = match &metadata {
+            Some(metadata) => Self::write_with_tags(storage.as_ref(), &paths.0, &mut content,
+                                                    &suffix, metadata),
+            None => storage.write(&paths.0, &mut content)
+                .map(|downloaded_size| (downloaded_size, downloaded_size))
+        }.map_err(|e| format!("Error saving episode: {}", e))?;
+        if downloaded_size > max_download_size {
+            storage.delete_file(&paths.0).expect("Error removing incomplete download");
+            return Err(format!("The episode is larger than the allowed {} bytes",
+                               max_download_size));
+        }
+        // Servers may close the connection early without an error
+        if let Some(content_length) = content_length.filter(|content_length| downloaded_size
+            < *content_length) {
+            storage.delete_file(&paths.0).expect("Error removing incomplete download");
+            return Err(format!("The download ended after {} of {} bytes", downloaded_size,
+                               content_length));
+        }
 
         PodcastEpisode::update_total_podcast_time_and_image(
                 &podcast_episode.episode_id,
@@ -94,6 +142,87 @@ impl DownloadService {
                 &paths.0.clone(),
             conn)
             .expect("TODO: panic message");
-        io::copy(&mut image_response, &mut image_out).expect("failed to copy content");
+        PodcastEpisode::update_file_size(conn, &podcast_episode.episode_id, file_size as i64);
+        storage.write(&paths.1, &mut image.as_slice())
+            .map_err(|e| format!("Error saving episode image: {}", e))?;
+        Ok(())
+    }
+
+    fn get_metadata(podcast_episode: &PodcastEpisode, podcast: &Podcast, image: &[u8],
+                    image_suffix: &str, http_client_factory: &HttpClientFactory,
+                    credential_scope: &CredentialScope, outbound_policy: &OutboundPolicy)
+        -> AudioMetadata {
+        let chapters = match &podcast_episode.chapters_url {
+            Some(chapters_url) => Self::download_chapters(chapters_url, podcast_episode
+                .total_time, http_client_factory, credential_scope, outbound_policy)
+                .unwrap_or_else(|e| {
+                    log::warn!("Error getting the chapters of episode {}: {}",
+                        podcast_episode.name, e);
+                    Vec::new()
+                }),
+            None => Vec::new()
+        };
+        AudioMetadata {
+            podcast_name: podcast.name.clone(),
+            title: podcast_episode.name.clone(),
+            date: parse_feed_date(&podcast_episode.date_of_recording),
+            description: podcast_episode.description.clone(),
+            chapters,
+            // The image of the episode is the image of the podcast if it has none
+            artwork: AudioTagService::get_artwork(image.to_vec(), image_suffix)
+        }
+    }
+
+    fn download_chapters(chapters_url: &str, duration: i32,
+                         http_client_factory: &HttpClientFactory,
+                         credential_scope: &CredentialScope, outbound_policy: &OutboundPolicy)
+        -> Result<Vec<AudioChapter>, String> {
+        outbound_policy.check_url(chapters_url)?;
+        let response = http_client_factory.get_with_credentials(chapters_url, &HeaderMap::new(),
+                                                                credential_scope)
+            .map_err(|e| format!("Error requesting chapters: {}", e))?;
+        AudioTagService::parse_chapters(&outbound_policy.read_response(response)?, duration)
+    }
+
+    /// Tags are written into a temporary file before it's stored, object stores can't change
+    /// stored files. A file that can't be tagged is stored as it was downloaded. Returns the
+    /// number of downloaded bytes without the tags and the size of the stored file.
+    /// Files in the media root are tagged where they are stored. Object stores get a copy that
+    /// is tagged in the media root on the disk first, episodes can be larger than a tmpfs.
+    fn write_with_tags(storage: &dyn StorageBackend, path: &str,
+                       content: &mut (dyn Read + Send), suffix: &str, metadata: &AudioMetadata)
+        -> std::io::Result<(u64, u64)> {
+        if let Some(local_path) = storage.local_path(path) {
+            let downloaded_size = storage.write(path, content)?;
+            if let Err(e) = AudioTagService::write_tags(&local_path, suffix, metadata) {
+                log::warn!("Error writing the tags of {}: {}", path, e);
+            }
+            return Ok((downloaded_size, std::fs::metadata(&local_path)?.len()));
+        }
+
+        let temporary_directory = Path::new(&EnvironmentService::new().storage_config.media_root)
+            .join(TEMPORARY_DIRECTORY);
+        std::fs::create_dir_all(&temporary_directory)?;
+        let temporary_path = temporary_directory.join(format!("{}.{}", uuid::Uuid::new_v4(),
+                                                              suffix));
+        let result = File::create(&temporary_path)
+            .and_then(|mut temporary_file| std::io::copy(content, &mut temporary_file))
+            .and_then(|downloaded_size| {
+                if let Err(e) = AudioTagService::write_tags(&temporary_path, suffix, metadata) {
+                    log::warn!("Error writing the tags of {}: {}", path, e);
+                }
+                let file_size = storage.write(path, &mut File::open(&temporary_path)?)?;
+                Ok((downloaded_size, file_size))
+            });
+        if let Err(e) = std::fs::remove_file(&temporary_path) {
+            log::error!("Error removing temporary file {}: {}", temporary_path.display(), e);
+        }
+        result
+    }
+
+    fn get_content_type(response: &Response) -> Option<String> {
+        response.headers().get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map(|content_type| content_type.to_string())
     }
 }
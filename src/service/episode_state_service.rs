@@ -0,0 +1,212 @@
+use std::collections::{HashMap, HashSet};
+use chrono::NaiveDateTime;
+use crate::constants::constants::PLAYED_THRESHOLD_PERCENT;
+use crate::DbConnection;
+use crate::exception::exceptions::PodFetchError;
+use crate::models::episode_state::{EpisodeState, EpisodeStateBulkModel, EpisodeStateDto,
+                                   EpisodeStateType};
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcast_history_item::PodcastHistoryItem;
+use crate::models::podcasts::Podcast;
+use crate::utils::time::get_start_of_day;
+
+/// The played state of episodes per user. Users can set it explicitly, otherwise it is inferred
+/// from how far they listened to an episode.
+pub struct EpisodeStateService;
+
+impl EpisodeStateService {
+    pub fn infer_state(watched_time: i32, total_time: i32) -> EpisodeStateType {
+        // Episodes without a known duration can't be finished
+        if total_time > 0 && watched_time as i64 * 100 >= total_time as i64
+            * PLAYED_THRESHOLD_PERCENT as i64 {
+            return EpisodeStateType::Played;
+        }
+        match watched_time > 0 {
+            true => EpisodeStateType::InProgress,
+            false => EpisodeStateType::New
+        }
+    }
+
+    /// The states of the episodes of the user by podcast episode id. Episodes that are missing
+    /// are new.
+    pub fn get_episode_states(conn: &mut DbConnection, username: &str)
+        -> HashMap<i32, EpisodeStateType> {
+        Self::load_states(conn, Some(username)).remove(username).unwrap_or_default()
+    }
+
+    /// The states of the episodes of every user by username and podcast episode id
+    pub fn get_all_episode_states(conn: &mut DbConnection)
+        -> HashMap<String, HashMap<i32, EpisodeStateType>> {
+        Self::load_states(conn, None)
+    }
+
+    pub fn get_played_episode_ids(conn: &mut DbConnection, username: &str) -> Vec<i32> {
+        Self::get_episode_states(conn, username)
+            .into_iter()
+            .filter(|(_, state)| state.is_played())
+            .map(|(podcast_episode_id, _)| podcast_episode_id)
+            .collect()
+    }
+
+    /// When the episodes were last played by any user, by podcast episode id. Only episodes that
+    /// are played are included, the date is the last time a user listened to or marked them.
+    pub fn get_last_played_dates(conn: &mut DbConnection) -> HashMap<i32, NaiveDateTime> {
+        let mut activity = HashMap::<(String, i32), NaiveDateTime>::new();
+        for (watch_log, podcast_episode_id) in PodcastHistoryItem::get_watch_logs_with_episode_ids(
+            conn) {
+            let date = activity.entry((watch_log.username, podcast_episode_id))
+                .or_insert(watch_log.date);
+            *date = (*date).max(watch_log.date);
+        }
+        for episode_state in EpisodeState::get_states(conn, None) {
+            let date = activity.entry((episode_state.username, episode_state.podcast_episode_id))
+                .or_insert(episode_state.updated_at);
+            *date = (*date).max(episode_state.updated_at);
+        }
+        let mut last_played_dates = HashMap::<i32, NaiveDateTime>::new();
+        for (listener, states) in Self::get_all_episode_states(conn) {
+            for (podcast_episode_id, state) in states {
+                let played_date = match state.is_played() {
+                    true => activity.get(&(listener.clone(), podcast_episode_id)),
+                    false => None
+                };
+                if let Some(played_date) = played_date {
+                    let date = last_played_dates.entry(podcast_episode_id)
+                        .or_insert(*played_date);
+                    *date = (*date).max(*played_date);
+                }
+            }
+        }
+        last_played_dates
+    }
+
+    /// The number of episodes per podcast the user hasn't played yet
+    pub fn get_unplayed_counts(conn: &mut DbConnection, username: &str) -> HashMap<i32, i64> {
+        let played_episode_ids = Self::get_played_episode_ids(conn, username)
+            .into_iter()
+            .collect::<HashSet<i32>>();
+        let mut unplayed_counts = HashMap::new();
+        for (podcast_episode_id, podcast_id) in PodcastEpisode::get_episode_ids(conn, None, None) {
+            if !played_episode_ids.contains(&podcast_episode_id) {
+                *unplayed_counts.entry(podcast_id).or_insert(0) += 1;
+            }
+        }
+        unplayed_counts
+    }
+
+    pub fn get_states_of_podcast(conn: &mut DbConnection, username: &str, podcast_id: i32)
+        -> Result<Vec<EpisodeStateDto>, PodFetchError> {
+        Podcast::get_podcast(conn, podcast_id).map_err(|_| PodFetchError::podcast_not_found())?;
+        let episode_states = Self::get_episode_states(conn, username);
+        Ok(PodcastEpisode::get_episodes_by_podcast_id(podcast_id, conn)
+            .into_iter()
+            .map(|podcast_episode| EpisodeStateDto {
+                state: episode_states.get(&podcast_episode.id).cloned()
+                    .unwrap_or(EpisodeStateType::New),
+                episode_id: podcast_episode.episode_id,
+                podcast_id: podcast_episode.podcast_id
+            })
+            .collect())
+    }
+
+    pub fn get_state(conn: &mut DbConnection, username: &str, episode_id: &str)
+        -> Result<EpisodeStateDto, PodFetchError> {
+        let podcast_episode = Self::find_episode(conn, episode_id)?;
+        let state = Self::get_episode_states(conn, username)
+            .get(&podcast_episode.id)
+            .cloned()
+            .unwrap_or(EpisodeStateType::New);
+        Ok(EpisodeStateDto {
+            episode_id: podcast_episode.episode_id,
+            podcast_id: podcast_episode.podcast_id,
+            state
+        })
+    }
+
+    pub fn set_state(conn: &mut DbConnection, username: &str, episode_id: &str,
+                     state: EpisodeStateType) -> Result<EpisodeStateDto, PodFetchError> {
+        let podcast_episode = Self::find_episode(conn, episode_id)?;
+        EpisodeState::set_states(conn, username, &[podcast_episode.id], state);
+        Ok(EpisodeStateDto {
+            episode_id: podcast_episode.episode_id,
+            podcast_id: podcast_episode.podcast_id,
+            state
+        })
+    }
+
+    /// Sets the state of every episode matching the filters. Returns the number of episodes.
+    pub fn set_states(conn: &mut DbConnection, username: &str, filter: &EpisodeStateBulkModel)
+        -> Result<usize, PodFetchError> {
+        if filter.episode_ids.is_none() && filter.podcast_id.is_none()
+            && filter.published_before.is_none() {
+            return Err(PodFetchError::invalid_episode_state_filter());
+        }
+        if let Some(podcast_id) = filter.podcast_id {
+            Podcast::get_podcast(conn, podcast_id)
+                .map_err(|_| PodFetchError::podcast_not_found())?;
+        }
+        let mut podcast_episode_ids = PodcastEpisode::get_episode_ids(
+            conn, filter.podcast_id, filter.published_before.map(get_start_of_day))
+            .into_iter()
+            .map(|(podcast_episode_id, _)| podcast_episode_id)
+            .collect::<Vec<i32>>();
+        if let Some(episode_ids) = &filter.episode_ids {
+            let mut selected_episode_ids = HashSet::new();
+            for episode_id in episode_ids {
+                selected_episode_ids.insert(Self::find_episode(conn, episode_id)?.id);
+            }
+            podcast_episode_ids.retain(|podcast_episode_id| selected_episode_ids
+                .contains(podcast_episode_id));
+        }
+        EpisodeState::set_states(conn, username, &podcast_episode_ids, filter.state);
+        Ok(podcast_episode_ids.len())
+    }
+
+    /// Listening to an episode that was marked as new again lets the history decide its state
+    pub fn on_listened(conn: &mut DbConnection, username: &str, episode_id: &str) {
+        let podcast_episode = match PodcastEpisode::get_podcast_episode_by_id(conn, episode_id)
+            .unwrap() {
+            Some(podcast_episode) => podcast_episode,
+            None => return
+        };
+        if let Some(episode_state) = EpisodeState::get_state(conn, username, podcast_episode.id) {
+            if EpisodeStateType::from_string(&episode_state.state) == EpisodeStateType::New {
+                EpisodeState::delete_state(conn, username, podcast_episode.id);
+            }
+        }
+    }
+
+    fn load_states(conn: &mut DbConnection, username: Option<&str>)
+        -> HashMap<String, HashMap<i32, EpisodeStateType>> {
+        // The furthest position counts, rewinding doesn't make an episode unplayed
+        let mut listening_progress = HashMap::<(String, i32), (i32, i32)>::new();
+        for (listener, podcast_episode_id, total_time, watched_time) in
+            PodcastHistoryItem::get_listening_progress(conn, username) {
+            let progress = listening_progress.entry((listener, podcast_episode_id))
+                .or_insert((total_time, 0));
+            progress.1 = progress.1.max(watched_time);
+        }
+        let mut episode_states = HashMap::<String, HashMap<i32, EpisodeStateType>>::new();
+        for ((listener, podcast_episode_id), (total_time, watched_time)) in listening_progress {
+            let state = Self::infer_state(watched_time, total_time);
+            if state != EpisodeStateType::New {
+                episode_states.entry(listener).or_default().insert(podcast_episode_id, state);
+            }
+        }
+        for episode_state in EpisodeState::get_states(conn, username) {
+            let user_states = episode_states.entry(episode_state.username).or_default();
+            match EpisodeStateType::from_string(&episode_state.state) {
+                EpisodeStateType::New => user_states.remove(&episode_state.podcast_episode_id),
+                state => user_states.insert(episode_state.podcast_episode_id, state)
+            };
+        }
+        episode_states
+    }
+
+    fn find_episode(conn: &mut DbConnection, episode_id: &str)
+        -> Result<PodcastEpisode, PodFetchError> {
+        PodcastEpisode::get_podcast_episode_by_id(conn, episode_id)
+            .unwrap()
+            .ok_or(PodFetchError::episode_not_found())
+    }
+}
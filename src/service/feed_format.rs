@@ -0,0 +1,478 @@
+use atom_syndication::extension::{Extension, ExtensionMap};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rss::Channel;
+
+use crate::models::feed::{Feed, FeedEnclosure, FeedItem};
+use crate::utils::feed_sanitizer::{decode_feed, escape_xml_entities};
+
+/// A syndication format podcasts can be published in. Every format is parsed into the same
+/// [`Feed`] model so that all of them share the episode insertion.
+pub trait FeedFormat {
+    fn name(&self) -> &'static str;
+
+    /// Checks if the document is written in this format.
+    fn detect(&self, document: &str, content_type: Option<&str>) -> bool;
+
+    fn parse(&self, document: &str) -> Result<Feed, String>;
+}
+
+pub struct RssFormat;
+
+pub struct AtomFormat;
+
+pub struct JsonFeedFormat;
+
+fn get_feed_formats() -> Vec<Box<dyn FeedFormat>> {
+    vec![Box::new(JsonFeedFormat), Box::new(AtomFormat), Box::new(RssFormat)]
+}
+
+/// Detects the format of a downloaded feed and parses it. Documents that match no format are
+/// handed to the RSS parser so that its error is reported.
+pub fn parse_feed(bytes: &[u8], content_type: Option<&str>) -> Result<Feed, String> {
+    let document = decode_feed(bytes, content_type);
+    let feed_format = get_feed_formats()
+        .into_iter()
+        .find(|feed_format| feed_format.detect(&document, content_type))
+        .unwrap_or(Box::new(RssFormat));
+
+    feed_format.parse(&document)
+        .map_err(|e| format!("Error parsing {} feed: {}", feed_format.name(), e))
+}
+
+// Declarations, processing instructions and comments don't start with a letter
+static ELEMENT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(
+    r"<([A-Za-z_][\w.-]*:)?([A-Za-z_][\w.-]*)").unwrap());
+
+fn get_root_element(document: &str) -> Option<String> {
+    ELEMENT_REGEX.captures(document).map(|captures| captures[2].to_string())
+}
+
+// Paged feeds link to older items with "next", archived feeds with "prev-archive"
+const PAGING_RELATIONS: [&str; 2] = ["next", "prev-archive"];
+
+fn get_next_page_url(links: &[atom_syndication::Link]) -> Option<String> {
+    PAGING_RELATIONS
+        .iter()
+        .find_map(|relation| links.iter().find(|link| link.rel() == *relation))
+        .and_then(|link| non_empty(Some(link.href())))
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_string())
+}
+
+// Prefix of the Podcasting 2.0 namespace, its chapters link to a JSON chapters file
+const PODCAST_NAMESPACE: &str = "podcast";
+
+// Season and episode numbers are positive integers by the iTunes specification
+fn parse_number(value: Option<&str>) -> Option<i32> {
+    value
+        .and_then(|value| value.trim().parse::<i32>().ok())
+        .filter(|number| *number >= 0)
+}
+
+impl FeedFormat for RssFormat {
+    fn name(&self) -> &'static str {
+        "RSS"
+    }
+
+    fn detect(&self, document: &str, _: Option<&str>) -> bool {
+        get_root_element(document).map(|root_element| root_element == "rss").unwrap_or(false)
+    }
+
+    fn parse(&self, document: &str) -> Result<Feed, String> {
+        let channel = Channel::read_from(escape_xml_entities(document).as_bytes())
+            .map_err(|e| e.to_string())?;
+        let itunes_ext = channel.itunes_ext.clone().unwrap_or_default();
+
+        let items = channel.items
+            .iter()
+            .map(|item| {
+                let itunes_item_ext = item.itunes_ext.clone().unwrap_or_default();
+                FeedItem {
+                    title: item.title.clone(),
+                    guid: item.guid.clone().map(|guid| guid.value),
+                    description: item.description.clone(),
+                    pub_date: item.pub_date.clone(),
+                    enclosure: item.enclosure.clone().map(|enclosure| FeedEnclosure {
+                        url: enclosure.url,
+                        mime_type: non_empty(Some(&enclosure.mime_type)),
+                        length: non_empty(Some(&enclosure.length)),
+                    }),
+                    image_url: itunes_item_ext.image,
+                    duration: itunes_item_ext.duration,
+                    season: parse_number(itunes_item_ext.season.as_deref()),
+                    episode_number: parse_number(itunes_item_ext.episode.as_deref()),
+                    chapters_url: item.extensions.get(PODCAST_NAMESPACE)
+                        .and_then(|podcast_extensions| podcast_extensions.get("chapters"))
+                        .and_then(|values| values.first())
+                        .and_then(|chapters| non_empty(chapters.attrs().get("url")
+                            .map(|url| url.as_str()))),
+                }
+            })
+            .collect();
+
+        Ok(Feed {
+            title: channel.title.clone(),
+            description: channel.description.clone(),
+            language: channel.language.clone(),
+            last_build_date: channel.last_build_date.clone(),
+            author: itunes_ext.author,
+            keywords: itunes_ext.categories
+                .iter()
+                .map(|category| category.text.clone())
+                .collect(),
+            image_url: channel.image.clone().map(|image| image.url).or(itunes_ext.image),
+            new_feed_url: itunes_ext.new_feed_url,
+            next_page_url: channel.atom_ext
+                .as_ref()
+                .and_then(|atom_ext| get_next_page_url(atom_ext.links())),
+            items,
+        })
+    }
+}
+
+impl AtomFormat {
+    fn get_itunes_extension<'a>(extensions: &'a ExtensionMap, name: &str) -> Option<&'a Extension> {
+        extensions.get("itunes")
+            .and_then(|itunes_extensions| itunes_extensions.get(name))
+            .and_then(|values| values.first())
+    }
+
+    fn get_itunes_image(extensions: &ExtensionMap) -> Option<String> {
+        Self::get_itunes_extension(extensions, "image")
+            .and_then(|image| image.attrs().get("href").cloned())
+    }
+}
+
+impl FeedFormat for AtomFormat {
+    fn name(&self) -> &'static str {
+        "Atom"
+    }
+
+    fn detect(&self, document: &str, _: Option<&str>) -> bool {
+        get_root_element(document).map(|root_element| root_element == "feed").unwrap_or(false)
+    }
+
+    fn parse(&self, document: &str) -> Result<Feed, String> {
+        let atom_feed = atom_syndication::Feed::read_from(escape_xml_entities(document).as_bytes())
+            .map_err(|e| e.to_string())?;
+
+        let items = atom_feed.entries()
+            .iter()
+            .map(|entry| {
+                let enclosure = entry.links()
+                    .iter()
+                    .find(|link| link.rel() == "enclosure")
+                    .map(|link| FeedEnclosure {
+                        url: link.href().to_string(),
+                        mime_type: non_empty(link.mime_type()),
+                        length: non_empty(link.length()),
+                    });
+                let description = entry.summary()
+                    .map(|summary| summary.as_str().to_string())
+                    .or(entry.content().and_then(|content| content.value().map(|value| value
+                        .to_string())));
+                FeedItem {
+                    title: non_empty(Some(entry.title().as_str())),
+                    guid: non_empty(Some(entry.id())),
+                    description,
+                    pub_date: Some(entry.published().unwrap_or(entry.updated()).to_rfc2822()),
+                    enclosure,
+                    image_url: Self::get_itunes_image(entry.extensions()),
+                    duration: Self::get_itunes_extension(entry.extensions(), "duration")
+                        .and_then(|duration| duration.value().map(|value| value.to_string())),
+                    season: parse_number(Self::get_itunes_extension(entry.extensions(), "season")
+                        .and_then(|season| season.value())),
+                    episode_number: parse_number(Self::get_itunes_extension(entry.extensions(),
+                                                                            "episode")
+                        .and_then(|episode| episode.value())),
+                    chapters_url: entry.extensions().get(PODCAST_NAMESPACE)
+                        .and_then(|podcast_extensions| podcast_extensions.get("chapters"))
+                        .and_then(|values| values.first())
+                        .and_then(|chapters| non_empty(chapters.attrs().get("url")
+                            .map(|url| url.as_str()))),
+                }
+            })
+            .collect();
+
+        Ok(Feed {
+            title: atom_feed.title().as_str().to_string(),
+            description: atom_feed.subtitle()
+                .map(|subtitle| subtitle.as_str().to_string())
+                .unwrap_or_default(),
+            language: atom_feed.lang().map(|lang| lang.to_string()),
+            last_build_date: Some(atom_feed.updated().to_rfc2822()),
+            author: atom_feed.authors().first().map(|author| author.name().to_string()),
+            keywords: atom_feed.categories()
+                .iter()
+                .map(|category| category.label().unwrap_or(category.term()).to_string())
+                .collect(),
+            image_url: non_empty(atom_feed.logo())
+                .or(Self::get_itunes_image(atom_feed.extensions()))
+                .or(non_empty(atom_feed.icon())),
+            new_feed_url: Self::get_itunes_extension(atom_feed.extensions(), "new-feed-url")
+                .and_then(|new_feed_url| non_empty(new_feed_url.value())),
+            next_page_url: get_next_page_url(atom_feed.links()),
+            items,
+        })
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct JsonFeedDocument {
+    title: String,
+    description: Option<String>,
+    icon: Option<String>,
+    favicon: Option<String>,
+    language: Option<String>,
+    next_url: Option<String>,
+    // JSON Feed 1.0 only knows a single author
+    author: Option<JsonFeedAuthor>,
+    authors: Vec<JsonFeedAuthor>,
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct JsonFeedAuthor {
+    name: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct JsonFeedItem {
+    // Ids are strings by specification but numbers are common
+    id: Option<serde_json::Value>,
+    title: Option<String>,
+    content_html: Option<String>,
+    content_text: Option<String>,
+    summary: Option<String>,
+    image: Option<String>,
+    date_published: Option<String>,
+    date_modified: Option<String>,
+    attachments: Vec<JsonFeedAttachment>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct JsonFeedAttachment {
+    url: String,
+    mime_type: Option<String>,
+    size_in_bytes: Option<u64>,
+    duration_in_seconds: Option<f64>,
+}
+
+impl FeedFormat for JsonFeedFormat {
+    fn name(&self) -> &'static str {
+        "JSON"
+    }
+
+    fn detect(&self, document: &str, content_type: Option<&str>) -> bool {
+        document.trim_start().starts_with('{') || content_type
+            .map(|content_type| content_type.contains("json"))
+            .unwrap_or(false)
+    }
+
+    fn parse(&self, document: &str) -> Result<Feed, String> {
+        let json_feed = serde_json::from_str::<JsonFeedDocument>(document.trim_start())
+            .map_err(|e| e.to_string())?;
+
+        let items = json_feed.items
+            .into_iter()
+            .map(|item| {
+                let attachment = item.attachments.into_iter().next();
+                FeedItem {
+                    title: item.title,
+                    guid: item.id.map(|id| match id {
+                        serde_json::Value::String(id) => id,
+                        id => id.to_string()
+                    }),
+                    description: item.content_html.or(item.content_text).or(item.summary),
+                    pub_date: item.date_published.or(item.date_modified),
+                    duration: attachment.as_ref()
+                        .and_then(|attachment| attachment.duration_in_seconds)
+                        .map(|duration| (duration.round() as u64).to_string()),
+                    enclosure: attachment.map(|attachment| FeedEnclosure {
+                        url: attachment.url,
+                        mime_type: attachment.mime_type,
+                        length: attachment.size_in_bytes.map(|size| size.to_string()),
+                    }),
+                    image_url: item.image,
+                    // JSON Feed has no season or episode numbers
+                    ..FeedItem::default()
+                }
+            })
+            .collect();
+
+        Ok(Feed {
+            title: json_feed.title,
+            description: json_feed.description.unwrap_or_default(),
+            language: json_feed.language,
+            last_build_date: None,
+            author: json_feed.authors
+                .into_iter()
+                .chain(json_feed.author)
+                .find_map(|author| author.name),
+            keywords: Vec::new(),
+            image_url: json_feed.icon.or(json_feed.favicon),
+            new_feed_url: None,
+            next_page_url: json_feed.next_url,
+            items,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::feed_format::{get_root_element, parse_feed};
+
+    const RSS_FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- Generated by a podcast host -->
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom"
+     xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd"
+     xmlns:podcast="https://podcastindex.org/namespace/1.0">
+  <channel>
+    <title>RSS Podcast</title>
+    <description>A podcast &amp; more</description>
+    <atom:link rel="self" href="https://example.com/feed.xml"/>
+    <atom:link rel="next" href="https://example.com/feed.xml?page=2"/>
+    <itunes:author>Host</itunes:author>
+    <item>
+      <title>Episode 1</title>
+      <guid>episode-1</guid>
+      <pubDate>Tue, 01 Aug 2023 10:00:00 +0000</pubDate>
+      <enclosure url="https://example.com/episode-1.mp3" type="audio/mpeg" length="1234"/>
+      <itunes:duration>01:02:03</itunes:duration>
+      <itunes:season>2</itunes:season>
+      <itunes:episode>-1</itunes:episode>
+      <podcast:chapters url="https://example.com/episode-1.json" type="application/json+chapters"/>
+    </item>
+  </channel>
+</rss>"#;
+
+    const ATOM_FEED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Atom Podcast</title>
+  <id>urn:uuid:60a76c80-d399-11d9-b93C-0003939e0af6</id>
+  <updated>2023-08-02T10:00:00Z</updated>
+  <author><name>Host</name></author>
+  <link rel="prev-archive" href="https://example.com/archive/2023-07.xml"/>
+  <entry>
+    <title>Episode 1</title>
+    <id>urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a</id>
+    <updated>2023-08-01T10:00:00Z</updated>
+    <summary>The first episode</summary>
+    <link rel="alternate" href="https://example.com/episode-1"/>
+    <link rel="enclosure" href="https://example.com/episode-1.m4a" type="audio/mp4"
+          length="5678"/>
+  </entry>
+</feed>"#;
+
+    const JSON_FEED: &str = r#"
+    {
+      "version": "https://jsonfeed.org/version/1.1",
+      "title": "JSON Podcast",
+      "next_url": "https://example.com/feed.json?page=2",
+      "authors": [{"name": "Host"}],
+      "items": [
+        {
+          "id": 1,
+          "title": "Episode 1",
+          "content_html": "<p>The first episode</p>",
+          "date_published": "2023-08-01T10:00:00Z",
+          "attachments": [
+            {"url": "https://example.com/episode-1.mp3", "mime_type": "audio/mpeg",
+             "size_in_bytes": 1234, "duration_in_seconds": 61.6},
+            {"url": "https://example.com/episode-1.ogg", "mime_type": "audio/ogg"}
+          ]
+        }
+      ]
+    }"#;
+
+    #[test]
+    fn test_get_root_element() {
+        assert_eq!(get_root_element(RSS_FEED).unwrap(), "rss");
+        assert_eq!(get_root_element(ATOM_FEED).unwrap(), "feed");
+        assert_eq!(get_root_element("<?xml version=\"1.0\"?><atom:feed xmlns:atom=\"\
+        http://www.w3.org/2005/Atom\"/>").unwrap(), "feed");
+        assert!(get_root_element("{\"title\": \"JSON\"}").is_none());
+    }
+
+    #[test]
+    fn test_parse_rss_feed() {
+        let feed = parse_feed(RSS_FEED.as_bytes(), Some("application/rss+xml")).unwrap();
+        assert_eq!(feed.title, "RSS Podcast");
+        assert_eq!(feed.description, "A podcast & more");
+        assert_eq!(feed.author.as_deref(), Some("Host"));
+        assert_eq!(feed.next_page_url.as_deref(), Some("https://example.com/feed.xml?page=2"));
+
+        let item = &feed.items[0];
+        assert_eq!(item.guid.as_deref(), Some("episode-1"));
+        let enclosure = item.enclosure.as_ref().unwrap();
+        assert_eq!(enclosure.url, "https://example.com/episode-1.mp3");
+        assert_eq!(enclosure.mime_type.as_deref(), Some("audio/mpeg"));
+        assert_eq!(enclosure.length.as_deref(), Some("1234"));
+        assert_eq!(item.duration.as_deref(), Some("01:02:03"));
+        assert_eq!(item.season, Some(2));
+        assert_eq!(item.episode_number, None);
+        assert_eq!(item.chapters_url.as_deref(), Some("https://example.com/episode-1.json"));
+    }
+
+    #[test]
+    fn test_parse_atom_feed() {
+        let feed = parse_feed(ATOM_FEED.as_bytes(), None).unwrap();
+        assert_eq!(feed.title, "Atom Podcast");
+        assert_eq!(feed.author.as_deref(), Some("Host"));
+        assert_eq!(feed.next_page_url.as_deref(),
+                   Some("https://example.com/archive/2023-07.xml"));
+
+        let item = &feed.items[0];
+        assert_eq!(item.guid.as_deref(), Some("urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a"));
+        assert_eq!(item.description.as_deref(), Some("The first episode"));
+        assert_eq!(item.pub_date.as_deref(), Some("Tue, 01 Aug 2023 10:00:00 +0000"));
+        let enclosure = item.enclosure.as_ref().unwrap();
+        assert_eq!(enclosure.url, "https://example.com/episode-1.m4a");
+        assert_eq!(enclosure.mime_type.as_deref(), Some("audio/mp4"));
+        assert_eq!(enclosure.length.as_deref(), Some("5678"));
+    }
+
+    #[test]
+    fn test_parse_json_feed() {
+        let feed = parse_feed(JSON_FEED.as_bytes(), Some("application/feed+json")).unwrap();
+        assert_eq!(feed.title, "JSON Podcast");
+        assert_eq!(feed.author.as_deref(), Some("Host"));
+        assert_eq!(feed.next_page_url.as_deref(), Some("https://example.com/feed.json?page=2"));
+
+        let item = &feed.items[0];
+        assert_eq!(item.guid.as_deref(), Some("1"));
+        assert_eq!(item.description.as_deref(), Some("<p>The first episode</p>"));
+        assert_eq!(item.duration.as_deref(), Some("62"));
+        // Only the first attachment is the enclosure
+        let enclosure = item.enclosure.as_ref().unwrap();
+        assert_eq!(enclosure.url, "https://example.com/episode-1.mp3");
+        assert_eq!(enclosure.mime_type.as_deref(), Some("audio/mpeg"));
+        assert_eq!(enclosure.length.as_deref(), Some("1234"));
+    }
+
+    #[test]
+    fn test_parse_feed_prefers_paged_over_archived_feeds() {
+        let feed = parse_feed(ATOM_FEED.replace("<link rel=\"prev-archive\"",
+                                                "<link rel=\"next\" href=\"https://example.com/\
+                                                page/2\"/><link rel=\"prev-archive\"")
+                                  .as_bytes(), None).unwrap();
+        assert_eq!(feed.next_page_url.as_deref(), Some("https://example.com/page/2"));
+    }
+
+    #[test]
+    fn test_parse_feed_reports_the_format() {
+        assert!(parse_feed(b"{\"items\": 1}", None).unwrap_err().starts_with("Error parsing JSON"));
+        assert!(parse_feed(b"<rss><channel>", None).unwrap_err().starts_with("Error parsing RSS"));
+        assert!(parse_feed(b"not a feed", None).unwrap_err().starts_with("Error parsing RSS"));
+    }
+}
@@ -2,36 +2,41 @@ use std::collections::HashMap;
 
 use crate::models::podcasts::Podcast;
 use crate::service::podcast_episode_service::PodcastEpisodeService;
-use reqwest::{Client, ClientBuilder};
-use std::io::{Error, Write};
+use reqwest::header::{CONTENT_TYPE, HeaderMap};
+use std::io::Error;
+use actix_web::web;
 
-use std::path::Path;
 use std::str::FromStr;
 
 use crate::models::podcast_episode::PodcastEpisode;
 use regex::Regex;
 use crate::config::dbconfig::establish_connection;
 
+use chrono::DateTime;
+use unicode_normalization::UnicodeNormalization;
+use crate::constants::constants::{DEFAULT_IMAGE_SUFFIX, MAX_DIRECTORY_NAME_LENGTH};
 use crate::controllers::settings_controller::ReplacementStrategy;
 use crate::DbConnection;
 use crate::models::settings::Setting;
+use crate::service::credential_service::CredentialScope;
+use crate::service::http_client::HttpClientFactory;
+use crate::service::outbound_policy::OutboundPolicy;
 use crate::service::path_service::PathService;
 use crate::service::settings_service::SettingsService;
+use crate::service::storage::StorageService;
+use crate::utils::path_template::{PathTemplate, TemplateValue, TemplateVariableKind};
 
 #[derive(Clone)]
 pub struct FileService {
-    pub client: Client,
 }
 
 impl FileService {
     pub fn new() -> Self {
         FileService {
-            client: ClientBuilder::new().build().unwrap(),
         }
     }
     pub fn new_db() -> Self {
         FileService {
-            client: ClientBuilder::new().build().unwrap(),
         }
     }
     pub fn check_if_podcast_main_image_downloaded(&mut self, podcast_id: &str, conn: &mut
@@ -41,32 +46,35 @@ impl FileService {
         match podcast {
             Some(podcast) => {
                 if !podcast.image_url.contains("http") {
-                    return Path::new(&podcast.image_url).exists();
+                    return StorageService::get_backend().exists(&podcast.image_url)
+                        .unwrap_or(false);
                 }
             }
             None => {
                 return false;
             }
         }
-        return false;
+        false
     }
 
     pub fn create_podcast_root_directory_exists() ->Result<(), Error> {
-        if !Path::new("podcasts").exists() {
-            return std::fs::create_dir("podcasts")
+        let storage = StorageService::get_backend();
+        if !storage.exists("")? {
+            return storage.create_dir_all("")
         }
 
         Ok(())
     }
 
-    pub fn create_podcast_directory_exists(podcast_title: &str, podcast_id: &String, conn:&mut DbConnection)
+    pub fn create_podcast_directory_exists(podcast_title: &str, podcast_id: &str, conn:&mut DbConnection)
         ->Result<String,
         Error> {
-        let escaped_title = prepare_podcast_title_to_directory(podcast_title,conn);
-        if !Path::new(&format!("podcasts/{}", escaped_title)).exists() {
-            std::fs::create_dir(&format!("podcasts/{}", escaped_title))
-                .expect(&*("Error creating directory when inserting ".to_owned() + &escaped_title));
-            Ok(format!("podcasts/{}", escaped_title))
+        let escaped_title = prepare_podcast_title_to_directory(podcast_title, podcast_id, conn);
+        let storage = StorageService::get_backend();
+        if !storage.exists(&escaped_title)? {
+            storage.create_dir_all(&escaped_title)
+                .unwrap_or_else(|_| panic!("{}", ("Error creating directory when inserting ".to_owned() + &escaped_title)));
+            Ok(escaped_title)
         }
         else{
             // Check if this is a new podcast with the same name as an old one
@@ -76,58 +84,141 @@ impl FileService {
             match podcast {
                 Some(_)=>{
                     // is the same podcast
-                    Ok(format!("podcasts/{}", escaped_title))
+                    Ok(escaped_title)
                 }
                 None=>{
                     // has not been inserted into the database yet
                     let mut i = 1;
-                    while Path::new(&format!("podcasts/{}-{}", escaped_title, i)).exists() {
+                    while storage.exists(&format!("{}-{}", escaped_title, i))? {
                         i += 1;
                     }
                     // This is save to insert because this directory does not exist
-                    std::fs::create_dir(&format!("podcasts/{}-{}", escaped_title, i))
+                    storage.create_dir_all(&format!("{}-{}", escaped_title, i))
                         .expect("Error creating directory");
-                    Ok(format!("podcasts/{}-{}", escaped_title, i))
+                    Ok(format!("{}-{}", escaped_title, i))
                 }
             }
         }
     }
 
-    pub async fn download_podcast_image(&self, podcast_path: &str, image_url: &str, podcast_id: &str, conn: &mut DbConnection) {
+    pub async fn download_podcast_image(&self, podcast_path: &str, image_url: &str, podcast_id: &str,
+                                        credential_scope: CredentialScope,
+                                        conn: &mut DbConnection) {
 
-        let image_response = self.client.get(image_url).send().await.unwrap();
-        let image_suffix = PodcastEpisodeService::get_url_file_suffix(image_url);
+        let outbound_policy = OutboundPolicy::new();
+        if let Err(e) = outbound_policy.check_url(image_url) {
+            log::error!("Not downloading the image of podcast {}: {}", podcast_path, e);
+            return;
+        }
+        let image_response = match HttpClientFactory::new()
+            .get_with_credentials_async(image_url, &HeaderMap::new(), &credential_scope).await {
+            Ok(image_response) => image_response,
+            Err(e) => {
+                log::error!("Error requesting the image of podcast {}: {}", podcast_path, e);
+                return;
+            }
+        };
+        let content_type = image_response.headers().get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map(|content_type| content_type.to_string());
+        let image_suffix = PodcastEpisodeService::get_file_suffix(image_url,
+                                                                  content_type.as_deref(),
+                                                                  DEFAULT_IMAGE_SUFFIX);
         let file_path = PathService::get_image_podcast_path_with_podcast_prefix(podcast_path, &image_suffix);
-        let mut image_out = std::fs::File::create(file_path.clone()).unwrap();
-        let bytes = image_response.bytes().await.unwrap();
-        image_out.write_all(&bytes).unwrap();
+        let bytes = match outbound_policy.read_response_async(image_response).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::error!("Error downloading the image of podcast {}: {}", podcast_path, e);
+                return;
+            }
+        };
+        let image_path = file_path.clone();
+        match web::block(move || StorageService::get_backend()
+            .write(&image_path, &mut bytes.as_slice())).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => {
+                log::error!("Error saving the image of podcast {}: {}", podcast_path, e);
+                return;
+            }
+            Err(e) => {
+                log::error!("Error saving the image of podcast {}: {}", podcast_path, e);
+                return;
+            }
+        }
         PodcastEpisode::update_podcast_image(podcast_id, &file_path, conn).unwrap();
     }
 
-    pub fn cleanup_old_episode(podcast: Podcast, episode: PodcastEpisode) -> std::io::Result<()> {
+    /// Deletes the directory of the episode with the audio file and its image
+    pub fn cleanup_old_episode(episode: PodcastEpisode, podcast: &Podcast) -> std::io::Result<()> {
         log::info!("Cleaning up old episode: {}", episode.episode_id);
-        std::fs::remove_dir_all(&format!(
-            "podcasts/{}/{}",
-            podcast.directory_id, episode.episode_id
-        ))
+        let episode_directory = match episode.local_url.rsplit_once('/') {
+            Some((episode_directory, _)) => episode_directory,
+            None => return Ok(())
+        };
+        let podcast_directory = podcast.directory_name.trim_end_matches('/');
+        // The episode directory must lie inside the podcast directory. Otherwise deleting it
+        // would take the other episodes of the podcast with it.
+        if episode_directory == podcast_directory
+            || !episode_directory.starts_with(&format!("{}/", podcast_directory)) {
+            return Err(Error::other(format!("Refusing to delete {} as it is not an episode \
+            directory of {}", episode_directory, podcast_directory)));
+        }
+        StorageService::get_backend().delete_dir(episode_directory)
     }
 
     pub fn delete_podcast_files(podcast_dir: &str){
-        std::fs::remove_dir_all(podcast_dir).expect("Error deleting podcast directory");
+        StorageService::get_backend().delete_dir(podcast_dir)
+            .expect("Error deleting podcast directory");
     }
 }
 
 
-pub fn prepare_podcast_title_to_directory(title: &str, conn:&mut DbConnection) ->String {
+const PODCAST_FORMAT_VARIABLES: [(&str, TemplateVariableKind); 2] = [
+    ("podcasttitle", TemplateVariableKind::Text),
+    ("podcastid", TemplateVariableKind::Text)
+];
+
+const EPISODE_FORMAT_VARIABLES: [(&str, TemplateVariableKind); 9] = [
+    ("episodetitle", TemplateVariableKind::Text),
+    ("podcasttitle", TemplateVariableKind::Text),
+    ("podcastid", TemplateVariableKind::Text),
+    ("date", TemplateVariableKind::Date),
+    ("season", TemplateVariableKind::Number),
+    ("episode", TemplateVariableKind::Number),
+    ("guid", TemplateVariableKind::Text),
+    ("author", TemplateVariableKind::Text),
+    ("filename", TemplateVariableKind::Text)
+];
+
+pub fn parse_podcast_format(format: &str) -> Result<PathTemplate, String> {
+    PathTemplate::parse(format, &PODCAST_FORMAT_VARIABLES, "podcasttitle")
+}
+
+pub fn parse_episode_format(format: &str) -> Result<PathTemplate, String> {
+    PathTemplate::parse(format, &EPISODE_FORMAT_VARIABLES, "episodetitle")
+}
+
+pub fn prepare_podcast_title_to_directory(title: &str, podcast_id: &str, conn:&mut DbConnection)
+    ->String {
     let mut settings_service = SettingsService::new();
     let retrieved_settings = settings_service.get_settings(conn).unwrap();
-    let final_string = perform_replacement(title, retrieved_settings.clone());
-
-    let fixed_string = retrieved_settings.podcast_format.replace("{}","{podcasttitle}");
+    render_podcast_directory(title, podcast_id, &retrieved_settings)
+}
 
-    let mut vars:HashMap<String,&str> = HashMap::new();
-    vars.insert("podcasttitle".to_string(), &final_string);
-    strfmt::strfmt(&fixed_string, &vars).unwrap()
+/// The directory name of the podcast format in the settings. It isn't checked whether another
+/// podcast already uses it.
+pub fn render_podcast_directory(title: &str, podcast_id: &str, settings: &Setting) -> String {
+    let podcast_format = parse_podcast_format(&settings.podcast_format)
+        .unwrap_or_else(|e| {
+            log::error!("Using the podcast title, the podcast format is invalid: {}", e);
+            parse_podcast_format("{}").unwrap()
+        });
+    let values = HashMap::from([
+        ("podcasttitle", TemplateValue::Text(title.to_string())),
+        ("podcastid", TemplateValue::Text(podcast_id.to_string()))
+    ]);
+    let directory_name = podcast_format.render(&values, &|value| sanitize_name(value, settings));
+    normalize_directory_name(&directory_name, podcast_id)
 }
 
 pub fn prepare_podcast_episode_title_to_directory(podcast_episode: PodcastEpisode, conn: &mut
@@ -135,27 +226,54 @@ DbConnection)
     ->String {
     let mut settings_service = SettingsService::new();
     let retrieved_settings = settings_service.get_settings(conn).unwrap();
-    if retrieved_settings.use_existing_filename{
-        let res_of_filename = get_filename_of_url(&podcast_episode.url);
-        if res_of_filename.is_ok(){
-                return res_of_filename.unwrap();
+    let podcast = Podcast::get_podcast(conn, podcast_episode.podcast_id).unwrap_or_default();
+    render_episode_directory(&podcast_episode, &podcast, &retrieved_settings)
+}
+
+/// The directory name of the episode format in the settings in single quotes, unless the file
+/// name of the url is used. It isn't checked whether another episode already uses it.
+pub fn render_episode_directory(podcast_episode: &PodcastEpisode, podcast: &Podcast,
+                                settings: &Setting) -> String {
+    let filename = get_filename_of_url(&podcast_episode.url).ok();
+    if settings.use_existing_filename {
+        if let Some(filename) = &filename {
+            return normalize_directory_name(&sanitize_name(filename, settings),
+                                            &podcast_episode.episode_id);
         }
     }
-    let final_string = perform_replacement(&podcast_episode.name,
-                                           retrieved_settings.clone())
-        .replace(|c: char| !c.is_ascii(), "");
-
-    let fixed_string = retrieved_settings.episode_format.replace("{}", "{episodetitle}")
-        .chars()
-        .filter(|&c| c as u32!= 44)
-        .collect::<String>();
-    let mut vars:HashMap<String, &str> = HashMap::new();
-    vars.insert("episodetitle".to_string(), &final_string);
-
-    format!("'{}'",strfmt::strfmt(&fixed_string, &vars).unwrap())
+    let episode_format = parse_episode_format(&settings.episode_format)
+        .unwrap_or_else(|e| {
+            log::error!("Using the episode title, the episode format is invalid: {}", e);
+            parse_episode_format("{}").unwrap()
+        });
+
+    let mut values = HashMap::from([
+        ("episodetitle", TemplateValue::Text(podcast_episode.name.clone())),
+        ("podcasttitle", TemplateValue::Text(podcast.name.clone())),
+        ("podcastid", TemplateValue::Text(podcast.directory_id.clone())),
+        ("guid", TemplateValue::Text(podcast_episode.guid.clone()))
+    ]);
+    if let Some(author) = &podcast.author {
+        values.insert("author", TemplateValue::Text(author.clone()));
+    }
+    if let Ok(date) = DateTime::parse_from_rfc3339(&podcast_episode.date_of_recording) {
+        values.insert("date", TemplateValue::Date(date));
+    }
+    if let Some(season) = podcast_episode.season {
+        values.insert("season", TemplateValue::Number(season));
+    }
+    if let Some(episode_number) = podcast_episode.episode_number {
+        values.insert("episode", TemplateValue::Number(episode_number));
+    }
+    if let Some(filename) = filename {
+        values.insert("filename", TemplateValue::Text(filename));
+    }
+    let directory_name = episode_format.render(&values, &|value| sanitize_name(value, settings));
+    // Existing libraries keep the quoted directory names of earlier versions
+    format!("'{}'", normalize_directory_name(&directory_name, &podcast_episode.episode_id))
 }
 
-fn perform_replacement(title: &str, retrieved_settings:Setting) -> String {
+fn perform_replacement(title: &str, retrieved_settings: &Setting) -> String {
     let mut final_string: String = title.to_string();
 
 
@@ -177,7 +295,37 @@ fn perform_replacement(title: &str, retrieved_settings:Setting) -> String {
             final_string = final_string.replace(":", "-")
         }
     }
-    deunicode::deunicode(&final_string)
+    final_string
+}
+
+/// Values of the formats never contain path separators or control characters, whatever the
+/// settings are
+fn sanitize_name(name: &str, settings: &Setting) -> String {
+    perform_replacement(name, settings)
+        .chars()
+        .filter(|c| !c.is_control())
+        .map(|c| match c {
+            '/' | '\\' => '-',
+            c => c
+        })
+        .collect()
+}
+
+/// Unicode is kept but normalized, the same title always results in the same bytes. Names
+/// that can't be used as a directory fall back to the id.
+fn normalize_directory_name(name: &str, fallback: &str) -> String {
+    let mut normalized_name = String::new();
+    for c in name.nfc() {
+        if normalized_name.len() + c.len_utf8() > MAX_DIRECTORY_NAME_LENGTH {
+            break;
+        }
+        normalized_name.push(c);
+    }
+    // Windows doesn't allow trailing dots and spaces, "." and ".." aren't names at all
+    match normalized_name.trim().trim_end_matches(['.', ' ']) {
+        "" => fallback.to_string(),
+        normalized_name => normalized_name.to_string()
+    }
 }
 
 
@@ -189,34 +337,36 @@ PodcastEpisode, image_suffix: &str, suffix: &str, settings:Setting, conn:&mut Db
                                                                                      String){
     let image_save_path;
     let podcast_save_path;
-    if podcast_episode.local_image_url.trim().len()==0 {
+    if podcast_episode.local_image_url.trim().is_empty() {
         if settings.use_existing_filename {
             let podcast_file_name = get_filename_of_url(&podcast_episode.url);
-            if podcast_file_name.is_err(){
-                // Just insert the name of the episode
-                image_save_path = PathService::get_image_path(
-                    &podcast.clone().directory_name,
-                    Some(podcast_episode.clone()),
-                    &image_suffix,
-                    &podcast_episode.name,
-                    conn
-                );
-            }
-            else {
+            match podcast_file_name {
                 // Insert the filename as requested by user
-                image_save_path = PathService::get_image_path(
-                    &podcast.clone().directory_name,
-                    None,
-                    &image_suffix,
-                    &podcast_file_name.unwrap(),
-                    conn
-                );
+                Ok(podcast_file_name) => {
+                    image_save_path = PathService::get_image_path(
+                        &podcast.clone().directory_name,
+                        None,
+                        image_suffix,
+                        &podcast_file_name,
+                        conn
+                    );
+                }
+                // Just insert the name of the episode
+                Err(_) => {
+                    image_save_path = PathService::get_image_path(
+                        &podcast.clone().directory_name,
+                        Some(podcast_episode.clone()),
+                        image_suffix,
+                        &podcast_episode.name,
+                        conn
+                    );
+                }
             }
         } else {
             image_save_path = PathService::get_image_path(
                 &podcast.clone().directory_name,
                 Some(podcast_episode.clone()),
-                &image_suffix,
+                image_suffix,
                 &podcast_episode.name
                     ,conn
             );
@@ -226,34 +376,36 @@ PodcastEpisode, image_suffix: &str, suffix: &str, settings:Setting, conn:&mut Db
         image_save_path = podcast_episode.clone().local_image_url
     }
 
-    if podcast_episode.local_url.trim().len()==0{
+    if podcast_episode.local_url.trim().is_empty(){
         if settings.use_existing_filename {
             let podcast_file_name = get_filename_of_url(&podcast_episode.url);
 
-            if podcast_file_name.is_err(){
-                podcast_save_path = PathService::get_podcast_episode_path(
-                    &podcast.directory_name.clone(),
-                    Some(podcast_episode),
-                    &suffix, &podcast_file_name.unwrap(),conn);
-            }
-            else{
-                podcast_save_path = PathService::get_podcast_episode_path(
-                    &podcast.directory_name.clone(),
-                    None,
-                    &suffix, &podcast_file_name.unwrap(),conn);
+            match podcast_file_name {
+                Ok(podcast_file_name) => {
+                    podcast_save_path = PathService::get_podcast_episode_path(
+                        &podcast.directory_name.clone(),
+                        None,
+                        suffix, &podcast_file_name,conn);
+                }
+                Err(_) => {
+                    podcast_save_path = PathService::get_podcast_episode_path(
+                        &podcast.directory_name.clone(),
+                        Some(podcast_episode.clone()),
+                        suffix, &podcast_episode.name,conn);
+                }
             }
         }
         else{
             podcast_save_path = PathService::get_podcast_episode_path(
                 &podcast.directory_name.clone(),
                 Some(podcast_episode.clone()),
-                &suffix, &podcast_episode.name,conn);
+                suffix, &podcast_episode.name,conn);
         }
     }
     else{
         podcast_save_path = podcast_episode.clone().local_url;
     }
-    return (image_save_path, podcast_save_path)
+    (image_save_path, podcast_save_path)
 }
 
 
@@ -265,7 +417,7 @@ fn get_filename_of_url(url: &str) -> Result<String,String> {
 
         return Ok(dir_name)
     }
-    return Err("Could not get filename".to_string());
+    Err("Could not get filename".to_string())
 }
 
 fn remove_extension(filename: &str) -> &str {
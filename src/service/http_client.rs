@@ -0,0 +1,309 @@
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+use reqwest::{Certificate, Client, ClientBuilder, Method, Proxy, Response, StatusCode};
+use reqwest::blocking::{Client as SyncClient, ClientBuilder as SyncClientBuilder,
+                        Response as SyncResponse};
+use reqwest::header::{HeaderMap, LOCATION};
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use crate::models::podcasts::Podcast;
+use crate::service::credential_service::{CredentialScope, CredentialService};
+use crate::service::environment_service::{EnvironmentService, HttpClientConfig};
+use crate::service::outbound_policy::{OutboundPolicy, OutboundResolver};
+
+/// Creates every http client PodFetch uses for outbound requests so that the user agent, proxy,
+/// timeouts, redirect limit, trusted certificates and ip version are configured in one place.
+pub struct HttpClientFactory {
+    config: HttpClientConfig
+}
+
+impl HttpClientFactory {
+    pub fn new() -> Self {
+        HttpClientFactory {
+            config: EnvironmentService::new().http_client_config
+        }
+    }
+
+    /// Uses the proxy configured for the podcast instead of the global one.
+    pub fn for_podcast(podcast: &Podcast) -> Self {
+        let mut http_client_factory = Self::new();
+        if podcast.proxy_url.is_some() {
+            http_client_factory.config.proxy_url = podcast.proxy_url.clone();
+        }
+        http_client_factory
+    }
+
+    /// Checks that the proxy and the certificates can be used.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(proxy_url) = &self.config.proxy_url {
+            Self::validate_proxy_url(proxy_url)?;
+        }
+        self.get_certificates().map(|_| ())
+    }
+
+    pub fn validate_proxy_url(proxy_url: &str) -> Result<(), String> {
+        Proxy::all(proxy_url)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid proxy {}: {}", CredentialService::redact_url(proxy_url),
+                                 e))
+    }
+
+    /// Builder for blocking clients. The read timeout applies to every single read so that long
+    /// downloads aren't aborted.
+    pub fn sync_client_builder(&self) -> SyncClientBuilder {
+        SyncClientBuilder::from(self.async_client_builder())
+            .timeout(Duration::from_secs(self.config.read_timeout))
+    }
+
+    pub fn build_sync_client(&self) -> SyncClient {
+        self.sync_client_builder().build().unwrap()
+    }
+
+    /// Blocking client for the object store of the storage backend
+    pub fn build_sync_object_store_client(&self) -> reqwest::Result<SyncClient> {
+        SyncClientBuilder::from(self.object_store_client_builder())
+            .timeout(Duration::from_secs(self.config.read_timeout))
+            .build()
+    }
+
+    /// Client for requests whose whole response is read at once. The read timeout limits the
+    /// complete request.
+    pub fn build_async_client(&self) -> Client {
+        self.async_client_builder()
+            .timeout(Duration::from_secs(self.config.read_timeout))
+            .build()
+            .unwrap()
+    }
+
+    /// Sends a GET request with the credentials of the scope. Redirects are followed here
+    /// instead of by the client, so that credentials are only sent to their origins and dropped
+    /// once a redirect leaves the origin of the request. Credentials in the url belong to its
+    /// origin.
+    pub fn get_with_credentials(&self, url: &str, headers: &HeaderMap,
+                                credential_scope: &CredentialScope)
+        -> Result<SyncResponse, String> {
+        let client = SyncClientBuilder::from(self.async_client_builder().redirect(Policy::none()))
+            .timeout(Duration::from_secs(self.config.read_timeout))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let outbound_policy = OutboundPolicy::from_config(&self.config);
+        let (mut url, url_scope) = Self::split_url_credentials(url)?;
+        let mut is_cross_origin = false;
+        for _ in 0..=self.config.max_redirects {
+            outbound_policy.check_url(url.as_str())?;
+            let response = client.get(url.clone())
+                .headers(Self::get_request_headers(&url, headers, credential_scope, &url_scope,
+                                                   is_cross_origin))
+                .send()
+                .map_err(|e| e.to_string())?;
+            match Self::get_redirect_url(&url, response.status(), response.headers()) {
+                Some(redirect_url) => {
+                    is_cross_origin |= redirect_url.origin() != url.origin();
+                    url = redirect_url
+                }
+                None => return Ok(response)
+            }
+        }
+        Err("Too many redirects".to_string())
+    }
+
+    /// Async variant of [`HttpClientFactory::get_with_credentials`] whose whole response is
+    /// read at once
+    pub async fn get_with_credentials_async(&self, url: &str, headers: &HeaderMap,
+                                            credential_scope: &CredentialScope)
+        -> Result<Response, String> {
+        self.send_with_credentials(Method::GET, url, headers, credential_scope,
+                                   Some(Duration::from_secs(self.config.read_timeout))).await
+    }
+
+    /// Async variant of [`HttpClientFactory::get_with_credentials`] whose response is streamed
+    /// to the user
+    pub async fn stream_with_credentials(&self, method: Method, url: &str, headers: &HeaderMap,
+                                         credential_scope: &CredentialScope)
+        -> Result<Response, String> {
+        self.send_with_credentials(method, url, headers, credential_scope, None).await
+    }
+
+    /// Client for the object store of the storage backend. Its responses are passed on to the
+    /// player as they are, so redirects aren't followed.
+    pub fn build_object_store_client(&self) -> Client {
+        self.object_store_client_builder().build().unwrap()
+    }
+
+    /// Host names of user controlled urls are resolved by the [`OutboundResolver`], so that the
+    /// addresses it checked are the ones that are connected to. It also puts the addresses of the
+    /// preferred ip version first.
+    fn async_client_builder(&self) -> ClientBuilder {
+        let mut outbound_policy = OutboundPolicy::from_config(&self.config);
+        // Connections to the proxy are resolved as well
+        for proxy_host in self.get_proxy_hosts() {
+            outbound_policy.allow_host_name(&proxy_host);
+        }
+        self.base_client_builder()
+            .redirect(self.get_redirect_policy())
+            .dns_resolver(Arc::new(OutboundResolver::new(outbound_policy,
+                                                             self.config.ip_version.clone())))
+    }
+
+    // The object store is configured by the admin and may be reachable in the local network only
+    fn object_store_client_builder(&self) -> ClientBuilder {
+        self.base_client_builder().redirect(Policy::none())
+    }
+
+    fn base_client_builder(&self) -> ClientBuilder {
+        let mut client_builder = ClientBuilder::new()
+            .user_agent(self.config.user_agent.clone())
+            .connect_timeout(Duration::from_secs(self.config.connect_timeout));
+        if let Some(proxy) = self.get_proxy() {
+            client_builder = client_builder.proxy(proxy);
+        }
+        for certificate in self.get_certificates().unwrap_or_default() {
+            client_builder = client_builder.add_root_certificate(certificate);
+        }
+        client_builder
+    }
+
+    /// Follows at most the configured number of redirects and checks the url of every hop
+    /// against the [`OutboundPolicy`]. Their addresses are checked when they are resolved.
+    pub fn get_redirect_policy(&self) -> Policy {
+        let max_redirects = self.config.max_redirects;
+        let outbound_policy = OutboundPolicy::from_config(&self.config);
+        Policy::custom(move |attempt| {
+            if attempt.previous().len() > max_redirects {
+                return attempt.error("Too many redirects");
+            }
+            match outbound_policy.check_url(attempt.url().as_str()) {
+                Ok(_) => attempt.follow(),
+                Err(e) => attempt.error(e)
+            }
+        })
+    }
+
+    // Without a configured proxy the system proxy variables (HTTP_PROXY, HTTPS_PROXY) still apply
+    fn get_proxy(&self) -> Option<Proxy> {
+        let proxy_url = self.config.proxy_url.as_ref()?;
+        match Proxy::all(proxy_url) {
+            Ok(proxy) => Some(proxy),
+            Err(e) => {
+                log::error!("Ignoring invalid proxy {}: {}", CredentialService::redact_url
+                    (proxy_url), e);
+                None
+            }
+        }
+    }
+
+    async fn send_with_credentials(&self, method: Method, url: &str, headers: &HeaderMap,
+                                   credential_scope: &CredentialScope, timeout: Option<Duration>)
+        -> Result<Response, String> {
+        let client = self.async_client_builder()
+            .redirect(Policy::none())
+            .build()
+            .map_err(|e| e.to_string())?;
+        let outbound_policy = OutboundPolicy::from_config(&self.config);
+        let (mut url, url_scope) = Self::split_url_credentials(url)?;
+        let mut is_cross_origin = false;
+        for _ in 0..=self.config.max_redirects {
+            outbound_policy.check_url(url.as_str())?;
+            let mut request = client.request(method.clone(), url.clone())
+                .headers(Self::get_request_headers(&url, headers, credential_scope, &url_scope,
+                                                   is_cross_origin));
+            if let Some(timeout) = timeout {
+                request = request.timeout(timeout);
+            }
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            match Self::get_redirect_url(&url, response.status(), response.headers()) {
+                Some(redirect_url) => {
+                    is_cross_origin |= redirect_url.origin() != url.origin();
+                    url = redirect_url
+                }
+                None => return Ok(response)
+            }
+        }
+        Err("Too many redirects".to_string())
+    }
+
+    fn split_url_credentials(url: &str) -> Result<(Url, CredentialScope), String> {
+        let (url, credentials) = CredentialService::split_credentials_from_url(url);
+        let parsed_url = Url::parse(&url).map_err(|e| format!("Invalid url: {}", e))?;
+        Ok((parsed_url, CredentialScope::new(&url, credentials)))
+    }
+
+    fn get_request_headers(url: &Url, headers: &HeaderMap, credential_scope: &CredentialScope,
+                           url_scope: &CredentialScope, is_cross_origin: bool) -> HeaderMap {
+        let mut request_headers = headers.clone();
+        if !is_cross_origin {
+            credential_scope.apply_to_headers(url, &mut request_headers);
+            url_scope.apply_to_headers(url, &mut request_headers);
+        }
+        request_headers
+    }
+
+    fn get_redirect_url(url: &Url, status: StatusCode, headers: &HeaderMap) -> Option<Url> {
+        if !status.is_redirection() || status == StatusCode::NOT_MODIFIED {
+            return None;
+        }
+        headers.get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| url.join(location).ok())
+    }
+
+    // The configured proxy or the system proxies reqwest uses without one
+    fn get_proxy_hosts(&self) -> Vec<String> {
+        let proxy_urls = match &self.config.proxy_url {
+            Some(proxy_url) => vec![proxy_url.clone()],
+            None => ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY", "http_proxy", "https_proxy",
+                "all_proxy"]
+                .iter()
+                .filter_map(|variable| std::env::var(variable).ok())
+                .collect()
+        };
+        proxy_urls.iter()
+            .filter_map(|proxy_url| Url::parse(proxy_url).ok())
+            .filter_map(|proxy_url| proxy_url.host_str().map(|host| host.to_string()))
+            .collect()
+    }
+
+    fn get_certificates(&self) -> Result<Vec<Certificate>, String> {
+        let mut certificates = Vec::new();
+        for path in &self.config.ca_certificates {
+            let pem_file = fs::read_to_string(path)
+                .map_err(|e| format!("Error reading certificate file {}: {}", path, e))?;
+            // A file may contain a whole bundle of certificates
+            for pem_certificate in pem_file.split_inclusive("-----END CERTIFICATE-----")
+                .filter(|pem_certificate| pem_certificate.contains("-----BEGIN CERTIFICATE-----")) {
+                let certificate = Certificate::from_pem(pem_certificate.trim().as_bytes())
+                    .map_err(|e| format!("Invalid certificate in {}: {}", path, e))?;
+                certificates.push(certificate);
+            }
+        }
+        Ok(certificates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::{AUTHORIZATION, HeaderMap};
+    use reqwest::Url;
+    use crate::models::podcast_credential::PodcastCredentials;
+    use crate::service::credential_service::CredentialScope;
+    use crate::service::http_client::HttpClientFactory;
+
+    #[test]
+    fn credentials_are_dropped_after_a_cross_origin_redirect() {
+        let credential_scope = CredentialScope::new("https://example.com/feed.xml",
+                                                    Some(PodcastCredentials::Bearer {
+                                                        token: "secret".to_string()
+                                                    }))
+            .with_media_urls(["https://media.example.net/episodes/1.mp3"]);
+        let get_headers = |url: &str, is_cross_origin: bool|
+            HttpClientFactory::get_request_headers(&Url::parse(url).unwrap(), &HeaderMap::new(),
+                                                   &credential_scope,
+                                                   &CredentialScope::default(), is_cross_origin);
+
+        assert!(get_headers("https://media.example.net/episodes/1.mp3", false)
+            .contains_key(AUTHORIZATION));
+        assert!(get_headers("https://media.example.net/episodes/1.mp3", true).is_empty());
+        assert!(get_headers("https://example.com/feed.xml", true).is_empty());
+    }
+}
@@ -0,0 +1,803 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use chrono::Utc;
+use fs_extra::dir::CopyOptions;
+use reqwest::header::HeaderMap;
+use crate::DbConnection;
+use crate::models::library_check::{LibraryCheckReport, LibraryIssue, LibraryIssueKind,
+                                   LibraryRepair, LibraryRepairAction, LibraryRepairFailure,
+                                   LibraryRepairResult};
+use crate::models::library_reorganization::{EpisodeReorganization, PathChange,
+                                            PodcastReorganization, ReorganizationFailure,
+                                            ReorganizationPlan, ReorganizationResult};
+use crate::models::media_path::{MediaPath, MediaPathColumn};
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcasts::Podcast;
+use crate::mutex::LockResultExt;
+use crate::service::audio_probe_service::AudioProbeService;
+use crate::service::credential_service::CredentialService;
+use crate::service::environment_service::{EnvironmentService, StorageBackendType};
+use crate::service::file_service::{prepare_podcast_episode_title_to_directory,
+                                   prepare_podcast_title_to_directory};
+use crate::service::http_client::HttpClientFactory;
+use crate::service::outbound_policy::OutboundPolicy;
+use crate::service::podcast_episode_service::PodcastEpisodeService;
+use crate::service::storage::{StorageBackend, StorageService};
+
+/// Orphans are moved here by the repair of the library, it's never checked itself
+const QUARANTINE_DIRECTORY: &str = ".quarantine";
+
+/// Downloads and the cleanup hold it for reading while they write or delete files. Moving and
+/// repairing the library holds it for writing, so that no file is written to a directory that
+/// is being moved or is taken for an orphan while it is downloaded.
+pub static LIBRARY_LOCK: RwLock<()> = RwLock::new(());
+
+/// Moving the media root to another directory
+#[derive(Debug)]
+pub struct RelocationPlan {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    /// Files and directories directly below the media root, they are moved as a whole
+    pub entries: Vec<OsString>,
+    /// Stored paths that still contain the old media root and become relative to it
+    pub path_updates: Vec<MediaPath>,
+    /// Stored files that don't exist in the media root
+    pub missing_files: Vec<String>
+}
+
+/// Maintenance of the downloaded files in the media root
+pub struct LibraryService;
+
+impl LibraryService {
+    /// Checks that the library can be moved to the new root and collects what would change
+    pub fn plan_relocation(conn: &mut DbConnection, new_root: &str)
+        -> Result<RelocationPlan, String> {
+        let storage_config = EnvironmentService::new().storage_config;
+        if storage_config.backend != StorageBackendType::Local {
+            return Err("Only libraries in the local storage can be relocated".to_string());
+        }
+        let from = PathBuf::from(&storage_config.media_root);
+        let to = PathBuf::from(new_root);
+        if !from.is_dir() {
+            return Err(format!("The media root {} doesn't exist", from.display()));
+        }
+        let absolute_from = fs::canonicalize(&from)
+            .map_err(|e| format!("Error resolving {}: {}", from.display(), e))?;
+        let absolute_to = Self::get_absolute_path(&to)?;
+        if absolute_to.starts_with(&absolute_from) || absolute_from.starts_with(&absolute_to) {
+            return Err(format!("{} and {} must not contain each other", from.display(),
+                               to.display()));
+        }
+        if to.exists() && (!to.is_dir() || fs::read_dir(&to)
+            .map_err(|e| format!("Error reading {}: {}", to.display(), e))?
+            .next()
+            .is_some()) {
+            return Err(format!("{} has to be a new or an empty directory", to.display()));
+        }
+
+        let mut entries = fs::read_dir(&from)
+            .and_then(|entries| entries
+                .map(|entry| entry.map(|entry| entry.file_name()))
+                .collect::<io::Result<Vec<_>>>())
+            .map_err(|e| format!("Error reading {}: {}", from.display(), e))?;
+        entries.sort();
+
+        // Paths of older versions start with the media root
+        let root_prefixes = [
+            format!("{}/", storage_config.media_root.trim_start_matches("./")),
+            format!("{}/", absolute_from.display())
+        ];
+        let mut path_updates = Vec::new();
+        let mut missing_files = Vec::new();
+        for media_path in MediaPath::get_media_paths(conn) {
+            let relative_path = Self::strip_media_root(&media_path.path, &root_prefixes);
+            if media_path.column != MediaPathColumn::PodcastDirectory
+                && !from.join(&relative_path).is_file() {
+                missing_files.push(relative_path.clone());
+            }
+            if relative_path != media_path.path {
+                path_updates.push(MediaPath {
+                    path: relative_path,
+                    ..media_path
+                });
+            }
+        }
+        Ok(RelocationPlan {
+            from,
+            to,
+            entries,
+            path_updates,
+            missing_files
+        })
+    }
+
+    /// Moves the files and saves the relative paths. Files that were already moved are moved
+    /// back if anything fails, so the library either is at the new root or at the old one.
+    pub fn relocate(conn: &mut DbConnection, plan: &RelocationPlan) -> Result<(), String> {
+        let _library_lock = LIBRARY_LOCK.write().ignore_poison();
+        fs::create_dir_all(&plan.to)
+            .map_err(|e| format!("Error creating {}: {}", plan.to.display(), e))?;
+        let mut moved_entries = Vec::new();
+        for entry in &plan.entries {
+            if let Err(e) = Self::move_path(&plan.from.join(entry), &plan.to.join(entry)) {
+                Self::move_back(plan, &moved_entries);
+                return Err(format!("Error moving {}: {}", entry.to_string_lossy(), e));
+            }
+            moved_entries.push(entry);
+        }
+        if let Err(e) = MediaPath::update_media_paths(conn, &plan.path_updates) {
+            Self::move_back(plan, &moved_entries);
+            return Err(format!("Error updating the stored paths: {}", e));
+        }
+        // The old media root is empty now, keeping it would only be confusing
+        if let Err(e) = fs::remove_dir(&plan.from) {
+            log::info!("Keeping the old media root {}: {}", plan.from.display(), e);
+        }
+        Ok(())
+    }
+
+    /// Compares the stored paths with the current naming settings. Only downloaded files are
+    /// included, podcasts and episodes whose paths already match are left out.
+    pub fn plan_reorganization(conn: &mut DbConnection) -> Result<ReorganizationPlan, String> {
+        let storage = StorageService::get_backend();
+        let mut podcasts = Podcast::get_all_podcasts(conn)?
+            .into_iter()
+            .filter(|podcast| !podcast.directory_name.is_empty())
+            .collect::<Vec<_>>();
+        podcasts.sort_by_key(|podcast| podcast.id);
+
+        let podcast_targets = podcasts.iter()
+            .map(|podcast| (podcast.directory_name.clone(),
+                            prepare_podcast_title_to_directory(&podcast.name,
+                                                               &podcast.directory_id, conn)))
+            .collect::<Vec<_>>();
+        let podcast_directories = Self::assign_paths(&podcast_targets, 1, storage.as_ref())?;
+
+        let mut reorganizations = Vec::new();
+        for (podcast, (directory, collision)) in podcasts.iter().zip(podcast_directories) {
+            let mut podcast_episodes = PodcastEpisode::get_episodes_by_podcast_id(podcast.id, conn)
+                .into_iter()
+                .filter(|episode| episode.is_downloaded()
+                    && Self::is_media_path(&episode.local_url)
+                    && episode.local_url.contains('/'))
+                .collect::<Vec<_>>();
+            podcast_episodes.sort_by_key(|episode| episode.id);
+            let episode_targets = podcast_episodes.iter()
+                .map(|episode| (Self::get_parent(&episode.local_url).to_string(),
+                                format!("{}/{}", directory,
+                                        prepare_podcast_episode_title_to_directory(
+                                            episode.clone(), conn))))
+                .collect::<Vec<_>>();
+            let episode_directories = Self::assign_paths(&episode_targets, 0,
+                                                         storage.as_ref())?;
+
+            let episodes = podcast_episodes.iter()
+                .zip(episode_directories)
+                .filter_map(|(episode, (episode_directory, collision))| {
+                    let current_directory = Self::get_parent(&episode.local_url);
+                    if current_directory == episode_directory {
+                        return None;
+                    }
+                    Some(EpisodeReorganization {
+                        podcast_episode_id: episode.id,
+                        name: episode.name.clone(),
+                        file: Self::move_into(&episode.local_url, &episode_directory),
+                        image: Some(&episode.local_image_url)
+                            .filter(|image| Self::is_media_path(image)
+                                && Self::get_parent(image) == current_directory)
+                            .map(|image| Self::move_into(image, &episode_directory)),
+                        directory: PathChange {
+                            from: current_directory.to_string(),
+                            to: episode_directory,
+                        },
+                        collision
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let directory_changed = directory != podcast.directory_name;
+            if !directory_changed && episodes.is_empty() {
+                continue;
+            }
+            reorganizations.push(PodcastReorganization {
+                podcast_id: podcast.id,
+                podcast_name: podcast.name.clone(),
+                image: Some(&podcast.image_url)
+                    .filter(|image| directory_changed && Self::is_media_path(image)
+                        && Self::get_parent(image) == podcast.directory_name)
+                    .map(|image| Self::move_into(image, &directory)),
+                directory: Some(PathChange {
+                    from: podcast.directory_name.clone(),
+                    to: directory
+                }).filter(|_| directory_changed),
+                collision,
+                episodes
+            });
+        }
+        Ok(ReorganizationPlan {
+            podcasts: reorganizations
+        })
+    }
+
+    /// Moves the files of the plan. The podcast is moved before its episodes, as they are moved
+    /// into its new directory. If the podcast fails, its episodes stay where they are. Every
+    /// episode is moved on its own, if one fails its files stay where they are and the others
+    /// are moved anyway.
+    pub fn reorganize(conn: &mut DbConnection, plan: &ReorganizationPlan) -> ReorganizationResult {
+        let _library_lock = LIBRARY_LOCK.write().ignore_poison();
+        let storage = StorageService::get_backend();
+        let mut result = ReorganizationResult::default();
+        for podcast in &plan.podcasts {
+            if let Some(directory) = &podcast.directory {
+                match Self::move_podcast(conn, storage.as_ref(), podcast, directory) {
+                    Ok(..) => result.moved_podcasts += 1,
+                    Err(e) => {
+                        result.failures.push(e);
+                        result.failures.extend(podcast.episodes.iter()
+                            .map(|episode| Self::reorganization_failure(
+                                &episode.name, &episode.file.from,
+                                "The directory of the podcast couldn't be moved".to_string())));
+                        continue;
+                    }
+                }
+            }
+            for episode in &podcast.episodes {
+                match Self::move_episode(conn, storage.as_ref(), episode) {
+                    Ok(..) => result.moved_episodes += 1,
+                    Err(e) => result.failures.push(e)
+                }
+            }
+
+            // Directories of files that couldn't be moved aren't empty and are kept
+            let old_directories = podcast.episodes.iter()
+                .map(|episode| &episode.directory.from)
+                .chain(podcast.directory.iter().map(|directory| &directory.from));
+            for old_directory in old_directories {
+                if let Err(e) = storage.delete_empty_dir(old_directory) {
+                    log::error!("Error deleting the empty directory {}: {}", old_directory, e);
+                }
+            }
+        }
+        result
+    }
+
+    /// Compares the downloaded episodes and images with the files in the media root. Files in
+    /// the quarantine directory aren't checked. Running downloads are waited for.
+    pub fn check_library(conn: &mut DbConnection) -> Result<LibraryCheckReport, String> {
+        // Files of running downloads aren't stored yet and would be taken for orphans
+        let _library_lock = LIBRARY_LOCK.write().ignore_poison();
+        let storage = StorageService::get_backend();
+        let quarantine_prefix = format!("{}/", QUARANTINE_DIRECTORY);
+        let files = storage.list_files("")
+            .map_err(|e| format!("Error listing the files of the media root: {}", e))?
+            .into_iter()
+            .filter(|(path, _)| !path.starts_with(&quarantine_prefix))
+            .collect::<HashMap<String, u64>>();
+        let mut podcasts = Podcast::get_all_podcasts(conn)?;
+        podcasts.sort_by_key(|podcast| podcast.id);
+        let mut episodes = PodcastEpisode::get_downloaded_episodes(conn);
+        episodes.sort_by_key(|(podcast_episode, _)| podcast_episode.id);
+
+        let mut issues = Vec::new();
+        let mut referenced_files = HashSet::new();
+        let mut needed_directories = HashSet::new();
+        for podcast in &podcasts {
+            if Self::is_media_path(&podcast.directory_name) {
+                Self::add_directories(&mut needed_directories, &podcast.directory_name);
+            }
+            if Self::is_media_path(&podcast.image_url) {
+                referenced_files.insert(podcast.image_url.as_str());
+                if !files.contains_key(&podcast.image_url) {
+                    issues.push(LibraryIssue {
+                        kind: LibraryIssueKind::MissingArtwork,
+                        path: podcast.image_url.clone(),
+                        podcast_id: Some(podcast.id),
+                        podcast_episode_id: None,
+                        name: Some(podcast.name.clone()),
+                        bytes: 0,
+                        details: None
+                    });
+                }
+            }
+        }
+
+        for (podcast_episode, podcast) in &episodes {
+            let episode_issue = |kind, bytes, details| LibraryIssue {
+                kind,
+                path: podcast_episode.local_url.clone(),
+                podcast_id: Some(podcast.id),
+                podcast_episode_id: Some(podcast_episode.id),
+                name: Some(podcast_episode.name.clone()),
+                bytes,
+                details
+            };
+            if Self::is_media_path(&podcast_episode.local_url) {
+                referenced_files.insert(podcast_episode.local_url.as_str());
+            }
+            let local_image_url = Some(&podcast_episode.local_image_url)
+                .filter(|image| Self::is_media_path(image));
+            if let Some(image) = local_image_url {
+                referenced_files.insert(image.as_str());
+            }
+            // Downloading the episode again also downloads its image
+            if let Some((kind, bytes, details)) = Self::check_episode_file(storage.as_ref(),
+                                                                          &files,
+                                                                          podcast_episode) {
+                issues.push(episode_issue(kind, bytes, details));
+            } else if let Some(image) = local_image_url
+                .filter(|image| !files.contains_key(*image)) {
+                issues.push(LibraryIssue {
+                    path: image.clone(),
+                    ..episode_issue(LibraryIssueKind::MissingArtwork, 0, None)
+                });
+            }
+        }
+        for referenced_file in &referenced_files {
+            Self::add_directories(&mut needed_directories, Self::get_parent(referenced_file));
+        }
+
+        let mut orphaned_files = files.iter()
+            .filter(|(path, _)| !referenced_files.contains(path.as_str()))
+            .collect::<Vec<_>>();
+        orphaned_files.sort();
+        let mut orphaned_directories = BTreeMap::<String, (u64, usize)>::new();
+        for (path, bytes) in orphaned_files {
+            match Self::get_orphaned_directory(path, &needed_directories) {
+                Some(directory) => {
+                    let (directory_bytes, file_count) = orphaned_directories.entry(directory)
+                        .or_default();
+                    *directory_bytes += bytes;
+                    *file_count += 1;
+                }
+                None => issues.push(Self::orphan_issue(LibraryIssueKind::OrphanedFile, path,
+                                                       *bytes, None))
+            }
+        }
+        for (directory, (bytes, file_count)) in orphaned_directories {
+            issues.push(Self::orphan_issue(LibraryIssueKind::OrphanedDirectory, &directory,
+                                           bytes, Some(format!("{} files", file_count))));
+        }
+        Ok(LibraryCheckReport {
+            checked_episodes: episodes.len(),
+            checked_files: files.len(),
+            issues
+        })
+    }
+
+    /// Fixes the issues of a check. Episodes with missing or damaged files are downloaded again
+    /// and are no longer marked as downloaded if that fails. Missing images are downloaded
+    /// again, orphans are moved to the quarantine directory or deleted. Orphans that a podcast
+    /// or an episode refers to by now are kept.
+    pub fn repair_library(conn: &mut DbConnection, report: &LibraryCheckReport,
+                          delete_orphans: bool) -> LibraryRepairResult {
+        let storage = StorageService::get_backend();
+        let episodes = PodcastEpisode::get_downloaded_episodes(conn)
+            .into_iter()
+            .map(|episode| (episode.0.id, episode))
+            .collect::<HashMap<i32, (PodcastEpisode, Podcast)>>();
+        // Every repair gets its own directory, so that orphans of earlier repairs are kept
+        let quarantine_directory = format!("{}/{}", QUARANTINE_DIRECTORY,
+                                           Utc::now().format("%Y-%m-%d-%H%M%S"));
+        let mut result = LibraryRepairResult::default();
+        // Downloads take the library lock themselves
+        let (download_issues, file_issues): (Vec<_>, Vec<_>) = report.issues.iter()
+            .partition(|issue| matches!(issue.kind, LibraryIssueKind::MissingFile
+                | LibraryIssueKind::EmptyFile | LibraryIssueKind::TruncatedFile));
+        for issue in download_issues {
+            let repaired = match issue.podcast_episode_id.and_then(|id| episodes.get(&id)) {
+                Some((podcast_episode, podcast)) => Self::download_episode_again(
+                    conn, storage.as_ref(), podcast_episode, podcast),
+                None => Err("The episode is no longer downloaded".to_string())
+            };
+            Self::add_repair(&mut result, issue, repaired);
+        }
+
+        let _library_lock = LIBRARY_LOCK.write().ignore_poison();
+        let media_paths = MediaPath::get_media_paths(conn);
+        for issue in file_issues {
+            let repaired = match issue.kind {
+                LibraryIssueKind::MissingArtwork => Self::download_artwork(
+                    conn, storage.as_ref(), issue,
+                    issue.podcast_episode_id.and_then(|id| episodes.get(&id))),
+                _ if Self::is_referenced(&issue.path, &media_paths) =>
+                    Err("The file is no longer an orphan".to_string()),
+                _ => Self::remove_orphan(storage.as_ref(), issue, &quarantine_directory,
+                                         delete_orphans)
+            };
+            Self::add_repair(&mut result, issue, repaired);
+        }
+        result
+    }
+
+    fn add_repair(result: &mut LibraryRepairResult, issue: &LibraryIssue,
+                  repaired: Result<LibraryRepairAction, String>) {
+        match repaired {
+            Ok(action) => result.repairs.push(LibraryRepair {
+                issue: issue.clone(),
+                action
+            }),
+            Err(error) => result.failures.push(LibraryRepairFailure {
+                issue: issue.clone(),
+                error
+            })
+        }
+    }
+
+    /// Whether a podcast or an episode refers to the path or to a path inside it
+    fn is_referenced(path: &str, media_paths: &[MediaPath]) -> bool {
+        let directory_prefix = format!("{}/", path);
+        media_paths.iter()
+            .any(|media_path| media_path.path == path
+                || media_path.path.starts_with(&directory_prefix))
+    }
+
+    fn check_episode_file(storage: &dyn StorageBackend, files: &HashMap<String, u64>,
+                          podcast_episode: &PodcastEpisode)
+        -> Option<(LibraryIssueKind, u64, Option<String>)> {
+        let bytes = match files.get(&podcast_episode.local_url) {
+            Some(bytes) if Self::is_media_path(&podcast_episode.local_url) => *bytes,
+            _ => return Some((LibraryIssueKind::MissingFile, 0, None))
+        };
+        if bytes == 0 {
+            return Some((LibraryIssueKind::EmptyFile, 0, None));
+        }
+        match podcast_episode.file_size {
+            Some(file_size) if bytes < file_size as u64 => Some((
+                LibraryIssueKind::TruncatedFile, bytes,
+                Some(format!("{} of {} bytes", bytes, file_size)))),
+            Some(..) => None,
+            // Older downloads have no size, their content tells whether they're complete
+            None => {
+                let local_path = storage.local_path(&podcast_episode.local_url)?;
+                let suffix = podcast_episode.local_url.rsplit_once('.')
+                    .map(|(_, suffix)| suffix)
+                    .unwrap_or_default();
+                match AudioProbeService::is_truncated(&local_path, suffix,
+                                                      podcast_episode.total_time) {
+                    Ok(true) => Some((LibraryIssueKind::TruncatedFile, bytes,
+                                      Some("The file ends before the episode".to_string()))),
+                    Ok(false) => None,
+                    Err(e) => {
+                        log::warn!("Error checking {}: {}", podcast_episode.local_url, e);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// The topmost directory of the file that no podcast or episode needs
+    fn get_orphaned_directory(path: &str, needed_directories: &HashSet<String>)
+        -> Option<String> {
+        let mut directory = String::new();
+        for segment in Self::get_parent(path).split('/').filter(|segment| !segment.is_empty()) {
+            if !directory.is_empty() {
+                directory.push('/');
+            }
+            directory.push_str(segment);
+            if !needed_directories.contains(&directory) {
+                return Some(directory);
+            }
+        }
+        None
+    }
+
+    fn add_directories(directories: &mut HashSet<String>, directory: &str) {
+        let mut directory = directory;
+        while !directory.is_empty() && directories.insert(directory.to_string()) {
+            directory = Self::get_parent(directory);
+        }
+    }
+
+    fn orphan_issue(kind: LibraryIssueKind, path: &str, bytes: u64, details: Option<String>)
+        -> LibraryIssue {
+        LibraryIssue {
+            kind,
+            path: path.to_string(),
+            podcast_id: None,
+            podcast_episode_id: None,
+            name: None,
+            bytes,
+            details
+        }
+    }
+
+    fn download_episode_again(conn: &mut DbConnection, storage: &dyn StorageBackend,
+                              podcast_episode: &PodcastEpisode, podcast: &Podcast)
+        -> Result<LibraryRepairAction, String> {
+        // The damaged file would otherwise be left behind if the new download gets another path
+        if Self::is_media_path(&podcast_episode.local_url)
+            && storage.exists(&podcast_episode.local_url).map_err(|e| e.to_string())? {
+            storage.delete_file(&podcast_episode.local_url)
+                .map_err(|e| format!("Error deleting {}: {}", podcast_episode.local_url, e))?;
+        }
+        PodcastEpisode::update_download_status_of_episode(podcast_episode.id, conn);
+        match PodcastEpisodeService::perform_download(podcast_episode, podcast_episode.clone(),
+                                                      podcast.clone(), conn) {
+            Ok(..) => Ok(LibraryRepairAction::Redownloaded),
+            Err(e) => {
+                log::warn!("Error downloading episode {} again: {}", podcast_episode.name, e);
+                let episode_directory = Self::get_parent(&podcast_episode.local_url);
+                if !episode_directory.is_empty() {
+                    if let Err(e) = storage.delete_empty_dir(episode_directory) {
+                        log::error!("Error deleting the empty directory {}: {}",
+                            episode_directory, e);
+                    }
+                }
+                Ok(LibraryRepairAction::StatusReset)
+            }
+        }
+    }
+
+    fn download_artwork(conn: &mut DbConnection, storage: &dyn StorageBackend,
+                        issue: &LibraryIssue, episode: Option<&(PodcastEpisode, Podcast)>)
+        -> Result<LibraryRepairAction, String> {
+        let (podcast, image_url) = match (issue.podcast_episode_id, episode) {
+            (Some(..), Some((podcast_episode, podcast))) => {
+                let image_url = match podcast_episode.image_url.is_empty() {
+                    true => podcast.original_image_url.clone(),
+                    false => podcast_episode.image_url.clone()
+                };
+                (podcast.clone(), image_url)
+            }
+            (Some(..), None) => return Err("The episode is no longer downloaded".to_string()),
+            (None, _) => {
+                let podcast = Podcast::get_podcast(conn, issue.podcast_id.unwrap_or_default())
+                    .map_err(|_| "The podcast no longer exists".to_string())?;
+                let image_url = podcast.original_image_url.clone();
+                (podcast, image_url)
+            }
+        };
+        if image_url.is_empty() {
+            return Err("The feed has no image".to_string());
+        }
+        let outbound_policy = OutboundPolicy::new();
+        outbound_policy.check_url(&image_url)?;
+        let credential_scope = CredentialService::get_credential_scope(conn, &podcast);
+        let response = HttpClientFactory::for_podcast(&podcast)
+            .get_with_credentials(&image_url, &HeaderMap::new(), &credential_scope)
+            .and_then(|response| response.error_for_status().map_err(|e| e.to_string()))
+            .map_err(|e| format!("Error requesting {}: {}", image_url, e))?;
+        let image = outbound_policy.read_response(response)?;
+        storage.write(&issue.path, &mut image.as_slice())
+            .map_err(|e| format!("Error saving {}: {}", issue.path, e))?;
+        Ok(LibraryRepairAction::ArtworkDownloaded)
+    }
+
+    fn remove_orphan(storage: &dyn StorageBackend, issue: &LibraryIssue,
+                     quarantine_directory: &str, delete_orphans: bool)
+        -> Result<LibraryRepairAction, String> {
+        let is_directory = issue.kind == LibraryIssueKind::OrphanedDirectory;
+        if delete_orphans {
+            match is_directory {
+                true => storage.delete_dir(&issue.path),
+                false => storage.delete_file(&issue.path)
+            }.map_err(|e| format!("Error deleting {}: {}", issue.path, e))?;
+            return Ok(LibraryRepairAction::Deleted);
+        }
+
+        let files = match is_directory {
+            true => storage.list_files(&issue.path)
+                .map_err(|e| format!("Error listing {}: {}", issue.path, e))?
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect(),
+            false => vec![issue.path.clone()]
+        };
+        let file_changes = files.iter()
+            .map(|file| PathChange {
+                from: file.clone(),
+                to: format!("{}/{}", quarantine_directory, file)
+            })
+            .collect::<Vec<_>>();
+        Self::move_files(storage, &file_changes.iter().collect::<Vec<_>>())
+            .map_err(|(path, e)| format!("Error moving {}: {}", path, e))?;
+        // Only empty directories are left on the local filesystem
+        if is_directory && storage.list_files(&issue.path)
+            .map_err(|e| format!("Error listing {}: {}", issue.path, e))?
+            .is_empty() {
+            storage.delete_dir(&issue.path)
+                .map_err(|e| format!("Error deleting {}: {}", issue.path, e))?;
+        }
+        Ok(LibraryRepairAction::Quarantined)
+    }
+
+    fn move_episode(conn: &mut DbConnection, storage: &dyn StorageBackend,
+                    episode: &EpisodeReorganization) -> Result<(), ReorganizationFailure> {
+        let file_changes = std::iter::once(&episode.file)
+            .chain(episode.image.iter())
+            .collect::<Vec<_>>();
+        Self::move_files(storage, &file_changes)
+            .map_err(|(path, e)| Self::reorganization_failure(&episode.name, path, e))?;
+
+        let mut media_paths = vec![MediaPath {
+            column: MediaPathColumn::EpisodeFile,
+            id: episode.podcast_episode_id,
+            path: episode.file.to.clone()
+        }];
+        if let Some(image) = &episode.image {
+            media_paths.push(MediaPath {
+                column: MediaPathColumn::EpisodeImage,
+                id: episode.podcast_episode_id,
+                path: image.to.clone()
+            });
+        }
+        if let Err(e) = MediaPath::update_media_paths(conn, &media_paths) {
+            Self::move_files_back(storage, &file_changes);
+            return Err(Self::reorganization_failure(&episode.name, &episode.file.from,
+                                                    format!("Error updating the stored paths: {}",
+                                                            e)));
+        }
+        Ok(())
+    }
+
+    fn move_podcast(conn: &mut DbConnection, storage: &dyn StorageBackend,
+                    podcast: &PodcastReorganization, directory: &PathChange)
+        -> Result<(), ReorganizationFailure> {
+        // Episodes are downloaded into the directory later on
+        storage.create_dir_all(&directory.to)
+            .map_err(|e| Self::reorganization_failure(&podcast.podcast_name, &directory.to,
+                                                      e.to_string()))?;
+        let file_changes = podcast.image.iter().collect::<Vec<_>>();
+        Self::move_files(storage, &file_changes)
+            .map_err(|(path, e)| Self::reorganization_failure(&podcast.podcast_name, path, e))?;
+
+        let mut media_paths = vec![MediaPath {
+            column: MediaPathColumn::PodcastDirectory,
+            id: podcast.podcast_id,
+            path: directory.to.clone()
+        }];
+        if let Some(image) = &podcast.image {
+            media_paths.push(MediaPath {
+                column: MediaPathColumn::PodcastImage,
+                id: podcast.podcast_id,
+                path: image.to.clone()
+            });
+        }
+        if let Err(e) = MediaPath::update_media_paths(conn, &media_paths) {
+            Self::move_files_back(storage, &file_changes);
+            return Err(Self::reorganization_failure(&podcast.podcast_name, &directory.from,
+                                                    format!("Error updating the stored paths: {}",
+                                                            e)));
+        }
+        Ok(())
+    }
+
+    /// Moves all files or none of them. Existing files are never overwritten.
+    fn move_files<'a>(storage: &dyn StorageBackend, file_changes: &[&'a PathChange])
+        -> Result<(), (&'a str, String)> {
+        for (i, file_change) in file_changes.iter().enumerate() {
+            let moved = match storage.exists(&file_change.to) {
+                Ok(true) => Err(format!("{} already exists", file_change.to)),
+                Ok(false) => storage.rename(&file_change.from, &file_change.to)
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string())
+            };
+            if let Err(e) = moved {
+                Self::move_files_back(storage, &file_changes[..i]);
+                return Err((&file_change.from, e));
+            }
+        }
+        Ok(())
+    }
+
+    fn move_files_back(storage: &dyn StorageBackend, file_changes: &[&PathChange]) {
+        for file_change in file_changes.iter().rev() {
+            if let Err(e) = storage.rename(&file_change.to, &file_change.from) {
+                log::error!("Error moving {} back to {}: {}", file_change.to, file_change.from, e);
+            }
+        }
+    }
+
+    /// Gives every entry its target path or, if another entry or an unknown file already has
+    /// it, the target path with the first free numeric suffix. Entries that keep their current
+    /// path are assigned first so that they don't get a suffix.
+    fn assign_paths(entries: &[(String, String)], first_suffix: usize,
+                    storage: &dyn StorageBackend) -> Result<Vec<(String, bool)>, String> {
+        let mut assigned_paths = entries.iter()
+            .map(|(current_path, target_path)| Some((target_path.clone(), false))
+                .filter(|_| current_path == target_path))
+            .collect::<Vec<_>>();
+        let mut claimed_paths = assigned_paths.iter()
+            .flatten()
+            .map(|(path, _)| path.clone())
+            .collect::<HashSet<_>>();
+        for ((current_path, target_path), assigned_path) in entries.iter()
+            .zip(assigned_paths.iter_mut())
+            .filter(|(_, assigned_path)| assigned_path.is_none()) {
+            let mut candidate = target_path.clone();
+            let mut suffix = first_suffix;
+            while claimed_paths.contains(&candidate) || (&candidate != current_path
+                && storage.exists(&candidate)
+                .map_err(|e| format!("Error checking {}: {}", candidate, e))?) {
+                candidate = format!("{}-{}", target_path, suffix);
+                suffix += 1;
+            }
+            claimed_paths.insert(candidate.clone());
+            *assigned_path = Some((candidate.clone(), &candidate != target_path));
+        }
+        Ok(assigned_paths.into_iter().flatten().collect())
+    }
+
+    fn move_into(path: &str, directory: &str) -> PathChange {
+        let file_name = path.rsplit_once('/').map(|(_, file_name)| file_name).unwrap_or(path);
+        PathChange {
+            from: path.to_string(),
+            to: format!("{}/{}", directory, file_name)
+        }
+    }
+
+    fn get_parent(path: &str) -> &str {
+        path.rsplit_once('/').map(|(parent, _)| parent).unwrap_or_default()
+    }
+
+    // Images that weren't downloaded are stored as urls
+    fn is_media_path(path: &str) -> bool {
+        !path.trim().is_empty() && !path.contains("://")
+    }
+
+    fn reorganization_failure(name: &str, path: &str, error: String) -> ReorganizationFailure {
+        ReorganizationFailure {
+            name: name.to_string(),
+            path: path.to_string(),
+            error
+        }
+    }
+
+    fn move_back(plan: &RelocationPlan, moved_entries: &[&OsString]) {
+        for entry in moved_entries.iter().rev() {
+            if let Err(e) = Self::move_path(&plan.to.join(entry), &plan.from.join(entry)) {
+                log::error!("Error moving {} back to {}: {}", entry.to_string_lossy(),
+                    plan.from.display(), e);
+            }
+        }
+    }
+
+    /// Renames the file or directory. Renaming isn't possible across filesystems, then it is
+    /// copied and deleted afterwards.
+    fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+        if fs::rename(from, to).is_ok() {
+            return Ok(());
+        }
+        if from.is_dir() {
+            let copy_options = CopyOptions {
+                copy_inside: true,
+                ..CopyOptions::new()
+            };
+            if let Err(e) = fs_extra::dir::copy(from, to, &copy_options) {
+                // Partial copies would block moving the directory back
+                fs::remove_dir_all(to).ok();
+                return Err(io::Error::other(e.to_string()));
+            }
+            return fs::remove_dir_all(from);
+        }
+        fs::copy(from, to)?;
+        fs::remove_file(from)
+    }
+
+    fn strip_media_root(path: &str, root_prefixes: &[String]) -> String {
+        let path = path.trim_start_matches("./");
+        root_prefixes.iter()
+            .find_map(|root_prefix| path.strip_prefix(root_prefix.as_str()))
+            .unwrap_or(path)
+            .to_string()
+    }
+
+    /// Resolves links of the part of the path that already exists
+    fn get_absolute_path(path: &Path) -> Result<PathBuf, String> {
+        let absolute_path = env::current_dir()
+            .map_err(|e| format!("Error resolving the working directory: {}", e))?
+            .join(path);
+        for ancestor in absolute_path.ancestors() {
+            if let Ok(canonical_ancestor) = fs::canonicalize(ancestor) {
+                return Ok(canonical_ancestor.join(absolute_path.strip_prefix(ancestor).unwrap()));
+            }
+        }
+        Ok(absolute_path)
+    }
+}
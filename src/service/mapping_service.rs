@@ -1,10 +1,13 @@
 use crate::models::favorites::Favorite;
 use crate::models::podcast_episode::PodcastEpisode;
-use crate::models::podcast_dto::PodcastDto;
+use crate::models::playback_preference::PlaybackPreferencesDto;
+use crate::models::podcast_dto::{PodcastDto, PodcastWithPreferences};
 use crate::models::models::{PodcastWatchedEpisodeModelWithPodcastEpisode};
 use crate::models::podcast_history_item::PodcastHistoryItem;
 use crate::service::environment_service;
 use crate::models::podcasts::Podcast;
+use crate::service::credential_service::CredentialService;
+use crate::service::storage::StorageService;
 
 
 #[derive(Clone)]
@@ -24,9 +27,9 @@ impl MappingService {
             id: podcast.id,
             name: podcast.name.clone(),
             directory_id: podcast.directory_id.clone(),
-            rssfeed: podcast.rssfeed.clone(),
+            rssfeed: CredentialService::redact_url(&podcast.rssfeed),
             image_url: environment_service::EnvironmentService::get_server_url(&self.env_service)
-                + &podcast.image_url.clone(),
+                + &StorageService::get_media_path(&podcast.image_url),
             language: podcast.language.clone(),
             keywords: podcast.keywords.clone(),
             summary: podcast.summary.clone(),
@@ -36,6 +39,18 @@ impl MappingService {
             active: podcast.active,
             original_image_url: podcast.original_image_url.clone(),
             directory_name: podcast.directory_name.clone(),
+            proxy_url: podcast.proxy_url.as_deref().map(CredentialService::redact_url),
+            write_metadata_tags: podcast.write_metadata_tags,
+        }
+    }
+
+    pub fn map_podcast_to_podcast_dto_with_preferences(&self, podcast: &Podcast,
+                                                       playback_preferences:
+                                                       PlaybackPreferencesDto)
+        -> PodcastWithPreferences {
+        PodcastWithPreferences {
+            podcast: self.map_podcast_to_podcast_dto(podcast),
+            playback_preferences
         }
     }
 
@@ -47,21 +62,24 @@ impl MappingService {
         let favorite = podcast_favorite_grouped.1.is_some() && podcast_favorite_grouped.1.clone()
             .unwrap().favored;
      PodcastDto{
-            id: podcast_favorite_grouped.0.id.clone(),
+            id: podcast_favorite_grouped.0.id,
             name: podcast_favorite_grouped.0.name.clone(),
          directory_id: podcast_favorite_grouped.0.directory_id.clone(),
-            rssfeed: podcast_favorite_grouped.0.rssfeed.clone(),
+            rssfeed: CredentialService::redact_url(&podcast_favorite_grouped.0.rssfeed),
             image_url: environment_service::EnvironmentService::get_server_url(&self.env_service)
-                + &podcast_favorite_grouped.0.image_url.clone(),
+                + &StorageService::get_media_path(&podcast_favorite_grouped.0.image_url),
             language: podcast_favorite_grouped.0.language.clone(),
             keywords: podcast_favorite_grouped.0.keywords.clone(),
             summary: podcast_favorite_grouped.0.summary.clone(),
             explicit: podcast_favorite_grouped.0.clone().explicit,
             last_build_date: podcast_favorite_grouped.0.clone().last_build_date,
             author: podcast_favorite_grouped.0.author.clone(),
-            active: podcast_favorite_grouped.0.active.clone(),
+            active: podcast_favorite_grouped.0.active,
             original_image_url: podcast_favorite_grouped.0.original_image_url.clone(),
-            favorites: favorite
+            favorites: favorite,
+            unplayed_episodes: 0,
+            playback_preferences: PlaybackPreferencesDto::default_for(podcast_favorite_grouped.0
+                .id)
      }
     }
 
@@ -81,15 +99,21 @@ impl MappingService {
             episode_id: podcast_episode.episode_id.clone(),
             name: podcast_episode.name.clone(),
             description: podcast_episode.description.clone(),
-            url: podcast_episode.url.clone(),
+            url: CredentialService::redact_url(&podcast_episode.url),
             date_of_recording: podcast_episode.date_of_recording.clone(),
             image_url: podcast_episode.image_url.clone(),
             total_time: podcast_episode.total_time,
-            local_url: podcast_episode.local_url.clone(),
-            local_image_url:  podcast_episode.local_image_url.clone(),
+            local_url: StorageService::get_media_path(&podcast_episode.local_url),
+            local_image_url: StorageService::get_media_path(&podcast_episode.local_image_url),
             status: podcast_episode.status.clone(),
-            download_time: podcast_episode.download_time.clone(),
+            download_time: podcast_episode.download_time,
             guid: podcast_episode.guid.clone(),
+            removed_upstream: podcast_episode.removed_upstream,
+            season: podcast_episode.season,
+            episode_number: podcast_episode.episode_number,
+            pinned: podcast_episode.pinned,
+            chapters_url: podcast_episode.chapters_url.clone(),
+            file_size: podcast_episode.file_size,
         }
     }
 
@@ -13,3 +13,24 @@ pub mod settings_service;
 pub mod telegram_api;
 pub mod user_management_service;
 pub mod subscription;
+pub mod feed_format;
+pub mod credential_service;
+pub mod http_client;
+pub mod outbound_policy;
+pub mod search_service;
+pub mod discovery;
+pub mod recommendation_service;
+pub mod tag_service;
+pub mod queue_service;
+pub mod playlist_service;
+pub mod episode_state_service;
+pub mod statistics_service;
+pub mod playback_preference_service;
+pub mod bookmark_service;
+pub mod personal_data_service;
+pub mod share_service;
+pub mod storage;
+pub mod library_service;
+pub mod cleanup_service;
+pub mod audio_tag_service;
+pub mod audio_probe_service;
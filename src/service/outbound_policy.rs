@@ -0,0 +1,340 @@
+use std::error::Error;
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::header::CONTENT_LENGTH;
+use reqwest::Url;
+use crate::service::environment_service::{EnvironmentService, HttpClientConfig, IpVersion};
+
+/// Decides which urls the server may fetch on behalf of its users. Feed, image, download and
+/// proxy urls are user controlled, so without it PodFetch could be used to reach services that
+/// are only reachable from the server, like cloud metadata endpoints.
+#[derive(Clone)]
+pub struct OutboundPolicy {
+    server_url: String,
+    allowed_schemes: Vec<String>,
+    allowed_hosts: Vec<AllowedHost>,
+    pub max_response_size: u64,
+    pub max_download_size: u64
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum AllowedHost {
+    Name(String),
+    Network(IpAddr, u8)
+}
+
+impl OutboundPolicy {
+    pub fn new() -> Self {
+        Self::from_config(&EnvironmentService::new().http_client_config)
+    }
+
+    pub fn from_config(config: &HttpClientConfig) -> Self {
+        OutboundPolicy {
+            server_url: EnvironmentService::new().server_url,
+            allowed_schemes: config.allowed_schemes.clone(),
+            allowed_hosts: config.allowed_hosts
+                .iter()
+                .filter_map(|allowed_host| Self::parse_allowed_host(allowed_host))
+                .collect(),
+            max_response_size: config.max_response_size,
+            max_download_size: config.max_download_size
+        }
+    }
+
+    /// Checks the scheme and the host of the url without resolving it, so that it can be called
+    /// from async handlers. The addresses of host names are checked by the [`OutboundResolver`]
+    /// of the http clients when they connect.
+    pub fn check_url(&self, url: &str) -> Result<(), String> {
+        let parsed_url = Url::parse(url).map_err(|e| format!("Invalid url: {}", e))?;
+        // PodFetch itself serves the default image of podcasts. The parsed url is compared so
+        // that dot segments can't leave the ui directory.
+        if parsed_url.as_str().starts_with(&format!("{}ui/", self.server_url)) {
+            return Ok(());
+        }
+        if !self.allowed_schemes.iter().any(|scheme| scheme == parsed_url.scheme()) {
+            return Err(format!("The scheme {} is not allowed", parsed_url.scheme()));
+        }
+        let host = parsed_url.host_str().ok_or("The url has no host".to_string())?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if self.is_allowed_host_name(host) {
+            return Ok(());
+        }
+        // The resolver lets the host of the server through for its ui files
+        if self.is_server_host(host) {
+            return Err(format!("Only the ui files of PodFetch may be requested from {}. Add it \
+            to HTTP_ALLOWED_HOSTS to allow it.", host));
+        }
+        // Addresses in urls aren't resolved
+        match host.parse::<IpAddr>() {
+            Ok(address) => self.check_addresses(host, &[address]),
+            Err(_) => Ok(())
+        }
+    }
+
+    /// Checks every address the host resolved to
+    pub fn check_addresses(&self, host: &str, addresses: &[IpAddr]) -> Result<(), String> {
+        if self.is_allowed_host_name(host) || self.is_server_host(host) {
+            return Ok(());
+        }
+        match addresses.iter().find(|address| !self.is_allowed_address(address)) {
+            Some(address) => Err(format!("{} resolves to the internal address {}. Add it to \
+            HTTP_ALLOWED_HOSTS to allow it.", host, address)),
+            None => Ok(())
+        }
+    }
+
+    /// Reads a whole response body. Bodies larger than the maximum response size are rejected.
+    pub fn read_response(&self, mut response: reqwest::blocking::Response)
+        -> Result<Vec<u8>, String> {
+        self.check_content_length(response.headers(), self.max_response_size)?;
+        let mut body = Vec::new();
+        (&mut response).take(self.max_response_size + 1)
+            .read_to_end(&mut body)
+            .map_err(|e| format!("Error reading response: {}", e))?;
+        if body.len() as u64 > self.max_response_size {
+            return Err(Self::size_exceeded_error(self.max_response_size));
+        }
+        Ok(body)
+    }
+
+    pub async fn read_response_async(&self, mut response: reqwest::Response)
+        -> Result<Vec<u8>, String> {
+        self.check_content_length(response.headers(), self.max_response_size)?;
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await
+            .map_err(|e| format!("Error reading response: {}", e))? {
+            body.extend_from_slice(&chunk);
+            if body.len() as u64 > self.max_response_size {
+                return Err(Self::size_exceeded_error(self.max_response_size));
+            }
+        }
+        Ok(body)
+    }
+
+    /// Checks the announced size of an episode download. A size of 0 allows any download.
+    pub fn check_download_size(&self, headers: &reqwest::header::HeaderMap) -> Result<(), String> {
+        match self.max_download_size {
+            0 => Ok(()),
+            max_download_size => self.check_content_length(headers, max_download_size)
+        }
+    }
+
+    fn check_content_length(&self, headers: &reqwest::header::HeaderMap, max_size: u64)
+        -> Result<(), String> {
+        let content_length = headers.get(CONTENT_LENGTH)
+            .and_then(|content_length| content_length.to_str().ok())
+            .and_then(|content_length| content_length.parse::<u64>().ok());
+        match content_length {
+            Some(content_length) if content_length > max_size => {
+                Err(Self::size_exceeded_error(max_size))
+            }
+            _ => Ok(())
+        }
+    }
+
+    fn size_exceeded_error(max_size: u64) -> String {
+        format!("The response is larger than the allowed {} bytes", max_size)
+    }
+
+    /// Allows the host name regardless of its addresses, e.g. of a configured proxy
+    pub fn allow_host_name(&mut self, host: &str) {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        self.allowed_hosts.push(match host.parse::<IpAddr>() {
+            Ok(address) => AllowedHost::Network(address, if address.is_ipv4() { 32 } else { 128 }),
+            Err(_) => AllowedHost::Name(host.to_string())
+        });
+    }
+
+    fn is_server_host(&self, host: &str) -> bool {
+        Url::parse(&self.server_url).ok()
+            .and_then(|server_url| server_url.host_str()
+                .map(|server_host| server_host.trim_start_matches('[').trim_end_matches(']')
+                    .eq_ignore_ascii_case(host)))
+            .unwrap_or(false)
+    }
+
+    fn is_allowed_host_name(&self, host: &str) -> bool {
+        self.allowed_hosts.iter().any(|allowed_host| match allowed_host {
+            AllowedHost::Name(name) => name.eq_ignore_ascii_case(host),
+            AllowedHost::Network(..) => false
+        })
+    }
+
+    fn is_allowed_address(&self, address: &IpAddr) -> bool {
+        !Self::is_internal_address(address) || self.allowed_hosts.iter().any(|allowed_host| {
+            match allowed_host {
+                AllowedHost::Network(network, prefix) => Self::is_in_network(address, network,
+                                                                             *prefix),
+                AllowedHost::Name(_) => false
+            }
+        })
+    }
+
+    fn is_internal_address(address: &IpAddr) -> bool {
+        match address {
+            IpAddr::V4(address) => Self::is_internal_ipv4_address(address),
+            IpAddr::V6(address) => match address.to_ipv4_mapped() {
+                Some(mapped_address) => Self::is_internal_ipv4_address(&mapped_address),
+                None => Self::is_internal_ipv6_address(address)
+            }
+        }
+    }
+
+    fn is_internal_ipv4_address(address: &Ipv4Addr) -> bool {
+        let octets = address.octets();
+        address.is_loopback() || address.is_private() || address.is_link_local()
+            || address.is_unspecified() || address.is_broadcast() || address.is_multicast()
+            // Carrier grade NAT
+            || (octets[0] == 100 && (octets[1] & 0b1100_0000) == 64)
+            || octets[0] == 0
+    }
+
+    fn is_internal_ipv6_address(address: &Ipv6Addr) -> bool {
+        let first_segment = address.segments()[0];
+        address.is_loopback() || address.is_unspecified() || address.is_multicast()
+            // Unique local addresses
+            || (first_segment & 0xfe00) == 0xfc00
+            // Link local addresses
+            || (first_segment & 0xffc0) == 0xfe80
+    }
+
+    fn is_in_network(address: &IpAddr, network: &IpAddr, prefix: u8) -> bool {
+        match (address, network) {
+            (IpAddr::V4(address), IpAddr::V4(network)) => {
+                let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                u32::from(*address) & mask == u32::from(*network) & mask
+            }
+            (IpAddr::V6(address), IpAddr::V6(network)) => {
+                let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                u128::from(*address) & mask == u128::from(*network) & mask
+            }
+            _ => false
+        }
+    }
+
+    // Entries are host names, addresses or networks in CIDR notation like 192.168.0.0/16
+    fn parse_allowed_host(allowed_host: &str) -> Option<AllowedHost> {
+        let (address, prefix) = match allowed_host.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (allowed_host, None)
+        };
+        match address.parse::<IpAddr>() {
+            Ok(address) => {
+                let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+                match prefix.map(|prefix| prefix.parse::<u8>()) {
+                    None => Some(AllowedHost::Network(address, max_prefix)),
+                    Some(Ok(prefix)) if prefix <= max_prefix => {
+                        Some(AllowedHost::Network(address, prefix))
+                    }
+                    _ => {
+                        log::error!("Ignoring invalid network {} of HTTP_ALLOWED_HOSTS",
+                            allowed_host);
+                        None
+                    }
+                }
+            }
+            Err(_) => Some(AllowedHost::Name(allowed_host.to_string()))
+        }
+    }
+}
+
+/// Resolves the host names of outbound requests and rejects them if one of their addresses is
+/// internal. Checking a url before its request would resolve the host twice, the second answer
+/// could be another address.
+pub struct OutboundResolver {
+    outbound_policy: OutboundPolicy,
+    ip_version: IpVersion
+}
+
+impl OutboundResolver {
+    pub fn new(outbound_policy: OutboundPolicy, ip_version: IpVersion) -> Self {
+        OutboundResolver {
+            outbound_policy,
+            ip_version
+        }
+    }
+
+    /// Moves the addresses of the preferred ip version to the front and keeps the others as a
+    /// fallback. The connector tries the family of the first address first and only falls back to
+    /// the other one if that fails, so hosts without an address of the preferred version still
+    /// work.
+    fn order_addresses(addresses: &mut [SocketAddr], ip_version: &IpVersion) {
+        match ip_version {
+            IpVersion::Any => {}
+            IpVersion::Ipv4 => addresses.sort_by_key(|address| !address.is_ipv4()),
+            IpVersion::Ipv6 => addresses.sort_by_key(|address| !address.is_ipv6())
+        }
+    }
+}
+
+impl Resolve for OutboundResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let outbound_policy = self.outbound_policy.clone();
+        let ip_version = self.ip_version.clone();
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            // The system resolver blocks, the port is set by the connector
+            let mut addresses = tokio::task::spawn_blocking(move || (name.as_str(), 0)
+                .to_socket_addrs()
+                .map(|addresses| addresses.collect::<Vec<SocketAddr>>()))
+                .await
+                .map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)?
+                .map_err(|e| format!("Could not resolve {}: {}", host, e))?;
+            let ip_addresses = addresses.iter()
+                .map(|address| address.ip())
+                .collect::<Vec<IpAddr>>();
+            outbound_policy.check_addresses(&host, &ip_addresses)?;
+            Self::order_addresses(&mut addresses, &ip_version);
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use crate::service::environment_service::IpVersion;
+    use crate::service::outbound_policy::OutboundResolver;
+
+    fn get_addresses() -> Vec<SocketAddr> {
+        ["[2001:db8::1]:0", "192.0.2.1:0", "[2001:db8::2]:0", "192.0.2.2:0"].iter()
+            .map(|address| address.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_order_addresses_prefers_ipv4() {
+        let mut addresses = get_addresses();
+        OutboundResolver::order_addresses(&mut addresses, &IpVersion::Ipv4);
+        assert_eq!(addresses, vec!["192.0.2.1:0".parse::<SocketAddr>().unwrap(),
+                                   "192.0.2.2:0".parse().unwrap(),
+                                   "[2001:db8::1]:0".parse().unwrap(),
+                                   "[2001:db8::2]:0".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_order_addresses_prefers_ipv6() {
+        let mut addresses = get_addresses();
+        OutboundResolver::order_addresses(&mut addresses, &IpVersion::Ipv6);
+        assert_eq!(addresses, vec!["[2001:db8::1]:0".parse::<SocketAddr>().unwrap(),
+                                   "[2001:db8::2]:0".parse().unwrap(),
+                                   "192.0.2.1:0".parse().unwrap(),
+                                   "192.0.2.2:0".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_order_addresses_keeps_the_resolver_order_without_preference() {
+        let mut addresses = get_addresses();
+        OutboundResolver::order_addresses(&mut addresses, &IpVersion::Any);
+        assert_eq!(addresses, get_addresses());
+    }
+
+    #[test]
+    fn test_order_addresses_falls_back_to_the_other_version() {
+        let mut addresses = vec!["192.0.2.1:0".parse::<SocketAddr>().unwrap()];
+        OutboundResolver::order_addresses(&mut addresses, &IpVersion::Ipv6);
+        assert_eq!(addresses, vec!["192.0.2.1:0".parse::<SocketAddr>().unwrap()]);
+    }
+}
@@ -1,10 +1,10 @@
 use std::io;
-use std::path::Path;
 use crate::DbConnection;
 use crate::models::podcast_episode::PodcastEpisode;
 use crate::models::podcasts::Podcast;
 
 use crate::service::file_service::{FileService, prepare_podcast_episode_title_to_directory};
+use crate::service::storage::StorageService;
 
 
 pub struct PathService {}
@@ -13,7 +13,7 @@ impl PathService {
     pub fn get_podcast_episode_path(directory: &str, episode: Option<PodcastEpisode>, suffix:
     &str, filename: &str, conn: &mut DbConnection)
         -> String {
-        return match episode {
+        match episode {
             Some(episode) => {
                 format!("{}/{}/podcast.{}", directory,
                         prepare_podcast_episode_title_to_directory(episode, conn), suffix)
@@ -27,7 +27,7 @@ impl PathService {
     pub fn get_image_path(directory: &str, episode: Option<PodcastEpisode>, _suffix: &str,
                           filename: &str,
                           conn: &mut DbConnection) -> String {
-        return match episode {
+        match episode {
             Some(episode) => {
                 format!("{}/{}", directory, prepare_podcast_episode_title_to_directory(episode,
                                                                                        conn))
@@ -39,31 +39,30 @@ impl PathService {
     }
 
     pub fn get_image_podcast_path_with_podcast_prefix(directory: &str, suffix: &str) -> String {
-        return format!("{}/image.{}", directory, suffix);
+        format!("{}/image.{}", directory, suffix)
     }
 
     pub fn check_if_podcast_episode_directory_available(base_path:&str, podcast: Podcast,conn: &mut DbConnection) ->
                                                                                           String {
         let mut i = 0;
-        if !Path::new(&base_path).exists() {
-            std::fs::create_dir(&base_path).map_err(|e| {
+        let storage = StorageService::get_backend();
+        if !storage.exists(base_path).unwrap_or(false) {
+            storage.create_dir_all(base_path).inspect_err(|_| {
                 Self::handle_error_when_creating_directory(podcast, conn);
-                return e
             }).expect("Error creating directory");
             return base_path.to_string();
         }
 
-        while Path::new(&format!("{}-{}",base_path, i)).exists() {
+        while storage.exists(&format!("{}-{}",base_path, i)).unwrap_or(false) {
             i += 1;
         }
         let final_path = format!("{}-{}",base_path, i);
         // This is save to insert because this directory does not exist
-        std::fs::create_dir(&final_path)
-            .map_err(|e| {
+        storage.create_dir_all(&final_path)
+            .inspect_err(|_| {
                 Self::handle_error_when_creating_directory(podcast, conn);
-                return e
             }).expect("Error creating directory");
-        return final_path;
+        final_path
     }
 
     fn handle_error_when_creating_directory(podcast:Podcast,conn: &mut DbConnection){
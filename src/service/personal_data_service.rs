@@ -0,0 +1,57 @@
+use chrono::{NaiveDateTime, Utc};
+use crate::DbConnection;
+use crate::exception::exceptions::PodFetchError;
+use crate::models::episode_state::EpisodeState;
+use crate::models::favorites::Favorite;
+use crate::models::personal_data::PersonalDataExport;
+use crate::models::podcast_history_item::PodcastHistoryItem;
+use crate::models::queue::QueueItem;
+use crate::models::user::User;
+use crate::service::bookmark_service::BookmarkService;
+use crate::service::mapping_service::MappingService;
+use crate::service::playback_preference_service::PlaybackPreferenceService;
+use crate::service::playlist_service::PlaylistService;
+use crate::service::tag_service::TagService;
+
+/// Collects the data of a user so they can take it with them
+pub struct PersonalDataService;
+
+impl PersonalDataService {
+    pub fn export(conn: &mut DbConnection, username: &str, mapping_service: &MappingService)
+        -> Result<PersonalDataExport, PodFetchError> {
+        let user = User::find_by_username(username, conn)
+            .ok_or(PodFetchError::user_not_found())?;
+        let favorite_podcast_ids = Favorite::get_all_favored(conn)
+            .into_iter()
+            .filter(|favorite| favorite.username == username)
+            .map(|favorite| favorite.podcast_id)
+            .collect();
+        let watch_history = PodcastHistoryItem::get_watch_logs_by_username(
+            username.to_string(), conn, NaiveDateTime::default())
+            .into_iter()
+            .map(|(watch_log, _, _)| watch_log)
+            .collect();
+        let queue = QueueItem::get_queue(conn, username)
+            .into_iter()
+            .map(|(_, podcast_episode, _)| podcast_episode.episode_id)
+            .collect();
+        let mut playback_preferences = PlaybackPreferenceService::get_custom_preferences(conn,
+                                                                                      username)
+            .into_values()
+            .collect::<Vec<_>>();
+        playback_preferences.sort_by_key(|preference| preference.podcast_id);
+
+        Ok(PersonalDataExport {
+            user: User::map_to_dto(user),
+            exported_at: Utc::now().naive_utc(),
+            favorite_podcast_ids,
+            watch_history,
+            episode_states: EpisodeState::get_states(conn, Some(username)),
+            tags: TagService::get_tags(conn, username),
+            queue,
+            playlists: PlaylistService::get_playlists(conn, username),
+            playback_preferences,
+            bookmarks: BookmarkService::get_bookmarks(conn, username, None, mapping_service)
+        })
+    }
+}
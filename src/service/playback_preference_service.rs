@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use crate::constants::constants::{MAX_PLAYBACK_SPEED, MAX_SKIP_SECONDS, MAX_VOLUME,
+                                  MIN_PLAYBACK_SPEED};
+use crate::DbConnection;
+use crate::exception::exceptions::PodFetchError;
+use crate::models::playback_preference::{PlaybackPreference, PlaybackPreferencesDto,
+                                         PlaybackPreferencesUpdateModel};
+use crate::models::podcasts::Podcast;
+
+/// The playback preferences of a user per podcast. Podcasts without preferences use the
+/// defaults, so every client plays a show the same way.
+pub struct PlaybackPreferenceService;
+
+impl PlaybackPreferenceService {
+    pub fn get_preferences(conn: &mut DbConnection, username: &str, podcast_id: i32)
+        -> Result<PlaybackPreferencesDto, PodFetchError> {
+        Self::find_podcast(conn, podcast_id)?;
+        Ok(Self::get_preferences_of_podcast(conn, username, podcast_id))
+    }
+
+    pub fn get_preferences_of_podcast(conn: &mut DbConnection, username: &str, podcast_id: i32)
+        -> PlaybackPreferencesDto {
+        PlaybackPreference::get_preference(conn, username, podcast_id)
+            .map(PlaybackPreferencesDto::from)
+            .unwrap_or(PlaybackPreferencesDto::default_for(podcast_id))
+    }
+
+    /// The preferences the user changed, by podcast id
+    pub fn get_custom_preferences(conn: &mut DbConnection, username: &str)
+        -> HashMap<i32, PlaybackPreferencesDto> {
+        PlaybackPreference::get_preferences_of_user(conn, username)
+            .into_iter()
+            .map(|preference| (preference.podcast_id, PlaybackPreferencesDto::from(preference)))
+            .collect()
+    }
+
+    pub fn save_preferences(conn: &mut DbConnection, username: &str, podcast_id: i32,
+                            preferences: &PlaybackPreferencesUpdateModel)
+        -> Result<PlaybackPreferencesDto, PodFetchError> {
+        Self::find_podcast(conn, podcast_id)?;
+        if !(MIN_PLAYBACK_SPEED..=MAX_PLAYBACK_SPEED).contains(&preferences.playback_speed)
+            || !(0..=MAX_VOLUME).contains(&preferences.volume)
+            || !(0..=MAX_SKIP_SECONDS).contains(&preferences.skip_intro_seconds)
+            || !(0..=MAX_SKIP_SECONDS).contains(&preferences.skip_outro_seconds) {
+            return Err(PodFetchError::invalid_playback_preferences());
+        }
+        let preference = PlaybackPreference::save_preference(conn, username, podcast_id,
+                                                             preferences);
+        Ok(PlaybackPreferencesDto::from(preference))
+    }
+
+    /// Goes back to the defaults
+    pub fn reset_preferences(conn: &mut DbConnection, username: &str, podcast_id: i32)
+        -> Result<PlaybackPreferencesDto, PodFetchError> {
+        Self::find_podcast(conn, podcast_id)?;
+        PlaybackPreference::delete_preference(conn, username, podcast_id);
+        Ok(PlaybackPreferencesDto::default_for(podcast_id))
+    }
+
+    fn find_podcast(conn: &mut DbConnection, podcast_id: i32) -> Result<Podcast, PodFetchError> {
+        Podcast::get_podcast(conn, podcast_id).map_err(|_| PodFetchError::podcast_not_found())
+    }
+}
@@ -0,0 +1,223 @@
+use uuid::Uuid;
+use crate::constants::constants::MAX_SMART_PLAYLIST_SIZE;
+use crate::DbConnection;
+use crate::exception::exceptions::PodFetchError;
+use crate::models::playlist::{Playlist, PlaylistCreateModel, PlaylistDto, PlaylistItem,
+                              PlaylistItemDto, SmartPlaylistRules};
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcasts::Podcast;
+use crate::models::tag::Tag;
+use crate::service::environment_service::EnvironmentService;
+use crate::service::mapping_service::MappingService;
+use crate::service::tag_service::TagService;
+
+/// Manages the manual and smart playlists of a user
+pub struct PlaylistService;
+
+impl PlaylistService {
+    pub fn get_playlists(conn: &mut DbConnection, username: &str) -> Vec<PlaylistDto> {
+        Playlist::get_playlists_of_user(conn, username)
+            .iter()
+            .map(Self::map_playlist)
+            .collect()
+    }
+
+    pub fn get_playlist(conn: &mut DbConnection, username: &str, playlist_id: i32)
+        -> Result<PlaylistDto, PodFetchError> {
+        let playlist = Self::find_playlist(conn, username, playlist_id)?;
+        Ok(Self::map_playlist(&playlist))
+    }
+
+    pub fn get_playlist_episodes(conn: &mut DbConnection, username: &str, playlist_id: i32,
+                                 mapping_service: &MappingService)
+        -> Result<Vec<PlaylistItemDto>, PodFetchError> {
+        let playlist = Self::find_playlist(conn, username, playlist_id)?;
+        Ok(Self::get_episodes(conn, &playlist)
+            .into_iter()
+            .map(|(podcast_episode, podcast)| PlaylistItemDto {
+                podcast_episode: mapping_service.map_podcastepisode_to_dto(&podcast_episode),
+                podcast: mapping_service.map_podcast_to_podcast_dto(&podcast)
+            })
+            .collect())
+    }
+
+    pub fn create_playlist(conn: &mut DbConnection, username: &str,
+                           playlist: &PlaylistCreateModel) -> Result<PlaylistDto, PodFetchError> {
+        let name = Self::validate_name(&playlist.name)?;
+        if Playlist::find_by_name(conn, username, &name).is_some() {
+            return Err(PodFetchError::playlist_already_exists());
+        }
+        let episode_ids = Self::validate_playlist(conn, username, playlist)?;
+        let created_playlist = Playlist::insert_playlist(conn, username, &name,
+                                                         playlist.rules.as_ref(),
+                                                         &Self::generate_feed_token());
+        PlaylistItem::save_items(conn, created_playlist.id, &episode_ids);
+        Ok(Self::map_playlist(&created_playlist))
+    }
+
+    /// Updates the name and rules of a playlist. The episodes of a manual playlist are only
+    /// replaced if they are given.
+    pub fn update_playlist(conn: &mut DbConnection, username: &str, playlist_id: i32,
+                           playlist: &PlaylistCreateModel) -> Result<PlaylistDto, PodFetchError> {
+        Self::find_playlist(conn, username, playlist_id)?;
+        let name = Self::validate_name(&playlist.name)?;
+        if let Some(existing_playlist) = Playlist::find_by_name(conn, username, &name) {
+            if existing_playlist.id != playlist_id {
+                return Err(PodFetchError::playlist_already_exists());
+            }
+        }
+        let episode_ids = Self::validate_playlist(conn, username, playlist)?;
+        let updated_playlist = Playlist::update_playlist(conn, playlist_id, &name,
+                                                         playlist.rules.as_ref());
+        if playlist.rules.is_some() || playlist.episode_ids.is_some() {
+            PlaylistItem::save_items(conn, playlist_id, &episode_ids);
+        }
+        Ok(Self::map_playlist(&updated_playlist))
+    }
+
+    pub fn delete_playlist(conn: &mut DbConnection, username: &str, playlist_id: i32)
+        -> Result<(), PodFetchError> {
+        Self::find_playlist(conn, username, playlist_id)?;
+        Playlist::delete_playlist(conn, playlist_id);
+        Ok(())
+    }
+
+    /// Appends an episode to a manual playlist
+    pub fn add_episode(conn: &mut DbConnection, username: &str, playlist_id: i32,
+                       episode_id: &str) -> Result<(), PodFetchError> {
+        let playlist = Self::find_playlist(conn, username, playlist_id)?;
+        if playlist.rules.is_some() {
+            return Err(PodFetchError::smart_playlist_not_editable());
+        }
+        let podcast_episode = Self::find_episode(conn, episode_id)?;
+        let mut episode_ids = PlaylistItem::get_episode_ids(conn, playlist_id);
+        if !episode_ids.contains(&podcast_episode.id) {
+            episode_ids.push(podcast_episode.id);
+            PlaylistItem::save_items(conn, playlist_id, &episode_ids);
+        }
+        Ok(())
+    }
+
+    pub fn remove_episode(conn: &mut DbConnection, username: &str, playlist_id: i32,
+                          episode_id: &str) -> Result<(), PodFetchError> {
+        let playlist = Self::find_playlist(conn, username, playlist_id)?;
+        if playlist.rules.is_some() {
+            return Err(PodFetchError::smart_playlist_not_editable());
+        }
+        let podcast_episode = Self::find_episode(conn, episode_id)?;
+        let mut episode_ids = PlaylistItem::get_episode_ids(conn, playlist_id);
+        episode_ids.retain(|playlist_episode_id| *playlist_episode_id != podcast_episode.id);
+        PlaylistItem::save_items(conn, playlist_id, &episode_ids);
+        Ok(())
+    }
+
+    /// Creates new feed links for the playlist. The old links stop working.
+    pub fn reset_feed_urls(conn: &mut DbConnection, username: &str, playlist_id: i32)
+        -> Result<PlaylistDto, PodFetchError> {
+        Self::find_playlist(conn, username, playlist_id)?;
+        let playlist = Playlist::update_feed_token(conn, playlist_id,
+                                                   &Self::generate_feed_token());
+        Ok(Self::map_playlist(&playlist))
+    }
+
+    /// The playlist the feed token belongs to together with its episodes
+    pub fn get_playlist_by_feed_token(conn: &mut DbConnection, feed_token: &str)
+        -> Option<(Playlist, Vec<(PodcastEpisode, Podcast)>)> {
+        let playlist = Playlist::get_by_feed_token(conn, feed_token)?;
+        let episodes = Self::get_episodes(conn, &playlist);
+        Some((playlist, episodes))
+    }
+
+    fn get_episodes(conn: &mut DbConnection, playlist: &Playlist)
+        -> Vec<(PodcastEpisode, Podcast)> {
+        match playlist.get_rules() {
+            Some(mut rules) => {
+                let tag_ids = rules.tag_id.map(|tag_id| TagService::get_tag_ids(
+                    conn, &playlist.username, tag_id));
+                rules.limit = Some(rules.limit.unwrap_or(MAX_SMART_PLAYLIST_SIZE));
+                Playlist::find_smart_playlist_episodes(conn, &playlist.username, &rules, tag_ids)
+            }
+            None => PlaylistItem::get_items(conn, playlist.id)
+        }
+    }
+
+    /// Checks the rules of a smart playlist or the episodes of a manual playlist. Returns the
+    /// episode ids of a manual playlist.
+    fn validate_playlist(conn: &mut DbConnection, username: &str,
+                         playlist: &PlaylistCreateModel) -> Result<Vec<i32>, PodFetchError> {
+        match &playlist.rules {
+            Some(rules) => {
+                if playlist.episode_ids.is_some() {
+                    return Err(PodFetchError::smart_playlist_not_editable());
+                }
+                Self::validate_rules(conn, username, rules)?;
+                Ok(vec![])
+            }
+            None => {
+                let mut episode_ids = Vec::new();
+                for episode_id in playlist.episode_ids.iter().flatten() {
+                    let podcast_episode = Self::find_episode(conn, episode_id)?;
+                    if !episode_ids.contains(&podcast_episode.id) {
+                        episode_ids.push(podcast_episode.id);
+                    }
+                }
+                Ok(episode_ids)
+            }
+        }
+    }
+
+    fn validate_rules(conn: &mut DbConnection, username: &str, rules: &SmartPlaylistRules)
+        -> Result<(), PodFetchError> {
+        if let Some(tag_id) = rules.tag_id {
+            Tag::get_tag_of_user(conn, tag_id, username).ok_or(PodFetchError::tag_not_found())?;
+        }
+        let min_duration = rules.min_duration.unwrap_or(0);
+        let max_duration = rules.max_duration.unwrap_or(i32::MAX);
+        if min_duration < 0 || max_duration < min_duration
+            || rules.published_within_days.map(|days| days <= 0).unwrap_or(false)
+            || rules.limit.map(|limit| limit <= 0 || limit > MAX_SMART_PLAYLIST_SIZE)
+            .unwrap_or(false) {
+            return Err(PodFetchError::invalid_smart_playlist_rules());
+        }
+        Ok(())
+    }
+
+    fn validate_name(name: &str) -> Result<String, PodFetchError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(PodFetchError::invalid_playlist_name());
+        }
+        Ok(name.to_string())
+    }
+
+    fn find_playlist(conn: &mut DbConnection, username: &str, playlist_id: i32)
+        -> Result<Playlist, PodFetchError> {
+        Playlist::get_playlist_of_user(conn, playlist_id, username)
+            .ok_or(PodFetchError::playlist_not_found())
+    }
+
+    fn find_episode(conn: &mut DbConnection, episode_id: &str)
+        -> Result<PodcastEpisode, PodFetchError> {
+        PodcastEpisode::get_podcast_episode_by_id(conn, episode_id)
+            .unwrap()
+            .ok_or(PodFetchError::episode_not_found())
+    }
+
+    fn generate_feed_token() -> String {
+        Uuid::new_v4().simple().to_string()
+    }
+
+    fn map_playlist(playlist: &Playlist) -> PlaylistDto {
+        let server_url = EnvironmentService::new().get_server_url();
+        PlaylistDto {
+            id: playlist.id,
+            name: playlist.name.clone(),
+            smart: playlist.rules.is_some(),
+            rules: playlist.get_rules(),
+            created_at: playlist.created_at,
+            rss_url: format!("{}rss/playlist/{}", server_url, playlist.feed_token),
+            m3u_url: format!("{}m3u/playlist/{}", server_url, playlist.feed_token),
+            pls_url: format!("{}pls/playlist/{}", server_url, playlist.feed_token)
+        }
+    }
+}
@@ -1,23 +1,22 @@
 use std::sync::{Arc, Mutex};
-use crate::constants::constants::{PodcastType, TELEGRAM_API_ENABLED};
+use crate::constants::constants::{DEFAULT_IMAGE_SUFFIX, DEFAULT_PODCAST_SUFFIX, FEED_ACCEPT_HEADER,
+                                   MAX_SEARCH_PAGE_SIZE,
+                                   PodcastType, TELEGRAM_API_ENABLED};
 use crate::models::podcast_episode::PodcastEpisode;
 use crate::models::podcasts::Podcast;
 use crate::models::messages::BroadcastMessage;
 use crate::models::web_socket_message::Lobby;
 use crate::service::download_service::DownloadService;
-use crate::service::file_service::{determine_image_and_local_podcast_audio_url, FileService};
+use crate::service::file_service::determine_image_and_local_podcast_audio_url;
 use crate::service::mapping_service::MappingService;
 
 use crate::utils::podcast_builder::PodcastBuilder;
 use actix::Addr;
 use actix_web::web;
-use diesel::{OptionalExtension, RunQueryDsl};
 use dotenv::var;
-use regex::Regex;
-use reqwest::blocking::ClientBuilder;
-use reqwest::header::{ACCEPT, HeaderMap};
-use reqwest::redirect::Policy;
-use rss::{Channel, Item};
+use reqwest::header::{ACCEPT, CONTENT_TYPE, HeaderMap};
+use reqwest::Url;
+use std::collections::{HashMap, HashSet};
 
 use crate::DbConnection;
 use crate::models::notification::Notification;
@@ -26,6 +25,18 @@ use crate::mutex::LockResultExt;
 use crate::service::environment_service::EnvironmentService;
 use crate::service::settings_service::SettingsService;
 use crate::service::telegram_api::send_new_episode_notification;
+use crate::models::feed_fetch_report::{FeedFetchReport, SkippedFeedItem};
+use crate::models::feed::{Feed, FeedItem};
+use crate::service::feed_format::parse_feed;
+use crate::service::credential_service::{CredentialScope, CredentialService};
+use crate::service::http_client::HttpClientFactory;
+use crate::service::outbound_policy::OutboundPolicy;
+use crate::service::search_service::SearchService;
+use crate::service::queue_service::QueueService;
+use crate::models::search::EpisodeSearchFilter;
+use crate::utils::feed_sanitizer::decode_html_entities;
+use crate::utils::time::parse_feed_date;
+use crate::utils::url_normalization::normalize_enclosure_url;
 
 #[derive(Clone)]
 pub struct PodcastEpisodeService {
@@ -51,12 +62,14 @@ impl PodcastEpisodeService {
         let settings = settings_service.get_settings(conn).unwrap();
         let podcast_episode_cloned = podcast_episode.clone();
         let podcast_cloned = podcast.clone();
-        let suffix = Self::get_url_file_suffix(&podcast_episode_cloned.url);
-        let image_suffix = Self::get_url_file_suffix(&podcast_episode_cloned.image_url);
+        let suffix = Self::get_file_suffix(&podcast_episode_cloned.url, None,
+                                           DEFAULT_PODCAST_SUFFIX);
+        let image_suffix = Self::get_file_suffix(&podcast_episode_cloned.image_url, None,
+                                                 DEFAULT_IMAGE_SUFFIX);
 
 
         let (image_save_path, podcast_save_path) = determine_image_and_local_podcast_audio_url
-            (podcast.clone(), podcast_episode.clone(), &suffix, &image_suffix, settings,conn);
+            (podcast.clone(), podcast_episode.clone(), &image_suffix, &suffix, settings,conn);
 
 
         match PodcastEpisode::check_if_downloaded(&podcast_episode.url, conn) {
@@ -71,28 +84,31 @@ impl PodcastEpisodeService {
                     .expect("Error saving total time of podcast episode.");
             }
             Ok(false) => {
-                let podcast_inserted = Self::perform_download(
+                let podcast_inserted = match Self::perform_download(
                     &podcast_episode,
                     podcast_episode_cloned,
                     podcast_cloned,
                     conn
-                );
+                ) {
+                    Ok(podcast_inserted) => podcast_inserted,
+                    Err(e) => {
+                        log::error!("Error downloading episode {}: {}", podcast_episode.name, e);
+                        return;
+                    }
+                };
                 let mapped_dto = self
                     .mapping_service
                     .map_podcastepisode_to_dto(&podcast_inserted);
-                match lobby {
-                    Some(lobby) => lobby.do_send(BroadcastMessage {
-                        message: format!(
-                            "Episode {} is now available offline",
-                            podcast_episode.name
-                        ),
-                        podcast: Option::from(podcast.clone()),
-                        type_of: PodcastType::AddPodcastEpisode,
-                        podcast_episode: Some(mapped_dto),
-                        podcast_episodes: None,
-                    }),
-                    None => {}
-                }
+                if let Some(lobby) = lobby { lobby.do_send(BroadcastMessage {
+                    message: format!(
+                        "Episode {} is now available offline",
+                        podcast_episode.name
+                    ),
+                    podcast: Option::from(podcast.clone()),
+                    type_of: PodcastType::AddPodcastEpisode,
+                    podcast_episode: Some(mapped_dto),
+                    podcast_episodes: None,
+                }) }
                 if var(TELEGRAM_API_ENABLED).is_ok(){
                     send_new_episode_notification(podcast_episode, podcast)
                 }
@@ -109,10 +125,10 @@ impl PodcastEpisodeService {
         podcast_episode_cloned: PodcastEpisode,
         podcast_cloned: Podcast,
         conn: &mut DbConnection,
-    ) -> PodcastEpisode {
+    ) -> Result<PodcastEpisode, String> {
         log::info!("Downloading podcast episode: {}", podcast_episode.name);
         let mut download_service = DownloadService::new();
-        download_service.download_podcast_episode(podcast_episode_cloned, podcast_cloned);
+        download_service.download_podcast_episode(podcast_episode_cloned, podcast_cloned)?;
         let podcast = PodcastEpisode::update_podcast_episode_status(&podcast_episode.url, "D", conn)
             .unwrap();
         let notification = Notification {
@@ -123,7 +139,7 @@ impl PodcastEpisodeService {
             status: "unread".to_string(),
         };
         Notification::insert_notification(notification,conn).unwrap();
-        return podcast;
+        Ok(podcast)
     }
 
     pub fn get_last_n_podcast_episodes(conn: &mut DbConnection, podcast: Podcast) ->
@@ -138,183 +154,288 @@ impl PodcastEpisodeService {
     // Used for creating/updating podcasts
     pub fn insert_podcast_episodes(&mut self, conn: &mut DbConnection, podcast: Podcast) ->
                                                                              Vec<PodcastEpisode> {
-        let is_redirected = Arc::new(Mutex::new(false)); // Variable to store the redirection status
+        let inserted_episodes = self.insert_episodes_of_feed(conn, podcast.clone(), false);
+        QueueService::append_new_episodes(conn, podcast.id, &inserted_episodes);
+        inserted_episodes
+    }
 
-        let returned_data_from_podcast_insert = Self::do_request_to_podcast_server(podcast.clone());
+    /// Inserts the whole back catalog of a podcast by following the page links of paged and
+    /// archived feeds up to the configured page limit.
+    pub fn insert_full_history(&mut self, conn: &mut DbConnection, podcast: Podcast) ->
+                                                                             Vec<PodcastEpisode> {
+        self.insert_episodes_of_feed(conn, podcast, true)
+    }
 
-        let channel = Channel::read_from(returned_data_from_podcast_insert.content.as_bytes())
-            .unwrap();
+    fn insert_episodes_of_feed(&mut self, conn: &mut DbConnection, podcast: Podcast,
+                               follow_pages: bool) -> Vec<PodcastEpisode> {
+        let is_redirected = Arc::new(Mutex::new(false)); // Variable to store the redirection status
+        let credential_scope = CredentialService::get_credential_scope(conn, &podcast);
+
+        let returned_data_from_podcast_insert = match Self::do_request_to_podcast_server(podcast
+            .clone(), &credential_scope) {
+            Ok(returned_data) => returned_data,
+            Err(e) => return Self::record_failed_fetch(conn, &podcast, e)
+        };
+        let mut feed = returned_data_from_podcast_insert.feed;
 
         if *is_redirected.clone().lock().ignore_poison() {
             log::info!("The podcast {} has moved to {}", podcast.name,
-                returned_data_from_podcast_insert.url);
+                CredentialService::redact_url(&returned_data_from_podcast_insert.url));
             Podcast::update_podcast_urls_on_redirect(podcast.id, returned_data_from_podcast_insert.url, conn);
-            Self::update_episodes_on_redirect(conn,channel.items());
         }
 
-        if channel.itunes_ext.is_some(){
-            let extension = channel.itunes_ext.clone().unwrap();
-
-            if extension.new_feed_url.is_some(){
-                let new_url = extension.new_feed_url.unwrap();
-                Podcast::update_podcast_urls_on_redirect(podcast.id, new_url, conn);
+        let mut feed_url = podcast.rssfeed.clone();
+        if let Some(new_url) = feed.new_feed_url.clone() {
+            if new_url != podcast.rssfeed {
+                Podcast::update_podcast_urls_on_redirect(podcast.id, new_url.clone(), conn);
 
-                let returned_data_from_server = Self::do_request_to_podcast_server(podcast.clone());
+                let mut moved_podcast = podcast.clone();
+                moved_podcast.rssfeed = new_url.clone();
 
-                let channel = Channel::read_from(returned_data_from_server.content.as_bytes())
-                    .unwrap();
-                let items = channel.items();
-                Self::update_episodes_on_redirect(conn, items);
+                // Episode urls that changed with the move are picked up by the guid matching below
+                match Self::do_request_to_podcast_server(moved_podcast, &credential_scope) {
+                    Ok(returned_data) => {
+                        feed = returned_data.feed;
+                        feed_url = new_url;
+                    }
+                    Err(e) => {
+                        log::error!("Error fetching the new feed of podcast {}: {}. Using the \
+                        old feed.", podcast.name, e);
+                    }
+                }
             }
         }
 
 
-        self.update_podcast_fields(channel.clone(), podcast.id.clone(),conn);
+        let feed_complete = match follow_pages {
+            true => Self::append_older_pages(&mut feed, &feed_url, &podcast, &credential_scope),
+            false => feed.next_page_url.is_none()
+        };
+
+        self.update_podcast_fields(feed.clone(), podcast.id,conn);
 
         let mut podcast_inserted = Vec::new();
 
 
-            match channel.image() {
-                Some(image) => {
-                   Podcast::update_original_image_url(&image.url.to_string(), podcast.id,
+            match &feed.image_url {
+                Some(image_url) => {
+                   Podcast::update_original_image_url(image_url, podcast.id,
                     conn);
                 }
                 None => {
                     let env = EnvironmentService::new();
-                    let url = env.server_url.clone().to_owned() + &"ui/default.jpg".to_string();
+                    let url = env.server_url.clone().to_owned() + "ui/default.jpg";
                     Podcast::update_original_image_url(&url, podcast.id,conn);
                 }
             }
 
-        for (_, item) in channel.items.iter().enumerate() {
-            let itunes_ext = item.clone().itunes_ext;
-
-            match itunes_ext {
-                Some(itunes_ext) => {
-                    let enclosure = item.enclosure();
-                    match enclosure {
-                        Some(enclosure)=>{
-                            let result =
-                                PodcastEpisode::get_podcast_episode_by_url(conn, &enclosure.url
-                                    .to_string(),
-                                                               Some(podcast.id));
-                            let mut duration_episode = 0;
-
-                            if result.is_err(){
-                                log::info!("Skipping episode {} with error: {}", item.clone().title
-                                    .unwrap_or("with no title".to_string()), result.err().unwrap());
-                                continue;
-                            }
-
-                            let result_unwrapped = result.clone().unwrap();
-
-                            if result_unwrapped.is_some()  && result_unwrapped.clone().unwrap()
-                                .podcast_id != podcast.id {
-
-                                let inserted_episode = PodcastEpisode::insert_podcast_episodes(conn,
-                                                                                   podcast.clone(),
-                                                                                   item.clone(),
-                                                                                   Some(result_unwrapped.unwrap().image_url),
-                                                                                   duration_episode as i32,
-                                );
-                                podcast_inserted.push(inserted_episode);
-                            }
-                            else if result_unwrapped.is_some(){
-                                let already_loaded_episode = result_unwrapped.unwrap();
-                                if already_loaded_episode.guid.is_empty() && item.guid().is_some
-                                () && !item.guid().unwrap().permalink{
-                                    PodcastEpisode::update_guid(conn,item.guid.clone().unwrap(),
-                                                    &already_loaded_episode.episode_id);
-                                }
-                            }
-
-                            if result.clone().unwrap().is_none() {
-                                // Insert new podcast episode
-                                match itunes_ext.clone().duration {
-                                    Some(duration) => {
-                                        duration_episode = Self::parse_duration(&duration);
-                                    }
-                                    None => {}
-                                }
-
-                                let inserted_episode = PodcastEpisode::insert_podcast_episodes(conn,
-                                                                                   podcast.clone(),
-                                                                                   item.clone(),
-                                                                                   itunes_ext.image,
-                                                                                   duration_episode as i32,
-                                );
-                                podcast_inserted.push(inserted_episode);
-                            }
-                        }
-                        None => {
-                            log::info!("Skipping episode {} without enclosure.", item.clone().title
-                                .unwrap_or("with no title".to_string()));
-                            continue;
-                        }
+        let mut known_episodes = EpisodeIdentityIndex::new(
+            PodcastEpisode::get_episodes_by_podcast_id(podcast.id, conn));
+        let mut episode_ids_in_feed = Vec::new();
+        let mut skipped_items = Vec::new();
+        let mut updated_episodes = 0;
+
+        for feed_item in feed.items.iter() {
+            let item = match Self::sanitize_feed_item(feed_item, &feed_url) {
+                Ok(item) => item,
+                Err(reason) => {
+                    log::info!("Skipping feed item {} of podcast {}: {}", feed_item.title
+                        .clone().unwrap_or("with no title".to_string()), podcast.name, reason);
+                    skipped_items.push(SkippedFeedItem {
+                        title: feed_item.title.clone(),
+                        guid: feed_item.guid.clone(),
+                        reason
+                    });
+                    continue;
+                }
+            };
+            let enclosure_url = item.enclosure.clone().map(|e| e.url).unwrap_or_default();
+            let item_guid = item.guid.clone().unwrap_or_default();
+            // Episodes without a duration are stored with 0
+            let duration_episode = item.duration.as_deref().map(Self::parse_duration).unwrap_or(0);
+            let image_of_episode = item.image_url.clone();
+
+            match known_episodes.find(&item_guid, &enclosure_url) {
+                Some(existing_episode) => {
+                    episode_ids_in_feed.push(existing_episode.id);
+                    let updated_episode = Self::apply_feed_item_to_episode(
+                        existing_episode.clone(), &item, duration_episode as i32);
+                    if updated_episode != existing_episode {
+                        log::debug!("Updating metadata of episode {}", updated_episode.name);
+                        let updated_episode = PodcastEpisode::update_podcast_episode(conn,
+                                                                                     updated_episode);
+                        known_episodes.replace(updated_episode);
+                        updated_episodes += 1;
                     }
-
                 }
                 None => {
-                    let opt_enclosure = &item.enclosure;
-                    if opt_enclosure.is_none() {
-                        log::info!("Skipping episode {} without enclosure.", item.clone().title.unwrap_or("with no title".to_string()));
-                        continue;
-                    }
-                    let result = PodcastEpisode::get_podcast_episode_by_url(
-                        conn, &opt_enclosure.clone().unwrap().url, None);
-                    // We can't retrieve the duration of the podcast episode, so we set it to 0
-
-                    if result.unwrap().is_none() {
-                        let duration_episode = 0;
-                        let inserted_episode = PodcastEpisode::insert_podcast_episodes(
-                            conn,
-                            podcast.clone(),
-                            item.clone(),
-                            Some("ui/default.jpg".parse().unwrap()),
-                            duration_episode as i32,
-                        );
-                        podcast_inserted.push(inserted_episode);
-                    }
+                    let inserted_episode = PodcastEpisode::insert_podcast_episodes(conn,
+                                                                       podcast.clone(),
+                                                                       item,
+                                                                       image_of_episode,
+                                                                       duration_episode as i32,
+                    );
+                    episode_ids_in_feed.push(inserted_episode.id);
+                    known_episodes.add(inserted_episode.clone());
+                    podcast_inserted.push(inserted_episode);
                 }
             }
         }
-        return podcast_inserted;
+
+        // An empty, broken or partially fetched feed should not flag the whole back catalog
+        if !episode_ids_in_feed.is_empty() && feed_complete {
+            PodcastEpisode::mark_removed_upstream(conn, podcast.id, &episode_ids_in_feed);
+        }
+        FeedFetchReport::succeeded(podcast.id, podcast_inserted.len() as i32, updated_episodes,
+                                   &skipped_items)
+            .save_report(conn);
+        podcast_inserted
     }
 
+    /// Adds the items of the older pages to the feed. Returns if every page could be fetched.
+    fn append_older_pages(feed: &mut Feed, feed_url: &str, podcast: &Podcast,
+                          credential_scope: &CredentialScope) -> bool {
+        let max_feed_pages = EnvironmentService::new().max_feed_pages;
+        let mut visited_pages = HashSet::from([feed_url.to_string()]);
+        let mut current_page_url = feed_url.to_string();
+        let mut next_page_url = feed.next_page_url.clone();
+        let mut fetched_pages = 1;
+
+        while let Some(page_url) = next_page_url {
+            let page_url = Url::parse(&current_page_url)
+                .and_then(|base_url| base_url.join(&page_url))
+                .map(|resolved_url| resolved_url.to_string())
+                .unwrap_or(page_url);
+            if !visited_pages.insert(page_url.clone()) {
+                log::info!("Feed of podcast {} links back to page {}", podcast.name, page_url);
+                return true;
+            }
+            if fetched_pages >= max_feed_pages {
+                log::info!("Stopped fetching the history of podcast {} after {} pages", podcast
+                    .name, fetched_pages);
+                return false;
+            }
 
-    fn update_episodes_on_redirect(conn: &mut DbConnection, items: &[Item]) {
-        for (_, item) in items.iter().enumerate(){
-            let opt_found_podcast_episode = Self::get_podcast_episode_by_guid(conn, item.title.as_ref().unwrap());
-            if opt_found_podcast_episode.is_some(){
-                let found_podcast_episode = opt_found_podcast_episode.unwrap();
-                let mut podcast_episode = found_podcast_episode.clone();
-                if item.itunes_ext.is_some(){
-                    podcast_episode.image_url = item.itunes_ext.as_ref().unwrap().image.clone()
-                        .unwrap();
+            let mut paged_podcast = podcast.clone();
+            paged_podcast.rssfeed = page_url.clone();
+            match Self::do_request_to_podcast_server(paged_podcast, credential_scope) {
+                Ok(returned_data) => {
+                    log::debug!("Fetched page {} of podcast {}", page_url, podcast.name);
+                    next_page_url = returned_data.feed.next_page_url;
+                    feed.items.extend(returned_data.feed.items);
+                    current_page_url = page_url;
+                    fetched_pages += 1;
+                }
+                Err(e) => {
+                    log::error!("Error fetching page {} of podcast {}: {}", page_url, podcast
+                        .name, e);
+                    return false;
                 }
-                podcast_episode.url = item.enclosure.as_ref().unwrap().url.to_string();
-                PodcastEpisode::update_podcast_episode(conn, podcast_episode);
             }
         }
+        true
     }
 
-    fn get_podcast_episode_by_guid(conn: &mut DbConnection, guid_to_search: &str) ->
-                                                                    Option<PodcastEpisode>{
-        use diesel::QueryDsl;
-        use diesel::ExpressionMethods;
-        use crate::dbconfig::schema::podcast_episodes::dsl::*;
-        podcast_episodes
-            .filter(guid.eq(guid_to_search))
-            .first::<PodcastEpisode>(conn)
-            .optional().expect("Error loading podcast episode")
+    fn record_failed_fetch(conn: &mut DbConnection, podcast: &Podcast, error: String)
+        -> Vec<PodcastEpisode> {
+        log::error!("Error refreshing podcast {}: {}", podcast.name, error);
+        FeedFetchReport::failed(podcast.id, error).save_report(conn);
+        Vec::new()
+    }
+
+    /// Repairs the common defects of feed items. Items that can't be turned into an episode are
+    /// rejected with the reason.
+    fn sanitize_feed_item(item: &FeedItem, feed_url: &str) -> Result<FeedItem, String> {
+        let mut enclosure = item.enclosure.clone()
+            .ok_or("Item has no enclosure".to_string())?;
+        let enclosure_url = enclosure.url.trim().to_string();
+        if enclosure_url.is_empty() {
+            return Err("Enclosure has no url".to_string());
+        }
+        // Relative enclosure urls are resolved against the feed
+        let resolved_url = Url::parse(&enclosure_url)
+            .or_else(|_| Url::parse(feed_url).and_then(|base_url| base_url.join(&enclosure_url)))
+            .map_err(|_| format!("Enclosure url {} is invalid", enclosure_url))?;
+        if resolved_url.scheme() != "http" && resolved_url.scheme() != "https" {
+            return Err(format!("Enclosure url {} is not a http url", enclosure_url));
+        }
+        enclosure.url = resolved_url.to_string();
+
+        let title = item.title.as_deref()
+            .map(|title| decode_html_entities(title.trim()))
+            .filter(|title| !title.is_empty())
+            .unwrap_or_else(|| Self::get_fallback_title(&resolved_url));
+
+        let mut sanitized_item = item.clone();
+        sanitized_item.title = Some(title);
+        sanitized_item.enclosure = Some(enclosure);
+        sanitized_item.guid = item.guid.as_deref()
+            .map(|guid| guid.trim().to_string())
+            .filter(|guid| !guid.is_empty());
+        Ok(sanitized_item)
+    }
+
+    fn get_fallback_title(enclosure_url: &Url) -> String {
+        let file_name = enclosure_url.path_segments()
+            .and_then(|mut segments| segments.rfind(|segment| !segment.is_empty()))
+            .map(|file_name| match file_name.rsplit_once('.') {
+                Some((name, _)) => name.to_string(),
+                None => file_name.to_string()
+            })
+            .map(|file_name| urlencoding::decode(&file_name)
+                .map(|decoded_name| decoded_name.to_string())
+                .unwrap_or(file_name))
+            .unwrap_or_default();
+        match file_name.trim().is_empty() {
+            true => "Untitled episode".to_string(),
+            false => file_name.trim().to_string()
+        }
+    }
+
+    /// Copies the metadata of a feed item onto an already known episode. Values the feed does not
+    /// provide are kept as they are.
+    fn apply_feed_item_to_episode(mut podcast_episode: PodcastEpisode, item: &FeedItem,
+                                  duration: i32) -> PodcastEpisode {
+        if let Some(title) = &item.title {
+            podcast_episode.name = title.to_string();
+        }
+        if let Some(description) = &item.description {
+            podcast_episode.description = description.to_string();
+        }
+        if let Some(enclosure) = &item.enclosure {
+            podcast_episode.url = enclosure.url.clone();
+        }
+        if let Some(date) = item.pub_date.as_deref().and_then(parse_feed_date) {
+            podcast_episode.date_of_recording = date.to_rfc3339();
+        }
+        if let Some(image) = &item.image_url {
+            podcast_episode.image_url = image.clone();
+        }
+        if duration > 0 {
+            podcast_episode.total_time = duration;
+        }
+        if let Some(guid) = &item.guid {
+            podcast_episode.guid = guid.clone();
+        }
+        if item.season.is_some() {
+            podcast_episode.season = item.season;
+        }
+        if item.episode_number.is_some() {
+            podcast_episode.episode_number = item.episode_number;
+        }
+        if item.chapters_url.is_some() {
+            podcast_episode.chapters_url = item.chapters_url.clone();
+        }
+        podcast_episode.removed_upstream = false;
+        podcast_episode
     }
 
     fn parse_duration(duration_str: &str) -> u32 {
         let parts: Vec<&str> = duration_str.split(":").collect();
         match parts.len() {
             1 => {
-                let seconds = parts[0].parse::<u32>().unwrap_or(0);
-                seconds
+                
+                parts[0].parse::<u32>().unwrap_or(0)
             }
             2 => {
                 let minutes = parts[0].parse::<u32>().unwrap_or(0);
@@ -338,20 +459,43 @@ impl PodcastEpisodeService {
         }
     }
 
-    pub fn get_url_file_suffix(url: &str) -> String {
-        let re = Regex::new(r#"\.(\w+)(?:\?.*)?$"#).unwrap();
-        let capture = re.captures(&url).unwrap();
-        return capture.get(1).unwrap().as_str().to_owned();
+    /// Returns the extension of the last path segment of an url or a local path, if it has one.
+    pub fn get_url_file_suffix(url: &str) -> Option<String> {
+        let path = match Url::parse(url) {
+            Ok(parsed_url) => parsed_url.path().to_string(),
+            Err(_) => url.split(['?', '#']).next().unwrap_or("").to_string()
+        };
+        let file_name = path.rsplit('/').next()?;
+        let (_, suffix) = file_name.rsplit_once('.')?;
+        if suffix.is_empty() || suffix.len() > 5 || !suffix.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        Some(suffix.to_string())
     }
 
-    pub fn query_for_podcast(&mut self, query: &str, conn:&mut DbConnection) -> Vec<PodcastEpisode> {
+    /// Determines the suffix a file is stored with. Urls without an extension fall back to the
+    /// MIME type reported for the file and finally to the given default.
+    pub fn get_file_suffix(url: &str, mime_type: Option<&str>, default_suffix: &str) -> String {
+        if let Some(suffix) = Self::get_url_file_suffix(url) {
+            return suffix;
+        }
+        mime_type
+            .and_then(|mime_type| mime_type.split(';').next())
+            .map(|mime_type| mime_type.trim().to_lowercase())
+            .and_then(|mime_type| MIME_TYPE_SUFFIXES
+                .iter()
+                .find(|(known_mime_type, _)| *known_mime_type == mime_type)
+                .map(|(_, suffix)| suffix.to_string()))
+            .unwrap_or(default_suffix.to_string())
+    }
 
-        let podcasts = Podcast::query_for_podcast(query,conn).unwrap();
-        let podcast_dto = podcasts
-            .iter()
-            .map(|podcast| self.mapping_service.map_podcastepisode_to_dto(podcast))
-            .collect::<Vec<PodcastEpisode>>();
-        return podcast_dto;
+    pub fn query_for_podcast(&mut self, query: &str, conn:&mut DbConnection) -> Vec<PodcastEpisode> {
+        SearchService::search_episodes(conn, query, &EpisodeSearchFilter::default(), 0,
+                                       MAX_SEARCH_PAGE_SIZE, &self.mapping_service)
+            .results
+            .into_iter()
+            .map(|result| result.podcast_episode)
+            .collect()
     }
 
     pub fn find_all_downloaded_podcast_episodes(&mut self, conn:&mut DbConnection, env: EnvironmentService) ->
@@ -360,12 +504,12 @@ impl PodcastEpisodeService {
         self.map_rss_podcast_episodes(env, result)
     }
 
-    fn map_rss_podcast_episodes(&mut self, env: EnvironmentService, result: Vec<PodcastEpisode>) -> Vec<PodcastEpisode> {
+    pub fn map_rss_podcast_episodes(&mut self, env: EnvironmentService, result: Vec<PodcastEpisode>) -> Vec<PodcastEpisode> {
         result
             .iter()
             .map(|podcast| {
                 let mut podcast_episode_dto = self.mapping_service.map_podcastepisode_to_dto(podcast);
-                return if podcast_episode_dto.is_downloaded() {
+                if podcast_episode_dto.is_downloaded() {
                     let local_url = self.map_to_local_url(&podcast_episode_dto.clone().local_url);
                     let local_image_url = self.map_to_local_url(&podcast_episode_dto.clone()
                         .local_image_url);
@@ -373,7 +517,7 @@ impl PodcastEpisodeService {
                     podcast_episode_dto.local_image_url = env.server_url.clone() + &local_image_url;
                     podcast_episode_dto.local_url = env.server_url.clone() + &local_url;
 
-                    return podcast_episode_dto
+                    podcast_episode_dto
                 } else {
                     podcast_episode_dto.local_image_url = podcast_episode_dto.clone().image_url;
                     podcast_episode_dto.local_url = podcast_episode_dto.clone().url;
@@ -388,7 +532,7 @@ impl PodcastEpisodeService {
     fn map_to_local_url(&mut self, url: &str) -> String {
         let splitted_url = url.split("/").collect::<Vec<&str>>();
         splitted_url.iter()
-            .map(|s| return if s.starts_with("podcasts.")|| s.starts_with("image.") {
+            .map(|s| if s.starts_with("podcasts.")|| s.starts_with("image.") {
                 s.to_string()
             } else {
                 urlencoding::encode(s).clone().to_string()
@@ -407,43 +551,16 @@ impl PodcastEpisodeService {
         self.map_rss_podcast_episodes(env, result)
     }
 
-    fn update_podcast_fields(&mut self, feed: Channel, podcast_id: i32, conn:&mut DbConnection) {
-        let itunes = feed.clone().itunes_ext;
-
-        match itunes {
-            Some(itunes) => {
-                let constructed_extra_fields = PodcastBuilder::new(podcast_id)
-                    .author(itunes.author)
-                    .last_build_date(feed.last_build_date)
-                    .description(feed.description)
-                    .language(feed.language)
-                    .keywords(itunes.categories)
-                    .build();
-
-                Podcast::update_podcast_fields(constructed_extra_fields,conn);
-            }
-            None => {}
-        }
-    }
-
-    pub fn cleanup_old_episodes(&mut self, days: i32, conn: &mut DbConnection) {
+    fn update_podcast_fields(&mut self, feed: Feed, podcast_id: i32, conn:&mut DbConnection) {
+        let constructed_extra_fields = PodcastBuilder::new(podcast_id)
+            .author(feed.author)
+            .last_build_date(feed.last_build_date)
+            .description(feed.description)
+            .language(feed.language)
+            .keywords(feed.keywords)
+            .build();
 
-        let old_podcast_episodes = PodcastEpisode::get_podcast_episodes_older_than_days(days,conn);
-
-        log::info!("Cleaning up {} old episodes", old_podcast_episodes.len());
-        for old_podcast in old_podcast_episodes {
-            let podcast = Podcast::get_podcast(conn,old_podcast.clone().podcast_id).unwrap();
-            let res = FileService::cleanup_old_episode(podcast, old_podcast.clone());
-
-            match res {
-                Ok(_) => {
-                    PodcastEpisode::update_download_status_of_episode(old_podcast.clone().id,conn);
-                }
-                Err(e) => {
-                    println!("Error deleting podcast episode.{}", e);
-                }
-            }
-        }
+        Podcast::update_podcast_fields(constructed_extra_fields,conn);
     }
 
     pub fn get_podcast_episodes_of_podcast(conn: &mut DbConnection, id_num: i32, last_id:
@@ -457,41 +574,142 @@ impl PodcastEpisodeService {
         PodcastEpisode::get_podcast_episode_by_id(conn, id_num)
     }
 
-    fn do_request_to_podcast_server(podcast:Podcast) ->RequestReturnType{
-        let is_redirected = Arc::new(Mutex::new(false)); // Variable to store the redirection status
-        let client = ClientBuilder::new().redirect(Policy::custom({
-            let is_redirected = Arc::clone(&is_redirected);
-
-            move |attempt|{
-
-                if attempt.previous().len() > 0 {
-                    *is_redirected.lock().unwrap() = true;
-                }
-                attempt.follow()
-            }
-        })).build().unwrap();
+    fn do_request_to_podcast_server(podcast:Podcast, credential_scope: &CredentialScope)
+        ->Result<RequestReturnType, String>{
+        let outbound_policy = OutboundPolicy::new();
+        outbound_policy.check_url(&podcast.rssfeed)?;
+        // Every redirect is checked against the policy and leaves the credentials behind if it
+        // leaves the origin of the feed
         let mut header_map = HeaderMap::new();
-        header_map.append(ACCEPT, "application/rss+xml,application/xml".parse().unwrap());
-        header_map.append("User-Agent", "PostmanRuntime/7.32.2".parse().unwrap());
-        let result = client
-            .get(podcast.clone().rssfeed)
-            .headers(header_map)
-            .send()
-            .unwrap();
+        header_map.append(ACCEPT, FEED_ACCEPT_HEADER.parse().unwrap());
+        let result = HttpClientFactory::for_podcast(&podcast)
+            .get_with_credentials(&podcast.rssfeed, &header_map, credential_scope)
+            .map_err(|e| format!("Error requesting feed: {}", e))?;
+        if !result.status().is_success() {
+            return Err(format!("Feed server responded with status {}", result.status()));
+        }
         let url = result.url().clone().to_string();
-        let content = result.text().unwrap().clone();
+        let content_type = result.headers().get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map(|content_type| content_type.to_string());
+        let bytes = outbound_policy.read_response(result)?;
+        let feed = parse_feed(&bytes, content_type.as_deref())?;
 
-        return RequestReturnType {
+        Ok(RequestReturnType {
             url,
-            content
-        }
+            feed
+        })
     }
 
 
 
 }
 
+const MIME_TYPE_SUFFIXES: [(&str, &str); 20] = [
+    ("audio/mpeg", "mp3"), ("audio/mp3", "mp3"), ("audio/mpeg3", "mp3"), ("audio/x-mpeg", "mp3"),
+    ("audio/mp4", "m4a"), ("audio/x-m4a", "m4a"), ("audio/aac", "aac"), ("audio/ogg", "ogg"),
+    ("audio/opus", "opus"), ("audio/wav", "wav"), ("audio/x-wav", "wav"), ("audio/flac", "flac"),
+    ("video/mp4", "mp4"), ("video/quicktime", "mov"), ("video/webm", "webm"),
+    ("video/x-m4v", "m4v"), ("image/jpeg", "jpg"), ("image/png", "png"), ("image/webp", "webp"),
+    ("image/gif", "gif"),
+];
+
 struct RequestReturnType {
     pub url:String,
-    pub content:String
+    pub feed:Feed
+}
+
+/// Resolves feed items to the already stored episodes of a podcast. The guid is the primary
+/// identity, the normalized enclosure url is used for items or episodes without one.
+struct EpisodeIdentityIndex {
+    episodes: Vec<PodcastEpisode>,
+    by_guid: HashMap<String, usize>,
+    by_url: HashMap<String, usize>
+}
+
+impl EpisodeIdentityIndex {
+    fn new(episodes: Vec<PodcastEpisode>) -> Self {
+        let mut index = EpisodeIdentityIndex {
+            episodes: Vec::new(),
+            by_guid: HashMap::new(),
+            by_url: HashMap::new()
+        };
+        for episode in episodes {
+            index.add(episode);
+        }
+        index
+    }
+
+    fn add(&mut self, episode: PodcastEpisode) {
+        let position = self.episodes.len();
+        if !episode.guid.is_empty() {
+            self.by_guid.insert(episode.guid.clone(), position);
+        }
+        self.by_url.insert(normalize_enclosure_url(&episode.url), position);
+        self.episodes.push(episode);
+    }
+
+    fn replace(&mut self, episode: PodcastEpisode) {
+        let position = self.episodes.iter().position(|e| e.id == episode.id);
+        if let Some(position) = position {
+            if !episode.guid.is_empty() {
+                self.by_guid.insert(episode.guid.clone(), position);
+            }
+            self.by_url.insert(normalize_enclosure_url(&episode.url), position);
+            self.episodes[position] = episode;
+        }
+    }
+
+    fn find(&self, guid: &str, url: &str) -> Option<PodcastEpisode> {
+        if !guid.is_empty() {
+            if let Some(position) = self.by_guid.get(guid) {
+                return Some(self.episodes[*position].clone());
+            }
+        }
+
+        let position = self.by_url.get(&normalize_enclosure_url(url))?;
+        let episode = &self.episodes[*position];
+        // A differing guid on the same url means the publisher reused a placeholder enclosure
+        if !guid.is_empty() && !episode.guid.is_empty() && episode.guid != guid {
+            return None;
+        }
+        Some(episode.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::podcast_episode::PodcastEpisode;
+    use crate::service::podcast_episode_service::EpisodeIdentityIndex;
+
+    fn get_episode(id: i32, guid: &str, url: &str) -> PodcastEpisode {
+        PodcastEpisode {
+            id,
+            guid: guid.to_string(),
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_items_without_guid_that_differ_by_query() {
+        let mut index = EpisodeIdentityIndex::new(vec![
+            get_episode(1, "", "https://example.com/download.php?id=1")]);
+        assert!(index.find("", "https://example.com/download.php?id=2").is_none());
+        index.add(get_episode(2, "", "https://example.com/download.php?id=2"));
+
+        assert_eq!(index.find("", "https://example.com/download.php?id=1").unwrap().id, 1);
+        assert_eq!(index.find("", "https://example.com/download.php?id=2&utm_source=rss")
+                       .unwrap().id, 2);
+    }
+
+    #[test]
+    fn test_find_prefers_the_guid() {
+        let index = EpisodeIdentityIndex::new(vec![
+            get_episode(1, "guid-1", "https://example.com/episode-1.mp3"),
+            get_episode(2, "guid-2", "https://example.com/episode-2.mp3")]);
+        assert_eq!(index.find("guid-2", "https://cdn.example.com/episode-2.mp3").unwrap().id, 2);
+        assert!(index.find("guid-3", "https://example.com/episode-1.mp3").is_none());
+        assert_eq!(index.find("", "https://example.com/episode-1.mp3?updated=5").unwrap().id, 1);
+    }
 }
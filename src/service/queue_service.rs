@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+use uuid::Uuid;
+use crate::DbConnection;
+use crate::exception::exceptions::PodFetchError;
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcasts::Podcast;
+use crate::models::queue::{QueueAutoAddPodcast, QueueFeedToken, QueueFeedUrls, QueueItem,
+                           QueueItemDto};
+use crate::service::environment_service::EnvironmentService;
+use crate::service::mapping_service::MappingService;
+
+/// The persisted "Up Next" queue of a user
+pub struct QueueService;
+
+impl QueueService {
+    pub fn get_queue(conn: &mut DbConnection, username: &str, mapping_service: &MappingService)
+        -> Vec<QueueItemDto> {
+        QueueItem::get_queue(conn, username)
+            .into_iter()
+            .map(|(queue_item, podcast_episode, podcast)| QueueItemDto {
+                position: queue_item.position,
+                added_at: queue_item.added_at,
+                podcast_episode: mapping_service.map_podcastepisode_to_dto(&podcast_episode),
+                podcast: mapping_service.map_podcast_to_podcast_dto(&podcast)
+            })
+            .collect()
+    }
+
+    /// Adds an episode at the position or at the end. Queued episodes are moved there.
+    pub fn add_episode(conn: &mut DbConnection, username: &str, episode_id: &str,
+                       position: Option<usize>) -> Result<(), PodFetchError> {
+        let podcast_episode = Self::find_episode(conn, episode_id)?;
+        let mut queue = QueueItem::get_queued_episode_ids(conn, username);
+        let queued = queue.contains(&podcast_episode.id);
+        if queued && position.is_none() {
+            return Ok(());
+        }
+        queue.retain(|queued_episode_id| *queued_episode_id != podcast_episode.id);
+        let position = position.unwrap_or(queue.len()).min(queue.len());
+        queue.insert(position, podcast_episode.id);
+        QueueItem::save_queue(conn, username, &queue);
+        Ok(())
+    }
+
+    pub fn remove_episode(conn: &mut DbConnection, username: &str, episode_id: &str)
+        -> Result<(), PodFetchError> {
+        let podcast_episode = Self::find_episode(conn, episode_id)?;
+        let mut queue = QueueItem::get_queued_episode_ids(conn, username);
+        if !queue.contains(&podcast_episode.id) {
+            return Err(PodFetchError::episode_not_in_queue());
+        }
+        queue.retain(|queued_episode_id| *queued_episode_id != podcast_episode.id);
+        QueueItem::save_queue(conn, username, &queue);
+        Ok(())
+    }
+
+    pub fn move_to_top(conn: &mut DbConnection, username: &str, episode_id: &str)
+        -> Result<(), PodFetchError> {
+        let podcast_episode = Self::find_episode(conn, episode_id)?;
+        let mut queue = QueueItem::get_queued_episode_ids(conn, username);
+        if !queue.contains(&podcast_episode.id) {
+            return Err(PodFetchError::episode_not_in_queue());
+        }
+        queue.retain(|queued_episode_id| *queued_episode_id != podcast_episode.id);
+        queue.insert(0, podcast_episode.id);
+        QueueItem::save_queue(conn, username, &queue);
+        Ok(())
+    }
+
+    /// Orders the queue like the given episode ids. They have to be the episodes of the queue.
+    pub fn reorder(conn: &mut DbConnection, username: &str, episode_ids: &[String])
+        -> Result<(), PodFetchError> {
+        let queue = QueueItem::get_queued_episode_ids(conn, username);
+        let mut new_queue = Vec::with_capacity(episode_ids.len());
+        for episode_id in episode_ids {
+            new_queue.push(Self::find_episode(conn, episode_id)?.id);
+        }
+        let unique_episodes = new_queue.iter().collect::<HashSet<&i32>>();
+        if new_queue.len() != queue.len() || unique_episodes.len() != queue.len()
+            || !queue.iter().all(|queued_episode_id| unique_episodes.contains(queued_episode_id)) {
+            return Err(PodFetchError::invalid_queue_order());
+        }
+        QueueItem::save_queue(conn, username, &new_queue);
+        Ok(())
+    }
+
+    pub fn clear(conn: &mut DbConnection, username: &str) {
+        QueueItem::save_queue(conn, username, &[]);
+    }
+
+    pub fn get_auto_add_podcasts(conn: &mut DbConnection, username: &str) -> Vec<i32> {
+        QueueAutoAddPodcast::get_podcast_ids(conn, username)
+    }
+
+    pub fn set_auto_add_podcast(conn: &mut DbConnection, username: &str, podcast_id: i32,
+                                auto_add: bool) -> Result<(), PodFetchError> {
+        match auto_add {
+            true => {
+                Podcast::get_podcast(conn, podcast_id)
+                    .map_err(|_| PodFetchError::podcast_not_found())?;
+                QueueAutoAddPodcast::add_podcast(conn, username, podcast_id);
+            }
+            false => QueueAutoAddPodcast::remove_podcast(conn, username, podcast_id)
+        }
+        Ok(())
+    }
+
+    /// Appends new episodes of a podcast to the queues of the users that selected it. The oldest
+    /// episode comes first.
+    pub fn append_new_episodes(conn: &mut DbConnection, podcast_id: i32,
+                               new_episodes: &[PodcastEpisode]) {
+        if new_episodes.is_empty() {
+            return;
+        }
+        let mut new_episodes = new_episodes.to_vec();
+        new_episodes.sort_by(|a, b| a.date_of_recording.cmp(&b.date_of_recording));
+        for username in QueueAutoAddPodcast::get_usernames(conn, podcast_id) {
+            let mut queue = QueueItem::get_queued_episode_ids(conn, &username);
+            for new_episode in &new_episodes {
+                if !queue.contains(&new_episode.id) {
+                    queue.push(new_episode.id);
+                }
+            }
+            log::debug!("Adding {} episodes to the queue of {}", new_episodes.len(), username);
+            QueueItem::save_queue(conn, &username, &queue);
+        }
+    }
+
+    /// The private feed urls of the queue. The token is created on first use.
+    pub fn get_feed_urls(conn: &mut DbConnection, username: &str) -> QueueFeedUrls {
+        let feed_token = match QueueFeedToken::get_by_username(conn, username) {
+            Some(feed_token) => feed_token,
+            None => Self::regenerate_feed_token(conn, username)
+        };
+        Self::map_feed_urls(&feed_token)
+    }
+
+    pub fn reset_feed_urls(conn: &mut DbConnection, username: &str) -> QueueFeedUrls {
+        let feed_token = Self::regenerate_feed_token(conn, username);
+        Self::map_feed_urls(&feed_token)
+    }
+
+    /// The queue of the user the token belongs to
+    pub fn get_queue_by_feed_token(conn: &mut DbConnection, token: &str)
+        -> Option<Vec<(PodcastEpisode, Podcast)>> {
+        let feed_token = QueueFeedToken::get_by_token(conn, token)?;
+        Some(QueueItem::get_queue(conn, &feed_token.username)
+            .into_iter()
+            .map(|(_, podcast_episode, podcast)| (podcast_episode, podcast))
+            .collect())
+    }
+
+    fn regenerate_feed_token(conn: &mut DbConnection, username: &str) -> QueueFeedToken {
+        let feed_token = QueueFeedToken {
+            username: username.to_string(),
+            token: Uuid::new_v4().simple().to_string()
+        };
+        feed_token.save_token(conn);
+        feed_token
+    }
+
+    fn map_feed_urls(feed_token: &QueueFeedToken) -> QueueFeedUrls {
+        let server_url = EnvironmentService::new().get_server_url();
+        QueueFeedUrls {
+            rss_url: format!("{}rss/queue/{}", server_url, feed_token.token),
+            m3u_url: format!("{}m3u/queue/{}", server_url, feed_token.token)
+        }
+    }
+
+    fn find_episode(conn: &mut DbConnection, episode_id: &str)
+        -> Result<PodcastEpisode, PodFetchError> {
+        PodcastEpisode::get_podcast_episode_by_id(conn, episode_id)
+            .unwrap()
+            .ok_or(PodFetchError::episode_not_found())
+    }
+}
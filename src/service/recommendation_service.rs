@@ -0,0 +1,413 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use chrono::{DateTime, Utc};
+use crate::DbConnection;
+use crate::models::episode::Episode;
+use crate::models::episode_state::EpisodeStateType;
+use crate::models::favorites::Favorite;
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcast_history_item::PodcastHistoryItem;
+use crate::models::podcasts::Podcast;
+use crate::models::recommendation::{EpisodeRecommendation, PodcastRecommendation,
+                                    RecommendationReason, RecommendationReasonType,
+                                    Recommendations};
+use crate::models::subscription::Subscription;
+use crate::service::episode_state_service::EpisodeStateService;
+use crate::service::mapping_service::MappingService;
+
+// A favorite counts as much as this many listened episodes
+const FAVORITE_WEIGHT: f64 = 3.0;
+const EPISODES_PER_PODCAST: usize = 3;
+const LANGUAGE_WEIGHT: f64 = 0.2;
+
+/// Suggests episodes and podcasts based on what the users of this instance listen to. Nothing
+/// leaves the server.
+pub struct RecommendationService;
+
+/// The listening signals of all users
+struct ListeningData {
+    // Number of listened episodes per podcast
+    listened_podcasts: HashMap<String, HashMap<i32, usize>>,
+    played_episodes: HashMap<String, HashSet<String>>,
+    // Episodes users marked as played or archived without listening to them
+    episode_states: HashMap<String, HashMap<i32, EpisodeStateType>>,
+    favorites: HashMap<String, HashSet<i32>>
+}
+
+impl ListeningData {
+    fn load(conn: &mut DbConnection) -> Self {
+        let mut listened_episodes = HashMap::<String, HashSet<(i32, String)>>::new();
+        for (username, podcast_id, episode_id) in PodcastHistoryItem::get_listened_episodes(conn)
+            .into_iter()
+            .chain(Episode::get_played_episodes(conn)) {
+            listened_episodes.entry(username).or_default().insert((podcast_id, episode_id));
+        }
+
+        let mut listening_data = ListeningData {
+            listened_podcasts: HashMap::new(),
+            played_episodes: HashMap::new(),
+            episode_states: EpisodeStateService::get_all_episode_states(conn),
+            favorites: HashMap::new()
+        };
+        for (username, episodes) in listened_episodes {
+            let mut listened_podcasts = HashMap::new();
+            let mut played_episodes = HashSet::new();
+            for (podcast_id, episode_id) in episodes {
+                *listened_podcasts.entry(podcast_id).or_insert(0) += 1;
+                played_episodes.insert(episode_id);
+            }
+            listening_data.listened_podcasts.insert(username.clone(), listened_podcasts);
+            listening_data.played_episodes.insert(username, played_episodes);
+        }
+        for favorite in Favorite::get_all_favored(conn) {
+            listening_data.favorites.entry(favorite.username).or_default()
+                .insert(favorite.podcast_id);
+        }
+        listening_data
+    }
+
+    fn get_usernames(&self) -> HashSet<&String> {
+        self.listened_podcasts.keys().chain(self.favorites.keys()).collect()
+    }
+
+    /// Podcasts the user listened to or marked as favorite
+    fn get_followed_podcasts(&self, username: &str) -> HashSet<i32> {
+        let mut followed_podcasts: HashSet<i32> = self.listened_podcasts.get(username)
+            .map(|listened_podcasts| listened_podcasts.keys().cloned().collect())
+            .unwrap_or_default();
+        if let Some(favorites) = self.favorites.get(username) {
+            followed_podcasts.extend(favorites);
+        }
+        followed_podcasts
+    }
+
+    fn get_listened_episode_count(&self, username: &str, podcast_id: i32) -> usize {
+        self.listened_podcasts.get(username)
+            .and_then(|listened_podcasts| listened_podcasts.get(&podcast_id))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    fn is_favorite(&self, username: &str, podcast_id: i32) -> bool {
+        self.favorites.get(username)
+            .map(|favorites| favorites.contains(&podcast_id))
+            .unwrap_or(false)
+    }
+
+    fn get_affinity(&self, username: &str, podcast_id: i32) -> f64 {
+        let favorite_weight = match self.is_favorite(username, podcast_id) {
+            true => FAVORITE_WEIGHT,
+            false => 0.0
+        };
+        // Podcasts that are only subscribed to still count
+        (self.get_listened_episode_count(username, podcast_id) as f64 + favorite_weight).max(1.0)
+    }
+
+    fn has_played(&self, username: &str, podcast_episode: &PodcastEpisode) -> bool {
+        self.played_episodes.get(username)
+            .map(|played_episodes| played_episodes.contains(&podcast_episode.episode_id))
+            .unwrap_or(false)
+            || self.episode_states.get(username)
+            .map(|episode_states| episode_states.contains_key(&podcast_episode.id))
+            .unwrap_or(false)
+    }
+}
+
+impl RecommendationService {
+    pub fn get_recommendations(conn: &mut DbConnection, username: &str, limit: usize,
+                               mapping_service: &MappingService) -> Recommendations {
+        let listening_data = ListeningData::load(conn);
+        let podcasts = Podcast::get_all_podcasts(conn)
+            .unwrap()
+            .into_iter()
+            .map(|podcast| (podcast.id, podcast))
+            .collect::<HashMap<i32, Podcast>>();
+        let subscribed_podcasts = Subscription::get_subscribed_podcast_ids(conn, username)
+            .into_iter()
+            .collect::<HashSet<i32>>();
+        let mut followed_podcasts = listening_data.get_followed_podcasts(username);
+        followed_podcasts.extend(&subscribed_podcasts);
+
+        let mut episodes = Self::recommend_episodes(conn, username, &listening_data, &podcasts,
+                                                    &followed_podcasts, &subscribed_podcasts);
+        episodes.truncate(limit);
+        let mut podcast_recommendations = Self::recommend_podcasts(username, &listening_data,
+                                                                   &podcasts, &followed_podcasts);
+        podcast_recommendations.truncate(limit);
+
+        Recommendations {
+            episodes: episodes.into_iter()
+                .map(|mut recommendation| {
+                    recommendation.podcast_episode = mapping_service
+                        .map_podcastepisode_to_dto(&recommendation.podcast_episode);
+                    recommendation.podcast = mapping_service
+                        .map_podcast_to_podcast_dto(&recommendation.podcast);
+                    recommendation
+                })
+                .collect(),
+            podcasts: podcast_recommendations.into_iter()
+                .map(|mut recommendation| {
+                    recommendation.podcast = mapping_service
+                        .map_podcast_to_podcast_dto(&recommendation.podcast);
+                    recommendation
+                })
+                .collect()
+        }
+    }
+
+    /// The newest unplayed episodes of the followed podcasts. Podcasts the user listens to a lot
+    /// and episodes other users played rank higher.
+    fn recommend_episodes(conn: &mut DbConnection, username: &str, listening_data: &ListeningData,
+                          podcasts: &HashMap<i32, Podcast>, followed_podcasts: &HashSet<i32>,
+                          subscribed_podcasts: &HashSet<i32>) -> Vec<EpisodeRecommendation> {
+        let max_affinity = followed_podcasts.iter()
+            .map(|podcast_id| listening_data.get_affinity(username, *podcast_id))
+            .fold(1.0, f64::max);
+        let mut other_listeners = HashMap::<&String, usize>::new();
+        for (other_username, played_episodes) in &listening_data.played_episodes {
+            if other_username != username {
+                for episode_id in played_episodes {
+                    *other_listeners.entry(episode_id).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut recommendations = vec![];
+        for podcast_id in followed_podcasts {
+            let podcast = match podcasts.get(podcast_id) {
+                Some(podcast) => podcast,
+                None => continue
+            };
+            let podcast_episodes = PodcastEpisode::get_podcast_episodes_of_podcast(conn,
+                                                                                   *podcast_id,
+                                                                                   None)
+                .unwrap()
+                .into_iter()
+                .filter(|podcast_episode| !listening_data.has_played(username, podcast_episode))
+                .filter(|podcast_episode| !podcast_episode.removed_upstream
+                    || podcast_episode.is_downloaded())
+                .take(EPISODES_PER_PODCAST);
+            for podcast_episode in podcast_episodes {
+                let mut reasons = vec![];
+                if listening_data.is_favorite(username, podcast.id) {
+                    reasons.push(RecommendationReason {
+                        reason_type: RecommendationReasonType::Favorite,
+                        message: format!("You marked {} as favorite", podcast.name),
+                        related_podcast_id: Some(podcast.id)
+                    });
+                }
+                let listened_episodes = listening_data.get_listened_episode_count(username,
+                                                                                  podcast.id);
+                if listened_episodes > 0 {
+                    reasons.push(RecommendationReason {
+                        reason_type: RecommendationReasonType::ListeningHistory,
+                        message: format!("You listened to {} of {}",
+                                         Self::pluralize(listened_episodes, "episode", "episodes"),
+                                         podcast.name),
+                        related_podcast_id: Some(podcast.id)
+                    });
+                } else if subscribed_podcasts.contains(&podcast.id) {
+                    reasons.push(RecommendationReason {
+                        reason_type: RecommendationReasonType::Subscription,
+                        message: format!("You subscribed to {}", podcast.name),
+                        related_podcast_id: Some(podcast.id)
+                    });
+                }
+                let listeners = other_listeners.get(&podcast_episode.episode_id).cloned()
+                    .unwrap_or(0);
+                if listeners > 0 {
+                    reasons.push(RecommendationReason {
+                        reason_type: RecommendationReasonType::CoListening,
+                        message: format!("{} played this episode",
+                                         Self::pluralize(listeners, "other listener",
+                                                         "other listeners")),
+                        related_podcast_id: None
+                    });
+                }
+
+                let score = listening_data.get_affinity(username, podcast.id) / max_affinity
+                    + Self::get_recency(&podcast_episode.date_of_recording)
+                    + (listeners as f64).ln_1p();
+                recommendations.push(EpisodeRecommendation {
+                    podcast_episode,
+                    podcast: podcast.clone(),
+                    score,
+                    reasons
+                });
+            }
+        }
+        Self::sort_by_score(&mut recommendations, |recommendation| recommendation.score);
+        recommendations
+    }
+
+    /// Podcasts of the library the user doesn't follow. They are ranked by how many users with
+    /// similar podcasts follow them and by how similar their keywords are.
+    fn recommend_podcasts(username: &str, listening_data: &ListeningData,
+                          podcasts: &HashMap<i32, Podcast>, followed_podcasts: &HashSet<i32>)
+        -> Vec<PodcastRecommendation> {
+        // Score, number of similar users and the shared podcasts they follow
+        let mut co_listening = HashMap::<i32, (f64, usize, HashMap<i32, usize>)>::new();
+        let mut followers = HashMap::<i32, usize>::new();
+        for other_username in listening_data.get_usernames() {
+            if other_username == username {
+                continue;
+            }
+            let other_podcasts = listening_data.get_followed_podcasts(other_username);
+            for podcast_id in &other_podcasts {
+                *followers.entry(*podcast_id).or_insert(0) += 1;
+            }
+            let shared_podcasts = other_podcasts.intersection(followed_podcasts)
+                .cloned()
+                .collect::<Vec<i32>>();
+            if shared_podcasts.is_empty() {
+                continue;
+            }
+            let similarity = shared_podcasts.len() as f64 / other_podcasts.len() as f64;
+            for podcast_id in other_podcasts.difference(followed_podcasts) {
+                let entry = co_listening.entry(*podcast_id).or_default();
+                entry.0 += similarity;
+                entry.1 += 1;
+                for shared_podcast in &shared_podcasts {
+                    *entry.2.entry(*shared_podcast).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let followed_keywords = followed_podcasts.iter()
+            .filter_map(|podcast_id| podcasts.get(podcast_id))
+            .map(|podcast| (podcast, Self::get_keywords(podcast)))
+            .filter(|(_, keywords)| !keywords.is_empty())
+            .collect::<Vec<(&Podcast, BTreeSet<String>)>>();
+        let followed_languages = followed_podcasts.iter()
+            .filter_map(|podcast_id| podcasts.get(podcast_id))
+            .filter_map(Self::get_language)
+            .collect::<HashSet<String>>();
+
+        let mut recommendations = vec![];
+        for podcast in podcasts.values() {
+            if followed_podcasts.contains(&podcast.id) {
+                continue;
+            }
+            let mut score = 0.0;
+            let mut reasons = vec![];
+
+            if let Some((co_listening_score, listeners, shared_podcasts)) =
+                co_listening.get(&podcast.id) {
+                let related_podcast = shared_podcasts.iter()
+                    .max_by_key(|(podcast_id, count)| (**count, -**podcast_id))
+                    .and_then(|(podcast_id, _)| podcasts.get(podcast_id));
+                if let Some(related_podcast) = related_podcast {
+                    score += co_listening_score;
+                    reasons.push(RecommendationReason {
+                        reason_type: RecommendationReasonType::CoListening,
+                        message: format!("{} of {} also {} to this podcast",
+                                         Self::pluralize(*listeners, "listener", "listeners"),
+                                         related_podcast.name,
+                                         if *listeners == 1 { "listens" } else { "listen" }),
+                        related_podcast_id: Some(related_podcast.id)
+                    });
+                }
+            }
+
+            let keywords = Self::get_keywords(podcast);
+            let most_similar = followed_keywords.iter()
+                .map(|(followed_podcast, followed_keywords)| {
+                    let shared_keywords = keywords.intersection(followed_keywords)
+                        .cloned()
+                        .collect::<Vec<String>>();
+                    let similarity = shared_keywords.len() as f64
+                        / keywords.union(followed_keywords).count().max(1) as f64;
+                    (similarity, shared_keywords, followed_podcast)
+                })
+                .filter(|(similarity, _, _)| *similarity > 0.0)
+                .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+            if let Some((similarity, shared_keywords, followed_podcast)) = most_similar {
+                score += similarity;
+                reasons.push(RecommendationReason {
+                    reason_type: RecommendationReasonType::Keywords,
+                    message: format!("Shares the keywords {} with {}",
+                                     shared_keywords.iter().take(3).cloned()
+                                         .collect::<Vec<String>>().join(", "),
+                                     followed_podcast.name),
+                    related_podcast_id: Some(followed_podcast.id)
+                });
+            }
+
+            // Users without any history get the podcasts most users follow
+            if followed_podcasts.is_empty() {
+                if let Some(followers) = followers.get(&podcast.id) {
+                    score += *followers as f64;
+                    reasons.push(RecommendationReason {
+                        reason_type: RecommendationReasonType::Popular,
+                        message: format!("{} on this instance {} this podcast",
+                                         Self::pluralize(*followers, "user", "users"),
+                                         if *followers == 1 { "follows" } else { "follow" }),
+                        related_podcast_id: None
+                    });
+                }
+            }
+
+            // The language alone isn't a reason to recommend a podcast
+            if reasons.is_empty() {
+                continue;
+            }
+            if let Some(language) = Self::get_language(podcast)
+                .filter(|language| followed_languages.contains(language)) {
+                score += LANGUAGE_WEIGHT;
+                reasons.push(RecommendationReason {
+                    reason_type: RecommendationReasonType::Language,
+                    message: format!("In the same language ({}) as podcasts you listen to",
+                                     language),
+                    related_podcast_id: None
+                });
+            }
+            recommendations.push(PodcastRecommendation {
+                podcast: podcast.clone(),
+                score,
+                reasons
+            });
+        }
+        Self::sort_by_score(&mut recommendations, |recommendation| recommendation.score);
+        recommendations
+    }
+
+    fn get_keywords(podcast: &Podcast) -> BTreeSet<String> {
+        podcast.keywords.as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|keyword| keyword.trim().to_lowercase())
+            .filter(|keyword| !keyword.is_empty())
+            .collect()
+    }
+
+    // en-US and en are the same language here
+    fn get_language(podcast: &Podcast) -> Option<String> {
+        podcast.language.as_deref()
+            .and_then(|language| language.split(['-', '_']).next())
+            .map(|language| language.trim().to_lowercase())
+            .filter(|language| !language.is_empty())
+    }
+
+    // 1 for episodes published today, 0.5 for a week old ones and so on
+    fn get_recency(date_of_recording: &str) -> f64 {
+        match DateTime::parse_from_rfc3339(date_of_recording) {
+            Ok(date) => {
+                let days = (Utc::now() - date.with_timezone(&Utc)).num_days().max(0);
+                1.0 / (1.0 + days as f64 / 7.0)
+            }
+            Err(_) => 0.0
+        }
+    }
+
+    fn sort_by_score<T>(recommendations: &mut [T], score: fn(&T) -> f64) {
+        recommendations.sort_by(|a, b| score(b).partial_cmp(&score(a))
+            .unwrap_or(Ordering::Equal));
+    }
+
+    fn pluralize(count: usize, singular: &str, plural: &str) -> String {
+        match count {
+            1 => format!("1 {}", singular),
+            _ => format!("{} {}", count, plural)
+        }
+    }
+}
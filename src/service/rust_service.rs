@@ -1,6 +1,6 @@
 use std::io::Error;
 use std::sync::{MutexGuard};
-use crate::constants::constants::{PodcastType, ITUNES_URL};
+use crate::constants::constants::PodcastType;
 use crate::models::podcast_dto::PodcastDto;
 use crate::models::podcasts::Podcast;
 
@@ -8,19 +8,20 @@ use crate::models::messages::BroadcastMessage;
 use crate::models::models::PodcastInsertModel;
 use crate::models::web_socket_message::Lobby;
 use crate::service::environment_service::EnvironmentService;
+use crate::service::credential_service::CredentialService;
+use crate::service::discovery::DiscoveryService;
+use crate::service::episode_state_service::EpisodeStateService;
 use crate::service::file_service::FileService;
+use crate::service::http_client::HttpClientFactory;
 use crate::service::mapping_service::MappingService;
+use crate::service::playback_preference_service::PlaybackPreferenceService;
 use crate::service::podcast_episode_service::PodcastEpisodeService;
-use crate::{DbConnection, unwrap_string};
+use crate::{DbConnection, get_default_image};
 use actix::Addr;
 use actix_web::web::Data;
-use reqwest::header::{HeaderMap, HeaderValue};
-use reqwest::{Client, ClientBuilder as AsyncClientBuilder};
-use serde_json::Value;
-use sha1::{Digest, Sha1};
-use std::time::SystemTime;
+use reqwest::Client;
+use rand::Rng;
 
-use serde::Serialize;
 use tokio::task::spawn_blocking;
 use crate::config::dbconfig::establish_connection;
 use crate::exception::exceptions::{PodFetchError};
@@ -38,70 +39,42 @@ pub struct PodcastService {
 impl PodcastService {
     pub fn new() -> PodcastService {
         PodcastService {
-            client: AsyncClientBuilder::new().build().unwrap(),
+            client: HttpClientFactory::new().build_async_client(),
             podcast_episode_service: PodcastEpisodeService::new(),
             environment_service: EnvironmentService::new(),
         }
     }
 
-    pub async fn find_podcast(&mut self, podcast: &str) -> Value {
-        let result = self
-            .client
-            .get(ITUNES_URL.to_owned() + podcast)
-            .send()
-            .await
-            .unwrap();
-        log::info!("Found podcast: {}", result.url());
-        let res_of_search =  result.json().await;
-        return if res_of_search.is_err() {
-            log::error!("Error searching for podcast: {}", res_of_search.err().unwrap());
-            serde_json::from_str("{}").unwrap()
-        } else {
-            res_of_search.unwrap()
-        }
-    }
-
-    pub async fn find_podcast_on_podindex(&mut self, podcast: &str) -> Value {
-        let headers = self.compute_podindex_header();
-        let result = self
-            .client
-            .get("https://api.podcastindex.org/api/1.0/search/byterm?q=".to_owned() + podcast)
-            .headers(headers)
-            .send()
-            .await
-            .unwrap();
-
-        log::info!("Found podcast: {}", result.url());
-        return result.json().await.unwrap();
-    }
-
-    pub async fn insert_podcast_from_podindex(&mut self, conn: &mut DbConnection, id: i32,
-                                              lobby: Data<Addr<Lobby>>) ->Result<Podcast,
-        PodFetchError>{
-        let mapping_service = MappingService::new();
-        let resp = self
-            .client
-            .get(
-                "https://api.podcastindex.org/api/1.0/podcasts/byfeedid?id=".to_owned()
-                    + &id.to_string(),
-            )
-            .headers(self.compute_podindex_header())
-            .send()
-            .await
-            .unwrap();
-
-        println!("Result: {:?}", resp);
-
-        let podcast = resp.json::<Value>().await.unwrap();
+    /// Subscribes to a podcast found by a discovery provider.
+    pub async fn insert_discovered_podcast(&mut self, conn: &mut DbConnection, provider_id: &str,
+                                           id: &str, lobby: Data<Addr<Lobby>>)
+        -> Result<Podcast, PodFetchError> {
+        let discovery_service = DiscoveryService::new();
+        let discovered_podcast = match discovery_service.get_provider(provider_id) {
+            Ok(provider) => provider.lookup(id).await,
+            Err(e) => Err(e)
+        };
+        let discovered_podcast = match discovered_podcast {
+            Ok(Some(discovered_podcast)) => discovered_podcast,
+            Ok(None) => return Err(PodFetchError::podcast_not_found_in_directory()),
+            Err(e) => {
+                log::error!("Error looking up podcast {} of {}: {}", id, provider_id, e);
+                return Err(PodFetchError::podcast_not_found_in_directory())
+            }
+        };
+        // Numeric directory ids are kept so that the podcast directory matches the old layout
+        let podcast_id = discovered_podcast.id.parse::<i32>()
+            .unwrap_or(rand::thread_rng().gen_range(100..10000000));
 
         self.handle_insert_of_podcast(conn,
             PodcastInsertModel {
-                title: unwrap_string(&podcast["feed"]["title"]),
-                id,
-                feed_url: unwrap_string(&podcast["feed"]["url"]),
-                image_url: unwrap_string(&podcast["feed"]["image"]),
+                title: discovered_podcast.title,
+                id: podcast_id,
+                feed_url: discovered_podcast.feed_url,
+                image_url: discovered_podcast.image_url.unwrap_or(get_default_image()),
+                credentials: None,
             },
-            mapping_service,
+            MappingService::new(),
             lobby,
         )
         .await
@@ -113,15 +86,23 @@ impl PodcastService {
         podcast_insert: PodcastInsertModel,
         mapping_service: MappingService,
         lobby: Data<Addr<Lobby>>) ->Result<Podcast,PodFetchError>{
-        let opt_podcast = Podcast::find_by_rss_feed_url(conn, &podcast_insert.feed_url.clone() );
+        // Credentials are kept out of the feed url so that they aren't shown or logged
+        let (feed_url, url_credentials) = CredentialService::split_credentials_from_url(
+            &podcast_insert.feed_url);
+        let credentials = podcast_insert.credentials.clone().or(url_credentials);
+        let opt_podcast = Podcast::find_by_rss_feed_url(conn, &feed_url);
         if opt_podcast.is_some() {
             return Err(PodFetchError::podcast_already_exists())
         }
 
         let fileservice = FileService::new();
 
-        let podcast_directory_created = FileService::create_podcast_directory_exists(&podcast_insert.title.clone(),
-                                                                                     &podcast_insert.id.clone().to_string(), conn);
+        let podcast_title = podcast_insert.title.clone();
+        let podcast_id = podcast_insert.id.to_string();
+        let podcast_directory_created = spawn_blocking(move || FileService::create_podcast_directory_exists(
+            &podcast_title, &podcast_id, &mut establish_connection()))
+            .await
+            .unwrap_or_else(|e| Err(Error::other(e)));
 
         if podcast_directory_created.is_err() {
                 log::error!("Error creating podcast directory");
@@ -131,16 +112,20 @@ impl PodcastService {
             conn,
             podcast_insert.title,
             podcast_insert.id.to_string(),
-            podcast_insert.feed_url,
+            feed_url,
             podcast_insert.image_url.clone(),
             podcast_directory_created.unwrap()
         );
+        if let Some(credentials) = &credentials {
+            CredentialService::save_credentials(conn, inserted_podcast.id, credentials);
+        }
 
         fileservice
             .download_podcast_image(&inserted_podcast.directory_name.clone().to_string(), &podcast_insert
-                .image_url.clone().to_string(), &podcast_insert.id.clone().to_string(), conn)
+                .image_url.clone().to_string(), &podcast_insert.id.clone().to_string(),
+                                    CredentialService::get_credential_scope(conn, &inserted_podcast), conn)
             .await;
-        let podcast = Podcast::get_podcast_by_track_id(conn, podcast_insert.id.clone())
+        let podcast = Podcast::get_podcast_by_track_id(conn, podcast_insert.id)
             .unwrap();
         lobby
             .get_ref()
@@ -163,7 +148,7 @@ impl PodcastService {
                     let mut podcast_episode_service = PodcastEpisodeService::new();
                     log::debug!("Inserting podcast episodes: {}", podcast.name);
                     let inserted_podcasts =
-                        podcast_episode_service.insert_podcast_episodes(&mut conn, podcast.clone());
+                        podcast_episode_service.insert_full_history(&mut conn, podcast.clone());
 
                     lobby.get_ref().do_send(BroadcastMessage {
                         podcast_episode: None,
@@ -221,6 +206,14 @@ impl PodcastService {
         self.schedule_episode_download(podcast.clone(), Some(lobby.clone()), conn);
     }
 
+    pub fn fetch_full_history(&mut self, podcast: Podcast, lobby: Data<Addr<Lobby>>, conn:&mut
+    DbConnection) {
+        log::info!("Fetching the full history of podcast: {}", podcast.name);
+        self.podcast_episode_service
+            .insert_full_history(conn, podcast.clone());
+        self.schedule_episode_download(podcast.clone(), Some(lobby.clone()), conn);
+    }
+
     pub fn update_favor_podcast(&mut self, id: i32, x: bool, username: String, conn: &mut DbConnection) {
         Favorite::update_podcast_favor(&id, x, conn,username).unwrap();
     }
@@ -240,75 +233,66 @@ impl PodcastService {
         Podcast::update_podcast_active(conn, id);
     }
 
-    fn compute_podindex_header(&mut self) -> HeaderMap {
-        let seconds = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let mut headers = HeaderMap::new();
-        let non_hashed_string = self.environment_service.podindex_api_key.clone().to_owned()
-            + &*self.environment_service.podindex_api_secret.clone()
-            + &seconds.to_string();
-        let mut hasher = Sha1::new();
-
-        hasher.update(non_hashed_string);
-
-        let hashed_auth_key = format!("{:x}", hasher.finalize());
-
-        headers.insert("User-Agent", HeaderValue::from_str("Podfetch").unwrap());
-        headers.insert(
-            "X-Auth-Key",
-            HeaderValue::from_str(&self.environment_service.podindex_api_key.clone()).unwrap(),
-        );
-        headers.insert(
-            "X-Auth-Date",
-            HeaderValue::from_str(&seconds.to_string()).unwrap(),
-        );
-        headers.insert(
-            "Authorization",
-            HeaderValue::from_str(&hashed_auth_key).unwrap(),
-        );
-
-        headers
-    }
-
     pub fn get_podcast(conn: &mut DbConnection, podcast_id_to_be_searched: i32)->Result<Podcast, Error>{
         Podcast::get_podcast(conn, podcast_id_to_be_searched)
     }
 
     pub fn get_podcasts(conn: &mut DbConnection, u: String, mapping_service: MutexGuard<MappingService>) ->
                                                                           Result<Vec<PodcastDto>, String> {
-        Podcast::get_podcasts(conn, u, mapping_service)
+        let podcasts = Podcast::get_podcasts(conn, u.clone(), mapping_service)?;
+        Ok(Self::add_user_data(conn, &u, podcasts))
     }
 
     pub fn search_podcasts_favored(
-        &mut self, order:OrderCriteria, title: Option<String>, latest_pub: OrderOption,
+        order:OrderCriteria, title: Option<String>, latest_pub: OrderOption,
         mapping_service: MutexGuard<MappingService>, conn: &mut
         DbConnection,
-        designated_username: String) -> Result<Vec<impl Serialize>, String>{
+        designated_username: String, tagged_podcast_ids: Option<Vec<i32>>)
+        -> Result<Vec<PodcastDto>, String>{
         let podcasts = Favorite::search_podcasts_favored(conn, order, title, latest_pub,
-                                                   designated_username);
+                                                   designated_username.clone(),
+                                                   tagged_podcast_ids);
         let mut podcast_dto_vec = Vec::new();
         for podcast in podcasts {
             let podcast_dto = mapping_service.map_podcast_to_podcast_dto_with_favorites_option(&podcast);
             podcast_dto_vec.push(podcast_dto);
         }
-        Ok(podcast_dto_vec)
+        Ok(Self::add_user_data(conn, &designated_username, podcast_dto_vec))
     }
 
 
-    pub fn search_podcasts(&mut self, order:OrderCriteria, mapping_service:
+    pub fn search_podcasts(order:OrderCriteria, mapping_service:
     MutexGuard<MappingService>,title:
                            Option<String>, latest_pub: OrderOption,conn: &mut
         DbConnection,
-                           designated_username: String) -> Result<Vec<PodcastDto>, String>{
-
-        let podcasts = Favorite::search_podcasts(conn, order, title, latest_pub, designated_username);
+                           designated_username: String, tagged_podcast_ids: Option<Vec<i32>>)
+        -> Result<Vec<PodcastDto>, String>{
+        let podcasts = Favorite::search_podcasts(conn, order, title, latest_pub,
+                                                 designated_username.clone(), tagged_podcast_ids);
         let mapped_result = podcasts
             .iter()
-            .map(|podcast| return mapping_service.map_podcast_to_podcast_dto_with_favorites
-            (&*podcast))
+            .map(|podcast| mapping_service.map_podcast_to_podcast_dto_with_favorites
+            (podcast))
             .collect::<Vec<PodcastDto>>();
-        Ok(mapped_result)
+        Ok(Self::add_user_data(conn, &designated_username, mapped_result))
+    }
+
+    /// Adds the unplayed episode counts and playback preferences of the user
+    fn add_user_data(conn: &mut DbConnection, username: &str, podcasts: Vec<PodcastDto>)
+        -> Vec<PodcastDto> {
+        let unplayed_counts = EpisodeStateService::get_unplayed_counts(conn, username);
+        let mut playback_preferences = PlaybackPreferenceService::get_custom_preferences(conn,
+                                                                                         username);
+        podcasts
+            .into_iter()
+            .map(|mut podcast| {
+                podcast.unplayed_episodes = unplayed_counts.get(&podcast.id).cloned()
+                    .unwrap_or(0);
+                if let Some(preferences) = playback_preferences.remove(&podcast.id) {
+                    podcast.playback_preferences = preferences;
+                }
+                podcast
+            })
+            .collect()
     }
 }
@@ -0,0 +1,81 @@
+use crate::constants::constants::MAX_SEARCH_PAGE_SIZE;
+use crate::DbConnection;
+use crate::models::search::{EpisodeSearchFilter, EpisodeSearchResult, PodcastSearchResult};
+use crate::service::mapping_service::MappingService;
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeSearchPage {
+    pub results: Vec<EpisodeSearchResult>,
+    pub offset: i64,
+    pub has_more: bool
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PodcastSearchPage {
+    pub results: Vec<PodcastSearchResult>,
+    pub offset: i64,
+    pub has_more: bool
+}
+
+/// Ranked full text search backed by FTS5 on SQLite and tsvector indexes on Postgres.
+pub struct SearchService;
+
+impl SearchService {
+    pub fn search_episodes(conn: &mut DbConnection, query: &str, filter: &EpisodeSearchFilter,
+                           offset: i64, limit: i64, mapping_service: &MappingService)
+        -> EpisodeSearchPage {
+        let terms = Self::get_search_terms(query);
+        if terms.is_empty() {
+            return EpisodeSearchPage { results: vec![], offset, has_more: false };
+        }
+        let limit = Self::get_limit(limit);
+        // One more result than requested tells whether there is another page
+        let mut results = EpisodeSearchResult::search(conn, &terms, filter, limit + 1,
+                                                      offset.max(0));
+        let has_more = results.len() as i64 > limit;
+        results.truncate(limit as usize);
+        let results = results.into_iter()
+            .map(|mut result| {
+                result.podcast_episode = mapping_service
+                    .map_podcastepisode_to_dto(&result.podcast_episode);
+                result
+            })
+            .collect();
+        EpisodeSearchPage { results, offset, has_more }
+    }
+
+    pub fn search_podcasts(conn: &mut DbConnection, query: &str, offset: i64, limit: i64,
+                           mapping_service: &MappingService) -> PodcastSearchPage {
+        let terms = Self::get_search_terms(query);
+        if terms.is_empty() {
+            return PodcastSearchPage { results: vec![], offset, has_more: false };
+        }
+        let limit = Self::get_limit(limit);
+        let mut results = PodcastSearchResult::search(conn, &terms, limit + 1, offset.max(0));
+        let has_more = results.len() as i64 > limit;
+        results.truncate(limit as usize);
+        let results = results.into_iter()
+            .map(|mut result| {
+                result.podcast = mapping_service.map_podcast_to_podcast_dto(&result.podcast);
+                result
+            })
+            .collect();
+        PodcastSearchPage { results, offset, has_more }
+    }
+
+    fn get_limit(limit: i64) -> i64 {
+        limit.clamp(1, MAX_SEARCH_PAGE_SIZE)
+    }
+
+    // Everything but letters and digits separates terms, so user input never reaches the query
+    // syntax of the database
+    fn get_search_terms(query: &str) -> Vec<String> {
+        query.split(|c: char| !c.is_alphanumeric())
+            .filter(|term| !term.is_empty())
+            .map(|term| term.to_lowercase())
+            .collect()
+    }
+}
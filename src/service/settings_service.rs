@@ -1,8 +1,11 @@
 
-use crate::constants::constants::ERR_SETTINGS_FORMAT;
-use crate::controllers::settings_controller::{UpdateNameSettings};
+use crate::constants::constants::NAMING_PREVIEW_LIMIT;
+use crate::controllers::settings_controller::{NamingPreview, UpdateNameSettings};
 use crate::DbConnection;
+use crate::models::podcast_episode::PodcastEpisode;
 use crate::models::settings::Setting;
+use crate::service::file_service::{parse_episode_format, parse_podcast_format,
+                                   render_episode_directory, render_podcast_directory};
 
 #[derive(Clone)]
 pub struct SettingsService{
@@ -30,22 +33,45 @@ impl SettingsService{
             return  Err(res.err().unwrap());
         }
 
+        Self::apply_name_settings(&mut settings_, update_model);
+        Ok(self.update_settings(settings_, conn))
+    }
+
+    /// Renders the formats for the newest episodes without saving the settings
+    pub fn preview_name(&mut self, update_model: UpdateNameSettings, conn: &mut DbConnection)
+        -> Result<Vec<NamingPreview>, String> {
+        let update_model = Self::validate_settings(update_model)?;
+        let mut settings_ = self.get_settings(conn).unwrap();
+        Self::apply_name_settings(&mut settings_, update_model);
+
+        let previews = PodcastEpisode::get_latest_episodes(conn, NAMING_PREVIEW_LIMIT)
+            .into_iter()
+            .map(|(podcast_episode, podcast)| NamingPreview {
+                path: format!("{}/{}",
+                              render_podcast_directory(&podcast.name, &podcast.directory_id,
+                                                       &settings_),
+                              render_episode_directory(&podcast_episode, &podcast, &settings_)),
+                podcast_name: podcast.name,
+                episode_name: podcast_episode.name
+            })
+            .collect();
+        Ok(previews)
+    }
+
+    fn apply_name_settings(settings_: &mut Setting, update_model: UpdateNameSettings) {
         settings_.replace_invalid_characters = update_model.replace_invalid_characters;
         settings_.use_existing_filename = update_model.use_existing_filenames;
         settings_.replacement_strategy = update_model.replacement_strategy.to_string();
         settings_.episode_format = update_model.episode_format;
         settings_.podcast_format = update_model.podcast_format;
-        Ok(self.update_settings(settings_, conn))
     }
 
 
     fn validate_settings(update_setttings: UpdateNameSettings)->Result<UpdateNameSettings, String>{
-        if !update_setttings.podcast_format.contains("{}"){
-            return Err(ERR_SETTINGS_FORMAT.to_string())
-        }
-        if !update_setttings.episode_format.contains("{}"){
-            return Err(ERR_SETTINGS_FORMAT.to_string())
-        }
-        return Ok(update_setttings)
+        parse_podcast_format(&update_setttings.podcast_format)
+            .map_err(|e| format!("Invalid podcast format: {}", e))?;
+        parse_episode_format(&update_setttings.episode_format)
+            .map_err(|e| format!("Invalid episode format: {}", e))?;
+        Ok(update_setttings)
     }
 }
\ No newline at end of file
@@ -0,0 +1,197 @@
+use base64::Engine;
+use base64::engine::general_purpose;
+use chrono::{Duration, NaiveDateTime, Utc};
+use ring::digest::{digest, SHA256};
+use ring::hmac;
+use uuid::Uuid;
+use crate::constants::constants::{DEFAULT_SHARE_VALIDITY_DAYS, MAX_SHARE_VALIDITY_DAYS,
+                                  SHARE_MEDIA_URL_VALIDITY_SECONDS};
+use crate::DbConnection;
+use crate::exception::exceptions::PodFetchError;
+use crate::models::episode_share::{EpisodeShare, EpisodeShareCreateModel, EpisodeShareDto};
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcasts::Podcast;
+use crate::service::bookmark_service::BookmarkService;
+use crate::service::credential_service::CredentialService;
+use crate::service::environment_service::EnvironmentService;
+use crate::service::mapping_service::MappingService;
+
+/// Public links to single episodes for people without an account. The share page only knows
+/// its own episode and plays it through a signed media url that expires after a few hours.
+pub struct ShareService;
+
+impl ShareService {
+    pub fn get_shares(conn: &mut DbConnection, username: &str, mapping_service: &MappingService)
+        -> Vec<EpisodeShareDto> {
+        let now = Utc::now().naive_utc();
+        EpisodeShare::get_shares_of_user(conn, username)
+            .into_iter()
+            .map(|(share, podcast_episode, podcast)|
+                Self::map_share(share, &podcast_episode, &podcast, now, mapping_service))
+            .collect()
+    }
+
+    pub fn create_share(conn: &mut DbConnection, username: &str, episode_id: &str,
+                        share: &EpisodeShareCreateModel, mapping_service: &MappingService)
+        -> Result<EpisodeShareDto, PodFetchError> {
+        let podcast_episode = PodcastEpisode::get_podcast_episode_by_id(conn, episode_id)
+            .unwrap()
+            .ok_or(PodFetchError::episode_not_found())?;
+        let podcast = Podcast::get_podcast(conn, podcast_episode.podcast_id)
+            .map_err(|_| PodFetchError::podcast_not_found())?;
+        let now = Utc::now().naive_utc();
+        let start_position = share.start_position.unwrap_or(0);
+        let expires_at = share.expires_at
+            .unwrap_or(now + Duration::days(DEFAULT_SHARE_VALIDITY_DAYS));
+        if start_position < 0 || expires_at <= now
+            || expires_at > now + Duration::days(MAX_SHARE_VALIDITY_DAYS) {
+            return Err(PodFetchError::invalid_share());
+        }
+
+        let created_share = EpisodeShare::insert_share(conn, &Uuid::new_v4().simple().to_string(),
+                                                       username, podcast_episode.id,
+                                                       start_position, expires_at, now);
+        Ok(Self::map_share(created_share, &podcast_episode, &podcast, now, mapping_service))
+    }
+
+    /// Revoking deletes the share, its page and media url stop working right away
+    pub fn revoke_share(conn: &mut DbConnection, username: &str, share_id: i32)
+        -> Result<(), PodFetchError> {
+        EpisodeShare::get_share_of_user(conn, share_id, username)
+            .ok_or(PodFetchError::share_not_found())?;
+        EpisodeShare::delete_share(conn, share_id);
+        Ok(())
+    }
+
+    /// The share of the token unless it expired
+    pub fn get_active_share(conn: &mut DbConnection, token: &str)
+        -> Option<(EpisodeShare, PodcastEpisode, Podcast)> {
+        EpisodeShare::get_by_token(conn, token)
+            .filter(|(share, _, _)| share.expires_at > Utc::now().naive_utc())
+    }
+
+    pub fn delete_expired_shares(conn: &mut DbConnection) {
+        let deleted_shares = EpisodeShare::delete_expired(conn, Utc::now().naive_utc());
+        if deleted_shares > 0 {
+            log::info!("Deleted {} expired share links", deleted_shares);
+        }
+    }
+
+    /// A media url of the share that is valid for a few hours, but never longer than the share
+    pub fn get_signed_media_url(share: &EpisodeShare) -> String {
+        let expires = (Utc::now().naive_utc()
+            + Duration::seconds(SHARE_MEDIA_URL_VALIDITY_SECONDS))
+            .min(share.expires_at)
+            .timestamp();
+        format!("{}share/{}/media?expires={}&signature={}",
+                EnvironmentService::new().get_server_url(), share.token, expires,
+                Self::sign(&share.token, expires))
+    }
+
+    pub fn verify_media_signature(token: &str, expires: i64, signature: &str) -> bool {
+        if expires < Utc::now().timestamp() {
+            return false;
+        }
+        match general_purpose::URL_SAFE_NO_PAD.decode(signature) {
+            Ok(signature) => hmac::verify(&Self::get_signing_key(),
+                                          Self::signed_message(token, expires).as_bytes(),
+                                          &signature).is_ok(),
+            Err(_) => false
+        }
+    }
+
+    /// A minimal player page, so the share can be opened without the web app
+    pub fn render_share_page(share: &EpisodeShare, podcast_episode: &PodcastEpisode,
+                             podcast: &Podcast) -> String {
+        let media_url = Self::get_signed_media_url(share);
+        let start = match share.start_position {
+            0 => String::new(),
+            start_position => format!("<p>Starts at {}</p>",
+                                      BookmarkService::format_position(start_position))
+        };
+        format!(r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="robots" content="noindex">
+<title>{episode_name} - {podcast_name}</title>
+<style>
+body {{ font-family: sans-serif; max-width: 40rem; margin: 2rem auto; padding: 0 1rem; color: #222; }}
+img {{ width: 12rem; height: 12rem; object-fit: cover; border-radius: 0.5rem; }}
+audio {{ width: 100%; margin-top: 1rem; }}
+h1 {{ font-size: 1.4rem; margin-bottom: 0.2rem; }}
+h2 {{ font-size: 1rem; font-weight: normal; color: #555; margin-top: 0; }}
+</style>
+</head>
+<body>
+<img src="{image_url}" alt="">
+<h1>{episode_name}</h1>
+<h2>{podcast_name}</h2>
+{start}
+<audio id="player" controls preload="metadata" src="{media_url}#t={start_position}"></audio>
+<script>
+var player = document.getElementById("player");
+player.addEventListener("loadedmetadata", function () {{
+    if (player.currentTime < {start_position}) {{
+        player.currentTime = {start_position};
+    }}
+}}, {{ once: true }});
+</script>
+</body>
+</html>
+"#,
+                episode_name = Self::escape_html(&podcast_episode.name),
+                podcast_name = Self::escape_html(&podcast.name),
+                image_url = Self::escape_html(&CredentialService::redact_url(
+                    &podcast_episode.image_url)),
+                media_url = Self::escape_html(&media_url),
+                start = start,
+                start_position = share.start_position)
+    }
+
+    pub fn render_not_found_page() -> String {
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta \
+        name=\"robots\" content=\"noindex\">\n<title>Link not available</title>\n</head>\n\
+        <body>\n<p>This link expired or was revoked.</p>\n</body>\n</html>\n".to_string()
+    }
+
+    fn map_share(share: EpisodeShare, podcast_episode: &PodcastEpisode, podcast: &Podcast,
+                 now: NaiveDateTime, mapping_service: &MappingService) -> EpisodeShareDto {
+        EpisodeShareDto {
+            id: share.id,
+            url: format!("{}share/{}", EnvironmentService::new().get_server_url(), share.token),
+            start_position: share.start_position,
+            expires_at: share.expires_at,
+            created_at: share.created_at,
+            expired: share.expires_at <= now,
+            podcast_episode: mapping_service.map_podcastepisode_to_dto(podcast_episode),
+            podcast: mapping_service.map_podcast_to_podcast_dto(podcast)
+        }
+    }
+
+    fn sign(token: &str, expires: i64) -> String {
+        let signature = hmac::sign(&Self::get_signing_key(),
+                                   Self::signed_message(token, expires).as_bytes());
+        general_purpose::URL_SAFE_NO_PAD.encode(signature.as_ref())
+    }
+
+    fn signed_message(token: &str, expires: i64) -> String {
+        format!("{}:{}", token, expires)
+    }
+
+    /// Derived from the instance secret, so the signing key differs from the credential key
+    fn get_signing_key() -> hmac::Key {
+        let mut key_material = b"podfetch-share-links:".to_vec();
+        key_material.extend(CredentialService::get_key_material());
+        hmac::Key::new(hmac::HMAC_SHA256, digest(&SHA256, &key_material).as_ref())
+    }
+
+    fn escape_html(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+            .replace('\'', "&#39;")
+    }
+}
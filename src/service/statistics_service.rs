@@ -0,0 +1,331 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike, Utc};
+use crate::constants::constants::{MAX_PLAYBACK_RATE, MAX_UNTRACKED_LISTENING_SECONDS,
+                                  STATS_TOP_LIMIT, WRAPPED_TOP_LIMIT};
+use crate::DbConnection;
+use crate::exception::exceptions::PodFetchError;
+use crate::models::episode_state::EpisodeStateType;
+use crate::models::listening_stats::{CompletionStats, ListeningPeriod, ListeningRecord,
+                                     ListeningRollup, ListeningStats, ListeningStreaks,
+                                     StatsExportFormat, TopEpisode, TopPodcast, WrappedReport};
+use crate::models::podcast_episode::PodcastEpisode;
+use crate::models::podcast_history_item::PodcastHistoryItem;
+use crate::service::episode_state_service::EpisodeStateService;
+
+/// Listening statistics derived from the watch logs. The logs are rolled up into listening time
+/// per user, episode and hour when they are written, the statistics are computed from these
+/// rollups.
+pub struct StatisticsService;
+
+impl StatisticsService {
+    /// Adds the listening time since the previous watch log of the episode to the rollups. Has to
+    /// be called before the new position is logged.
+    pub fn record_listening(conn: &mut DbConnection, username: &str, episode_id: &str,
+                            position: i32) {
+        let podcast_episode = match PodcastEpisode::get_podcast_episode_by_id(conn, episode_id)
+            .unwrap() {
+            Some(podcast_episode) => podcast_episode,
+            None => return
+        };
+        let previous_log = PodcastHistoryItem::get_latest_watch_log(conn, username, episode_id);
+        let now = Utc::now().naive_utc();
+        let listened_seconds = Self::get_listened_seconds(previous_log.as_ref(), position, now);
+        if listened_seconds > 0 {
+            ListeningRollup::add_listened_seconds(conn, &ListeningRollup {
+                username: username.to_string(),
+                day: now.date(),
+                hour: now.hour() as i32,
+                podcast_id: podcast_episode.podcast_id,
+                podcast_episode_id: podcast_episode.id,
+                listened_seconds
+            });
+        }
+    }
+
+    /// Builds the rollups from the watch logs if there are none yet, e.g. after upgrading
+    pub fn backfill_rollups(conn: &mut DbConnection) {
+        if !ListeningRollup::has_rollups(conn) {
+            Self::rebuild_rollups(conn);
+        }
+    }
+
+    pub fn rebuild_rollups(conn: &mut DbConnection) {
+        let mut rollups = HashMap::<(String, NaiveDate, i32, i32), (i32, i32)>::new();
+        let mut previous_log: Option<PodcastHistoryItem> = None;
+        for (watch_log, podcast_episode_id) in
+            PodcastHistoryItem::get_watch_logs_with_episode_ids(conn) {
+            let previous_log_of_episode = previous_log.as_ref().filter(|previous_log|
+                previous_log.username == watch_log.username
+                    && previous_log.episode_id == watch_log.episode_id);
+            let listened_seconds = Self::get_listened_seconds(previous_log_of_episode,
+                                                              watch_log.watched_time,
+                                                              watch_log.date);
+            if listened_seconds > 0 {
+                let rollup = rollups.entry((watch_log.username.clone(), watch_log.date.date(),
+                                            watch_log.date.hour() as i32, podcast_episode_id))
+                    .or_insert((watch_log.podcast_id, 0));
+                rollup.1 += listened_seconds;
+            }
+            previous_log = Some(watch_log);
+        }
+        let rollups = rollups
+            .into_iter()
+            .map(|((username, day, hour, podcast_episode_id), (podcast_id, listened_seconds))|
+                ListeningRollup {
+                    username,
+                    day,
+                    hour,
+                    podcast_id,
+                    podcast_episode_id,
+                    listened_seconds
+                })
+            .collect::<Vec<ListeningRollup>>();
+        log::info!("Rebuilt {} listening rollups", rollups.len());
+        ListeningRollup::replace_rollups(conn, &rollups);
+    }
+
+    /// The statistics of the user or, without a user, of the whole instance
+    pub fn get_stats(conn: &mut DbConnection, username: Option<&str>, from: Option<NaiveDate>,
+                     until: Option<NaiveDate>) -> Result<ListeningStats, PodFetchError> {
+        Self::validate_range(from, until)?;
+        let records = ListeningRollup::get_records(conn, username, from, until);
+        let episode_states = match username {
+            Some(username) => HashMap::from([(username.to_string(),
+                                              EpisodeStateService::get_episode_states(conn,
+                                                                                      username))]),
+            None => EpisodeStateService::get_all_episode_states(conn)
+        };
+        Ok(Self::compute_stats(&records, &episode_states, from, until, STATS_TOP_LIMIT))
+    }
+
+    pub fn get_wrapped(conn: &mut DbConnection, username: &str, year: i32)
+        -> Result<WrappedReport, PodFetchError> {
+        let from = NaiveDate::from_ymd_opt(year, 1, 1)
+            .ok_or(PodFetchError::invalid_stats_range())?;
+        let until = NaiveDate::from_ymd_opt(year, 12, 31)
+            .ok_or(PodFetchError::invalid_stats_range())?;
+        let records = ListeningRollup::get_records(conn, Some(username), Some(from), Some(until));
+        let episode_states = HashMap::from([(username.to_string(),
+                                             EpisodeStateService::get_episode_states(conn,
+                                                                                     username))]);
+        let stats = Self::compute_stats(&records, &episode_states, Some(from), Some(until),
+                                        WRAPPED_TOP_LIMIT);
+        let new_podcasts = ListeningRollup::get_first_listening_days(conn, username)
+            .into_iter()
+            .filter(|(_, first_day)| first_day.map(|first_day| first_day.year() == year)
+                .unwrap_or(false))
+            .count() as i64;
+        let favorite_hour = match stats.total_seconds > 0 {
+            true => (0..24u32).max_by_key(|hour| (stats.hours[*hour as usize],
+                                                  std::cmp::Reverse(*hour))),
+            false => None
+        };
+        Ok(WrappedReport {
+            year,
+            total_seconds: stats.total_seconds,
+            listening_days: stats.days.len() as i64,
+            episodes_started: stats.completion.started_episodes,
+            episodes_completed: stats.completion.completed_episodes,
+            new_podcasts,
+            busiest_day: Self::get_busiest_period(&stats.days),
+            busiest_month: Self::get_busiest_period(&stats.months),
+            top_podcasts: stats.top_podcasts,
+            top_episodes: stats.top_episodes,
+            longest_streak: stats.streaks,
+            favorite_hour
+        })
+    }
+
+    /// Exports the listening time per hour and episode of the user
+    pub fn export(conn: &mut DbConnection, username: &str, from: Option<NaiveDate>,
+                  until: Option<NaiveDate>, format: StatsExportFormat)
+        -> Result<String, PodFetchError> {
+        Self::validate_range(from, until)?;
+        let records = ListeningRollup::get_records(conn, Some(username), from, until);
+        Ok(match format {
+            StatsExportFormat::Json => serde_json::to_string(&records).unwrap(),
+            StatsExportFormat::Csv => Self::to_csv(&records)
+        })
+    }
+
+    /// Counts the progress since the previous log as listening time unless it is more than
+    /// could have been played since then. Going back doesn't count.
+    fn get_listened_seconds(previous_log: Option<&PodcastHistoryItem>, position: i32,
+                            logged_at: NaiveDateTime) -> i32 {
+        let (previous_position, max_listened_seconds) = match previous_log {
+            Some(previous_log) => (previous_log.watched_time,
+                                   (logged_at - previous_log.date).num_seconds().max(0)
+                                       * MAX_PLAYBACK_RATE),
+            None => (0, MAX_UNTRACKED_LISTENING_SECONDS)
+        };
+        let progress = position as i64 - previous_position as i64;
+        progress.clamp(0, max_listened_seconds) as i32
+    }
+
+    fn compute_stats(records: &[ListeningRecord],
+                     episode_states: &HashMap<String, HashMap<i32, EpisodeStateType>>,
+                     from: Option<NaiveDate>, until: Option<NaiveDate>, top_limit: usize)
+        -> ListeningStats {
+        let mut days = BTreeMap::<NaiveDate, i64>::new();
+        let mut weeks = BTreeMap::<(i32, u32), i64>::new();
+        let mut months = BTreeMap::<(i32, u32), i64>::new();
+        let mut hours = vec![0i64; 24];
+        let mut podcasts = HashMap::<i32, TopPodcast>::new();
+        let mut podcast_episodes = HashMap::<i32, HashSet<i32>>::new();
+        let mut episodes = HashMap::<i32, TopEpisode>::new();
+        let mut started_episodes = HashSet::<(&String, i32)>::new();
+        let mut listeners = HashSet::<&String>::new();
+        for record in records {
+            let listened_seconds = record.listened_seconds as i64;
+            *days.entry(record.day).or_insert(0) += listened_seconds;
+            let week = record.day.iso_week();
+            *weeks.entry((week.year(), week.week())).or_insert(0) += listened_seconds;
+            *months.entry((record.day.year(), record.day.month())).or_insert(0)
+                += listened_seconds;
+            if let Some(hour) = hours.get_mut(record.hour as usize) {
+                *hour += listened_seconds;
+            }
+            podcasts.entry(record.podcast_id).or_insert_with(|| TopPodcast {
+                podcast_id: record.podcast_id,
+                name: record.podcast_name.clone(),
+                listened_seconds: 0,
+                episodes: 0
+            }).listened_seconds += listened_seconds;
+            podcast_episodes.entry(record.podcast_id).or_default()
+                .insert(record.podcast_episode_id);
+            episodes.entry(record.podcast_episode_id).or_insert_with(|| TopEpisode {
+                episode_id: record.episode_id.clone(),
+                podcast_id: record.podcast_id,
+                name: record.episode_name.clone(),
+                podcast_name: record.podcast_name.clone(),
+                listened_seconds: 0
+            }).listened_seconds += listened_seconds;
+            started_episodes.insert((&record.username, record.podcast_episode_id));
+            listeners.insert(&record.username);
+        }
+        for (podcast_id, podcast) in podcasts.iter_mut() {
+            podcast.episodes = podcast_episodes.get(podcast_id).map(|episodes| episodes.len())
+                .unwrap_or(0) as i64;
+        }
+
+        let completed_episodes = started_episodes
+            .iter()
+            .filter(|(username, podcast_episode_id)| episode_states.get(*username)
+                .and_then(|episode_states| episode_states.get(podcast_episode_id))
+                .map(|state| state.is_played())
+                .unwrap_or(false))
+            .count() as i64;
+        let started_episodes = started_episodes.len() as i64;
+        let mut top_podcasts = podcasts.into_values().collect::<Vec<TopPodcast>>();
+        top_podcasts.sort_by(|a, b| b.listened_seconds.cmp(&a.listened_seconds)
+            .then(a.name.cmp(&b.name)));
+        top_podcasts.truncate(top_limit);
+        let mut top_episodes = episodes.into_values().collect::<Vec<TopEpisode>>();
+        top_episodes.sort_by(|a, b| b.listened_seconds.cmp(&a.listened_seconds)
+            .then(a.name.cmp(&b.name)));
+        top_episodes.truncate(top_limit);
+
+        ListeningStats {
+            from,
+            until,
+            total_seconds: days.values().sum(),
+            listeners: listeners.len() as i64,
+            streaks: Self::get_streaks(days.keys().cloned().collect()),
+            days: days.into_iter()
+                .map(|(day, listened_seconds)| ListeningPeriod {
+                    period: day.format("%Y-%m-%d").to_string(),
+                    listened_seconds
+                })
+                .collect(),
+            weeks: weeks.into_iter()
+                .map(|((year, week), listened_seconds)| ListeningPeriod {
+                    period: format!("{}-W{:02}", year, week),
+                    listened_seconds
+                })
+                .collect(),
+            months: months.into_iter()
+                .map(|((year, month), listened_seconds)| ListeningPeriod {
+                    period: format!("{}-{:02}", year, month),
+                    listened_seconds
+                })
+                .collect(),
+            hours,
+            top_podcasts,
+            top_episodes,
+            completion: CompletionStats {
+                started_episodes,
+                completed_episodes,
+                completion_rate: match started_episodes {
+                    0 => 0.0,
+                    _ => completed_episodes as f64 / started_episodes as f64
+                }
+            }
+        }
+    }
+
+    /// The streaks of consecutive listening days. The days have to be sorted.
+    fn get_streaks(days: Vec<NaiveDate>) -> ListeningStreaks {
+        let mut streaks = ListeningStreaks::default();
+        let mut streak_start: Option<NaiveDate> = None;
+        let mut previous_day: Option<NaiveDate> = None;
+        for day in days {
+            if previous_day.and_then(|previous_day| previous_day.succ_opt()) != Some(day) {
+                streak_start = Some(day);
+            }
+            let streak_days = (day - streak_start.unwrap()).num_days() + 1;
+            if streak_days > streaks.longest_days {
+                streaks.longest_days = streak_days;
+                streaks.longest_from = streak_start;
+                streaks.longest_until = Some(day);
+            }
+            previous_day = Some(day);
+        }
+        let today = Utc::now().date_naive();
+        if let (Some(streak_start), Some(last_day)) = (streak_start, previous_day) {
+            if last_day == today || last_day.succ_opt() == Some(today) {
+                streaks.current_days = (last_day - streak_start).num_days() + 1;
+            }
+        }
+        streaks
+    }
+
+    fn get_busiest_period(periods: &[ListeningPeriod]) -> Option<ListeningPeriod> {
+        periods.iter()
+            .fold(None, |busiest: Option<&ListeningPeriod>, period| match busiest {
+                Some(busiest) if busiest.listened_seconds >= period.listened_seconds =>
+                    Some(busiest),
+                _ => Some(period)
+            })
+            .cloned()
+    }
+
+    fn validate_range(from: Option<NaiveDate>, until: Option<NaiveDate>)
+        -> Result<(), PodFetchError> {
+        if let (Some(from), Some(until)) = (from, until) {
+            if from > until {
+                return Err(PodFetchError::invalid_stats_range());
+            }
+        }
+        Ok(())
+    }
+
+    fn to_csv(records: &[ListeningRecord]) -> String {
+        let mut csv = String::from("day,hour,podcast_id,podcast,episode_id,episode,\
+        listened_seconds\n");
+        for record in records {
+            csv.push_str(&format!("{},{},{},{},{},{},{}\n", record.day, record.hour,
+                                  record.podcast_id, Self::escape_csv(&record.podcast_name),
+                                  Self::escape_csv(&record.episode_id),
+                                  Self::escape_csv(&record.episode_name),
+                                  record.listened_seconds));
+        }
+        csv
+    }
+
+    fn escape_csv(value: &str) -> String {
+        match value.contains([',', '"', '\n', '\r']) {
+            true => format!("\"{}\"", value.replace('"', "\"\"")),
+            false => value.to_string()
+        }
+    }
+}
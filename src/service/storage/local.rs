@@ -0,0 +1,113 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::PathBuf;
+use crate::service::storage::StorageBackend;
+
+/// Keeps the files in a directory of the local filesystem.
+pub struct LocalStorage {
+    root: PathBuf
+}
+
+impl LocalStorage {
+    pub fn new(root: &str) -> Self {
+        LocalStorage {
+            root: PathBuf::from(root)
+        }
+    }
+
+    fn resolve(&self, path: &str) -> PathBuf {
+        match path.is_empty() {
+            true => self.root.clone(),
+            false => self.root.join(path)
+        }
+    }
+
+    fn collect_files(&self, path: &str, files: &mut Vec<(String, u64)>) -> io::Result<()> {
+        for entry in fs::read_dir(self.resolve(path))? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let entry_path = match path.is_empty() {
+                true => name,
+                false => format!("{}/{}", path, name)
+            };
+            let metadata = fs::metadata(entry.path())?;
+            match metadata.is_dir() {
+                true => self.collect_files(&entry_path, files)?,
+                false => files.push((entry_path, metadata.len()))
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StorageBackend for LocalStorage {
+    fn create_dir_all(&self, path: &str) -> io::Result<()> {
+        fs::create_dir_all(self.resolve(path))
+    }
+
+    fn exists(&self, path: &str) -> io::Result<bool> {
+        Ok(self.resolve(path).exists())
+    }
+
+    fn write(&self, path: &str, content: &mut (dyn Read + Send)) -> io::Result<u64> {
+        let path = self.resolve(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = File::create(path)?;
+        io::copy(content, &mut file)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        let to = self.resolve(to);
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(self.resolve(from), to)
+    }
+
+    fn delete_file(&self, path: &str) -> io::Result<()> {
+        fs::remove_file(self.resolve(path))
+    }
+
+    fn delete_empty_dir(&self, path: &str) -> io::Result<()> {
+        let path = self.resolve(path);
+        match fs::read_dir(&path) {
+            Ok(mut entries) => match entries.next() {
+                None => fs::remove_dir(path),
+                Some(_) => Ok(())
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e)
+        }
+    }
+
+    fn delete_dir(&self, path: &str) -> io::Result<()> {
+        match fs::remove_dir_all(self.resolve(path)) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result
+        }
+    }
+
+    fn dir_size(&self, path: &str) -> io::Result<u64> {
+        let path = self.resolve(path);
+        if !path.exists() {
+            return Ok(0);
+        }
+        fs_extra::dir::get_size(path).map_err(|e| io::Error::other(e.to_string()))
+    }
+
+    fn list_files(&self, path: &str) -> io::Result<Vec<(String, u64)>> {
+        let mut files = Vec::new();
+        match self.collect_files(path, &mut files) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            result => result.map(|_| files)
+        }
+    }
+
+    fn local_path(&self, path: &str) -> Option<PathBuf> {
+        Some(self.resolve(path))
+    }
+}
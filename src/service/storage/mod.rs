@@ -0,0 +1,252 @@
+pub mod local;
+pub mod s3;
+
+use std::{fs, io};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use actix_files::NamedFile;
+use actix_web::{web, HttpRequest, HttpResponse};
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+use crate::constants::constants::MEDIA_URL_PATH;
+use crate::service::environment_service::{EnvironmentService, MediaDelivery, StorageBackendType};
+use crate::service::credential_service::{CredentialScope, CredentialService};
+use crate::service::http_client::HttpClientFactory;
+use crate::service::storage::local::LocalStorage;
+use crate::service::storage::s3::S3Storage;
+
+// Conditional requests and ranges of the player are passed on to the object store
+const MEDIA_FORWARDED_HEADERS: [&str; 5] = ["range", "if-range", "if-match", "if-none-match",
+    "if-modified-since"];
+const MEDIA_RETURNED_HEADERS: [&str; 7] = ["content-type", "content-length", "content-range",
+    "accept-ranges", "etag", "last-modified", "cache-control"];
+
+/// Stores the downloaded episodes and images. Paths are relative to the media root and use `/`
+/// as separator, e.g. `My Podcast/'Episode 1'/podcast.mp3`. The empty path is the media root.
+pub trait StorageBackend: Send + Sync {
+    /// Object stores have no directories, their paths exist once a file is written.
+    fn create_dir_all(&self, path: &str) -> io::Result<()>;
+
+    /// Whether a file or a directory with files exists
+    fn exists(&self, path: &str) -> io::Result<bool>;
+
+    /// Streams the content into the file, missing directories are created. Returns the number
+    /// of bytes written.
+    fn write(&self, path: &str, content: &mut (dyn Read + Send)) -> io::Result<u64>;
+
+    /// Moves the file, missing directories are created
+    fn rename(&self, from: &str, to: &str) -> io::Result<()>;
+
+    fn delete_file(&self, path: &str) -> io::Result<()>;
+
+    /// Deletes the directory if nothing is left in it. Object stores have no empty directories.
+    fn delete_empty_dir(&self, _path: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Deletes the directory with everything in it. Missing directories are no error.
+    fn delete_dir(&self, path: &str) -> io::Result<()>;
+
+    /// The size of every file in the directory in bytes
+    fn dir_size(&self, path: &str) -> io::Result<u64>;
+
+    /// Every file in the directory and its subdirectories with its size in bytes. The paths are
+    /// relative to the media root, missing directories have no files.
+    fn list_files(&self, path: &str) -> io::Result<Vec<(String, u64)>>;
+
+    /// The file on the local filesystem, if the backend keeps its files there
+    fn local_path(&self, _path: &str) -> Option<PathBuf> {
+        None
+    }
+
+    /// A url that serves the file without further credentials until it expires
+    fn presigned_url(&self, _path: &str, _expires_in: u64) -> Option<String> {
+        None
+    }
+}
+
+/// Creates the configured [`StorageBackend`] and serves its files.
+pub struct StorageService;
+
+impl StorageService {
+    pub fn get_backend() -> Box<dyn StorageBackend> {
+        let environment_service = EnvironmentService::new();
+        let storage_config = environment_service.storage_config;
+        match storage_config.backend {
+            StorageBackendType::Local => Box::new(LocalStorage::new(&storage_config.media_root)),
+            StorageBackendType::S3 => Box::new(S3Storage::new(storage_config.s3_config.unwrap(),
+                                                              &storage_config.media_root))
+        }
+    }
+
+    /// The path relative to the server url the file is served at. Urls of files that weren't
+    /// downloaded and empty paths are kept.
+    pub fn get_media_path(path: &str) -> String {
+        match path.is_empty() || path.contains("://") {
+            true => path.to_string(),
+            false => format!("{}/{}", MEDIA_URL_PATH, path)
+        }
+    }
+
+    /// Serves a stored file to a player. Local files support ranges, files of an object store
+    /// are streamed through PodFetch or redirected to, depending on the media delivery.
+    pub async fn get_media_response(path: &str, req: &HttpRequest) -> HttpResponse {
+        if !Self::is_media_path(path) {
+            return HttpResponse::NotFound().finish();
+        }
+        let environment_service = EnvironmentService::new();
+        let backend = Self::get_backend();
+        if let (Some(local_path), Some(media_root)) = (backend.local_path(path),
+                                                       backend.local_path("")) {
+            let file_path = web::block(move || Self::get_file_below_root(&local_path,
+                                                                         &media_root))
+                .await
+                .ok()
+                .flatten();
+            return match file_path {
+                Some(file_path) => match NamedFile::open_async(file_path).await {
+                    Ok(file) => file.disable_content_disposition().into_response(req),
+                    Err(_) => HttpResponse::NotFound().finish()
+                },
+                None => HttpResponse::NotFound().finish()
+            };
+        }
+        let presigned_url_expiry = environment_service.storage_config.s3_config
+            .map(|s3_config| s3_config.presigned_url_expiry)
+            .unwrap_or_default();
+        let presigned_url = match backend.presigned_url(path, presigned_url_expiry) {
+            Some(presigned_url) => presigned_url,
+            None => return HttpResponse::NotFound().finish()
+        };
+        match environment_service.storage_config.media_delivery {
+            MediaDelivery::Redirect => HttpResponse::Found()
+                .insert_header(("Location", presigned_url))
+                .finish(),
+            MediaDelivery::Proxy => Self::proxy_media(&presigned_url, req).await
+        }
+    }
+
+    /// Whether the path stays below the media root. Joining an absolute path would replace the
+    /// root, so only names of files and directories are allowed.
+    pub fn is_media_path(path: &str) -> bool {
+        !path.is_empty() && Path::new(path).components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    }
+
+    /// Resolves symbolic links and returns the file only if it is still below the media root
+    fn get_file_below_root(path: &Path, media_root: &Path) -> Option<PathBuf> {
+        let path = fs::canonicalize(path).ok()?;
+        let media_root = fs::canonicalize(media_root).ok()?;
+        path.starts_with(media_root).then_some(path)
+    }
+
+    /// Streams an episode that isn't downloaded from the podcast server. Its url and the
+    /// credentials of the podcast don't reach the player.
+    pub async fn proxy_remote_media(url: &str, http_client_factory: &HttpClientFactory,
+                                    credential_scope: &CredentialScope, req: &HttpRequest)
+        -> HttpResponse {
+        let response = match http_client_factory.stream_with_credentials(
+            Method::GET, url, &Self::get_forwarded_headers(req), credential_scope).await {
+            Ok(response) => response,
+            Err(e) => {
+                log::error!("Error requesting media of {}: {}", CredentialService::redact_url(url),
+                    e);
+                return HttpResponse::BadGateway().finish();
+            }
+        };
+        Self::get_proxied_response(response)
+    }
+
+    async fn proxy_media(presigned_url: &str, req: &HttpRequest) -> HttpResponse {
+        let client = HttpClientFactory::new().build_object_store_client();
+        let response = match client.get(presigned_url)
+            .headers(Self::get_forwarded_headers(req))
+            .send()
+            .await {
+            Ok(response) => response,
+            Err(e) => {
+                log::error!("Error requesting media of the object store: {}", e);
+                return HttpResponse::BadGateway().finish();
+            }
+        };
+        Self::get_proxied_response(response)
+    }
+
+    fn get_forwarded_headers(req: &HttpRequest) -> HeaderMap {
+        let mut header_map = HeaderMap::new();
+        for (header_name, header_value) in req.headers() {
+            if MEDIA_FORWARDED_HEADERS.contains(&header_name.as_str()) {
+                header_map.append(header_name.clone(), header_value.clone());
+            }
+        }
+        header_map
+    }
+
+    fn get_proxied_response(response: reqwest::Response) -> HttpResponse {
+        let status = actix_web::http::StatusCode::from_u16(response.status().as_u16()).unwrap();
+        if !status.is_success() && status != actix_web::http::StatusCode::NOT_MODIFIED {
+            // The error documents of the object store aren't meant for players
+            return HttpResponse::build(status).finish();
+        }
+        let mut client_response = HttpResponse::build(status);
+        for (header_name, header_value) in response.headers() {
+            if MEDIA_RETURNED_HEADERS.contains(&header_name.as_str()) {
+                client_response.insert_header((header_name.as_str(), header_value.as_bytes()));
+            }
+        }
+        client_response.streaming(response.bytes_stream())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use actix_web::App;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::http::StatusCode;
+    use crate::controllers::media_controller::get_media;
+    use crate::service::storage::StorageService;
+
+    #[test]
+    fn test_is_media_path() {
+        assert!(StorageService::is_media_path("My Podcast/'Episode 1'/podcast.mp3"));
+        assert!(StorageService::is_media_path("My Podcast/image.jpg"));
+        assert!(!StorageService::is_media_path(""));
+        assert!(!StorageService::is_media_path("/etc/passwd"));
+        assert!(!StorageService::is_media_path("/proc/self/cwd/db/credentials.key"));
+        assert!(!StorageService::is_media_path("../db/credentials.key"));
+        assert!(!StorageService::is_media_path("My Podcast/../../db/credentials.key"));
+    }
+
+    #[test]
+    fn test_get_file_below_root() {
+        let directory = std::env::temp_dir().join(format!("podfetch-media-{}",
+                                                          std::process::id()));
+        let media_root = directory.join("podcasts");
+        fs::create_dir_all(media_root.join("My Podcast")).unwrap();
+        fs::write(media_root.join("My Podcast/podcast.mp3"), "episode").unwrap();
+        fs::write(directory.join("credentials.key"), "key").unwrap();
+
+        assert!(StorageService::get_file_below_root(&media_root.join("My Podcast/podcast.mp3"),
+                                                    &media_root).is_some());
+        assert!(StorageService::get_file_below_root(&media_root.join("../credentials.key"),
+                                                    &media_root).is_none());
+        assert!(StorageService::get_file_below_root(&directory.join("credentials.key"),
+                                                    &media_root).is_none());
+        assert!(StorageService::get_file_below_root(&media_root.join("missing.mp3"),
+                                                    &media_root).is_none());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_get_media_rejects_paths_outside_of_the_media_root() {
+        let app = init_service(App::new().service(get_media)).await;
+        for uri in ["/podcasts//etc/passwd", "/podcasts/%2Fetc%2Fpasswd",
+            "/podcasts//proc/self/cwd/db/credentials.key", "/podcasts/..%2Fdb%2Fcredentials.key",
+            "/podcasts/My%20Podcast/..%2F..%2Fdb%2Fcredentials.key"] {
+            let req = TestRequest::get().uri(uri).to_request();
+            let response = call_service(&app, req).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{}", uri);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::{HashMap, HashSet};
+use crate::DbConnection;
+use crate::exception::exceptions::PodFetchError;
+use crate::models::podcasts::Podcast;
+use crate::models::tag::{Tag, TagCreateModel, TagDto, TagsPodcast};
+
+/// Manages the tags of a user. Tags can be nested to build folders.
+pub struct TagService;
+
+impl TagService {
+    pub fn get_tags(conn: &mut DbConnection, username: &str) -> Vec<TagDto> {
+        let tags = Tag::get_tags_of_user(conn, username);
+        let tag_ids = tags.iter().map(|tag| tag.id).collect::<Vec<i32>>();
+        let mut podcast_ids = HashMap::<i32, Vec<i32>>::new();
+        for tagged_podcast in TagsPodcast::get_tagged_podcasts(conn, &tag_ids) {
+            podcast_ids.entry(tagged_podcast.tag_id).or_default().push(tagged_podcast.podcast_id);
+        }
+        tags.into_iter()
+            .map(|tag| {
+                let podcast_ids = podcast_ids.remove(&tag.id).unwrap_or_default();
+                TagDto::from((tag, podcast_ids))
+            })
+            .collect()
+    }
+
+    pub fn create_tag(conn: &mut DbConnection, username: &str, tag: &TagCreateModel)
+        -> Result<Tag, PodFetchError> {
+        let name = Self::validate_name(&tag.name)?;
+        if let Some(parent_id) = tag.parent_id {
+            Tag::get_tag_of_user(conn, parent_id, username)
+                .ok_or(PodFetchError::tag_not_found())?;
+        }
+        if Tag::find_by_name(conn, username, &name, tag.parent_id).is_some() {
+            return Err(PodFetchError::tag_already_exists());
+        }
+        Ok(Tag::insert_tag(conn, username, &name, tag.parent_id, tag.color.clone()))
+    }
+
+    pub fn update_tag(conn: &mut DbConnection, username: &str, tag_id: i32,
+                      tag: &TagCreateModel) -> Result<Tag, PodFetchError> {
+        Tag::get_tag_of_user(conn, tag_id, username).ok_or(PodFetchError::tag_not_found())?;
+        let name = Self::validate_name(&tag.name)?;
+        if let Some(parent_id) = tag.parent_id {
+            let tags = Tag::get_tags_of_user(conn, username);
+            if !tags.iter().any(|tag| tag.id == parent_id) {
+                return Err(PodFetchError::tag_not_found());
+            }
+            if Self::get_nested_tag_ids(&tags, tag_id).contains(&parent_id) {
+                return Err(PodFetchError::invalid_tag_parent());
+            }
+        }
+        if let Some(existing_tag) = Tag::find_by_name(conn, username, &name, tag.parent_id) {
+            if existing_tag.id != tag_id {
+                return Err(PodFetchError::tag_already_exists());
+            }
+        }
+        Ok(Tag::update_tag(conn, tag_id, &name, tag.parent_id, tag.color.clone()))
+    }
+
+    pub fn delete_tag(conn: &mut DbConnection, username: &str, tag_id: i32)
+        -> Result<(), PodFetchError> {
+        let tag = Tag::get_tag_of_user(conn, tag_id, username)
+            .ok_or(PodFetchError::tag_not_found())?;
+        Tag::delete_tag(conn, &tag);
+        Ok(())
+    }
+
+    pub fn add_podcast_to_tag(conn: &mut DbConnection, username: &str, tag_id: i32,
+                              podcast_id: i32) -> Result<(), PodFetchError> {
+        Tag::get_tag_of_user(conn, tag_id, username).ok_or(PodFetchError::tag_not_found())?;
+        Podcast::get_podcast(conn, podcast_id).map_err(|_| PodFetchError::podcast_not_found())?;
+        TagsPodcast::add_podcast_to_tag(conn, tag_id, podcast_id);
+        Ok(())
+    }
+
+    pub fn remove_podcast_from_tag(conn: &mut DbConnection, username: &str, tag_id: i32,
+                                   podcast_id: i32) -> Result<(), PodFetchError> {
+        Tag::get_tag_of_user(conn, tag_id, username).ok_or(PodFetchError::tag_not_found())?;
+        TagsPodcast::remove_podcast_from_tag(conn, tag_id, podcast_id);
+        Ok(())
+    }
+
+    /// The tag and the tags nested inside it. Tags of other users have no tag ids.
+    pub fn get_tag_ids(conn: &mut DbConnection, username: &str, tag_id: i32) -> Vec<i32> {
+        let tags = Tag::get_tags_of_user(conn, username);
+        if !tags.iter().any(|tag| tag.id == tag_id) {
+            return vec![];
+        }
+        Self::get_nested_tag_ids(&tags, tag_id).into_iter().collect()
+    }
+
+    /// The podcasts tagged with the tag or one of the tags nested inside it. Tags of other users
+    /// don't match any podcast.
+    pub fn get_podcast_ids_of_tag(conn: &mut DbConnection, username: &str, tag_id: i32)
+        -> Vec<i32> {
+        let tag_ids = Self::get_tag_ids(conn, username, tag_id);
+        TagsPodcast::get_tagged_podcasts(conn, &tag_ids)
+            .into_iter()
+            .map(|tagged_podcast| tagged_podcast.podcast_id)
+            .collect::<HashSet<i32>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Used by the OPML import to reuse folders that already exist.
+    pub fn find_or_create_tag(conn: &mut DbConnection, username: &str, name: &str,
+                              parent_id: Option<i32>) -> Option<Tag> {
+        let name = Self::validate_name(name).ok()?;
+        match Tag::find_by_name(conn, username, &name, parent_id) {
+            Some(tag) => Some(tag),
+            None => Some(Tag::insert_tag(conn, username, &name, parent_id, None))
+        }
+    }
+
+    /// The tag itself and all tags nested inside it
+    fn get_nested_tag_ids(tags: &[Tag], tag_id: i32) -> HashSet<i32> {
+        let mut nested_tag_ids = HashSet::from([tag_id]);
+        let mut tags_to_visit = vec![tag_id];
+        while let Some(parent_id) = tags_to_visit.pop() {
+            for tag in tags.iter().filter(|tag| tag.parent_id == Some(parent_id)) {
+                if nested_tag_ids.insert(tag.id) {
+                    tags_to_visit.push(tag.id);
+                }
+            }
+        }
+        nested_tag_ids
+    }
+
+    fn validate_name(name: &str) -> Result<String, PodFetchError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(PodFetchError::invalid_tag_name());
+        }
+        Ok(name.to_string())
+    }
+}
@@ -29,7 +29,7 @@ impl UserManagementService {
             has_whitespace |= c.is_whitespace();
             has_lower |= c.is_lowercase();
             has_upper |= c.is_uppercase();
-            has_digit |= c.is_digit(10);
+            has_digit |= c.is_ascii_digit();
         }
 
         !has_whitespace && has_upper && has_lower && has_digit && password.len() >= 8
@@ -43,7 +43,7 @@ impl UserManagementService {
         PodFetchError>{
 
             // Check if the invite is valid
-        return match Invite::find_invite(invite_id.clone(), conn) {
+        match Invite::find_invite(invite_id.clone(), conn) {
             Ok(invite) => {
                 match invite {
                     Some(invite) => {
@@ -103,12 +103,12 @@ impl UserManagementService {
     pub fn delete_user(user: User, conn: &mut DbConnection)->Result<(), PodFetchError>{
 
         User::delete_user(&user, conn).expect("Error deleting User");
-        return Ok(())
+        Ok(())
     }
 
     pub fn update_user(user_to_update: User, conn: &mut DbConnection) ->Result<UserWithoutPassword,
         PodFetchError>{
-        return match User::update_role(&user_to_update, conn) {
+        match User::update_role(&user_to_update, conn) {
             Ok(user) => {
                 match User::update_explicit_consent(&user_to_update, conn){
                     Ok(_) => {}
@@ -193,7 +193,7 @@ impl UserManagementService {
             return Err(PodFetchError::no_permissions_to_onboard_user())
         }
 
-        return Ok(User::find_all_users(conn))
+        Ok(User::find_all_users(conn))
     }
 
     pub fn delete_invite(invite_id: String, conn: &mut DbConnection)->Result<(), PodFetchError>{
@@ -24,14 +24,13 @@ impl QueryOrdering {
             Self::Descending => query.into_boxed().order(expr.desc()),
         }
     }
-    fn order_query2<'a, Expr, Q, DB>(
+    fn order_query2<Expr, Q>(
         &self,
         query: Q,
         expr: Expr,
     ) -> <Q as OrderDsl<Asc<Expr>>>::Output
         where
             Expr: ExpressionMethods,
-            DB: Backend,
             Q: OrderDsl<Asc<Expr>>
             + OrderDsl<Desc<Expr>, Output = <Q as OrderDsl<Asc<Expr>>>::Output>,
     {
@@ -70,7 +69,7 @@ impl<'a, Expr, DB, Q> DynOrderDsl2<'a, DB, Expr> for Q
         order: QueryOrdering,
         expr: Expr,
     ) -> <Q as OrderDsl<Asc<Expr>>>::Output {
-        order.order_query2::<_, _, DB>(self, expr)
+        order.order_query2(self, expr)
     }
 }
 
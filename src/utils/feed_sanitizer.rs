@@ -0,0 +1,221 @@
+use encoding_rs::{Encoding, UTF_8};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+// Entities that are predefined in XML and must not be touched
+const XML_ENTITIES: [&str; 5] = ["amp", "lt", "gt", "quot", "apos"];
+
+// HTML entities that show up in real world feeds but are unknown to XML parsers
+const HTML_ENTITIES: [(&str, u32); 104] = [
+    ("nbsp", 160), ("iexcl", 161), ("cent", 162), ("pound", 163), ("curren", 164),
+    ("yen", 165), ("brvbar", 166), ("sect", 167), ("uml", 168), ("copy", 169), ("ordf", 170),
+    ("laquo", 171), ("not", 172), ("shy", 173), ("reg", 174), ("macr", 175), ("deg", 176),
+    ("plusmn", 177), ("sup2", 178), ("sup3", 179), ("acute", 180), ("micro", 181),
+    ("para", 182), ("middot", 183), ("cedil", 184), ("sup1", 185), ("ordm", 186),
+    ("raquo", 187), ("frac14", 188), ("frac12", 189), ("frac34", 190), ("iquest", 191),
+    ("Agrave", 192), ("Aacute", 193), ("Acirc", 194), ("Atilde", 195), ("Auml", 196),
+    ("Aring", 197), ("AElig", 198), ("Ccedil", 199), ("Egrave", 200), ("Eacute", 201),
+    ("Ecirc", 202), ("Euml", 203), ("Igrave", 204), ("Iacute", 205), ("Icirc", 206),
+    ("Iuml", 207), ("Ntilde", 209), ("Ograve", 210), ("Oacute", 211), ("Ocirc", 212),
+    ("Otilde", 213), ("Ouml", 214), ("times", 215), ("Oslash", 216), ("Ugrave", 217),
+    ("Uacute", 218), ("Ucirc", 219), ("Uuml", 220), ("Yacute", 221), ("szlig", 223),
+    ("agrave", 224), ("aacute", 225), ("acirc", 226), ("atilde", 227), ("auml", 228),
+    ("aring", 229), ("aelig", 230), ("ccedil", 231), ("egrave", 232), ("eacute", 233),
+    ("ecirc", 234), ("euml", 235), ("igrave", 236), ("iacute", 237), ("icirc", 238),
+    ("iuml", 239), ("ntilde", 241), ("ograve", 242), ("oacute", 243), ("ocirc", 244),
+    ("otilde", 245), ("ouml", 246), ("divide", 247), ("oslash", 248), ("ugrave", 249),
+    ("uacute", 250), ("ucirc", 251), ("uuml", 252), ("yacute", 253), ("yuml", 255),
+    ("ndash", 8211), ("mdash", 8212), ("lsquo", 8216), ("rsquo", 8217), ("sbquo", 8218),
+    ("ldquo", 8220), ("rdquo", 8221), ("bdquo", 8222), ("bull", 8226), ("hellip", 8230),
+    ("euro", 8364), ("trade", 8482),
+];
+
+static XML_DECLARATION_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(
+    r#"^(\s*<\?xml[^>]*?encoding\s*=\s*["'])[^"']*(["'])"#).unwrap());
+static ENCODING_DECLARATION_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(
+    r#"<\?xml[^>]*?encoding\s*=\s*["']([A-Za-z0-9_.:-]+)["']"#).unwrap());
+static CHARSET_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(
+    r#"(?i)charset\s*=\s*["']?([A-Za-z0-9_.:-]+)"#).unwrap());
+static ENTITY_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(
+    r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[A-Za-z][A-Za-z0-9]*);").unwrap());
+static AMPERSAND_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(
+    r"&(#[0-9]+;|#[xX][0-9a-fA-F]+;|[A-Za-z][A-Za-z0-9]*;)?").unwrap());
+static LINE_BREAK_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<br\s*/?>|</p>").unwrap());
+static TAG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+/// Decodes the raw bytes of a feed to UTF-8. The encoding is taken from the byte order mark, the
+/// charset of the content type or the XML declaration, in that order.
+pub fn decode_feed(bytes: &[u8], content_type: Option<&str>) -> String {
+    let (decoded_feed, _, had_errors) = detect_encoding(bytes, content_type).decode(bytes);
+    if had_errors {
+        log::info!("Feed contained invalid characters for its encoding. They were replaced.");
+    }
+    XML_DECLARATION_REGEX.replace(&decoded_feed, "${1}UTF-8${2}").to_string()
+}
+
+/// Rewrites HTML entities and stray ampersands of a XML feed so that XML parsers accept it.
+pub fn escape_xml_entities(feed: &str) -> String {
+    let mut escaped_feed = String::with_capacity(feed.len());
+    let mut remaining_feed = feed;
+
+    while let Some(cdata_start) = remaining_feed.find("<![CDATA[") {
+        escaped_feed.push_str(&escape_entities(&remaining_feed[..cdata_start]));
+        let cdata_section = &remaining_feed[cdata_start..];
+        match cdata_section.find("]]>") {
+            Some(cdata_end) => {
+                escaped_feed.push_str(&cdata_section[..cdata_end + 3]);
+                remaining_feed = &cdata_section[cdata_end + 3..];
+            }
+            None => {
+                // Unterminated section, the XML parser will report it
+                escaped_feed.push_str(cdata_section);
+                remaining_feed = "";
+            }
+        }
+    }
+    escaped_feed.push_str(&escape_entities(remaining_feed));
+    escaped_feed
+}
+
+/// Replaces HTML entities in plain text like titles, e.g. "Q&amp;A" turns into "Q&A".
+pub fn decode_html_entities(text: &str) -> String {
+    ENTITY_REGEX.replace_all(text, |captures: &Captures| {
+        let entity = &captures[1];
+        let code_point = match entity.strip_prefix('#') {
+            Some(number) => match number.strip_prefix(['x', 'X']) {
+                Some(hex_number) => u32::from_str_radix(hex_number, 16).ok(),
+                None => number.parse::<u32>().ok()
+            },
+            None => match entity {
+                "amp" => Some('&' as u32),
+                "lt" => Some('<' as u32),
+                "gt" => Some('>' as u32),
+                "quot" => Some('"' as u32),
+                "apos" => Some('\'' as u32),
+                _ => find_html_entity(entity)
+            }
+        };
+        match code_point.and_then(char::from_u32) {
+            Some(character) => character.to_string(),
+            None => captures[0].to_string()
+        }
+    }).to_string()
+}
+
+/// Turns an HTML description into plain text. Paragraphs and line breaks become new lines, other
+/// tags are removed and entities are decoded.
+pub fn to_plain_text(description: &str) -> String {
+    let text = LINE_BREAK_REGEX.replace_all(description, "\n");
+    decode_html_entities(&TAG_REGEX.replace_all(&text, ""))
+        .trim()
+        .to_string()
+}
+
+fn detect_encoding(bytes: &[u8], content_type: Option<&str>) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+
+    let charset_of_header = content_type
+        .and_then(|content_type| CHARSET_REGEX.captures(content_type))
+        .and_then(|captures| Encoding::for_label(captures[1].as_bytes()));
+    if let Some(encoding) = charset_of_header {
+        return encoding;
+    }
+
+    // The declaration is ASCII compatible in every encoding a feed realistically uses
+    let start_of_document = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_string();
+    ENCODING_DECLARATION_REGEX.captures(&start_of_document)
+        .and_then(|captures| Encoding::for_label(captures[1].as_bytes()))
+        .unwrap_or(UTF_8)
+}
+
+fn escape_entities(xml: &str) -> String {
+    AMPERSAND_REGEX.replace_all(xml, |captures: &Captures| {
+        match captures.get(1) {
+            Some(entity) => {
+                let entity = entity.as_str().trim_end_matches(';');
+                if entity.starts_with('#') || XML_ENTITIES.contains(&entity) {
+                    return captures[0].to_string();
+                }
+                match find_html_entity(entity) {
+                    Some(code_point) => format!("&#{};", code_point),
+                    None => format!("&amp;{};", entity)
+                }
+            }
+            None => "&amp;".to_string()
+        }
+    }).to_string()
+}
+
+fn find_html_entity(name: &str) -> Option<u32> {
+    HTML_ENTITIES
+        .iter()
+        .find(|(entity_name, _)| *entity_name == name)
+        .map(|(_, code_point)| *code_point)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::feed_sanitizer::{decode_feed, decode_html_entities, escape_xml_entities,
+                                       to_plain_text};
+
+    #[test]
+    fn test_decode_feed_encoding_of_xml_declaration() {
+        let feed = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><title>Caf\xe9</title>";
+        assert_eq!(decode_feed(feed, None),
+                   "<?xml version=\"1.0\" encoding=\"UTF-8\"?><title>Café</title>");
+    }
+
+    #[test]
+    fn test_decode_feed_charset_of_content_type() {
+        let feed = b"<?xml version='1.0' encoding='UTF-8'?><title>Caf\xe9</title>";
+        assert_eq!(decode_feed(feed, Some("application/rss+xml; charset=\"windows-1252\"")),
+                   "<?xml version='1.0' encoding='UTF-8'?><title>Café</title>");
+    }
+
+    #[test]
+    fn test_decode_feed_byte_order_mark() {
+        let feed = "<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><title>Café</title>"
+            .encode_utf16()
+            .flat_map(|code_unit| code_unit.to_le_bytes())
+            .collect::<Vec<u8>>();
+        let feed = [vec![0xFF, 0xFE], feed].concat();
+        assert_eq!(decode_feed(&feed, Some("text/xml; charset=ISO-8859-1")),
+                   "<?xml version=\"1.0\" encoding=\"UTF-8\"?><title>Café</title>");
+    }
+
+    #[test]
+    fn test_decode_feed_invalid_utf8() {
+        assert_eq!(decode_feed(b"<title>Caf\xe9</title>", None), "<title>Caf\u{FFFD}</title>");
+    }
+
+    #[test]
+    fn test_escape_xml_entities() {
+        assert_eq!(escape_xml_entities("<title>Q&A &nbsp;&amp; &copy; &#8211; &#x2014; &foo; \
+        &lt;</title>"),
+                   "<title>Q&amp;A &#160;&amp; &#169; &#8211; &#x2014; &amp;foo; &lt;</title>");
+    }
+
+    #[test]
+    fn test_escape_xml_entities_keeps_cdata() {
+        assert_eq!(escape_xml_entities("<a>&</a><![CDATA[Q&A &nbsp;]]><b>&nbsp;</b>\
+        <![CDATA[&"),
+                   "<a>&amp;</a><![CDATA[Q&A &nbsp;]]><b>&#160;</b><![CDATA[&");
+    }
+
+    #[test]
+    fn test_decode_html_entities() {
+        assert_eq!(decode_html_entities("Q&amp;A &#8211; &#x2014; &hellip; &quot;Hi&quot; \
+        &unknown; &#xFFFFFF;"),
+                   "Q&A – — … \"Hi\" &unknown; &#xFFFFFF;");
+    }
+
+    #[test]
+    fn test_to_plain_text() {
+        assert_eq!(to_plain_text("<p>First &amp; <b>bold</b></p><p>Second<br/>line</p>"),
+                   "First & bold\nSecond\nline");
+        assert_eq!(to_plain_text("  Plain text  "), "Plain text");
+        assert_eq!(to_plain_text("<a href=\"https://example.com\">Link</a>"), "Link");
+    }
+}
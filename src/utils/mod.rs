@@ -1,6 +1,8 @@
 pub mod podcast_builder;
-mod rss_extension;
 pub mod do_retry;
 pub mod time;
 pub mod dyn_query;
-pub mod append_to_header;
+pub mod url_normalization;
+pub mod feed_sanitizer;
+pub mod path_template;
+pub mod search_highlight;
@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+use chrono::{DateTime, FixedOffset};
+use chrono::format::{Item, StrftimeItems};
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const MAX_NUMBER_WIDTH: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TemplateVariableKind {
+    Text,
+    /// Can be padded with zeros, `{episode:03}`
+    Number,
+    /// Is formatted with strftime, `{date:%Y-%m}`
+    Date
+}
+
+#[derive(Debug, Clone)]
+pub enum TemplateValue {
+    Text(String),
+    Number(i32),
+    Date(DateTime<FixedOffset>)
+}
+
+#[derive(Debug, Clone)]
+enum TemplatePart {
+    Literal(String),
+    Variable {
+        name: String,
+        format: Option<String>,
+        default: Option<String>
+    },
+    /// Renders its parts only if the variable has a value or, if negated, if it has none
+    Conditional {
+        name: String,
+        negated: bool,
+        parts: Vec<TemplatePart>
+    }
+}
+
+/// A format for file and directory names.
+///
+/// * `{name}` is replaced with the value of the variable, `{}` with the default variable
+/// * `{name:format}` formats dates with strftime and pads numbers with zeros (`{episode:03}`)
+/// * `{name|default}` uses the default if the variable has no value
+/// * `{?name}...{/name}` is only rendered if the variable has a value, `{!name}...{/name}` only
+///   if it has none
+/// * `{{` and `}}` are literal braces
+#[derive(Debug, Clone)]
+pub struct PathTemplate {
+    parts: Vec<TemplatePart>
+}
+
+impl PathTemplate {
+    /// Parses the template and checks that it only uses the given variables with formats that
+    /// fit their kind.
+    pub fn parse(template: &str, variables: &[(&str, TemplateVariableKind)],
+                 default_variable: &str) -> Result<PathTemplate, String> {
+        let mut parser = TemplateParser {
+            chars: template.chars().peekable(),
+            variables,
+            default_variable
+        };
+        let parts = parser.parse_parts(None)?;
+        if !Self::contains_variable(&parts) {
+            return Err("The format has to contain at least one variable like {}".to_string());
+        }
+        Ok(PathTemplate {
+            parts
+        })
+    }
+
+    /// Every value is passed through `sanitize` before it's inserted, the literal text of the
+    /// template is inserted as it is.
+    pub fn render(&self, values: &HashMap<&str, TemplateValue>, sanitize: &dyn Fn(&str) -> String)
+        -> String {
+        let mut rendered = String::new();
+        Self::render_parts(&self.parts, values, sanitize, &mut rendered);
+        rendered
+    }
+
+    fn render_parts(parts: &[TemplatePart], values: &HashMap<&str, TemplateValue>,
+                    sanitize: &dyn Fn(&str) -> String, rendered: &mut String) {
+        for part in parts {
+            match part {
+                TemplatePart::Literal(text) => rendered.push_str(text),
+                TemplatePart::Variable { name, format, default } => {
+                    let value = Self::get_value(values, name, format.as_deref())
+                        .map(|value| sanitize(&value))
+                        .filter(|value| !value.trim().is_empty())
+                        .or(default.as_deref().map(sanitize))
+                        .unwrap_or_default();
+                    rendered.push_str(&value);
+                }
+                TemplatePart::Conditional { name, negated, parts } => {
+                    if Self::get_value(values, name, None).is_some() != *negated {
+                        Self::render_parts(parts, values, sanitize, rendered);
+                    }
+                }
+            }
+        }
+    }
+
+    fn get_value(values: &HashMap<&str, TemplateValue>, name: &str, format: Option<&str>)
+        -> Option<String> {
+        let value = match values.get(name)? {
+            TemplateValue::Text(text) => text.clone(),
+            TemplateValue::Number(number) => match format {
+                Some(width) => format!("{:0width$}", number, width = width.parse().unwrap_or(0)),
+                None => number.to_string()
+            },
+            TemplateValue::Date(date) => date
+                .format(format.unwrap_or(DEFAULT_DATE_FORMAT))
+                .to_string()
+        };
+        Some(value).filter(|value| !value.trim().is_empty())
+    }
+
+    fn contains_variable(parts: &[TemplatePart]) -> bool {
+        parts.iter().any(|part| match part {
+            TemplatePart::Literal(..) => false,
+            TemplatePart::Variable { .. } => true,
+            TemplatePart::Conditional { parts, .. } => Self::contains_variable(parts)
+        })
+    }
+}
+
+struct TemplateParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    variables: &'a [(&'a str, TemplateVariableKind)],
+    default_variable: &'a str
+}
+
+impl TemplateParser<'_> {
+    /// Parses until the end of the template or until the closing tag of the open conditional
+    fn parse_parts(&mut self, open_conditional: Option<&str>) -> Result<Vec<TemplatePart>, String> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        while let Some(c) = self.chars.next() {
+            match c {
+                '{' if self.chars.next_if_eq(&'{').is_some() => literal.push('{'),
+                '}' if self.chars.next_if_eq(&'}').is_some() => literal.push('}'),
+                '}' => return Err("Unexpected } in the format, use }} for a literal }"
+                    .to_string()),
+                '/' | '\\' => return Err("The format must not contain / or \\".to_string()),
+                '{' => {
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    }
+                    let tag = self.read_tag()?;
+                    if let Some(name) = tag.strip_prefix('/') {
+                        return match open_conditional == Some(name) {
+                            true => Ok(parts),
+                            false => Err(format!("{{/{}}} closes no open {{?{}}}", name, name))
+                        };
+                    }
+                    parts.push(self.parse_tag(&tag)?);
+                }
+                c => literal.push(c)
+            }
+        }
+        if let Some(name) = open_conditional {
+            return Err(format!("{{?{}}} is never closed with {{/{}}}", name, name));
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+        Ok(parts)
+    }
+
+    fn read_tag(&mut self) -> Result<String, String> {
+        let mut tag = String::new();
+        for c in self.chars.by_ref() {
+            match c {
+                '}' => return Ok(tag),
+                '{' => return Err("Variables can't be nested".to_string()),
+                c => tag.push(c)
+            }
+        }
+        Err(format!("{{{} is never closed with }}", tag))
+    }
+
+    fn parse_tag(&mut self, tag: &str) -> Result<TemplatePart, String> {
+        if let Some((negated, name)) = tag.strip_prefix('?').map(|name| (false, name))
+            .or(tag.strip_prefix('!').map(|name| (true, name))) {
+            self.get_kind(name)?;
+            return Ok(TemplatePart::Conditional {
+                name: name.to_string(),
+                negated,
+                parts: self.parse_parts(Some(name))?
+            });
+        }
+        let (variable, default) = match tag.split_once('|') {
+            Some((variable, default)) => (variable, Some(default.to_string())),
+            None => (tag, None)
+        };
+        let (name, format) = match variable.split_once(':') {
+            Some((name, format)) => (name, Some(format.to_string())),
+            None => (variable, None)
+        };
+        let name = match name.trim() {
+            "" => self.default_variable,
+            name => name
+        };
+        let kind = self.get_kind(name)?;
+        if let Some(format) = &format {
+            Self::check_format(name, kind, format)?;
+        }
+        Ok(TemplatePart::Variable {
+            name: name.to_string(),
+            format,
+            default
+        })
+    }
+
+    fn get_kind(&self, name: &str) -> Result<TemplateVariableKind, String> {
+        self.variables.iter()
+            .find(|(variable, _)| *variable == name)
+            .map(|(_, kind)| *kind)
+            .ok_or_else(|| format!("Unknown variable {}, available are {}", name, self.variables
+                .iter()
+                .map(|(variable, _)| *variable)
+                .collect::<Vec<_>>()
+                .join(", ")))
+    }
+
+    fn check_format(name: &str, kind: TemplateVariableKind, format: &str) -> Result<(), String> {
+        match kind {
+            TemplateVariableKind::Text => Err(format!("{} can't be formatted", name)),
+            TemplateVariableKind::Number => match format.parse::<usize>() {
+                Ok(width) if width <= MAX_NUMBER_WIDTH && format.chars().all(|c| c.is_ascii_digit())
+                => Ok(()),
+                _ => Err(format!("The format of {} has to be the number of digits, e.g. {{{}:02}}",
+                                 name, name))
+            },
+            TemplateVariableKind::Date => match StrftimeItems::new(format)
+                .any(|item| matches!(item, Item::Error)) {
+                true => Err(format!("{} is no valid date format", format)),
+                false => Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use chrono::DateTime;
+    use crate::utils::path_template::{PathTemplate, TemplateValue, TemplateVariableKind};
+
+    const VARIABLES: [(&str, TemplateVariableKind); 4] = [
+        ("title", TemplateVariableKind::Text),
+        ("author", TemplateVariableKind::Text),
+        ("episode", TemplateVariableKind::Number),
+        ("date", TemplateVariableKind::Date)
+    ];
+
+    fn parse(template: &str) -> Result<PathTemplate, String> {
+        PathTemplate::parse(template, &VARIABLES, "title")
+    }
+
+    fn render(template: &str, values: &HashMap<&str, TemplateValue>) -> String {
+        parse(template).unwrap().render(values, &|value| value.replace(':', "-"))
+    }
+
+    fn values() -> HashMap<&'static str, TemplateValue> {
+        HashMap::from([
+            ("title", TemplateValue::Text("Intro: Part 1".to_string())),
+            ("episode", TemplateValue::Number(7)),
+            ("date", TemplateValue::Date(DateTime::parse_from_rfc3339("2023-10-21T08:00:00+02:00")
+                .unwrap()))
+        ])
+    }
+
+    #[test]
+    fn test_render_variables() {
+        assert_eq!(render("{}", &values()), "Intro- Part 1");
+        assert_eq!(render("{episode} - {title}", &values()), "7 - Intro- Part 1");
+        assert_eq!(render("{ }", &values()), "Intro- Part 1");
+    }
+
+    #[test]
+    fn test_render_formats() {
+        assert_eq!(render("{episode:03}", &values()), "007");
+        assert_eq!(render("{episode:0}", &values()), "7");
+        assert_eq!(render("{date}", &values()), "2023-10-21");
+        assert_eq!(render("{date:%Y-%m} {}", &values()), "2023-10 Intro- Part 1");
+    }
+
+    #[test]
+    fn test_render_defaults() {
+        assert_eq!(render("{author|Unknown: Author}", &values()), "Unknown- Author");
+        let mut values = values();
+        values.insert("author", TemplateValue::Text("  ".to_string()));
+        assert_eq!(render("{author|Unknown}", &values), "Unknown");
+        values.insert("author", TemplateValue::Text("Jane".to_string()));
+        assert_eq!(render("{author|Unknown}", &values), "Jane");
+        assert_eq!(render("{author}{title}", &HashMap::new()), "");
+    }
+
+    #[test]
+    fn test_render_conditionals() {
+        let template = "{?episode}E{episode:02} {/episode}{!author}Anonymous {/author}{}";
+        assert_eq!(render(template, &values()), "E07 Anonymous Intro- Part 1");
+        let mut values = values();
+        values.remove("episode");
+        values.insert("author", TemplateValue::Text("Jane".to_string()));
+        assert_eq!(render(template, &values), "Intro- Part 1");
+        assert_eq!(render("{?episode}{?date}{date:%Y}{/date}{/episode}", &values), "");
+    }
+
+    #[test]
+    fn test_render_literal_braces() {
+        assert_eq!(render("{{{episode}}} {title}: }}", &values()), "{7} Intro- Part 1: }");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (template, error) in [
+            ("", "The format has to contain at least one variable like {}"),
+            ("Episodes {{}}", "The format has to contain at least one variable like {}"),
+            ("{name}", "Unknown variable name, available are title, author, episode, date"),
+            ("{title", "{title is never closed with }"),
+            ("{title}}", "Unexpected } in the format, use }} for a literal }"),
+            ("{{title}", "Unexpected } in the format, use }} for a literal }"),
+            ("{title{episode}}", "Variables can't be nested"),
+            ("{date:%Y}/{title}", "The format must not contain / or \\"),
+            ("{title}\\{episode}", "The format must not contain / or \\"),
+            ("{?episode}{title}", "{?episode} is never closed with {/episode}"),
+            ("{?episode}{title}{/date}", "{/date} closes no open {?date}"),
+            ("{title}{/title}", "{/title} closes no open {?title}"),
+            ("{?name}{title}{/name}", "Unknown variable name, available are title, author, \
+            episode, date"),
+            ("{title:03}", "title can't be formatted"),
+            ("{episode:x}", "The format of episode has to be the number of digits, e.g. \
+            {episode:02}"),
+            ("{episode:+3}", "The format of episode has to be the number of digits, e.g. \
+            {episode:02}"),
+            ("{episode:11}", "The format of episode has to be the number of digits, e.g. \
+            {episode:02}"),
+            ("{date:%Y-%}", "%Y-% is no valid date format"),
+        ] {
+            assert_eq!(parse(template).unwrap_err(), error, "{}", template);
+        }
+    }
+}
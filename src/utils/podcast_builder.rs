@@ -1,5 +1,3 @@
-use rss::extension::itunes::ITunesCategory;
-
 pub struct PodcastBuilder {
     id: i32,
     description: String,
@@ -23,7 +21,7 @@ pub struct PodcastExtra {
 
 impl PodcastBuilder {
     pub fn new(podcast_id: i32) -> PodcastBuilder {
-        return PodcastBuilder {
+        PodcastBuilder {
             id: podcast_id,
             description: "".to_string(),
             language: "".to_string(),
@@ -31,66 +29,56 @@ impl PodcastBuilder {
             last_build_date: "".to_string(),
             explicit: false,
             author: "".to_string(),
-        };
+        }
     }
 
     pub fn description(&mut self, description: String) -> &mut PodcastBuilder {
         self.description = description;
-        return self;
+        self
     }
 
     pub fn language(&mut self, language: Option<String>) -> &mut PodcastBuilder {
-        if language.is_some() {
-            self.language = language.unwrap();
+        if let Some(language) = language {
+            self.language = language;
         }
-        return self;
+        self
     }
 
-    pub fn keywords(&mut self, keywords: Vec<ITunesCategory>) -> &mut PodcastBuilder {
-        self.keywords = keywords
-            .iter()
-            .map(|x| x.text.clone())
-            .collect::<Vec<String>>()
-            .join(",");
-        return self;
+    pub fn keywords(&mut self, keywords: Vec<String>) -> &mut PodcastBuilder {
+        self.keywords = keywords.join(",");
+        self
     }
 
     pub fn last_build_date(&mut self, last_build_date: Option<String>) -> &mut PodcastBuilder {
-        if last_build_date.is_some() {
-            self.last_build_date = last_build_date.unwrap();
+        if let Some(last_build_date) = last_build_date {
+            self.last_build_date = last_build_date;
         }
-        return self;
+        self
     }
 
     pub fn explicit(&mut self, explicit: Option<String>) -> &mut PodcastBuilder {
-        match explicit {
-            Some(explicit) => {
-                self.explicit = explicit == "yes";
-            }
-            None => {}
+        if let Some(explicit) = explicit {
+            self.explicit = explicit == "yes";
         }
-        return self;
+        self
     }
 
     pub fn author(&mut self, author: Option<String>) -> &mut PodcastBuilder {
-        match author {
-            Some(author) => {
-                self.author = author;
-            }
-            None => {}
+        if let Some(author) = author {
+            self.author = author;
         }
-        return self;
+        self
     }
 
     pub fn build(&self) -> PodcastExtra {
-        return PodcastExtra {
-            id: self.id.clone(),
-            explicit: self.explicit.clone(),
+        PodcastExtra {
+            id: self.id,
+            explicit: self.explicit,
             description: self.description.clone(),
             language: self.language.clone(),
             keywords: self.keywords.clone(),
             last_build_date: self.last_build_date.clone(),
             author: self.author.clone(),
-        };
+        }
     }
 }
@@ -1,18 +0,0 @@
-use std::sync::LockResult;
-
-pub trait LockResultExt {
-    type Guard;
-
-    /// Returns the lock guard even if the mutex is [poisoned].
-    ///
-    /// [poisoned]: https://doc.rust-lang.org/stable/std/sync/struct.Mutex.html#poisoning
-    fn ignore_poison(self) -> Self::Guard;
-}
-
-impl<Guard> LockResultExt for LockResult<Guard> {
-    type Guard = Guard;
-
-    fn ignore_poison(self) -> Guard {
-        self.unwrap_or_else(|e| e.into_inner())
-    }
-}
@@ -0,0 +1,147 @@
+// Feed texts are untrusted, everything but the marks is escaped
+const MARK_START: &str = "<mark>";
+const MARK_END: &str = "</mark>";
+const ELLIPSIS: &str = "…";
+
+/// Escapes the plain text for HTML and wraps every word that starts with one of the lowercase
+/// search terms in `<mark>` tags.
+pub fn highlight(text: &str, terms: &[String]) -> String {
+    to_html(&split_words(text), terms)
+}
+
+/// The part of the plain text around its first match with at most `max_words` words,
+/// highlighted like [`highlight`]. Texts without a match are cut after their first words.
+pub fn snippet(text: &str, terms: &[String], max_words: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let segments = split_words(&text);
+    let word_positions = segments.iter()
+        .enumerate()
+        .filter(|(_, (_, is_word))| *is_word)
+        .map(|(position, _)| position)
+        .collect::<Vec<usize>>();
+    if max_words == 0 || word_positions.len() <= max_words {
+        return to_html(&segments, terms);
+    }
+
+    let first_match = word_positions.iter()
+        .position(|position| is_match(segments[*position].0, terms))
+        .unwrap_or(0);
+    // A few words before the match give it some context
+    let first_word = first_match.saturating_sub(max_words / 4)
+        .min(word_positions.len() - max_words);
+    let last_word = first_word + max_words - 1;
+    let start = match first_word {
+        0 => 0,
+        _ => word_positions[first_word]
+    };
+    let end = match last_word == word_positions.len() - 1 {
+        true => segments.len(),
+        false => word_positions[last_word] + 1
+    };
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push_str(ELLIPSIS);
+    }
+    snippet.push_str(&to_html(&segments[start..end], terms));
+    if end < segments.len() {
+        snippet.push_str(ELLIPSIS);
+    }
+    snippet
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// Words are split like the search terms, at everything that isn't a letter or a digit
+fn split_words(text: &str) -> Vec<(&str, bool)> {
+    let mut segments = Vec::new();
+    let mut segment_start = 0;
+    let mut in_word = None;
+    for (index, character) in text.char_indices() {
+        let is_word = character.is_alphanumeric();
+        if in_word.is_some_and(|in_word| in_word != is_word) {
+            segments.push((&text[segment_start..index], !is_word));
+            segment_start = index;
+        }
+        in_word = Some(is_word);
+    }
+    if let Some(in_word) = in_word {
+        segments.push((&text[segment_start..], in_word));
+    }
+    segments
+}
+
+fn is_match(word: &str, terms: &[String]) -> bool {
+    let word = word.to_lowercase();
+    terms.iter().any(|term| word.starts_with(term.as_str()))
+}
+
+fn to_html(segments: &[(&str, bool)], terms: &[String]) -> String {
+    segments.iter()
+        .map(|(segment, is_word)| match *is_word && is_match(segment, terms) {
+            true => format!("{}{}{}", MARK_START, escape_html(segment), MARK_END),
+            false => escape_html(segment)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::search_highlight::{escape_html, highlight, snippet};
+
+    fn get_terms(terms: &[&str]) -> Vec<String> {
+        terms.iter().map(|term| term.to_string()).collect()
+    }
+
+    #[test]
+    fn test_highlight_marks_prefixes_of_words() {
+        assert_eq!(highlight("Rusty rust and Trust", &get_terms(&["rust"])),
+                   "<mark>Rusty</mark> <mark>rust</mark> and Trust");
+        assert_eq!(highlight("Café Crème", &get_terms(&["caf", "crè"])),
+                   "<mark>Café</mark> <mark>Crème</mark>");
+        assert_eq!(highlight("", &get_terms(&["rust"])), "");
+    }
+
+    #[test]
+    fn test_highlight_escapes_markup() {
+        assert_eq!(highlight("<img src=x onerror=alert(1)> Rust & \"Go\"", &get_terms(&["rust"])),
+                   "&lt;img src=x onerror=alert(1)&gt; <mark>Rust</mark> &amp; &quot;Go&quot;");
+        assert_eq!(highlight("<mark>Rust</mark>", &get_terms(&["mark"])),
+                   "&lt;<mark>mark</mark>&gt;Rust&lt;/<mark>mark</mark>&gt;");
+    }
+
+    #[test]
+    fn test_snippet_of_short_text() {
+        assert_eq!(snippet("A text\n about   rust", &get_terms(&["rust"]), 32),
+                   "A text about <mark>rust</mark>");
+    }
+
+    #[test]
+    fn test_snippet_around_the_first_match() {
+        let text = (1..=20).map(|number| format!("word{}", number)).collect::<Vec<String>>()
+            .join(" ") + " rust " + &(21..=40).map(|number| format!("word{}", number))
+            .collect::<Vec<String>>().join(" ");
+        assert_eq!(snippet(&text, &get_terms(&["rust"]), 8),
+                   "…word19 word20 <mark>rust</mark> word21 word22 word23 word24 word25…");
+        assert_eq!(snippet(&text, &get_terms(&["word1"]), 4),
+                   "<mark>word1</mark> word2 word3 word4…");
+        assert_eq!(snippet(&text, &get_terms(&["word40"]), 4),
+                   "…word37 word38 word39 <mark>word40</mark>");
+    }
+
+    #[test]
+    fn test_snippet_without_match() {
+        assert_eq!(snippet("one two three four", &get_terms(&["rust"]), 2), "one two…");
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(escape_html("<a href='x'>&</a>"), "&lt;a href=&#39;x&#39;&gt;&amp;&lt;/a&gt;");
+    }
+}
@@ -1,5 +1,20 @@
 use std::time::SystemTime;
-use chrono::{NaiveDateTime, Utc};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+// Time zone abbreviations used in feeds that RFC 2822 does not know about
+const TIME_ZONE_ABBREVIATIONS: [(&str, &str); 10] = [
+    ("CET", "+0100"), ("CEST", "+0200"), ("BST", "+0100"), ("IST", "+0530"), ("EET", "+0200"),
+    ("EEST", "+0300"), ("AEST", "+1000"), ("AEDT", "+1100"), ("JST", "+0900"), ("UTC", "+0000"),
+];
+
+// Publishers regularly get the weekday wrong, which makes the whole date invalid
+static WEEKDAY_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(
+    r"(?i)^\s*(mon|tue|wed|thu|fri|sat|sun)[a-z]*\.?,?\s+").unwrap());
+static TIME_ZONE_ABBREVIATION_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(&format!(
+    r"\b({})$", TIME_ZONE_ABBREVIATIONS.map(|(abbreviation, _)| abbreviation).join("|")))
+    .unwrap());
 
 
 pub fn get_current_timestamp()->i64{
@@ -13,13 +28,81 @@ pub fn get_current_timestamp_str()->NaiveDateTime{
 }
 
 
+/// Dates of filters are days in UTC
+pub fn get_start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+}
+
 pub fn opt_or_empty_string<T:ToString>(opt: Option<T>) ->  String {
     match opt {
         Some(s) => {
             s.to_string()
         },
         None => {
-            return "".to_string()
+            "".to_string()
         },
     }
-}
\ No newline at end of file
+}
+
+/// Parses the publication date of a feed item. Besides RFC 2822 and RFC 3339 a number of
+/// commonly seen deviations like wrong weekdays or unknown time zone abbreviations are accepted.
+pub fn parse_feed_date(date: &str) -> Option<DateTime<FixedOffset>> {
+    if let Some(parsed_date) = diligent_date_parser::parse_date(date) {
+        return Some(parsed_date);
+    }
+
+    let without_weekday = WEEKDAY_REGEX.replace(date, "");
+    let cleaned_date = TIME_ZONE_ABBREVIATION_REGEX.replace(without_weekday.trim(),
+                                                            |captures: &Captures| {
+        TIME_ZONE_ABBREVIATIONS.iter()
+            .find(|(abbreviation, _)| *abbreviation == &captures[1])
+            .map(|(_, offset)| *offset)
+            .unwrap_or_default()
+    });
+    diligent_date_parser::parse_date(&cleaned_date)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset};
+    use crate::utils::time::parse_feed_date;
+
+    fn date(rfc_3339_date: &str) -> Option<DateTime<FixedOffset>> {
+        Some(DateTime::parse_from_rfc3339(rfc_3339_date).unwrap())
+    }
+
+    #[test]
+    fn test_parse_feed_date_standard_formats() {
+        assert_eq!(parse_feed_date("Fri, 24 May 2013 10:30:00 +0200"),
+                   date("2013-05-24T10:30:00+02:00"));
+        assert_eq!(parse_feed_date("Fri, 24 May 2013 10:30:00 GMT"),
+                   date("2013-05-24T10:30:00+00:00"));
+        assert_eq!(parse_feed_date("2013-05-24T10:30:00Z"), date("2013-05-24T10:30:00+00:00"));
+    }
+
+    #[test]
+    fn test_parse_feed_date_wrong_weekday() {
+        assert_eq!(parse_feed_date("Mon, 24 May 2013 10:30:00 +0000"),
+                   date("2013-05-24T10:30:00+00:00"));
+        assert_eq!(parse_feed_date("Tuesday, 24 May 2013 10:30:00 +0000"),
+                   date("2013-05-24T10:30:00+00:00"));
+    }
+
+    #[test]
+    fn test_parse_feed_date_time_zone_abbreviations() {
+        assert_eq!(parse_feed_date("Fri, 24 May 2013 10:30:00 CEST"),
+                   date("2013-05-24T10:30:00+02:00"));
+        assert_eq!(parse_feed_date("Fri, 24 May 2013 10:30:00 AEST "),
+                   date("2013-05-24T10:30:00+10:00"));
+        assert_eq!(parse_feed_date("Sun, 24 May 2013 10:30:00 JST"),
+                   date("2013-05-24T10:30:00+09:00"));
+    }
+
+    #[test]
+    fn test_parse_feed_date_malformed() {
+        assert_eq!(parse_feed_date(""), None);
+        assert_eq!(parse_feed_date("not a date"), None);
+        assert_eq!(parse_feed_date("Fri, 32 May 2013 10:30:00 +0000"), None);
+        assert_eq!(parse_feed_date("Fri, 24 May 2013 25:30:00 CEST"), None);
+    }
+}
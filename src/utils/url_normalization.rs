@@ -0,0 +1,162 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Url;
+
+// Analytics and tracking services that prepend themselves to the real enclosure url. They are
+// stripped so that an episode keeps its identity when a publisher adds or removes one of them.
+const TRACKING_PREFIXES: [&str; 12] = [
+    r"^dts\.podtrac\.com/redirect\.[a-z0-9]+/",
+    r"^(www\.)?podtrac\.com/pts/redirect\.[a-z0-9]+/",
+    r"^chrt\.fm/track/[^/]+/",
+    r"^pdst\.fm/e/",
+    r"^op3\.dev/e(,[^/]*)?/",
+    r"^pfx\.vpixl\.com/[^/]+/",
+    r"^arttrk\.com/p/[^/]+/",
+    r"^verifi\.podscribe\.com/rss/p/",
+    r"^mgln\.ai/e/[^/]+/",
+    r"^prfx\.byspotify\.com/e/",
+    r"^claritaspod\.com/measure/",
+    r"^media\.blubrry\.com/[^/]+/",
+];
+
+// Query parameters that only track the listener or the feed reader. The other parameters can
+// identify the file, e.g. `download.php?id=1`, and are kept.
+const TRACKING_PARAMETER_PREFIXES: [&str; 1] = ["utm_"];
+const TRACKING_PARAMETERS: [&str; 11] = ["updated", "ref", "source", "from", "fbclid", "gclid",
+    "awcollectionid", "awepisodeid", "awgenre", "listeningsessionid", "nocache"];
+
+static TRACKING_PREFIX_REGEXES: Lazy<Vec<Regex>> = Lazy::new(|| TRACKING_PREFIXES
+    .iter()
+    .map(|prefix| Regex::new(prefix).unwrap())
+    .collect());
+
+/// Reduces an enclosure url to the part that identifies the media file: scheme, fragment,
+/// known tracking prefixes and tracking query parameters are removed and the host is lowercased.
+pub fn normalize_enclosure_url(url: &str) -> String {
+    let trimmed_url = url.trim();
+    let (host_and_path, query) = match Url::parse(trimmed_url) {
+        Ok(parsed_url) => {
+            (format!("{}{}", parsed_url.host_str().unwrap_or("").to_lowercase(),
+                     parsed_url.path()), parsed_url.query().unwrap_or("").to_string())
+        }
+        Err(_) => {
+            let without_fragment = trimmed_url.split('#').next().unwrap_or("");
+            let (without_query, query) = without_fragment.split_once('?')
+                .unwrap_or((without_fragment, ""));
+            let without_scheme = match without_query.find("://") {
+                Some(index) => &without_query[index + 3..],
+                None => without_query
+            };
+            (without_scheme.to_string(), query.to_string())
+        }
+    };
+
+    let mut normalized_url = host_and_path;
+    // Tracking prefixes are frequently chained, so strip until nothing matches anymore
+    loop {
+        let stripped_url = TRACKING_PREFIX_REGEXES
+            .iter()
+            .find(|regex| regex.is_match(&normalized_url))
+            .map(|regex| regex.replace(&normalized_url, "").to_string());
+        match stripped_url {
+            Some(stripped_url) => {
+                normalized_url = stripped_url
+                    .trim_start_matches("https://")
+                    .trim_start_matches("http://")
+                    .to_string();
+            }
+            None => break
+        }
+    }
+
+    let normalized_url = normalized_url.trim_start_matches("www.");
+    match normalize_query(&query) {
+        normalized_query if normalized_query.is_empty() => normalized_url.to_string(),
+        normalized_query => format!("{}?{}", normalized_url, normalized_query)
+    }
+}
+
+/// Removes the tracking parameters and sorts the others, so that their order doesn't matter
+fn normalize_query(query: &str) -> String {
+    let mut parameters = query.split('&')
+        .filter(|parameter| !parameter.is_empty())
+        .filter(|parameter| {
+            let name = parameter.split('=').next().unwrap_or("").to_lowercase();
+            !TRACKING_PARAMETERS.contains(&name.as_str()) && !TRACKING_PARAMETER_PREFIXES.iter()
+                .any(|prefix| name.starts_with(prefix))
+        })
+        .collect::<Vec<&str>>();
+    parameters.sort();
+    parameters.join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::url_normalization::normalize_enclosure_url;
+
+    #[test]
+    fn test_normalize_enclosure_url_strips_scheme_tracking_parameters_and_fragment() {
+        assert_eq!(normalize_enclosure_url(" https://WWW.Example.com/Episode%201.mp3?updated=1#t=5 "),
+                   "example.com/Episode%201.mp3");
+        assert_eq!(normalize_enclosure_url("http://example.com/episode.mp3"),
+                   normalize_enclosure_url("https://example.com/episode.mp3?utm_source=rss&\
+                   utm_medium=feed"));
+    }
+
+    #[test]
+    fn test_normalize_enclosure_url_keeps_identifying_parameters() {
+        assert_eq!(normalize_enclosure_url("https://example.com/download.php?id=1&utm_source=rss"),
+                   "example.com/download.php?id=1");
+        assert_ne!(normalize_enclosure_url("https://example.com/download.php?id=1"),
+                   normalize_enclosure_url("https://example.com/download.php?id=2"));
+        assert_eq!(normalize_enclosure_url("https://example.com/download.php?id=1&show=2"),
+                   normalize_enclosure_url("https://example.com/download.php?show=2&id=1"));
+    }
+
+    #[test]
+    fn test_normalize_enclosure_url_without_valid_url() {
+        assert_eq!(normalize_enclosure_url("example.com/episode.mp3?updated=1"),
+                   "example.com/episode.mp3");
+        assert_eq!(normalize_enclosure_url("example.com/download.php?id=1#t=5"),
+                   "example.com/download.php?id=1");
+        assert_eq!(normalize_enclosure_url(""), "");
+    }
+
+    #[test]
+    fn test_normalize_enclosure_url_strips_tracking_prefixes() {
+        let expected_url = "traffic.megaphone.fm/ABC123.mp3";
+        for url in [
+            "https://dts.podtrac.com/redirect.mp3/traffic.megaphone.fm/ABC123.mp3",
+            "https://www.podtrac.com/pts/redirect.mp3/traffic.megaphone.fm/ABC123.mp3",
+            "https://chrt.fm/track/12ABC/traffic.megaphone.fm/ABC123.mp3",
+            "https://pdst.fm/e/traffic.megaphone.fm/ABC123.mp3",
+            "https://op3.dev/e/https://traffic.megaphone.fm/ABC123.mp3",
+            "https://op3.dev/e,pg=9b024349-ccf0-5f69-a609-6b82873eab3c/traffic.megaphone.fm/ABC123.mp3",
+            "https://pfx.vpixl.com/6qj4J/traffic.megaphone.fm/ABC123.mp3",
+            "https://arttrk.com/p/ABCDE/traffic.megaphone.fm/ABC123.mp3",
+            "https://verifi.podscribe.com/rss/p/traffic.megaphone.fm/ABC123.mp3",
+            "https://mgln.ai/e/123/traffic.megaphone.fm/ABC123.mp3",
+            "https://prfx.byspotify.com/e/traffic.megaphone.fm/ABC123.mp3",
+            "https://claritaspod.com/measure/traffic.megaphone.fm/ABC123.mp3",
+            "https://media.blubrry.com/show/traffic.megaphone.fm/ABC123.mp3",
+        ] {
+            assert_eq!(normalize_enclosure_url(url), expected_url, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_normalize_enclosure_url_strips_chained_redirect_prefixes() {
+        assert_eq!(normalize_enclosure_url("https://dts.podtrac.com/redirect.mp3/chrt.fm/track/\
+        12ABC/pdst.fm/e/https://www.traffic.megaphone.fm/ABC123.mp3?updated=1"),
+                   "traffic.megaphone.fm/ABC123.mp3");
+        assert_eq!(normalize_enclosure_url("https://dts.podtrac.com/redirect.mp3/chrt.fm/track/\
+        12ABC/traffic.megaphone.fm/ABC123.mp3"),
+                   normalize_enclosure_url("https://traffic.megaphone.fm/ABC123.mp3"));
+    }
+
+    #[test]
+    fn test_normalize_enclosure_url_keeps_unknown_hosts() {
+        assert_eq!(normalize_enclosure_url("https://podtrac.example.com/redirect.mp3/episode.mp3"),
+                   "podtrac.example.com/redirect.mp3/episode.mp3");
+    }
+}
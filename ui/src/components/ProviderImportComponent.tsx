@@ -8,7 +8,7 @@ import {useAppDispatch, useAppSelector} from "../store/hooks"
 import {setSearchedPodcasts} from "../store/CommonSlice"
 import {setModalOpen} from "../store/ModalSlice"
 import {AddTypes} from "../models/AddTypes"
-import {AgnosticPodcastDataModel, GeneralModel, PodIndexModel} from "../models/PodcastAddModel"
+import {AgnosticPodcastDataModel, DiscoveredPodcast} from "../models/PodcastAddModel"
 import {CustomButtonSecondary} from './CustomButtonSecondary'
 import {CustomInput} from './CustomInput'
 import {Spinner} from "./Spinner"
@@ -36,33 +36,20 @@ export const ProviderImportComponent:FC<ProviderImportComponent> = ({selectedSea
     }
     useDebounce(()=>{
             setLoading(true)
-            selectedSearchType === "itunes"?
-                axios.get(apiURL+"/podcasts/0/"+searchText+"/search")
-                    .then((v:AxiosResponse<GeneralModel>)=>{
-                        setLoading(false)
-                        const agnosticModel:AgnosticPodcastDataModel[] = v.data.results.map((podcast)=>{
-                            return {
-                                title: podcast.collectionName,
-                                artist: podcast.artistName,
-                                id: podcast.trackId,
-                                imageUrl: podcast.artworkUrl600
-                            }
-                        })
-
-                        dispatch(setSearchedPodcasts(agnosticModel))
-                    }):axios.get(apiURL+"/podcasts/1/"+searchText+"/search")
-                    .then((v:AxiosResponse<PodIndexModel>)=>{
-                        setLoading(false)
-                        let agnosticModel: AgnosticPodcastDataModel[] = v.data.feeds.map((podcast)=>{
-                            return {
-                                title: podcast.title,
-                                artist: podcast.author,
-                                id: podcast.id,
-                                imageUrl: podcast.artwork
-                            }
-                        })
-                        dispatch(setSearchedPodcasts(agnosticModel))
+            axios.get(apiURL+"/discovery/"+selectedSearchType+"/search", {params: {q: searchText}})
+                .then((v:AxiosResponse<DiscoveredPodcast[]>)=>{
+                    setLoading(false)
+                    const agnosticModel:AgnosticPodcastDataModel[] = v.data.map((podcast)=>{
+                        return {
+                            title: podcast.title,
+                            artist: podcast.author ?? '',
+                            id: Number(podcast.id),
+                            imageUrl: podcast.imageUrl ?? ''
+                        }
                     })
+                    dispatch(setSearchedPodcasts(agnosticModel))
+                })
+                .catch(()=>setLoading(false))
         },
         2000,[searchText])
 
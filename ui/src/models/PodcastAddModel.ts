@@ -1,13 +1,14 @@
-export type PodcastAddModel = {
-    artworkUrl600: string,
-    artistName: string,
-    collectionName: string,
-    trackId: number
-}
-
-export type GeneralModel = {
-   resultCount: number,
-        results: PodcastAddModel[]
+export type DiscoveredPodcast = {
+    provider: string,
+    id: string,
+    title: string,
+    author?: string,
+    description?: string,
+    feedUrl: string,
+    imageUrl?: string,
+    websiteUrl?: string,
+    categories: string[],
+    episodeCount?: number
 }
 
 
@@ -17,13 +18,3 @@ export type AgnosticPodcastDataModel = {
     artist: string,
     id: number
 }
-
-
-export type PodIndexModel = {
-    feeds: [{
-        artwork: string,
-        title: string,
-        id: number,
-        author: string
-    }]
-}